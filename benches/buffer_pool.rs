@@ -0,0 +1,71 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+// Compares the throughput of reading an archive of many small Deflate entries with and without buffer reuse.
+//
+// Run with: cargo bench --bench buffer_pool
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_zip::read::mem::ZipFileReader;
+use async_zip::read::pool::BufferPool;
+use async_zip::read::ReaderOptions;
+use async_zip::write::{EntryOptions, ZipFileWriter};
+use async_zip::Compression;
+use tokio::io::{sink, AsyncWriteExt};
+
+const ENTRY_COUNT: usize = 10_000;
+const ITERATIONS: u32 = 5;
+
+async fn create_archive() -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut writer = ZipFileWriter::new(&mut archive);
+
+    for index in 0..ENTRY_COUNT {
+        let opts = EntryOptions::new(format!("small/{}.txt", index), Compression::Deflate);
+        writer.write_entry_whole(opts, format!("entry number {}\n", index).repeat(8).as_bytes()).await.unwrap();
+    }
+
+    writer.close().await.unwrap();
+    archive
+}
+
+async fn read_entries(archive: &[u8], pool: Arc<BufferPool>) -> Duration {
+    let options = ReaderOptions::new().buffer_pool(pool);
+    let mut zip = ZipFileReader::with_options(archive, options).await.unwrap();
+
+    let start = Instant::now();
+    for index in 0..ENTRY_COUNT {
+        let reader = zip.entry_reader(index).await.unwrap();
+        reader.copy_to_end_crc(&mut sink(), 65536).await.unwrap();
+    }
+    start.elapsed()
+}
+
+#[tokio::main]
+async fn main() {
+    let archive = create_archive().await;
+    let mut stdout = tokio::io::stdout();
+
+    // A pool which retains no buffers behaves as if there were no pool at all.
+    for (label, max_buffers) in [("without reuse", 0), ("with reuse", 16)] {
+        let pool = Arc::new(BufferPool::new(max_buffers, 1024 * 1024));
+
+        let mut total = Duration::ZERO;
+        for _ in 0..ITERATIONS {
+            total += read_entries(&archive, pool.clone()).await;
+        }
+
+        let average = total / ITERATIONS;
+        let throughput = ENTRY_COUNT as f64 / average.as_secs_f64();
+        let line = format!(
+            "{:>13}: {:>8.2?} per archive ({:.0} entries/s, {} allocations)\n",
+            label,
+            average,
+            throughput,
+            pool.allocations()
+        );
+        stdout.write_all(line.as_bytes()).await.unwrap();
+    }
+}
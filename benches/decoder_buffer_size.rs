@@ -0,0 +1,67 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+// Compares the throughput of reading a large Deflate entry with small and large decoder buffers.
+//
+// Run with: cargo bench --bench decoder_buffer_size
+
+use std::time::{Duration, Instant};
+
+use async_zip::read::mem::ZipFileReader;
+use async_zip::read::ReaderOptions;
+use async_zip::write::{EntryOptions, ZipFileWriter};
+use async_zip::Compression;
+use tokio::io::{sink, AsyncWriteExt};
+
+const ENTRY_SIZE: usize = 64 * 1024 * 1024;
+const ITERATIONS: u32 = 5;
+
+async fn create_archive() -> Vec<u8> {
+    // Mildly compressible data so that the decoder has real work to do.
+    let mut state: u32 = 0x1234_5678;
+    let data: Vec<u8> = (0..ENTRY_SIZE)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            b"abcdefghijklmnop"[(state % 16) as usize]
+        })
+        .collect();
+
+    let mut archive = Vec::new();
+    let mut writer = ZipFileWriter::new(&mut archive);
+    let opts = EntryOptions::new(String::from("large.bin"), Compression::Deflate);
+    writer.write_entry_whole(opts, &data).await.unwrap();
+    writer.close().await.unwrap();
+
+    archive
+}
+
+async fn read_entry(archive: &[u8], buffer_size: usize) -> Duration {
+    let options = ReaderOptions::new().decoder_buffer_size(buffer_size);
+    let mut zip = ZipFileReader::with_options(archive, options).await.unwrap();
+
+    let start = Instant::now();
+    let reader = zip.entry_reader(0).await.unwrap();
+    reader.copy_to_end_crc(&mut sink(), 65536).await.unwrap();
+    start.elapsed()
+}
+
+#[tokio::main]
+async fn main() {
+    let archive = create_archive().await;
+    let mut stdout = tokio::io::stdout();
+
+    for buffer_size in [8 * 1024, 256 * 1024] {
+        let mut total = Duration::ZERO;
+        for _ in 0..ITERATIONS {
+            total += read_entry(&archive, buffer_size).await;
+        }
+
+        let average = total / ITERATIONS;
+        let throughput = ENTRY_SIZE as f64 / average.as_secs_f64() / (1024.0 * 1024.0);
+        let line =
+            format!("{:>4} KiB buffer: {:>8.2?} per entry ({:.1} MiB/s)\n", buffer_size / 1024, average, throughput);
+        stdout.write_all(line.as_bytes()).await.unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Parses the central directory of arbitrary bytes (falling back to recovery) and reads every entry, both by index
+//! and sequentially with prefetching. Any panic (or abort from a runaway allocation) is a bug; errors are expected.
+//!
+//! Run with `cargo +nightly fuzz run seek_entries` from the repository root.
+
+#![no_main]
+
+use async_zip::read::prefetch::PrefetchOptions;
+use async_zip::read::seek::ZipFileReader;
+use async_zip::read::ReaderOptions;
+
+use std::io::Cursor;
+
+use libfuzzer_sys::fuzz_target;
+use tokio::io::AsyncReadExt;
+
+fuzz_target!(|data: &[u8]| {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+    runtime.block_on(async {
+        let options = ReaderOptions::new().prefetch(PrefetchOptions::new());
+        let mut zip_reader = match ZipFileReader::with_recovery(Cursor::new(data), options).await {
+            Ok(zip_reader) => zip_reader,
+            Err(_) => return,
+        };
+        let mut buffer = Vec::new();
+
+        for index in 0..zip_reader.entries().len() {
+            if let Ok(mut entry_reader) = zip_reader.entry_reader(index).await {
+                buffer.clear();
+                let _ = (&mut entry_reader).take(1 << 20).read_to_end(&mut buffer).await;
+            }
+        }
+
+        let mut entries = zip_reader.sequential();
+        while let Ok(Some(mut entry_reader)) = entries.next_entry().await {
+            buffer.clear();
+            let _ = (&mut entry_reader).take(1 << 20).read_to_end(&mut buffer).await;
+        }
+    });
+});
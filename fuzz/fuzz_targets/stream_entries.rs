@@ -0,0 +1,31 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Reads every entry of arbitrary bytes with the stream reader. Any panic (or abort from a runaway allocation) is a
+//! bug; errors are expected.
+//!
+//! Run with `cargo +nightly fuzz run stream_entries` from the repository root.
+
+#![no_main]
+
+use async_zip::read::stream::ZipFileReader;
+
+use libfuzzer_sys::fuzz_target;
+use tokio::io::AsyncReadExt;
+
+fuzz_target!(|data: &[u8]| {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+    runtime.block_on(async {
+        let mut zip_reader = ZipFileReader::new(data);
+        let mut buffer = Vec::new();
+
+        while let Ok(Some(mut entry_reader)) = zip_reader.entry_reader().await {
+            buffer.clear();
+
+            if (&mut entry_reader).take(1 << 20).read_to_end(&mut buffer).await.is_err() {
+                break;
+            }
+        }
+    });
+});
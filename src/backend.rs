@@ -0,0 +1,327 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Support for compression methods implemented outside of this crate.
+//!
+//! A [`CompressionBackend`] maps a compression method to a decoder (and optionally an encoder) built upon tokio's I/O
+//! traits. Once registered with a reader (via [`ReaderOptions::compression_backend()`]) or a writer (via
+//! [`ZipFileWriter::compression_backend()`]), entries using the method are read or written as any other, with their
+//! method represented by [`Compression::Custom`]. Backends are only consulted for methods without built-in support.
+//!
+//! Decoders read from a [`BackendReader`] and encoders write to a [`BackendWriter`], which are fed from and drained
+//! into the archive by this crate. A decoder which consumes no more input than its compressed stream occupies (as with
+//! those of `async-compression`) may also be used for stream-read entries with data descriptors.
+//!
+//! # Example
+//! ```
+//! # use async_zip::backend::{BackendReader, BackendWriter, CompressionBackend};
+//! # use async_zip::error::Result;
+//! # use async_zip::read::ReaderOptions;
+//! # use async_zip::write::CompressionLevel;
+//! # use tokio::io::{AsyncRead, AsyncWrite};
+//! #
+//! /// A backend for a proprietary method (here, data stored as-is under method 200).
+//! struct Identity;
+//!
+//! impl CompressionBackend for Identity {
+//!     fn method(&self) -> u16 {
+//!         200
+//!     }
+//!
+//!     fn decoder(&self, reader: BackendReader) -> Result<Box<dyn AsyncRead + Unpin + Send>> {
+//!         Ok(Box::new(reader))
+//!     }
+//!
+//!     fn encoder(&self, writer: BackendWriter, _: CompressionLevel) -> Result<Box<dyn AsyncWrite + Unpin + Send>> {
+//!         Ok(Box::new(writer))
+//!     }
+//! }
+//!
+//! let options = ReaderOptions::new().compression_backend(Identity);
+//! ```
+//!
+//! [`ReaderOptions::compression_backend()`]: crate::read::ReaderOptions::compression_backend
+//! [`ZipFileWriter::compression_backend()`]: crate::write::ZipFileWriter::compression_backend
+//! [`Compression::Custom`]: crate::Compression::Custom
+
+use crate::error::{Result, ZipError};
+use crate::write::CompressionLevel;
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, ReadBuf};
+
+/// An implementation of a compression method which isn't supported by this crate.
+pub trait CompressionBackend: Send + Sync {
+    /// Returns the compression method (as stored within an entry's headers) which this backend implements.
+    fn method(&self) -> u16;
+
+    /// Constructs a decoder which decompresses the data read from the provided reader.
+    fn decoder(&self, reader: BackendReader) -> Result<Box<dyn AsyncRead + Unpin + Send>>;
+
+    /// Constructs an encoder which compresses the data written to it into the provided writer.
+    ///
+    /// Backends only support decompression unless this is implemented. The encoder is shut down once an entry's data
+    /// has been written, and should write any remaining compressed data when it is.
+    fn encoder(&self, writer: BackendWriter, level: CompressionLevel) -> Result<Box<dyn AsyncWrite + Unpin + Send>> {
+        let _ = (writer, level);
+        Err(ZipError::FeatureNotSupported("Compression with a decompression-only backend"))
+    }
+}
+
+/// The compression backends registered with a reader or writer.
+#[derive(Clone, Default)]
+pub(crate) struct Backends(Vec<Arc<dyn CompressionBackend>>);
+
+impl Backends {
+    /// Registers a backend, replacing any previously registered for the same method.
+    pub(crate) fn register(&mut self, backend: Arc<dyn CompressionBackend>) {
+        self.0.retain(|existing| existing.method() != backend.method());
+        self.0.push(backend);
+    }
+
+    /// Returns the backend registered for the provided method.
+    pub(crate) fn get(&self, method: u16) -> Result<&Arc<dyn CompressionBackend>> {
+        self.0.iter().find(|backend| backend.method() == method).ok_or(ZipError::UnsupportedCompressionError(method))
+    }
+}
+
+impl std::fmt::Debug for Backends {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.0.iter().map(|backend| backend.method())).finish()
+    }
+}
+
+/// The input handed from an entry's data to a backend's decoder.
+#[derive(Default)]
+struct Handoff {
+    bytes: Vec<u8>,
+    consumed: u64,
+    wanted: bool,
+    eof: bool,
+}
+
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A reader over an entry's compressed data, from which a backend's decoder reads.
+///
+/// Input is only provided whilst the decoder is being polled by the entry reader it belongs to, so this reader is only
+/// of use to the decoder it was passed to.
+pub struct BackendReader {
+    handoff: Arc<Mutex<Handoff>>,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl AsyncBufRead for BackendReader {
+    fn poll_fill_buf(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<tokio::io::Result<&[u8]>> {
+        let this = self.get_mut();
+
+        if this.position == this.buffer.len() {
+            let mut handoff = lock(&this.handoff);
+            if handoff.bytes.is_empty() && !handoff.eof {
+                // The backend decoder (and so this reader) is polled again once more input has been handed off.
+                handoff.wanted = true;
+                return Poll::Pending;
+            }
+
+            std::mem::swap(&mut this.buffer, &mut handoff.bytes);
+            handoff.bytes.clear();
+            this.position = 0;
+        }
+
+        Poll::Ready(Ok(&this.buffer[this.position..]))
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        self.position += amt;
+        lock(&self.handoff).consumed += amt as u64;
+    }
+}
+
+impl AsyncRead for BackendReader {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let bytes = ready!(self.as_mut().poll_fill_buf(c))?;
+        let length = std::cmp::min(bytes.len(), b.remaining());
+        b.put_slice(&bytes[..length]);
+        self.consume(length);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A decoder which decompresses an entry's data with a backend's decoder.
+pub(crate) struct BackendDecoder<R> {
+    inner: R,
+    handoff: Arc<Mutex<Handoff>>,
+
+    // Held within a mutex (which is never locked, as it's only accessed mutably) so that entry readers remain `Sync`.
+    decoder: Mutex<Box<dyn AsyncRead + Unpin + Send>>,
+
+    // The number of bytes of the inner reader's buffer handed off, and of those, how many have been consumed.
+    handed: usize,
+    settled: u64,
+}
+
+impl<R: AsyncBufRead + Unpin> BackendDecoder<R> {
+    pub(crate) fn new(inner: R, backend: &dyn CompressionBackend) -> Result<Self> {
+        let handoff = Arc::new(Mutex::new(Handoff::default()));
+        let decoder = backend.decoder(BackendReader { handoff: handoff.clone(), buffer: Vec::new(), position: 0 })?;
+
+        Ok(BackendDecoder { inner, handoff, decoder: Mutex::new(decoder), handed: 0, settled: 0 })
+    }
+
+    pub(crate) fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Consumes the input which the backend's decoder has consumed from the inner reader.
+    fn settle(&mut self) {
+        let consumed = lock(&self.handoff).consumed;
+        let amount = (consumed - self.settled) as usize;
+
+        Pin::new(&mut self.inner).consume(amount);
+        self.handed -= amount;
+        self.settled = consumed;
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncRead for BackendDecoder<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        loop {
+            let decoder = self.decoder.get_mut().unwrap_or_else(PoisonError::into_inner);
+            let poll = Pin::new(decoder).poll_read(c, b);
+            self.settle();
+
+            let wanted = std::mem::take(&mut lock(&self.handoff).wanted);
+            if poll.is_ready() || !wanted {
+                return poll;
+            }
+
+            // The decoder only wants more input once it has consumed all it was handed.
+            let this = &mut *self;
+            let bytes = ready!(Pin::new(&mut this.inner).poll_fill_buf(c))?;
+            let mut handoff = lock(&this.handoff);
+            match bytes.is_empty() {
+                true => handoff.eof = true,
+                false => handoff.bytes.extend_from_slice(bytes),
+            }
+            this.handed = bytes.len();
+        }
+    }
+}
+
+/// A writer into which a backend's encoder writes an entry's compressed data.
+pub struct BackendWriter {
+    output: Arc<Mutex<Vec<u8>>>,
+}
+
+impl BackendWriter {
+    /// Constructs a writer along with the buffer which its data is appended to.
+    pub(crate) fn new() -> (Self, Arc<Mutex<Vec<u8>>>) {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        (BackendWriter { output: output.clone() }, output)
+    }
+
+    /// Appends compressed data (as this writer never waits, encoders within this crate needn't be polled to do so).
+    pub(crate) fn append(&self, bytes: &[u8]) {
+        lock(&self.output).extend_from_slice(bytes);
+    }
+}
+
+impl AsyncWrite for BackendWriter {
+    fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<tokio::io::Result<usize>> {
+        self.append(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// An encoder which compresses an entry's data with a backend's encoder.
+pub(crate) struct BackendEncoder<W> {
+    inner: W,
+    output: Arc<Mutex<Vec<u8>>>,
+
+    // Held within a mutex (which is never locked, as it's only accessed mutably) so that entry writers remain `Sync`.
+    encoder: Mutex<Box<dyn AsyncWrite + Unpin + Send>>,
+
+    // Compressed data taken from the backend's encoder, which is yet to be written to the inner writer.
+    pending: Vec<u8>,
+    pending_position: usize,
+}
+
+impl<W: AsyncWrite + Unpin> BackendEncoder<W> {
+    pub(crate) fn new(inner: W, backend: &dyn CompressionBackend, level: CompressionLevel) -> Result<Self> {
+        let (writer, output) = BackendWriter::new();
+        let encoder = backend.encoder(writer, level)?;
+
+        let encoder = Mutex::new(encoder);
+        Ok(BackendEncoder { inner, output, encoder, pending: Vec::new(), pending_position: 0 })
+    }
+
+    pub(crate) fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn encoder(&mut self) -> Pin<&mut (dyn AsyncWrite + Unpin + Send)> {
+        Pin::new(self.encoder.get_mut().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Writes all compressed data taken from the backend's encoder to the inner writer.
+    fn poll_drain(&mut self, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        loop {
+            if self.pending_position == self.pending.len() {
+                self.pending.clear();
+                self.pending_position = 0;
+                std::mem::swap(&mut self.pending, &mut lock(&self.output));
+
+                if self.pending.is_empty() {
+                    return Poll::Ready(Ok(()));
+                }
+            }
+
+            let written = ready!(Pin::new(&mut self.inner).poll_write(c, &self.pending[self.pending_position..]))?;
+            if written == 0 {
+                return Poll::Ready(Err(tokio::io::ErrorKind::WriteZero.into()));
+            }
+            self.pending_position += written;
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for BackendEncoder<W> {
+    fn poll_write(mut self: Pin<&mut Self>, c: &mut Context<'_>, buf: &[u8]) -> Poll<tokio::io::Result<usize>> {
+        ready!(self.poll_drain(c))?;
+        self.encoder().poll_write(c, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        ready!(self.encoder().poll_flush(c))?;
+        ready!(self.poll_drain(c))?;
+        Pin::new(&mut self.inner).poll_flush(c)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        ready!(self.encoder().poll_shutdown(c))?;
+        ready!(self.poll_drain(c))?;
+        Pin::new(&mut self.inner).poll_shutdown(c)
+    }
+}
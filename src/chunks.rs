@@ -0,0 +1,41 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! An adapter which reads from an [`AsyncRead`] in [`Bytes`] chunks, as yielded by the crate's `futures` streams.
+
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// A reader whose data is read in chunks of at most a fixed size, each split from a shared buffer.
+pub(crate) struct Chunks<R> {
+    reader: R,
+    chunk_size: usize,
+    buffer: BytesMut,
+}
+
+impl<R: AsyncRead + Unpin> Chunks<R> {
+    pub(crate) fn new(reader: R, chunk_size: usize) -> Self {
+        Chunks { reader, chunk_size: chunk_size.max(1), buffer: BytesMut::new() }
+    }
+
+    /// Reads the next chunk, or returns `None` once the reader has ended.
+    pub(crate) fn poll_chunk(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<Option<Bytes>>> {
+        // Chunks are split from the front of the buffer, and later reads fill what remains of it. So, the buffer is
+        // only zero-filled once it has been used up, when it reclaims its allocation if every chunk split from it has
+        // been dropped.
+        if self.buffer.is_empty() {
+            self.buffer.resize(self.chunk_size, 0);
+        }
+
+        let mut buf = ReadBuf::new(&mut self.buffer);
+        ready!(Pin::new(&mut self.reader).poll_read(cx, &mut buf))?;
+
+        match buf.filled().len() {
+            0 => Poll::Ready(Ok(None)),
+            read => Poll::Ready(Ok(Some(self.buffer.split_to(read).freeze()))),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Support for sources and destinations which implement the `futures-io` traits rather than tokio's.
+//!
+//! The readers and writers of this crate are built upon tokio's I/O traits, but never depend upon the tokio runtime
+//! (except for the path-based constructors). With the `futures-io` feature enabled, they can be constructed directly
+//! from `futures-io` types (eg. via [`seek::ZipFileReader::from_futures_io()`]), and entry readers and stream writers
+//! also implement the `futures-io` traits. This allows the crate to be used on any executor.
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::read::seek::ZipFileReader;
+//! # use async_zip::error::ZipError;
+//! #
+//! # async fn run(file: impl futures_io::AsyncRead + futures_io::AsyncSeek + Unpin) -> Result<(), ZipError> {
+//! let mut zip = ZipFileReader::from_futures_io(file).await?;
+//! let reader = zip.entry_reader(0).await?;
+//! #   Ok(())
+//! # }
+//! ```
+//!
+//! [`seek::ZipFileReader::from_futures_io()`]: crate::read::seek::ZipFileReader::from_futures_io
+
+use crate::read::ZipEntryReader;
+use crate::write::EntryStreamWriter;
+
+use std::io::SeekFrom;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::ReadBuf;
+
+/// A wrapper which implements tokio's I/O traits for a type implementing the `futures-io` traits.
+#[derive(Debug)]
+pub struct Compat<T> {
+    inner: T,
+    seek: Option<SeekFrom>,
+}
+
+impl<T> Compat<T> {
+    /// Wraps the provided reader or writer.
+    pub fn new(inner: T) -> Self {
+        Compat { inner, seek: None }
+    }
+
+    /// Returns a shared reference to the wrapped reader or writer.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped reader or writer.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes the wrapper and returns the wrapped reader or writer.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: futures_io::AsyncRead + Unpin> tokio::io::AsyncRead for Compat<T> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        let read = ready!(Pin::new(&mut self.inner).poll_read(c, b.initialize_unfilled()))?;
+        b.advance(read);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: futures_io::AsyncBufRead + Unpin> tokio::io::AsyncBufRead for Compat<T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<std::io::Result<&[u8]>> {
+        Pin::new(&mut self.get_mut().inner).poll_fill_buf(c)
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut self.inner).consume(amt)
+    }
+}
+
+impl<T: futures_io::AsyncSeek + Unpin> tokio::io::AsyncSeek for Compat<T> {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        // The futures-io trait seeks in a single call, so the seek is only started once it's polled for completion.
+        self.seek = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        let position = self.seek.unwrap_or(SeekFrom::Current(0));
+        let offset = ready!(Pin::new(&mut self.inner).poll_seek(c, position))?;
+
+        self.seek = None;
+        Poll::Ready(Ok(offset))
+    }
+}
+
+impl<T: futures_io::AsyncWrite + Unpin> tokio::io::AsyncWrite for Compat<T> {
+    fn poll_write(mut self: Pin<&mut Self>, c: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(c, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(c)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(c)
+    }
+}
+
+/// Reads into a futures-io buffer from a reader implementing tokio's trait.
+fn poll_read_slice<R: tokio::io::AsyncRead + Unpin>(
+    reader: &mut R,
+    c: &mut Context<'_>,
+    buf: &mut [u8],
+) -> Poll<std::io::Result<usize>> {
+    let mut buf = ReadBuf::new(buf);
+    ready!(Pin::new(reader).poll_read(c, &mut buf))?;
+    Poll::Ready(Ok(buf.filled().len()))
+}
+
+impl<'a, R: tokio::io::AsyncRead + Unpin> futures_io::AsyncRead for ZipEntryReader<'a, R> {
+    fn poll_read(self: Pin<&mut Self>, c: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+        poll_read_slice(self.get_mut(), c, buf)
+    }
+}
+
+impl<'b, W: tokio::io::AsyncWrite + Unpin> futures_io::AsyncWrite for EntryStreamWriter<'b, W> {
+    fn poll_write(self: Pin<&mut Self>, c: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        tokio::io::AsyncWrite::poll_write(self, c, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        tokio::io::AsyncWrite::poll_flush(self, c)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        tokio::io::AsyncWrite::poll_shutdown(self, c)
+    }
+}
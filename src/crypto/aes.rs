@@ -0,0 +1,110 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! WinZip's AES encryption scheme (AE-1 and AE-2).
+//!
+//! Keys are derived from the password and a per-entry salt via PBKDF2-HMAC-SHA1. Data is encrypted with AES in CTR
+//! mode (with a little-endian counter starting at one), and authenticated by an HMAC-SHA1 code over the encrypted data.
+
+use crate::spec::encryption::{AesStrength, AES_AUTH_CODE_LENGTH, AES_VERIFICATION_LENGTH};
+
+use aes::{Aes128, Aes192, Aes256};
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+/// The number of PBKDF2 iterations with which keys are derived from the password.
+const KEY_DERIVATION_ITERATIONS: u32 = 1000;
+
+type Ctr<C> = ctr::Ctr128LE<C>;
+
+#[derive(Clone)]
+enum AesCtr {
+    Aes128(Ctr<Aes128>),
+    Aes192(Ctr<Aes192>),
+    Aes256(Ctr<Aes256>),
+}
+
+impl AesCtr {
+    fn apply_keystream(&mut self, bytes: &mut [u8]) {
+        match self {
+            AesCtr::Aes128(ctr) => ctr.apply_keystream(bytes),
+            AesCtr::Aes192(ctr) => ctr.apply_keystream(bytes),
+            AesCtr::Aes256(ctr) => ctr.apply_keystream(bytes),
+        }
+    }
+}
+
+/// The state of the cipher and authentication code of a single AES-encrypted entry.
+#[derive(Clone)]
+pub(crate) struct AesCipher {
+    ctr: AesCtr,
+    hmac: Hmac<Sha1>,
+}
+
+impl AesCipher {
+    /// Derives the cipher's keys from the password and salt, returning them along with the password verification
+    /// value.
+    pub(crate) fn new(password: &[u8], strength: AesStrength, salt: &[u8]) -> (Self, [u8; AES_VERIFICATION_LENGTH]) {
+        let key_length = strength.key_length();
+        let mut keys = [0; 32 * 2 + AES_VERIFICATION_LENGTH];
+        let keys = &mut keys[..key_length * 2 + AES_VERIFICATION_LENGTH];
+        pbkdf2::pbkdf2_hmac::<Sha1>(password, salt, KEY_DERIVATION_ITERATIONS, keys);
+
+        let (cipher_key, keys) = keys.split_at(key_length);
+        let (mac_key, verification) = keys.split_at(key_length);
+
+        let iv = 1u128.to_le_bytes().into();
+        let ctr = match strength {
+            AesStrength::Aes128 => AesCtr::Aes128(Ctr::new(cipher_key.into(), &iv)),
+            AesStrength::Aes192 => AesCtr::Aes192(Ctr::new(cipher_key.into(), &iv)),
+            AesStrength::Aes256 => AesCtr::Aes256(Ctr::new(cipher_key.into(), &iv)),
+        };
+        let hmac = Hmac::new_from_slice(mac_key).expect("HMAC keys may be of any length");
+
+        (AesCipher { ctr, hmac }, verification.try_into().unwrap())
+    }
+
+    /// Derives the cipher's keys from the password and a randomly generated salt, returning them along with the
+    /// encryption header which precedes an entry's data (the salt followed by the password verification value).
+    pub(crate) fn random(password: &[u8], strength: AesStrength) -> std::io::Result<(Self, Vec<u8>)> {
+        let mut header = vec![0; strength.salt_length()];
+        getrandom::fill(&mut header)?;
+
+        let (cipher, verification) = AesCipher::new(password, strength, &header);
+        header.extend_from_slice(&verification);
+        Ok((cipher, header))
+    }
+
+    /// Derives the cipher's keys from the password and the salt at the start of an entry's encryption header,
+    /// returning them only if the verification value which follows the salt matches the password.
+    pub(crate) fn from_header(password: &[u8], strength: AesStrength, header: &[u8]) -> Option<Self> {
+        let (salt, verification) = header.split_at(strength.salt_length());
+        let (cipher, expected) = AesCipher::new(password, strength, salt);
+
+        (verification == expected).then_some(cipher)
+    }
+
+    /// Decrypts the provided bytes in place.
+    pub(crate) fn decrypt(&mut self, bytes: &mut [u8]) {
+        self.hmac.update(bytes);
+        self.ctr.apply_keystream(bytes);
+    }
+
+    /// Encrypts the provided bytes in place.
+    pub(crate) fn encrypt(&mut self, bytes: &mut [u8]) {
+        self.ctr.apply_keystream(bytes);
+        self.hmac.update(bytes);
+    }
+
+    /// Returns the authentication code which follows an entry's data, over all of the data encrypted.
+    pub(crate) fn authentication_code(self) -> [u8; AES_AUTH_CODE_LENGTH] {
+        let code = self.hmac.finalize().into_bytes();
+        code[..AES_AUTH_CODE_LENGTH].try_into().unwrap()
+    }
+
+    /// Returns whether the authentication code which follows an entry's data matches all of the data decrypted.
+    pub(crate) fn verify(&self, code: &[u8]) -> bool {
+        code.len() == AES_AUTH_CODE_LENGTH && self.hmac.clone().verify_truncated_left(code).is_ok()
+    }
+}
@@ -0,0 +1,30 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! The ciphers with which entries' data may be encrypted.
+
+#[cfg(feature = "aes")]
+pub(crate) mod aes;
+pub(crate) mod zipcrypto;
+
+use std::sync::Arc;
+
+/// A password used to encrypt or decrypt entries, whose bytes are kept out of debug output.
+#[derive(Clone)]
+pub(crate) struct Password(Arc<[u8]>);
+
+impl Password {
+    pub(crate) fn new(password: &[u8]) -> Self {
+        Password(password.into())
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Password {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Password(..)")
+    }
+}
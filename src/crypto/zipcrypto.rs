@@ -0,0 +1,89 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! The traditional PKWARE encryption scheme (ZipCrypto).
+//!
+//! The scheme is weak by modern standards, but remains the default of many archivers (eg. Info-ZIP's `zip -P`) when
+//! a password is provided.
+
+/// The length of the encryption header which precedes the data of each encrypted entry.
+pub(crate) const ZIPCRYPTO_HEADER_LENGTH: usize = 12;
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+/// Builds the lookup table of the (uninverted) CRC-32 function used to update the cipher's keys.
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+
+    while index < 256 {
+        let mut value = index as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            value = match value & 1 {
+                1 => (value >> 1) ^ 0xEDB88320,
+                _ => value >> 1,
+            };
+            bit += 1;
+        }
+
+        table[index] = value;
+        index += 1;
+    }
+
+    table
+}
+
+fn crc32_update(crc: u32, byte: u8) -> u32 {
+    (crc >> 8) ^ CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize]
+}
+
+/// The three keys which make up the state of the ZipCrypto stream cipher.
+#[derive(Clone, Copy)]
+pub(crate) struct ZipCryptoKeys([u32; 3]);
+
+impl ZipCryptoKeys {
+    /// Initialises the keys with the provided password.
+    pub(crate) fn new(password: &[u8]) -> Self {
+        let mut keys = ZipCryptoKeys([0x12345678, 0x23456789, 0x34567890]);
+        password.iter().for_each(|byte| keys.update(*byte));
+        keys
+    }
+
+    /// Initialises the keys with the provided password and decrypts an entry's encryption header with them.
+    ///
+    /// The final byte of the header should match the provided check byte (the high byte of the entry's CRC32 value, or
+    /// of its last modification time if its CRC32 value wasn't known when it was written). A mismatch almost always
+    /// means that the password was incorrect, though one in every 256 incorrect passwords will pass this check.
+    pub(crate) fn from_header(password: &[u8], header: &[u8], check: u8) -> Option<Self> {
+        let mut keys = ZipCryptoKeys::new(password);
+        let mut header = header.to_vec();
+        keys.decrypt(&mut header);
+
+        (header.last() == Some(&check)).then_some(keys)
+    }
+
+    /// Updates the keys with the next byte of plaintext.
+    fn update(&mut self, byte: u8) {
+        let [key0, key1, key2] = &mut self.0;
+
+        *key0 = crc32_update(*key0, byte);
+        *key1 = key1.wrapping_add(*key0 & 0xFF).wrapping_mul(134775813).wrapping_add(1);
+        *key2 = crc32_update(*key2, (*key1 >> 24) as u8);
+    }
+
+    /// Returns the byte of keystream which the next byte of data is combined with.
+    fn stream_byte(&self) -> u8 {
+        let temp = (self.0[2] | 2) & 0xFFFF;
+        (temp.wrapping_mul(temp ^ 1) >> 8) as u8
+    }
+
+    /// Decrypts the provided bytes in place.
+    pub(crate) fn decrypt(&mut self, bytes: &mut [u8]) {
+        for byte in bytes {
+            *byte ^= self.stream_byte();
+            self.update(*byte);
+        }
+    }
+}
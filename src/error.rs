@@ -21,6 +21,70 @@ pub enum ZipError {
     FeatureNotSupported(&'static str),
     #[error("A computed CRC32 value did not match the expected value.")]
     CRC32CheckError,
+    #[error("An entry's computed CRC32 value ({actual:#x}) didn't match its expected value ({expected:#x}).")]
+    CrcMismatch { expected: u32, actual: u32 },
     #[error("Entry index was out of bounds.")]
     EntryIndexOutOfBounds,
+    #[error("No entry named '{0}' exists within the archive.")]
+    EntryNotFound(String),
+    #[error("An entry's uncompressed size ({0} bytes) exceeded the limit of {1} bytes.")]
+    EntryTooLarge(u64, u64),
+    #[error("An entry's declared size ({0} bytes) didn't match the size of its data ({1} bytes).")]
+    DeclaredSizeMismatch(u64, u64),
+    #[error("An entry's data exceeded its declared uncompressed size.")]
+    UncompressedSizeExceeded,
+    #[error("A field's length ({0} bytes) exceeded the maximum of 65535 bytes.")]
+    FieldTooLong(usize),
+    #[error("An entry is still open; close it or call rollback_open_entry() first.")]
+    EntryStillOpen,
+    #[error("The writer can no longer produce a consistent archive after an error from its inner writer.")]
+    WriterPoisoned,
+    #[error("An entry was larger than the maximum shard size.")]
+    ShardSizeExceeded,
+    #[error("Nested archives exceeded the maximum depth of {0}.")]
+    NestingDepthExceeded(usize),
+    #[error("An I/O error occurred for the file at {0:?}: '{1:?}'.")]
+    PathError(std::path::PathBuf, std::io::Error),
+    #[error("The entry's data extends beyond the end of the recovered archive.")]
+    PartialEntry,
+    #[error("No end of central directory header could be found at the end of the archive.")]
+    EndOfCentralDirectoryNotFound,
+    #[error("Decompression was aborted as the {0} exceeded its limit of {1}.")]
+    DecompressionLimitExceeded(&'static str, u64),
+    #[error("The {0} declared by a header lies outside of the archive.")]
+    HeaderOutOfBounds(&'static str),
+    #[error("The data of the entries '{0}' and '{1}' overlap within the archive.")]
+    OverlappingEntries(String, String),
+    #[error("An MS-DOS date ({0:#x}) and time ({1:#x}) pair didn't represent a valid date and time.")]
+    InvalidDateTime(u16, u16),
+    #[error("A Zip64 extended information extra field was missing values for saturated header fields.")]
+    Zip64ExtraFieldIncomplete,
+    #[error("The extra data field with header ID {0:#x} was malformed.")]
+    InvalidExtraField(u16),
+    #[error("An entry's data descriptor couldn't be found or didn't match its data.")]
+    InvalidDataDescriptor,
+    #[error("The archive ended part-way through an entry.")]
+    Truncated,
+    #[error("An entry is encrypted but no password was provided.")]
+    PasswordRequired,
+    #[error("The password provided was incorrect for an encrypted entry.")]
+    IncorrectPassword,
+    #[error("The authentication code of an AES-encrypted entry didn't match its data.")]
+    AuthenticationCodeMismatch,
+    #[error("The ZIP sink was closed, or an item was sent before the sink was ready.")]
+    SinkUnavailable,
+    #[error("The archive was written shorter than the data it overwrote, and must be truncated to {0} bytes.")]
+    TruncationRequired(u64),
+    #[error("The entry named '{0}' would be extracted outside of the destination directory.")]
+    UnsafeEntryPath(String),
+    #[error("The symbolic link entry named '{0}' has a target outside of the destination directory.")]
+    UnsafeSymlinkTarget(String),
+    #[error("The glob pattern '{0}' holds a set which is never closed.")]
+    InvalidGlob(String),
+    #[error("An entry exceeded 4 GiB, but its local file header was written without room for Zip64 sizes.")]
+    Zip64NotReserved,
+    #[error("A comment's encoding conflicted with the one declared by its entry's local file header.")]
+    CommentEncodingMismatch,
+    #[error("The local file header of the entry '{0}' conflicted with its central directory record.")]
+    LocalHeaderMismatch(String),
 }
@@ -0,0 +1,419 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module of helpers for moving entries between ZIP files and the filesystem.
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::read::seek::ZipFileReader;
+//! # use async_zip::error::ZipError;
+//! #
+//! # async fn run() -> Result<(), ZipError> {
+//! let mut zip = ZipFileReader::open("./Archive.zip").await?;
+//! async_zip::fs::extract(&mut zip, "./output").await?;
+//! #   Ok(())
+//! # }
+//! ```
+//! ```no_run
+//! # use async_zip::fs::DirOptions;
+//! # use async_zip::write::ZipFileWriter;
+//! # use async_zip::error::ZipError;
+//! #
+//! # async fn run() -> Result<(), ZipError> {
+//! let mut writer = ZipFileWriter::create("./Archive.zip").await?;
+//! async_zip::fs::write_dir(&mut writer, "./input", DirOptions::new()).await?;
+//! writer.close().await?;
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::error::{Result, ZipError};
+use crate::read::seek::ZipFileReader;
+use crate::spec::compression::Compression;
+use crate::spec::string::{StringEncoding, ZipString};
+use crate::utils::path_error;
+use crate::write::entry_whole::EntryWholeWriter;
+use crate::write::{CompressionLevel, EntryOptions, EntryStreamWriter, ZipFileWriter};
+
+use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
+use std::fs::Metadata;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite, AsyncWriteExt};
+
+/// The size of the buffer through which each entry's data is copied into its file.
+const EXTRACT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// The policy used to extract entries which represent symbolic links (see [`ZipEntry::is_symlink()`]).
+///
+/// [`ZipEntry::is_symlink()`]: crate::read::ZipEntry::is_symlink
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymlinkPolicy {
+    /// Extract each link as a regular file holding its target path.
+    #[default]
+    Materialize,
+    /// Don't extract links at all.
+    Skip,
+    /// Create each link as a symbolic link on Unix, or as a regular file holding its target path elsewhere.
+    ///
+    /// Links are only created once every other entry has been extracted, so that nothing is ever written through
+    /// one. Each link's target must be a relative path which stays within the destination directory, and no entry's
+    /// path (nor any link's target) may pass through a link, otherwise [`ZipError::UnsafeSymlinkTarget`] or
+    /// [`ZipError::UnsafeEntryPath`] is returned.
+    Create,
+}
+
+/// A set of options for extracting a ZIP file via [`extract_with_options()`].
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    symlinks: SymlinkPolicy,
+}
+
+impl ExtractOptions {
+    /// Construct a new set of options with their default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Consume the options and override the policy used to extract symbolic links (defaults to
+    /// [`SymlinkPolicy::Materialize`]).
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+}
+
+/// Extracts every entry of a ZIP file into the provided destination directory (which is created if it doesn't exist).
+///
+/// Directory entries are created as directories, and the parent directories of every file are created as needed.
+/// Existing files are overwritten. Each entry's CRC32 value is checked once its data has been written. On Unix, the
+/// permissions of files are restored from their entries (see [`ZipEntry::permissions()`]), if they have any. Symbolic
+/// links are extracted as regular files holding their target paths, unless another [`SymlinkPolicy`] is chosen via
+/// [`extract_with_options()`].
+///
+/// Before anything is extracted, every entry's name is checked to be a relative path which stays within the
+/// destination directory (see [`ZipEntry::enclosed_name()`]). If any isn't, [`ZipError::UnsafeEntryPath`] is
+/// returned. Entries are read in order (see [`ZipFileReader::sequential()`]), so are prefetched if enabled.
+///
+/// [`ZipEntry::permissions()`]: crate::read::ZipEntry::permissions
+/// [`ZipEntry::enclosed_name()`]: crate::read::ZipEntry::enclosed_name
+pub async fn extract<R>(reader: &mut ZipFileReader<R>, dest_dir: impl AsRef<Path>) -> Result<()>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    extract_with_options(reader, dest_dir, ExtractOptions::default()).await
+}
+
+/// Extracts every entry of a ZIP file into the provided destination directory, with a set of options.
+///
+/// See [`extract()`] for details.
+pub async fn extract_with_options<R>(
+    reader: &mut ZipFileReader<R>,
+    dest_dir: impl AsRef<Path>,
+    options: ExtractOptions,
+) -> Result<()>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    let dest_dir = dest_dir.as_ref();
+    let paths = reader
+        .entries()
+        .iter()
+        .map(|entry| entry.enclosed_name().ok_or_else(|| ZipError::UnsafeEntryPath(entry.name().to_string())))
+        .collect::<Result<Vec<_>>>()?;
+
+    let create_links = cfg!(unix) && options.symlinks == SymlinkPolicy::Create;
+    let links = match create_links {
+        true => link_paths(reader, &paths)?,
+        false => HashSet::new(),
+    };
+    let mut pending_links = Vec::new();
+
+    tokio::fs::create_dir_all(dest_dir).await.map_err(path_error(dest_dir))?;
+    let mut entries = reader.sequential();
+
+    for relative_path in paths {
+        let entry_reader = entries.next_entry().await?.expect("an entry exists for each path");
+        let path = dest_dir.join(&relative_path);
+
+        if entry_reader.entry().is_symlink() {
+            match options.symlinks {
+                SymlinkPolicy::Skip => continue,
+                SymlinkPolicy::Create if create_links => {
+                    let name = entry_reader.entry().name().to_string();
+                    let target = entry_reader.read_symlink_target().await?;
+                    let mut parent = Vec::new();
+                    walk_enclosed(&mut parent, &relative_path, &HashSet::new());
+                    parent.pop();
+
+                    if !walk_enclosed(&mut parent, &target, &links) {
+                        return Err(ZipError::UnsafeSymlinkTarget(name));
+                    }
+                    pending_links.push((path, target));
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        // Only the permission bits are restored (not the setuid, setgid, or sticky bits).
+        #[cfg(unix)]
+        let permissions = entry_reader.entry().permissions().map(|permissions| permissions & 0o777);
+
+        if entry_reader.entry().dir() {
+            tokio::fs::create_dir_all(&path).await.map_err(path_error(&path))?;
+            continue;
+        }
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(path_error(parent))?;
+        }
+
+        let mut file = File::create(&path).await.map_err(path_error(&path))?;
+        entry_reader.copy_to_end_crc(&mut file, EXTRACT_BUFFER_SIZE).await?;
+        file.flush().await.map_err(path_error(&path))?;
+
+        #[cfg(unix)]
+        if let Some(permissions) = permissions {
+            use std::os::unix::fs::PermissionsExt;
+
+            let permissions = std::fs::Permissions::from_mode(permissions);
+            tokio::fs::set_permissions(&path, permissions).await.map_err(path_error(&path))?;
+        }
+    }
+
+    #[cfg(unix)]
+    for (path, target) in pending_links {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(path_error(parent))?;
+        }
+        tokio::fs::symlink(&target, &path).await.map_err(path_error(&path))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the (lexically resolved) paths of every symbolic link entry, checking that no entry's path passes through
+/// one of them.
+fn link_paths<R>(reader: &ZipFileReader<R>, paths: &[PathBuf]) -> Result<HashSet<Vec<OsString>>>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    let mut links = HashSet::new();
+    for (entry, path) in reader.entries().iter().zip(paths) {
+        if entry.is_symlink() {
+            let mut link = Vec::new();
+            walk_enclosed(&mut link, path, &HashSet::new());
+            links.insert(link);
+        }
+    }
+
+    for (entry, path) in reader.entries().iter().zip(paths) {
+        if !walk_enclosed(&mut Vec::new(), path, &links) {
+            return Err(ZipError::UnsafeEntryPath(entry.name().to_string()));
+        }
+    }
+
+    Ok(links)
+}
+
+/// Lexically resolves a relative path from the provided components, returning whether it stays within the
+/// destination directory without passing through any of the provided links.
+fn walk_enclosed(current: &mut Vec<OsString>, path: &Path, links: &HashSet<Vec<OsString>>) -> bool {
+    for component in path.components() {
+        if !current.is_empty() && links.contains(current) {
+            return false;
+        }
+
+        match component {
+            Component::Prefix(_) | Component::RootDir => return false,
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if current.pop().is_none() {
+                    return false;
+                }
+            }
+            Component::Normal(component) => current.push(component.to_os_string()),
+        }
+    }
+
+    true
+}
+
+/// A set of options for writing a directory tree via [`write_dir()`].
+#[derive(Debug, Clone)]
+pub struct DirOptions {
+    compression: Option<Compression>,
+    level: Option<CompressionLevel>,
+    prefix: String,
+    preserve_metadata: bool,
+}
+
+impl Default for DirOptions {
+    fn default() -> Self {
+        DirOptions { compression: None, level: None, prefix: String::new(), preserve_metadata: true }
+    }
+}
+
+impl DirOptions {
+    /// Construct a new set of options with their default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Consume the options and override the compression method of files (defaults to the writer's default, or the
+    /// method chosen by its [`EntryDefaults::compression_policy`] if set).
+    ///
+    /// [`EntryDefaults::compression_policy`]: crate::write::EntryDefaults::compression_policy
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Consume the options and override the level of compression of files (defaults to the writer's default).
+    pub fn level(mut self, level: CompressionLevel) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Consume the options and override the directory within the archive which entries are written into (eg.
+    /// `"assets"`), which is the archive's root by default.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Consume the options and override whether each entry takes its file's modification date and (on Unix) its
+    /// permissions, rather than the writer's defaults (true by default).
+    pub fn preserve_metadata(mut self, preserve: bool) -> Self {
+        self.preserve_metadata = preserve;
+        self
+    }
+}
+
+/// Writes every file and directory within the provided directory into a ZIP file, walking the tree asynchronously.
+///
+/// Entries are named by their path relative to the directory (with `/` separators), and are written in a stable
+/// order (by name, with each directory's entry preceding its contents). File contents are streamed rather than read
+/// into memory. Symbolic links to files are followed, whereas those to directories are skipped to avoid cycles.
+///
+/// A manifest of the entries written is returned in the same order, naming each alongside the compression method it
+/// was written with (eg. as chosen by the writer's [`EntryDefaults::compression_policy`]).
+///
+/// [`EntryDefaults::compression_policy`]: crate::write::EntryDefaults::compression_policy
+pub async fn write_dir<W>(
+    writer: &mut ZipFileWriter<W>,
+    path: impl AsRef<Path>,
+    options: DirOptions,
+) -> Result<Vec<(String, Compression)>>
+where
+    W: AsyncWrite + Unpin,
+{
+    let prefix = options.prefix.trim_matches('/').as_bytes();
+    let mut manifest = Vec::new();
+    let mut pending = Vec::new();
+    push_children(&mut pending, path.as_ref(), prefix).await?;
+
+    while let Some((path, mut name)) = pending.pop() {
+        let mut metadata = tokio::fs::symlink_metadata(&path).await.map_err(path_error(&path))?;
+        if metadata.is_symlink() {
+            metadata = tokio::fs::metadata(&path).await.map_err(path_error(&path))?;
+            if metadata.is_dir() {
+                continue;
+            }
+        }
+
+        if metadata.is_dir() {
+            push_children(&mut pending, &path, &name).await?;
+            name.push(b'/');
+
+            let entry_options = dir_entry_options(writer, &options, name, &metadata, Some(Compression::Stored))?;
+            manifest.push((entry_options.filename.as_str().to_owned(), entry_options.compression));
+            EntryWholeWriter::from_raw(writer, entry_options, &[]).write().await?;
+        } else {
+            let entry_options = dir_entry_options(writer, &options, name, &metadata, options.compression)?;
+            manifest.push((entry_options.filename.as_str().to_owned(), entry_options.compression));
+
+            let mut file = File::open(&path).await.map_err(path_error(&path))?;
+            let mut entry_writer = EntryStreamWriter::from_raw(writer, entry_options, None).await?;
+            tokio::io::copy(&mut file, &mut entry_writer).await?;
+            entry_writer.close().await?;
+        }
+    }
+
+    Ok(manifest)
+}
+
+/// Queues the children of a directory (in reverse order by name, as they're popped off the end) along with their
+/// entry names.
+async fn push_children(pending: &mut Vec<(PathBuf, Vec<u8>)>, dir: &Path, name: &[u8]) -> Result<()> {
+    let mut read_dir = tokio::fs::read_dir(dir).await.map_err(path_error(dir))?;
+    let mut children = Vec::new();
+
+    while let Some(child) = read_dir.next_entry().await.map_err(path_error(dir))? {
+        let mut child_name = name.to_vec();
+        if !child_name.is_empty() {
+            child_name.push(b'/');
+        }
+        child_name.extend_from_slice(&name_bytes(&child.file_name()));
+        children.push((child.path(), child_name));
+    }
+
+    children.sort_by(|(_, a), (_, b)| b.cmp(a));
+    pending.extend(children);
+    Ok(())
+}
+
+/// Resolves the options of an entry written from the filesystem, taking its file's metadata if it's to be preserved.
+fn dir_entry_options<W: AsyncWrite + Unpin>(
+    writer: &ZipFileWriter<W>,
+    options: &DirOptions,
+    name: Vec<u8>,
+    metadata: &Metadata,
+    compression: Option<Compression>,
+) -> Result<crate::write::defaults::ResolvedOptions> {
+    let encoding = match std::str::from_utf8(&name) {
+        Ok(_) => StringEncoding::Utf8,
+        Err(_) => StringEncoding::Unknown,
+    };
+
+    let mut entry_options = EntryOptions::named(ZipString::new(name, encoding));
+    if let Some(compression) = compression {
+        entry_options = entry_options.compression(compression);
+    }
+    if let Some(level) = options.level {
+        entry_options = entry_options.level(level);
+    }
+
+    let mut resolved = entry_options.resolve(&writer.defaults)?;
+    if options.preserve_metadata {
+        if let Ok(modified) = metadata.modified() {
+            resolved.last_modified = DateTime::<Utc>::from(modified);
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            resolved.permissions = Some(metadata.permissions().mode() as u16);
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Returns the bytes of a file name (which are exact on Unix, and otherwise lossy if it isn't valid Unicode).
+fn name_bytes(name: &OsStr) -> Vec<u8> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        name.as_bytes().to_vec()
+    }
+
+    #[cfg(not(unix))]
+    {
+        name.to_string_lossy().into_owned().into_bytes()
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module which supports matching entry names against glob patterns (eg. `assets/**/*.png`).
+//!
+//! Patterns are matched against whole names, one `/`-separated component at a time:
+//! - `?` matches any single character.
+//! - `*` matches any number of characters.
+//! - `[abc]`, `[a-z]`, and `[!a-z]` match any single character of (or not of) a set.
+//! - `**`, as a whole component, matches any number of components (including none).
+//!
+//! No wildcard ever matches a `/`, and a trailing `/` (as held by the names of directory entries) is ignored.
+//! Special characters may be matched literally via a set (eg. `[*]`).
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::read::seek::ZipFileReader;
+//! # use async_zip::error::ZipError;
+//! # use tokio::fs::File;
+//! #
+//! # async fn run() -> Result<(), ZipError> {
+//! let mut zip = ZipFileReader::new(File::open("./Archive.zip").await?).await?;
+//! let indexes: Vec<usize> = zip.entries_matching("assets/**/*.png")?.map(|(index, _)| index).collect();
+//!
+//! for index in indexes {
+//!     let data = zip.entry_reader(index).await?.read_to_end_crc().await?;
+//! }
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::error::{Result, ZipError};
+
+/// A compiled glob pattern which entry names can be matched against.
+///
+/// See the [module-level documentation](self) for the supported syntax. Patterns may also be used to skip entries of a
+/// stream reader, via [`ZipFileReader::skip_to_matching()`].
+///
+/// [`ZipFileReader::skip_to_matching()`]: crate::read::stream::ZipFileReader::skip_to_matching
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    components: Vec<Component>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Component {
+    Globstar,
+    Tokens(Vec<Token>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    Any,
+    Star,
+    Set { negated: bool, ranges: Vec<(char, char)> },
+}
+
+impl Token {
+    fn matches(&self, character: char) -> bool {
+        match self {
+            Token::Literal(literal) => *literal == character,
+            Token::Any | Token::Star => true,
+            Token::Set { negated, ranges } => {
+                ranges.iter().any(|(start, end)| (*start..=*end).contains(&character)) != *negated
+            }
+        }
+    }
+}
+
+impl Glob {
+    /// Compiles a glob pattern, failing with [`ZipError::InvalidGlob`] if a set is never closed.
+    pub fn new(pattern: &str) -> Result<Glob> {
+        let pattern = pattern.strip_suffix('/').unwrap_or(pattern);
+        let components = pattern.split('/').map(|component| match component {
+            "**" => Some(Component::Globstar),
+            component => parse_component(component).map(Component::Tokens),
+        });
+        let components = components.collect::<Option<_>>().ok_or_else(|| ZipError::InvalidGlob(pattern.to_string()))?;
+
+        Ok(Glob { components })
+    }
+
+    /// Returns whether or not the provided entry name matches this pattern.
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.strip_suffix('/').unwrap_or(name);
+        match_components(&self.components, &name.split('/').collect::<Vec<_>>())
+    }
+}
+
+fn parse_component(component: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut characters = component.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        let token = match character {
+            '?' => Token::Any,
+            // Consecutive stars within a component match the same as a single star.
+            '*' if tokens.last() == Some(&Token::Star) => continue,
+            '*' => Token::Star,
+            '[' => {
+                let negated = characters.next_if(|next| *next == '!' || *next == '^').is_some();
+                let mut ranges = Vec::new();
+
+                // A closing bracket immediately after the opening bracket is part of the set.
+                let mut start = characters.next()?;
+                loop {
+                    // A hyphen forms a range unless it's the last character of the set.
+                    let mut lookahead = characters.clone();
+                    let end = match (lookahead.next(), lookahead.next()) {
+                        (Some('-'), Some(end)) if end != ']' => {
+                            characters.nth(1);
+                            end
+                        }
+                        _ => start,
+                    };
+                    ranges.push((start, end));
+
+                    match characters.next()? {
+                        ']' => break,
+                        next => start = next,
+                    }
+                }
+
+                Token::Set { negated, ranges }
+            }
+            literal => Token::Literal(literal),
+        };
+        tokens.push(token);
+    }
+
+    Some(tokens)
+}
+
+fn match_components(pattern: &[Component], name: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((Component::Globstar, rest)) => (0..=name.len()).any(|skipped| match_components(rest, &name[skipped..])),
+        Some((Component::Tokens(tokens), rest)) => match name.split_first() {
+            Some((first, name)) => match_tokens(tokens, first) && match_components(rest, name),
+            None => false,
+        },
+    }
+}
+
+/// Matches a single component, backtracking to the most recent star whenever a character doesn't match.
+fn match_tokens(tokens: &[Token], name: &str) -> bool {
+    let name: Vec<char> = name.chars().collect();
+    let (mut token, mut character) = (0, 0);
+    let mut backtrack = None;
+
+    while character < name.len() {
+        match tokens.get(token) {
+            Some(Token::Star) => {
+                backtrack = Some((token, character));
+                token += 1;
+                continue;
+            }
+            Some(next) if next.matches(name[character]) => {
+                token += 1;
+                character += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        match backtrack {
+            Some((star, consumed)) => {
+                backtrack = Some((star, consumed + 1));
+                token = star + 1;
+                character = consumed + 1;
+            }
+            None => return false,
+        }
+    }
+
+    tokens[token..].iter().all(|token| *token == Token::Star)
+}
@@ -5,19 +5,36 @@
 //!
 //! ## Features
 //! - Asynchronous design powered by tokio.
-//! - Support for Stored, Deflate, bzip2, LZMA, zstd, and xz compression methods.
+//! - Support for Stored, Deflate, bzip2, LZMA, zstd, and xz compression methods (and reading Deflate64).
 //! - Various different reading approaches (seek, stream, filesystem, in-memory buffer).
 //! - Support for writing complete data (u8 slices) or stream writing using data descriptors.
 //! - Aims for reasonable [specification](https://pkware.cachefly.net/webdocs/casestudies/APPNOTE.TXT) compliance.
 //!
 //! [Read more.](https://github.com/Majored/rs-async-zip)
 
+pub mod backend;
+#[cfg(feature = "stream")]
+pub(crate) mod chunks;
+#[cfg(feature = "futures-io")]
+pub mod compat;
+pub(crate) mod crypto;
 pub mod error;
+pub mod fs;
+pub mod glob;
 pub mod read;
 pub(crate) mod spec;
 #[cfg(test)]
 pub(crate) mod tests;
+#[cfg(all(feature = "uring", target_os = "linux"))]
+pub mod uring;
 pub(crate) mod utils;
 pub mod write;
+#[cfg(feature = "zstd")]
+pub(crate) mod zstd_dictionary;
 
 pub use crate::spec::compression::Compression;
+pub use crate::spec::date::NtfsTimestamps;
+pub use crate::spec::encryption::{AesStrength, Encryption};
+pub use crate::spec::extra::ExtraField;
+pub use crate::spec::string::{StringEncoding, ZipString};
+pub use crate::spec::version::CreatorOs;
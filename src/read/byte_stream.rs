@@ -0,0 +1,51 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! An adapter which yields the data of an entry from the `futures` [`Stream`] interface, as [`Bytes`] chunks.
+
+use crate::chunks::Chunks;
+use crate::read::ZipEntryReader;
+
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use tokio::io::AsyncRead;
+
+/// A [`Stream`] which yields the data of an entry in chunks, for use with `hyper` bodies, `tonic` streams, and the
+/// like.
+///
+/// Data is validated as it's read (as with any other read from an entry reader), so an incorrect CRC32 value is
+/// yielded as an error in place of the end of the stream. The stream ends after yielding the first error.
+///
+/// This stream is constructed via [`ZipEntryReader::into_byte_stream()`].
+pub struct ByteStream<'a, R: AsyncRead + Unpin> {
+    chunks: Option<Chunks<ZipEntryReader<'a, R>>>,
+}
+
+impl<'a, R: AsyncRead + Unpin> ZipEntryReader<'a, R> {
+    /// Consumes this reader and returns a [`Stream`] over the entry's data, in chunks of at most `chunk_size` bytes.
+    ///
+    /// See [`ByteStream`] for details.
+    pub fn into_byte_stream(self, chunk_size: usize) -> ByteStream<'a, R> {
+        ByteStream { chunks: Some(Chunks::new(self, chunk_size)) }
+    }
+}
+
+impl<'a, R: AsyncRead + Unpin> Stream for ByteStream<'a, R> {
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let Some(chunks) = &mut this.chunks else {
+            return Poll::Ready(None);
+        };
+
+        let result = ready!(chunks.poll_chunk(cx));
+        if !matches!(result, Ok(Some(_))) {
+            this.chunks = None;
+        }
+        Poll::Ready(result.transpose())
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module for reading ZIP file entries concurrently from an in-memory buffer held as [`Bytes`].
+//!
+//! Unlike the [`mem`](crate::read::mem) reader, this reader borrows nothing; it's cheaply cloneable and
+//! `Send + Sync + 'static`, and its entry readers own a reference-counted handle to the buffer. As such, an archive
+//! held in memory (eg. by a web server) may be shared between many tasks which each read its entries without locking.
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::read::bytes::ZipFileReader;
+//! # use async_zip::error::ZipError;
+//! # use bytes::Bytes;
+//! #
+//! # async fn run(data: Bytes) -> Result<(), ZipError> {
+//! let zip = ZipFileReader::new(data).await?;
+//!
+//! let mut handles = Vec::new();
+//! for index in 0..zip.entries().len() {
+//!     let zip = zip.clone();
+//!     handles.push(tokio::spawn(async move { zip.entry_reader(index).await?.read_to_end_crc().await }));
+//! }
+//!
+//! for handle in handles {
+//!     let data = handle.await.unwrap()?;
+//! }
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::error::{Result, ZipError};
+use crate::read::{CompressionReader, EntryIndex, OwnedEntryReader, ReaderOptions, ZipEntry, ZipEntryReader};
+
+use std::io::Cursor;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use tokio::io::AsyncReadExt;
+
+/// The type returned as an entry reader within this concurrent module.
+pub type ConcurrentReader = OwnedEntryReader<Cursor<Bytes>>;
+
+/// A cloneable reader which acts concurrently over an in-memory buffer held as [`Bytes`].
+#[derive(Clone)]
+pub struct ZipFileReader {
+    pub(crate) data: Bytes,
+    pub(crate) entries: Arc<Vec<ZipEntry>>,
+    pub(crate) options: ReaderOptions,
+    pub(crate) index: Arc<EntryIndex>,
+}
+
+impl ZipFileReader {
+    /// Constructs a new ZIP file reader from an in-memory buffer.
+    pub async fn new(data: Bytes) -> Result<ZipFileReader> {
+        Self::with_options(data, ReaderOptions::default()).await
+    }
+
+    /// Constructs a new ZIP file reader from an in-memory buffer and a set of options.
+    pub async fn with_options(data: Bytes, options: ReaderOptions) -> Result<ZipFileReader> {
+        let mut cursor = Cursor::new(&data[..]);
+        let entries = crate::read::seek::read_cd(&mut cursor, options.parse_mode).await?;
+        crate::read::seek::validate_entries(&mut cursor, &entries, &options).await?;
+
+        let (entries, index) = (Arc::new(entries), Arc::new(EntryIndex::default()));
+        Ok(ZipFileReader { data, entries, options, index })
+    }
+
+    crate::read::reader_entry_impl!();
+
+    /// Returns a shared reference to the buffer this reader acts over.
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// Opens an entry at the provided index for reading.
+    ///
+    /// The returned reader owns a copy of the entry and a handle to the buffer, so it borrows nothing from this reader.
+    pub async fn entry_reader(&self, index: usize) -> Result<ConcurrentReader> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+
+        let mut cursor = Cursor::new(self.data.clone());
+        entry.seek_to_data_offset(&mut cursor).await?;
+
+        let reader = cursor.take(entry.compressed_size.unwrap());
+        let reader = CompressionReader::from_reader(entry, reader, &self.options)?;
+
+        Ok(ZipEntryReader::from_owned(entry.clone(), reader, &self.options))
+    }
+}
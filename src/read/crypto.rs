@@ -0,0 +1,323 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Support for decrypting entries' data before it's passed to their decoders.
+//!
+//! An encrypted entry's data is preceded by an encryption header which is used to verify the password, and may be
+//! followed by a trailer which authenticates the data. Neither is passed on to the decoder.
+
+#[cfg(feature = "aes")]
+use crate::crypto::aes::AesCipher;
+use crate::crypto::zipcrypto::{ZipCryptoKeys, ZIPCRYPTO_HEADER_LENGTH};
+use crate::crypto::Password;
+use crate::error::{Result, ZipError};
+use crate::read::{ReaderOptions, ZipEntry};
+use crate::spec::encryption::Encryption;
+#[cfg(feature = "aes")]
+use crate::spec::encryption::{AesStrength, AES_AUTH_CODE_LENGTH, AES_VERIFICATION_LENGTH};
+
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf, Take};
+
+/// The scheme an entry was encrypted with, along with everything needed to verify the password once the entry's
+/// encryption header has been read.
+enum Scheme {
+    ZipCrypto {
+        password: Password,
+        check: u8,
+    },
+    #[cfg(feature = "aes")]
+    Aes {
+        password: Password,
+        strength: AesStrength,
+    },
+}
+
+impl Scheme {
+    fn header_length(&self) -> usize {
+        match self {
+            Scheme::ZipCrypto { .. } => ZIPCRYPTO_HEADER_LENGTH,
+            #[cfg(feature = "aes")]
+            Scheme::Aes { strength, .. } => strength.salt_length() + AES_VERIFICATION_LENGTH,
+        }
+    }
+
+    fn trailer_length(&self) -> usize {
+        match self {
+            Scheme::ZipCrypto { .. } => 0,
+            #[cfg(feature = "aes")]
+            Scheme::Aes { .. } => AES_AUTH_CODE_LENGTH,
+        }
+    }
+
+    /// Constructs the cipher for an entry's data from its encryption header, verifying the password.
+    fn cipher(&self, header: &[u8]) -> tokio::io::Result<Cipher> {
+        let cipher = match self {
+            Scheme::ZipCrypto { password, check } => {
+                ZipCryptoKeys::from_header(password.as_bytes(), header, *check).map(Cipher::ZipCrypto)
+            }
+            #[cfg(feature = "aes")]
+            Scheme::Aes { password, strength } => AesCipher::from_header(password.as_bytes(), *strength, header)
+                .map(|cipher| Cipher::Aes(Box::new(cipher))),
+        };
+
+        cipher.ok_or_else(|| invalid(ZipError::IncorrectPassword))
+    }
+}
+
+#[derive(Clone)]
+enum Cipher {
+    ZipCrypto(ZipCryptoKeys),
+    #[cfg(feature = "aes")]
+    Aes(Box<AesCipher>),
+}
+
+impl Cipher {
+    fn decrypt(&mut self, bytes: &mut [u8]) {
+        match self {
+            Cipher::ZipCrypto(keys) => keys.decrypt(bytes),
+            #[cfg(feature = "aes")]
+            Cipher::Aes(cipher) => cipher.decrypt(bytes),
+        }
+    }
+
+    #[cfg_attr(not(feature = "aes"), allow(unused_variables))]
+    fn verify(&self, trailer: &[u8]) -> tokio::io::Result<()> {
+        match self {
+            Cipher::ZipCrypto(_) => Ok(()),
+            #[cfg(feature = "aes")]
+            Cipher::Aes(cipher) if cipher.verify(trailer) => Ok(()),
+            #[cfg(feature = "aes")]
+            Cipher::Aes(_) => Err(invalid(ZipError::AuthenticationCodeMismatch)),
+        }
+    }
+}
+
+/// The decryption state of a single encrypted entry.
+struct Decryption {
+    scheme: Scheme,
+    header: Vec<u8>,
+    cipher: Option<Cipher>,
+    trailer: Option<Vec<u8>>,
+    verified: bool,
+
+    // Bytes held within the inner reader's buffer which have been decrypted ahead of being consumed (buffered reads
+    // only), along with the state of the cipher once all of them have been.
+    plain: Vec<u8>,
+    plain_position: usize,
+    ahead: Option<Cipher>,
+}
+
+impl Decryption {
+    /// Reads the encryption header and verifies the password, if that hasn't already been done.
+    fn poll_header<R: AsyncRead + Unpin>(
+        &mut self,
+        reader: &mut Take<R>,
+        c: &mut Context<'_>,
+    ) -> Poll<tokio::io::Result<()>> {
+        if self.cipher.is_none() {
+            ready!(poll_read_exact(reader, c, &mut self.header, self.scheme.header_length()))?;
+            self.cipher = Some(self.scheme.cipher(&self.header)?);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    /// Reads the trailer which follows the entry's data and verifies it, once the data has been read to its end.
+    fn poll_trailer<R: AsyncRead + Unpin>(
+        &mut self,
+        reader: &mut Take<R>,
+        c: &mut Context<'_>,
+    ) -> Poll<tokio::io::Result<()>> {
+        let length = self.scheme.trailer_length();
+        if self.verified || length == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let trailer = self.trailer.get_or_insert_with(|| {
+            reader.set_limit(length as u64);
+            Vec::with_capacity(length)
+        });
+        ready!(poll_read_exact(reader, c, trailer, length))?;
+
+        self.cipher.as_ref().unwrap().verify(trailer)?;
+        self.verified = true;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Reads from the reader until the provided vector holds the provided number of bytes.
+fn poll_read_exact<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    c: &mut Context<'_>,
+    bytes: &mut Vec<u8>,
+    length: usize,
+) -> Poll<tokio::io::Result<()>> {
+    while bytes.len() < length {
+        let mut buffer = [0; 32];
+        let mut buffer = ReadBuf::new(&mut buffer[..std::cmp::min(length - bytes.len(), 32)]);
+        ready!(Pin::new(&mut *reader).poll_read(c, &mut buffer))?;
+
+        if buffer.filled().is_empty() {
+            return Poll::Ready(Err(std::io::ErrorKind::UnexpectedEof.into()));
+        }
+        bytes.extend_from_slice(buffer.filled());
+    }
+
+    Poll::Ready(Ok(()))
+}
+
+/// Fails if the inner reader has ended before reaching its limit (ie. the source ended part-way through the entry).
+///
+/// The error holds no [`ZipError`], which distinguishes it from data that doesn't match the entry's headers.
+fn check_source<R: AsyncRead>(reader: &Take<R>, ended: bool) -> tokio::io::Result<()> {
+    match ended && reader.limit() > 0 {
+        true => Err(std::io::ErrorKind::UnexpectedEof.into()),
+        false => Ok(()),
+    }
+}
+
+/// Returns the I/O error which reports a failure to decrypt an entry.
+fn invalid(error: ZipError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
+
+/// A reader which decrypts an entry's data before it's passed to its decoder, if the entry is encrypted.
+///
+/// Unencrypted data is passed through untouched. A single reader should either be read via [`AsyncRead`] or via
+/// [`AsyncBufRead`], but not both.
+pub(crate) struct DecryptReader<R> {
+    reader: Take<R>,
+    decryption: Option<Decryption>,
+}
+
+impl<R: AsyncRead + Unpin> DecryptReader<R> {
+    /// Constructs a reader over the data of the provided entry, using the password held within the options if the
+    /// entry is encrypted.
+    ///
+    /// The inner reader's limit must be the entry's compressed size (unless the entry's data is followed by a data
+    /// descriptor and its encryption scheme has no trailer).
+    pub(crate) fn new(mut reader: Take<R>, entry: &ZipEntry, options: &ReaderOptions) -> Result<Self> {
+        let encryption = match entry.encryption() {
+            Some(encryption) => encryption,
+            None => return Ok(DecryptReader { reader, decryption: None }),
+        };
+
+        let password = options.password.clone().ok_or(ZipError::PasswordRequired)?;
+        let scheme = match encryption {
+            Encryption::ZipCrypto => {
+                let check = match entry.data_descriptor() {
+                    true => (crate::spec::date::chrono_to_zip_time(entry.last_modified()).0 >> 8) as u8,
+                    false => (entry.crc32().unwrap_or_default() >> 24) as u8,
+                };
+                Scheme::ZipCrypto { password, check }
+            }
+            #[cfg(feature = "aes")]
+            Encryption::Aes(strength) => Scheme::Aes { password, strength },
+            #[cfg(not(feature = "aes"))]
+            Encryption::Aes(_) => {
+                return Err(ZipError::FeatureNotSupported("AES encryption (enable the `aes` feature)"))
+            }
+        };
+
+        // The trailer is read separately once the data (and so the inner reader) has been read to its end.
+        reader.set_limit(reader.limit().saturating_sub(scheme.trailer_length() as u64));
+
+        let decryption = Decryption {
+            header: Vec::with_capacity(scheme.header_length()),
+            scheme,
+            cipher: None,
+            trailer: None,
+            verified: false,
+            plain: Vec::new(),
+            plain_position: 0,
+            ahead: None,
+        };
+        Ok(DecryptReader { reader, decryption: Some(decryption) })
+    }
+
+    /// Returns a shared reference to the inner reader.
+    pub(crate) fn get_ref(&self) -> &Take<R> {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the inner reader.
+    pub(crate) fn get_mut(&mut self) -> &mut Take<R> {
+        &mut self.reader
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for DecryptReader<R> {
+    fn poll_read(self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let DecryptReader { reader, decryption } = self.get_mut();
+        let prev_len = b.filled().len();
+        let Some(decryption) = decryption else {
+            ready!(Pin::new(&mut *reader).poll_read(c, b))?;
+            return Poll::Ready(check_source(reader, b.filled().len() == prev_len && b.remaining() > 0));
+        };
+
+        ready!(decryption.poll_header(reader, c))?;
+        ready!(Pin::new(&mut *reader).poll_read(c, b))?;
+
+        if b.filled().len() == prev_len && b.remaining() > 0 {
+            check_source(reader, true)?;
+            ready!(decryption.poll_trailer(reader, c))?;
+        }
+
+        decryption.cipher.as_mut().unwrap().decrypt(&mut b.filled_mut()[prev_len..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncBufRead for DecryptReader<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<&[u8]>> {
+        let DecryptReader { reader, decryption } = self.get_mut();
+        let Some(decryption) = decryption else {
+            let ended = ready!(Pin::new(&mut *reader).poll_fill_buf(c))?.is_empty();
+            check_source(reader, ended)?;
+            return Pin::new(reader).poll_fill_buf(c);
+        };
+
+        ready!(decryption.poll_header(reader, c))?;
+
+        // Bytes are decrypted with a copy of the cipher, which only replaces the original once they've all been
+        // consumed. This leaves the inner reader positioned exactly after the bytes the decoder used (eg. before a
+        // data descriptor).
+        if decryption.plain_position == decryption.plain.len() {
+            let available = ready!(Pin::new(&mut *reader).poll_fill_buf(c))?;
+            if available.is_empty() {
+                check_source(reader, true)?;
+                ready!(decryption.poll_trailer(reader, c))?;
+                return Poll::Ready(Ok(&[]));
+            }
+
+            let mut ahead = decryption.cipher.clone().unwrap();
+            decryption.plain.clear();
+            decryption.plain.extend_from_slice(available);
+            ahead.decrypt(&mut decryption.plain);
+
+            decryption.plain_position = 0;
+            decryption.ahead = Some(ahead);
+        }
+
+        Poll::Ready(Ok(&decryption.plain[decryption.plain_position..]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let DecryptReader { reader, decryption } = self.get_mut();
+        let Some(decryption) = decryption else {
+            return Pin::new(reader).consume(amt);
+        };
+
+        decryption.plain_position += amt;
+        if decryption.plain_position == decryption.plain.len() {
+            if let Some(ahead) = decryption.ahead.take() {
+                decryption.cipher = Some(ahead);
+            }
+        }
+
+        Pin::new(reader).consume(amt);
+    }
+}
@@ -0,0 +1,368 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A decoder for Deflate64 ("enhanced deflate"), which isn't supported by any of this crate's compression
+//! dependencies.
+//!
+//! Deflate64 is identical to Deflate except that its window is 64 KiB rather than 32 KiB, that length code 285 is
+//! followed by 16 extra bits (giving lengths of up to 65,538 bytes), and that distance codes 30 and 31 are valid.
+
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+/// The size of the window which distances may refer back through.
+const WINDOW_SIZE: usize = 64 * 1024;
+
+/// The number of decoded bytes which are held before they must be read.
+const OUTPUT_LIMIT: usize = 32 * 1024;
+
+/// The longest code of any Huffman code.
+const MAX_BITS: usize = 15;
+
+const LENGTH_BASE: [u32; 29] =
+    [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 3];
+const LENGTH_EXTRA: [u32; 29] =
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 16];
+const DISTANCE_BASE: [u32; 32] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577, 32769, 49153,
+];
+const DISTANCE_EXTRA: [u32; 32] =
+    [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13, 14, 14];
+
+/// The order in which the lengths of the code length code are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// The reason decoding stopped before completing a step.
+enum Stop {
+    Input,
+    Invalid(&'static str),
+}
+
+type Step<T> = std::result::Result<T, Stop>;
+
+/// A canonical Huffman code, decoded by counting the codes of each length (as with zlib's `puff`).
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Constructs the code with the provided code length for each symbol, unless it's over-subscribed.
+    fn new(lengths: &[u8]) -> Step<Huffman> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        lengths.iter().for_each(|length| counts[*length as usize] += 1);
+        counts[0] = 0;
+
+        let mut left = 1i32;
+        for count in &counts[1..] {
+            left = (left << 1) - *count as i32;
+            if left < 0 {
+                return Err(Stop::Invalid("over-subscribed Huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for length in 1..=MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+
+        let mut symbols = vec![0; offsets[MAX_BITS + 1] as usize];
+        for (symbol, length) in lengths.iter().enumerate().filter(|(_, length)| **length != 0) {
+            symbols[offsets[*length as usize] as usize] = symbol as u16;
+            offsets[*length as usize] += 1;
+        }
+
+        Ok(Huffman { counts, symbols })
+    }
+
+    /// Constructs the fixed literal/length and distance codes.
+    fn fixed() -> (Huffman, Huffman) {
+        let mut lengths = [0u8; 288];
+        lengths[..144].fill(8);
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        lengths[280..].fill(8);
+
+        let literals = Huffman::new(&lengths).ok().unwrap();
+        let distances = Huffman::new(&[5; 32]).ok().unwrap();
+        (literals, distances)
+    }
+}
+
+/// Compressed input, read from least to most significant bit of each byte.
+#[derive(Default)]
+struct BitInput {
+    bytes: Vec<u8>,
+    position: usize,
+}
+
+impl BitInput {
+    fn bits(&mut self, count: u32) -> Step<u32> {
+        if self.position + count as usize > self.bytes.len() * 8 {
+            return Err(Stop::Input);
+        }
+
+        let mut value = 0;
+        for bit in 0..count as usize {
+            let position = self.position + bit;
+            value |= (((self.bytes[position / 8] >> (position % 8)) & 1) as u32) << bit;
+        }
+
+        self.position += count as usize;
+        Ok(value)
+    }
+
+    fn symbol(&mut self, huffman: &Huffman) -> Step<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+
+        for count in &huffman.counts[1..] {
+            code |= self.bits(1)? as i32;
+            let count = *count as i32;
+            if code - count < first {
+                return Ok(huffman.symbols[(index + code - first) as usize]);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(Stop::Invalid("invalid Huffman code"))
+    }
+
+    fn align(&mut self) {
+        self.position = self.position.next_multiple_of(8);
+    }
+}
+
+enum State {
+    Header,
+    Stored(usize),
+    Codes(Box<(Huffman, Huffman)>),
+    Done,
+}
+
+/// The state of a Deflate64 stream being decoded.
+struct Inflater {
+    input: BitInput,
+    state: State,
+    last: bool,
+
+    // The decoded data which is yet to be read, preceded by (at least) a window of that which has been.
+    output: Vec<u8>,
+    read: usize,
+}
+
+impl Inflater {
+    /// Decodes as much of the input as possible, returning once a reasonable amount of data has been decoded.
+    ///
+    /// Steps which run out of input are undone so that they can be retried once more input has been fed.
+    fn inflate(&mut self) -> Step<()> {
+        while !matches!(self.state, State::Done) && self.output.len() - self.read < OUTPUT_LIMIT {
+            let position = self.input.position;
+
+            match self.step() {
+                Ok(()) => {}
+                Err(Stop::Input) => {
+                    self.input.position = position;
+                    return match self.output.len() > self.read {
+                        true => Ok(()),
+                        false => Err(Stop::Input),
+                    };
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn step(&mut self) -> Step<()> {
+        let Inflater { input, state, last, output, .. } = self;
+
+        match state {
+            State::Header => {
+                *last = input.bits(1)? == 1;
+                *state = match input.bits(2)? {
+                    0 => {
+                        input.align();
+                        let length = input.bits(16)?;
+                        if length != !input.bits(16)? & 0xFFFF {
+                            return Err(Stop::Invalid("stored block length mismatch"));
+                        }
+                        State::Stored(length as usize)
+                    }
+                    1 => State::Codes(Box::new(Huffman::fixed())),
+                    2 => State::Codes(Box::new(dynamic_codes(input)?)),
+                    _ => return Err(Stop::Invalid("invalid block type")),
+                };
+            }
+            State::Stored(0) => *state = block_end(*last),
+            State::Stored(remaining) => {
+                let start = input.position / 8;
+                let length = std::cmp::min(*remaining, input.bytes.len() - start);
+                if length == 0 {
+                    return Err(Stop::Input);
+                }
+
+                output.extend_from_slice(&input.bytes[start..start + length]);
+                input.position += length * 8;
+                *remaining -= length;
+            }
+            State::Codes(codes) => {
+                let (literals, distances) = &**codes;
+                match input.symbol(literals)? {
+                    symbol @ 0..=255 => output.push(symbol as u8),
+                    256 => *state = block_end(*last),
+                    symbol => {
+                        let index = symbol as usize - 257;
+                        if index >= LENGTH_BASE.len() {
+                            return Err(Stop::Invalid("invalid length code"));
+                        }
+                        let length = LENGTH_BASE[index] + input.bits(LENGTH_EXTRA[index])?;
+
+                        let index = input.symbol(distances)? as usize;
+                        let distance = (DISTANCE_BASE[index] + input.bits(DISTANCE_EXTRA[index])?) as usize;
+                        if distance > output.len() {
+                            return Err(Stop::Invalid("distance too far back"));
+                        }
+
+                        let start = output.len() - distance;
+                        output.reserve(length as usize);
+                        for index in start..start + length as usize {
+                            output.push(output[index]);
+                        }
+                    }
+                }
+            }
+            State::Done => {}
+        };
+
+        Ok(())
+    }
+
+    /// Returns the decoded data which is yet to be read.
+    fn pending(&self) -> &[u8] {
+        &self.output[self.read..]
+    }
+
+    /// Marks decoded data as read, discarding any which has fallen out of the window.
+    fn consume(&mut self, amount: usize) {
+        self.read += amount;
+
+        if self.read > WINDOW_SIZE * 2 {
+            self.output.drain(..self.read - WINDOW_SIZE);
+            self.read = WINDOW_SIZE;
+        }
+    }
+
+    /// Appends compressed input, discarding that which has already been decoded.
+    fn feed(&mut self, bytes: &[u8]) {
+        let decoded = self.input.position / 8;
+        self.input.bytes.drain(..decoded);
+        self.input.position -= decoded * 8;
+        self.input.bytes.extend_from_slice(bytes);
+    }
+}
+
+fn block_end(last: bool) -> State {
+    match last {
+        true => State::Done,
+        false => State::Header,
+    }
+}
+
+/// Reads the literal/length and distance codes of a block compressed with dynamic Huffman codes.
+fn dynamic_codes(input: &mut BitInput) -> Step<(Huffman, Huffman)> {
+    let literal_count = input.bits(5)? as usize + 257;
+    let distance_count = input.bits(5)? as usize + 1;
+    let code_length_count = input.bits(4)? as usize + 4;
+    if literal_count > 286 {
+        return Err(Stop::Invalid("too many length codes"));
+    }
+
+    let mut lengths = [0u8; 19];
+    for index in CODE_LENGTH_ORDER.iter().take(code_length_count) {
+        lengths[*index] = input.bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&lengths)?;
+
+    let mut lengths = vec![0u8; literal_count + distance_count];
+    let mut index = 0;
+    while index < lengths.len() {
+        let (length, repeat) = match input.symbol(&code_lengths)? {
+            length @ 0..=15 => (length as u8, 1),
+            16 if index == 0 => return Err(Stop::Invalid("repeated length with no first length")),
+            16 => (lengths[index - 1], 3 + input.bits(2)? as usize),
+            17 => (0, 3 + input.bits(3)? as usize),
+            _ => (0, 11 + input.bits(7)? as usize),
+        };
+
+        if index + repeat > lengths.len() {
+            return Err(Stop::Invalid("too many code lengths"));
+        }
+        lengths[index..index + repeat].fill(length);
+        index += repeat;
+    }
+
+    if lengths[256] == 0 {
+        return Err(Stop::Invalid("missing end-of-block code"));
+    }
+
+    let literals = Huffman::new(&lengths[..literal_count])?;
+    let distances = Huffman::new(&lengths[literal_count..])?;
+    Ok((literals, distances))
+}
+
+/// A decoder which decompresses Deflate64 data read from an inner buffered reader.
+pub(crate) struct Deflate64Decoder<R> {
+    reader: R,
+    inflater: Inflater,
+}
+
+impl<R> Deflate64Decoder<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        let inflater =
+            Inflater { input: BitInput::default(), state: State::Header, last: false, output: Vec::new(), read: 0 };
+        Deflate64Decoder { reader, inflater }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncRead for Deflate64Decoder<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let Deflate64Decoder { reader, inflater } = &mut *self;
+
+        loop {
+            let pending = inflater.pending();
+            if !pending.is_empty() {
+                let length = std::cmp::min(pending.len(), b.remaining());
+                b.put_slice(&pending[..length]);
+                inflater.consume(length);
+                return Poll::Ready(Ok(()));
+            }
+            if matches!(inflater.state, State::Done) {
+                return Poll::Ready(Ok(()));
+            }
+
+            match inflater.inflate() {
+                Ok(()) => {}
+                Err(Stop::Invalid(reason)) => {
+                    return Poll::Ready(Err(tokio::io::Error::new(tokio::io::ErrorKind::InvalidData, reason)));
+                }
+                Err(Stop::Input) => {
+                    let bytes = ready!(Pin::new(&mut *reader).poll_fill_buf(c))?;
+                    if bytes.is_empty() {
+                        return Poll::Ready(Err(tokio::io::ErrorKind::UnexpectedEof.into()));
+                    }
+
+                    let length = bytes.len();
+                    inflater.feed(bytes);
+                    Pin::new(&mut *reader).consume(length);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Support for reading the data descriptors which follow the data of stream-written entries.
+//!
+//! The local file header of such an entry holds neither its sizes nor its CRC32 value, so a stream reader only finds
+//! the end of its data once a compressed entry has been fully decoded, or by scanning a Stored entry's data for a
+//! descriptor which matches it. Data which is skipped rather than read is scanned without being decoded.
+
+use crate::error::ZipError;
+use crate::read::pool::SharedBufReader;
+
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use crc32fast::Hasher;
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf, Take};
+
+/// The length of the longest form of data descriptor (with a signature and 64-bit sizes).
+pub(crate) const MAX_DESCRIPTOR_LENGTH: usize = 24;
+
+/// The number of bytes which must be available to rule out a descriptor (and the header signature after it) when
+/// scanning data without decoding it.
+const SKIP_LOOKAHEAD: usize = MAX_DESCRIPTOR_LENGTH + 4;
+
+/// The values held within an entry's data descriptor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DataDescriptor {
+    pub(crate) crc: u32,
+    pub(crate) compressed_size: u64,
+    pub(crate) uncompressed_size: u64,
+}
+
+/// The progress made towards reading an entry's data descriptor.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum DescriptorState {
+    /// The entry's sizes and CRC32 value are already known.
+    NotExpected,
+    /// The entry's data is still being read, of which `read` uncompressed bytes have been so far.
+    Expected { zip64: bool, read: u64 },
+    /// The entry's data has been read up to and including its data descriptor.
+    Read(DataDescriptor),
+}
+
+/// Parses the data descriptor at the start of the provided bytes, returning it along with its length.
+///
+/// A descriptor may or may not start with a signature, and holds either 32-bit or 64-bit sizes, none of which is
+/// flagged. So, only a form whose sizes match those of the data it follows is accepted. 64-bit sizes are tried first
+/// if the entry's local file header held a Zip64 extra field, as the two forms are otherwise ambiguous for empty data.
+pub(crate) fn parse(
+    bytes: &[u8],
+    compressed_size: u64,
+    uncompressed_size: u64,
+    zip64: bool,
+) -> Option<(DataDescriptor, usize)> {
+    forms(bytes, zip64).find(|(descriptor, _)| {
+        descriptor.compressed_size == compressed_size && descriptor.uncompressed_size == uncompressed_size
+    })
+}
+
+/// Returns every form of data descriptor which the start of the provided bytes could hold, along with its length.
+fn forms(bytes: &[u8], zip64: bool) -> impl Iterator<Item = (DataDescriptor, usize)> + '_ {
+    let signed = bytes.get(0..4) == Some(&crate::spec::delimiter::DDD.to_le_bytes());
+    let starts: &[usize] = if signed { &[4, 0] } else { &[0] };
+    let widths: [usize; 2] = if zip64 { [8, 4] } else { [4, 8] };
+
+    starts.iter().flat_map(move |start| widths.into_iter().map(move |width| (*start, width))).filter_map(
+        move |(start, width)| {
+            let fields = bytes.get(start..start + 4 + width * 2)?;
+            let size = |index: usize| match width {
+                4 => u32::from_le_bytes(fields[index..index + 4].try_into().unwrap()) as u64,
+                _ => u64::from_le_bytes(fields[index..index + 8].try_into().unwrap()),
+            };
+
+            let descriptor = DataDescriptor {
+                crc: u32::from_le_bytes(fields[0..4].try_into().unwrap()),
+                compressed_size: size(4),
+                uncompressed_size: size(4 + width),
+            };
+            Some((descriptor, fields.len() + start))
+        },
+    )
+}
+
+/// Discards the rest of an entry's data up to and including its data descriptor without decoding it, where `skipped`
+/// is the number of bytes of the entry's data which have already been consumed (and is advanced as more are).
+///
+/// Without decoding the data, neither its uncompressed size nor its CRC32 value is known. So, a descriptor is only
+/// accepted if its compressed size matches the data before it and it's directly followed by the signature of either a
+/// local file header or a central directory header.
+pub(crate) fn poll_skip<R: AsyncRead + Unpin>(
+    reader: &mut SharedBufReader<'_, R>,
+    c: &mut Context<'_>,
+    skipped: &mut u64,
+    zip64: bool,
+) -> Poll<tokio::io::Result<()>> {
+    loop {
+        let available = ready!(reader.poll_fill_min(c, SKIP_LOOKAHEAD))?;
+        let exhausted = available.len() < SKIP_LOOKAHEAD;
+
+        // Unless the inner reader is exhausted, a descriptor starting near the buffer's end can't yet be ruled out.
+        let scannable = match exhausted {
+            true => available.len(),
+            false => available.len() - SKIP_LOOKAHEAD + 1,
+        };
+
+        let found = (0..scannable).find_map(|offset| {
+            let bytes = &available[offset..];
+            let (_, length) = forms(bytes, zip64).find(|(descriptor, length)| {
+                descriptor.compressed_size == *skipped + offset as u64 && precedes_header(&bytes[*length..])
+            })?;
+            Some((offset, length))
+        });
+
+        if let Some((offset, length)) = found {
+            *skipped += offset as u64;
+            Pin::new(&mut *reader).consume(offset + length);
+            reader.shared_mut().set_entry_ended(true);
+            return Poll::Ready(Ok(()));
+        }
+
+        if exhausted {
+            return Poll::Ready(Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof)));
+        }
+
+        *skipped += scannable as u64;
+        Pin::new(&mut *reader).consume(scannable);
+    }
+}
+
+/// Returns whether the provided bytes start with the signature of a header which may follow an entry's data.
+fn precedes_header(bytes: &[u8]) -> bool {
+    let signature = bytes.get(0..4).map(|signature| u32::from_le_bytes(signature.try_into().unwrap()));
+    matches!(signature, Some(crate::spec::delimiter::LFHD | crate::spec::delimiter::CDFHD))
+}
+
+/// Reads the data descriptor which follows a compressed entry's data, once its decoder has consumed all of that data.
+///
+/// The provided reader is the decoder's source, whose limit started at [`u64::MAX`] so that the number of compressed
+/// bytes consumed can be derived from it.
+pub(crate) fn poll_read<R: AsyncRead + Unpin>(
+    source: &mut Take<SharedBufReader<'_, R>>,
+    c: &mut Context<'_>,
+    uncompressed_size: u64,
+    zip64: bool,
+) -> Poll<tokio::io::Result<DataDescriptor>> {
+    let compressed_size = u64::MAX - source.limit();
+    let reader = source.get_mut();
+
+    let available = ready!(reader.poll_fill_min(c, MAX_DESCRIPTOR_LENGTH))?;
+    let (descriptor, length) = parse(available, compressed_size, uncompressed_size, zip64).ok_or_else(invalid)?;
+
+    Pin::new(&mut *reader).consume(length);
+    reader.shared_mut().set_entry_ended(true);
+    Poll::Ready(Ok(descriptor))
+}
+
+/// A reader over the data of a Stored entry which is followed by a data descriptor.
+///
+/// As nothing marks the end of the data, each position is checked for a descriptor whose sizes and CRC32 value match
+/// all of the data before it. Bytes are only yielded once it's certain that no descriptor starts at them.
+pub(crate) struct DescriptorScanner<'a, R: AsyncRead + Unpin> {
+    reader: SharedBufReader<'a, R>,
+    hasher: Hasher,
+    read: u64,
+    zip64: bool,
+    descriptor: Option<DataDescriptor>,
+}
+
+impl<'a, R: AsyncRead + Unpin> DescriptorScanner<'a, R> {
+    pub(crate) fn new(reader: SharedBufReader<'a, R>, zip64: bool) -> Self {
+        Self { reader, hasher: Hasher::new(), read: 0, zip64, descriptor: None }
+    }
+
+    /// Returns the entry's data descriptor, if the end of its data has been reached.
+    pub(crate) fn descriptor(&self) -> tokio::io::Result<DataDescriptor> {
+        self.descriptor.ok_or_else(invalid)
+    }
+}
+
+impl<'a, R: AsyncRead + Unpin> AsyncRead for DescriptorScanner<'a, R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        if self.descriptor.is_some() || b.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let DescriptorScanner { reader, hasher, read, zip64, descriptor } = &mut *self;
+        let available = ready!(reader.poll_fill_min(c, MAX_DESCRIPTOR_LENGTH))?;
+        if available.is_empty() {
+            return Poll::Ready(Err(invalid()));
+        }
+
+        // Unless the inner reader is exhausted, a descriptor starting near the buffer's end can't yet be ruled out.
+        let scannable = match available.len() < MAX_DESCRIPTOR_LENGTH {
+            true => available.len(),
+            false => available.len() - MAX_DESCRIPTOR_LENGTH + 1,
+        };
+        let scannable = std::cmp::min(scannable, b.remaining());
+
+        let found =
+            (0..scannable).find_map(|offset| Some((offset, matches(hasher, *read, *zip64, available, offset)?)));
+        let yielded = found.map(|(offset, _)| offset).unwrap_or(scannable);
+
+        b.put_slice(&available[..yielded]);
+        hasher.update(&available[..yielded]);
+        *read += yielded as u64;
+
+        let consumed = yielded + found.map(|(_, (_, length))| length).unwrap_or_default();
+        Pin::new(&mut *reader).consume(consumed);
+
+        if let Some((_, (found, _))) = found {
+            *descriptor = Some(found);
+            reader.shared_mut().set_entry_ended(true);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Returns whether a descriptor matching all of the data before it starts at the provided offset into `bytes`.
+fn matches(hasher: &Hasher, read: u64, zip64: bool, bytes: &[u8], offset: usize) -> Option<(DataDescriptor, usize)> {
+    let size = read + offset as u64;
+    let (descriptor, length) = parse(&bytes[offset..], size, size, zip64)?;
+
+    // Only compute the CRC32 value once the far cheaper size comparisons have matched.
+    let mut hasher = hasher.clone();
+    hasher.update(&bytes[..offset]);
+    (hasher.finalize() == descriptor.crc).then_some((descriptor, length))
+}
+
+/// Returns the error for an entry whose data descriptor couldn't be found or didn't match its data.
+fn invalid() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, ZipError::InvalidDataDescriptor)
+}
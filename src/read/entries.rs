@@ -0,0 +1,134 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! An adapter which yields the entries of an archive from the `futures` [`Stream`] interface.
+
+use crate::error::Result;
+use crate::read::{seek, stream, ZipEntry, ZipEntryReader};
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::{AsyncRead, AsyncSeek};
+
+/// An entry and its data, as yielded by an [`EntryStream`].
+pub type EntryData = (ZipEntry, Vec<u8>);
+
+type PendingEntry<'a, S> = Pin<Box<dyn Future<Output = (S, Option<Result<EntryData>>)> + Send + 'a>>;
+
+enum State<'a, S> {
+    Idle(S),
+    Reading(PendingEntry<'a, S>),
+    Finished,
+}
+
+/// A [`Stream`] which reads each entry of an archive in turn, yielding it alongside its data.
+///
+/// Each entry's data is read to its end (with the CRC32 check integrated) before it's yielded, so yielded items
+/// borrow nothing and may be processed concurrently (eg. via `StreamExt::try_for_each_concurrent()`) whilst the next
+/// entry is read. The stream ends after yielding the first error.
+///
+/// As each entry is held in memory, the reader's [`ReaderOptions::max_entry_size()`] should be set when reading
+/// untrusted archives: entries which declare a larger size then fail with [`ZipError::EntryTooLarge`] before any of
+/// their data is read, and reading stops once an entry's data exceeds it.
+///
+/// [`ReaderOptions::max_entry_size()`]: crate::read::ReaderOptions::max_entry_size
+/// [`ZipError::EntryTooLarge`]: crate::error::ZipError::EntryTooLarge
+///
+/// This stream is constructed via [`stream::ZipFileReader::into_stream()`] or [`seek::ZipFileReader::into_stream()`].
+pub struct EntryStream<'a, S> {
+    state: State<'a, S>,
+    next: fn(S) -> PendingEntry<'a, S>,
+}
+
+impl<'a, S: Unpin> Stream for EntryStream<'a, S> {
+    type Item = Result<EntryData>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            match std::mem::replace(&mut this.state, State::Finished) {
+                State::Idle(reader) => this.state = State::Reading((this.next)(reader)),
+                State::Reading(mut pending) => {
+                    let (reader, item) = match pending.as_mut().poll(cx) {
+                        Poll::Ready(output) => output,
+                        Poll::Pending => {
+                            this.state = State::Reading(pending);
+                            return Poll::Pending;
+                        }
+                    };
+
+                    if let Some(Ok(_)) = item {
+                        this.state = State::Idle(reader);
+                    }
+                    return Poll::Ready(item);
+                }
+                State::Finished => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> stream::ZipFileReader<R> {
+    /// Consumes this reader and returns a [`Stream`] over its remaining entries and their data.
+    ///
+    /// See [`EntryStream`] for details.
+    pub fn into_stream<'a>(self) -> EntryStream<'a, Self>
+    where
+        R: Send + 'a,
+    {
+        EntryStream { state: State::Idle(self), next: |reader| Box::pin(next_streamed(reader)) }
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> seek::ZipFileReader<R> {
+    /// Consumes this reader and returns a [`Stream`] over its entries and their data, in central directory order.
+    ///
+    /// See [`EntryStream`] for details.
+    pub fn into_stream<'a>(self) -> EntryStream<'a, (Self, usize)>
+    where
+        R: Send + 'a,
+    {
+        EntryStream { state: State::Idle((self, 0)), next: |reader| Box::pin(next_seeked(reader)) }
+    }
+}
+
+async fn next_streamed<R: AsyncRead + Unpin>(
+    mut reader: stream::ZipFileReader<R>,
+) -> (stream::ZipFileReader<R>, Option<Result<EntryData>>) {
+    let item = match reader.entry_reader().await {
+        Ok(Some(entry_reader)) => Some(read_entry(entry_reader).await),
+        Ok(None) => None,
+        Err(error) => Some(Err(error)),
+    };
+
+    (reader, item)
+}
+
+async fn next_seeked<R: AsyncRead + AsyncSeek + Unpin>(
+    (mut reader, index): (seek::ZipFileReader<R>, usize),
+) -> ((seek::ZipFileReader<R>, usize), Option<Result<EntryData>>) {
+    let item = match index < reader.entries.len() {
+        true => match reader.entry_reader(index).await {
+            Ok(entry_reader) => Some(read_entry(entry_reader).await),
+            Err(error) => Some(Err(error)),
+        },
+        false => None,
+    };
+
+    ((reader, index + 1), item)
+}
+
+/// Reads an entry's data to its end, refusing it up front if it declares a size larger than the maximum entry size.
+async fn read_entry<R: AsyncRead + Unpin>(entry_reader: ZipEntryReader<'_, R>) -> Result<EntryData> {
+    let entry = entry_reader.entry().clone();
+    let data = match entry_reader.options.max_entry_size {
+        Some(limit) => entry_reader.read_to_end_limited(limit).await?,
+        None => entry_reader.read_to_end_crc().await?,
+    };
+
+    Ok((entry, data))
+}
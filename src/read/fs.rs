@@ -25,11 +25,10 @@
 
 use super::CompressionReader;
 use crate::error::{Result, ZipError};
-use crate::read::{ZipEntry, ZipEntryReader};
+use crate::read::{EntryIndex, OwnedEntryReader, ReaderOptions, ZipEntry, ZipEntryReader};
 
-use std::io::SeekFrom;
 use tokio::fs::File;
-use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::io::AsyncReadExt;
 
 /// The type returned as an entry reader within this concurrent module.
 pub type ConcurrentReader<'a> = ZipEntryReader<'a, File>;
@@ -38,15 +37,23 @@ pub type ConcurrentReader<'a> = ZipEntryReader<'a, File>;
 pub struct ZipFileReader {
     pub(crate) filename: String,
     pub(crate) entries: Vec<ZipEntry>,
+    pub(crate) options: ReaderOptions,
+    pub(crate) index: EntryIndex,
 }
 
 impl ZipFileReader {
     /// Constructs a new ZIP file reader from a filename.
     pub async fn new(filename: String) -> Result<ZipFileReader> {
-        let mut fs_file = File::open(&filename).await?;
-        let entries = crate::read::seek::read_cd(&mut fs_file).await?;
+        Self::with_options(filename, ReaderOptions::default()).await
+    }
+
+    /// Constructs a new ZIP file reader from a filename and a set of options.
+    pub async fn with_options(filename: String, options: ReaderOptions) -> Result<ZipFileReader> {
+        let mut fs_file = crate::utils::open_file(filename.as_ref()).await?;
+        let entries = crate::read::seek::read_cd(&mut fs_file, options.parse_mode).await?;
+        crate::read::seek::validate_entries(&mut fs_file, &entries, &options).await?;
 
-        Ok(ZipFileReader { filename, entries })
+        Ok(ZipFileReader { filename, entries, options, index: EntryIndex::default() })
     }
 
     crate::read::reader_entry_impl!();
@@ -55,16 +62,29 @@ impl ZipFileReader {
     pub async fn entry_reader(&self, index: usize) -> Result<ConcurrentReader<'_>> {
         let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
 
-        if entry.data_descriptor() {
-            return Err(ZipError::FeatureNotSupported("Entries with data descriptors"));
-        }
+        let mut fs_file = crate::utils::open_file(self.filename.as_ref()).await?;
+        entry.seek_to_data_offset(&mut fs_file).await?;
+
+        let reader = fs_file.take(entry.compressed_size.unwrap());
+        let reader = CompressionReader::from_reader(entry, reader, &self.options)?;
+
+        Ok(ZipEntryReader::from_raw(entry, reader, &self.options))
+    }
+
+    /// Opens an entry at the provided index for reading, returning a reader which owns a copy of the entry and its own
+    /// handle to the file.
+    ///
+    /// Unlike [`ZipFileReader::entry_reader()`], the returned reader doesn't borrow this reader, and so may be moved
+    /// into a spawned task or outlive this reader entirely.
+    pub async fn owned_entry_reader(&self, index: usize) -> Result<OwnedEntryReader<File>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
 
-        let mut fs_file = File::open(&self.filename).await?;
-        fs_file.seek(SeekFrom::Start(entry.data_offset())).await?;
+        let mut fs_file = crate::utils::open_file(self.filename.as_ref()).await?;
+        entry.seek_to_data_offset(&mut fs_file).await?;
 
-        let reader = fs_file.take(entry.compressed_size.unwrap().into());
-        let reader = CompressionReader::from_reader(entry.compression(), reader);
+        let reader = fs_file.take(entry.compressed_size.unwrap());
+        let reader = CompressionReader::from_reader(entry, reader, &self.options)?;
 
-        Ok(ZipEntryReader::from_raw(entry, reader, false))
+        Ok(ZipEntryReader::from_owned(entry.clone(), reader, &self.options))
     }
 }
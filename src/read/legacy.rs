@@ -0,0 +1,367 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Decoders for the legacy Shrink and Implode compression methods, as written by PKZIP 1.x and other DOS-era tools.
+//!
+//! Neither method is supported by any of this crate's compression dependencies. As archives which use them are small
+//! by modern standards, an entry's compressed data is read in full and then decoded in one go. So that this can't be
+//! used to exhaust memory, [`ReaderOptions::max_entry_size()`] is checked before any data is buffered.
+
+use crate::error::ZipError;
+use crate::read::{ReaderOptions, ZipEntry};
+use crate::spec::compression::Compression;
+
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// The method (and method-specific options) with which an entry was compressed.
+#[derive(Clone, Copy)]
+enum Method {
+    Shrink,
+    Implode { large_window: bool, literal_tree: bool },
+}
+
+/// A decoder which decompresses Shrink or Implode data read from an inner reader.
+pub(crate) struct LegacyDecoder<R> {
+    reader: R,
+    method: Method,
+    size: usize,
+    limit: Option<u64>,
+    input: Vec<u8>,
+
+    // The decoded data and the amount of it read so far, once the input has been read in full and decoded.
+    output: Option<(Vec<u8>, usize)>,
+}
+
+impl<R> LegacyDecoder<R> {
+    pub(crate) fn new(reader: R, entry: &ZipEntry, options: &ReaderOptions) -> Self {
+        let method = match entry.compression() {
+            Compression::Shrink => Method::Shrink,
+            _ => Method::Implode {
+                large_window: entry.compression_options & 0b01 != 0,
+                literal_tree: entry.compression_options & 0b10 != 0,
+            },
+        };
+        let size = entry.uncompressed_size.unwrap_or_default() as usize;
+
+        LegacyDecoder { reader, method, size, limit: options.max_entry_size, input: Vec::new(), output: None }
+    }
+}
+
+/// Fails if the provided size exceeds the maximum size of an entry, if one is set.
+fn check_limit(limit: Option<u64>, description: &'static str, size: usize) -> tokio::io::Result<()> {
+    match limit.filter(|limit| size as u64 > *limit) {
+        Some(limit) => Err(tokio::io::Error::new(
+            tokio::io::ErrorKind::InvalidData,
+            ZipError::DecompressionLimitExceeded(description, limit),
+        )),
+        None => Ok(()),
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for LegacyDecoder<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let LegacyDecoder { reader, method, size, limit, input, output } = &mut *self;
+        check_limit(*limit, "entry's declared size", *size)?;
+
+        loop {
+            if let Some((output, read)) = output {
+                let length = std::cmp::min(output.len() - *read, b.remaining());
+                b.put_slice(&output[*read..*read + length]);
+                *read += length;
+                return Poll::Ready(Ok(()));
+            }
+
+            let mut chunk = [0; 8192];
+            let mut chunk = ReadBuf::new(&mut chunk);
+            ready!(Pin::new(&mut *reader).poll_read(c, &mut chunk))?;
+
+            if chunk.filled().is_empty() {
+                let decoded = match method {
+                    Method::Shrink => unshrink(input, *size),
+                    Method::Implode { large_window, literal_tree } => {
+                        explode(input, *size, *large_window, *literal_tree)
+                    }
+                };
+                let decoded = decoded
+                    .ok_or_else(|| tokio::io::Error::new(tokio::io::ErrorKind::InvalidData, "invalid legacy data"))?;
+                *output = Some((decoded, 0));
+            } else {
+                check_limit(*limit, "entry's compressed size", input.len() + chunk.filled().len())?;
+                input.extend_from_slice(chunk.filled());
+            }
+        }
+    }
+}
+
+/// Compressed input, read from least to most significant bit of each byte.
+struct BitInput<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitInput<'a> {
+    fn bits(&mut self, count: usize) -> Option<u32> {
+        if self.position + count > self.bytes.len() * 8 {
+            return None;
+        }
+
+        let mut value = 0;
+        for bit in 0..count {
+            let position = self.position + bit;
+            value |= (((self.bytes[position / 8] >> (position % 8)) & 1) as u32) << bit;
+        }
+
+        self.position += count;
+        Some(value)
+    }
+}
+
+/// The largest code used by Shrink, and the code which precedes a control code.
+const MAX_CODE: usize = 8191;
+const CONTROL_CODE: usize = 256;
+
+/// An entry within Shrink's string table.
+#[derive(Clone, Copy)]
+struct ShrinkCode {
+    // The code's prefix (or the code itself for literals), or `None` if the code is free.
+    prefix: Option<u16>,
+    last_byte: u8,
+
+    // The length of the code's string (or `None` if its prefix was cleared when the code was added), and the
+    // position of its most recent occurrence within the output.
+    length: Option<usize>,
+    position: usize,
+}
+
+/// Decodes data compressed with Shrink (dynamic LZW with partial clearing).
+fn unshrink(input: &[u8], size: usize) -> Option<Vec<u8>> {
+    let free = ShrinkCode { prefix: None, last_byte: 0, length: None, position: 0 };
+    let mut table = vec![free; MAX_CODE + 1];
+    for (code, entry) in table.iter_mut().enumerate().take(CONTROL_CODE) {
+        *entry = ShrinkCode { prefix: Some(code as u16), last_byte: code as u8, length: Some(1), position: 0 };
+    }
+
+    let mut free_codes: Vec<usize> = (CONTROL_CODE + 1..=MAX_CODE).rev().collect();
+    let mut input = BitInput { bytes: input, position: 0 };
+    let mut output = Vec::with_capacity(std::cmp::min(size, 1 << 20));
+    let mut code_size = 9;
+
+    let first = input.bits(code_size)? as usize;
+    if first >= CONTROL_CODE {
+        return None;
+    }
+    output.push(first as u8);
+    let mut previous = first;
+
+    while output.len() < size {
+        let Some(code) = input.bits(code_size) else { break };
+        let code = code as usize;
+
+        if code == CONTROL_CODE {
+            match input.bits(code_size)? {
+                1 if code_size < 13 => code_size += 1,
+                2 => partial_clear(&mut table, &mut free_codes),
+                _ => return None,
+            }
+            continue;
+        }
+
+        let first_byte = output[table[previous].position];
+
+        // A code may be used before it's added to the table, if it's the previous string extended by its first byte.
+        if free_codes.last() == Some(&code) {
+            table[code] = ShrinkCode {
+                prefix: Some(previous as u16),
+                last_byte: first_byte,
+                length: Some(table[previous].length? + 1),
+                position: table[previous].position,
+            };
+        }
+
+        let position = output.len();
+        let entry = table[code];
+        match (entry.prefix, entry.length) {
+            _ if code < CONTROL_CODE => output.push(code as u8),
+            (None, _) => return None,
+            (Some(prefix), _) if code > CONTROL_CODE && prefix as usize == code => return None,
+            (Some(_), Some(length)) => copy_within(&mut output, entry.position, length),
+            (Some(prefix), None) => {
+                let prefix = prefix as usize;
+                if free_codes.last() == Some(&prefix) {
+                    table[prefix] = ShrinkCode {
+                        prefix: Some(previous as u16),
+                        last_byte: output[table[previous].position],
+                        length: Some(table[previous].length? + 1),
+                        position: table[previous].position,
+                    };
+                }
+
+                let length = table[prefix].prefix.and(table[prefix].length)?;
+                copy_within(&mut output, table[prefix].position, length);
+                output.push(entry.last_byte);
+                table[code].length = Some(length + 1);
+            }
+        }
+
+        // The previous string, extended by the first byte of this one, is added to the table if there's room.
+        if let Some(new_code) = free_codes.pop() {
+            // If the previous code was freed by a partial clear, this string is unknown until the code is reused.
+            let length = table[previous].prefix.and(table[previous].length).map(|length| length + 1);
+            let last_byte = output[position];
+            table[new_code] = ShrinkCode { prefix: Some(previous as u16), last_byte, length, ..table[previous] };
+        }
+
+        table[code].position = position;
+        previous = code;
+    }
+
+    output.truncate(size);
+    Some(output)
+}
+
+/// Frees every code which isn't the prefix of another, making them available for reuse in ascending order.
+fn partial_clear(table: &mut [ShrinkCode], free_codes: &mut Vec<usize>) {
+    let mut is_prefix = vec![false; MAX_CODE + 1];
+    for entry in &table[CONTROL_CODE + 1..] {
+        if let Some(prefix) = entry.prefix {
+            is_prefix[prefix as usize] = true;
+        }
+    }
+
+    free_codes.clear();
+    for code in (CONTROL_CODE + 1..=MAX_CODE).rev() {
+        if !is_prefix[code] {
+            table[code].prefix = None;
+            free_codes.push(code);
+        }
+    }
+}
+
+/// Appends a copy of previously-decoded data (which may overlap the data being appended).
+fn copy_within(output: &mut Vec<u8>, start: usize, length: usize) {
+    for index in start..start + length {
+        output.push(output[index]);
+    }
+}
+
+/// The longest code of any Shannon-Fano tree.
+const MAX_TREE_BITS: usize = 16;
+
+/// A Shannon-Fano tree, decoded as a canonical Huffman code of the reversed code lengths (as with Info-ZIP's and
+/// hwzip's implementations).
+struct Tree {
+    counts: [u16; MAX_TREE_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Tree {
+    /// Reads a tree of the provided number of symbols, which is stored as run-length encoded code lengths.
+    fn read(input: &mut BitInput<'_>, symbols: usize) -> Option<Tree> {
+        let mut lengths = Vec::with_capacity(symbols);
+        for _ in 0..input.bits(8)? + 1 {
+            let byte = input.bits(8)?;
+            let (length, repeat) = ((byte & 0xF) + 1, (byte >> 4) + 1);
+            lengths.extend(std::iter::repeat_n(length as u8, repeat as usize));
+        }
+        if lengths.len() != symbols {
+            return None;
+        }
+        lengths.reverse();
+
+        let mut counts = [0u16; MAX_TREE_BITS + 1];
+        lengths.iter().for_each(|length| counts[*length as usize] += 1);
+
+        // Only complete trees are valid.
+        let mut left = 1i32;
+        for count in &counts[1..] {
+            left = (left << 1) - *count as i32;
+            if left < 0 {
+                return None;
+            }
+        }
+        if left != 0 {
+            return None;
+        }
+
+        let mut offsets = [0u16; MAX_TREE_BITS + 2];
+        for length in 1..=MAX_TREE_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+
+        let mut sorted = vec![0; symbols];
+        for (symbol, length) in lengths.iter().enumerate() {
+            sorted[offsets[*length as usize] as usize] = (symbols - 1 - symbol) as u16;
+            offsets[*length as usize] += 1;
+        }
+
+        Some(Tree { counts, symbols: sorted })
+    }
+
+    /// Decodes a symbol, whose code is stored with its bits inverted.
+    fn decode(&self, input: &mut BitInput<'_>) -> Option<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+
+        for count in &self.counts[1..] {
+            code |= (input.bits(1)? ^ 1) as i32;
+            let count = *count as i32;
+            if code - count < first {
+                return Some(self.symbols[(index + code - first) as usize]);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        None
+    }
+}
+
+/// Decodes data compressed with Implode (LZ77 with Shannon-Fano coded literals, lengths, and distances).
+fn explode(input: &[u8], size: usize, large_window: bool, literal_tree: bool) -> Option<Vec<u8>> {
+    let mut input = BitInput { bytes: input, position: 0 };
+    let literals = match literal_tree {
+        true => Some(Tree::read(&mut input, 256)?),
+        false => None,
+    };
+    let lengths = Tree::read(&mut input, 64)?;
+    let distances = Tree::read(&mut input, 64)?;
+
+    let distance_bits = if large_window { 7 } else { 6 };
+    let min_length = if literal_tree { 3 } else { 2 };
+    let mut output = Vec::with_capacity(std::cmp::min(size, 1 << 20));
+
+    while output.len() < size {
+        if input.bits(1)? == 1 {
+            let literal = match &literals {
+                Some(literals) => literals.decode(&mut input)? as u8,
+                None => input.bits(8)? as u8,
+            };
+            output.push(literal);
+            continue;
+        }
+
+        let low = input.bits(distance_bits)? as usize;
+        let distance = ((distances.decode(&mut input)? as usize) << distance_bits | low) + 1;
+        let mut length = lengths.decode(&mut input)? as usize;
+        if length == 63 {
+            length += input.bits(8)? as usize;
+        }
+        length += min_length;
+
+        // Distances which reach before the start of the data refer to the initially zero-filled window.
+        for _ in 0..length {
+            let byte = match output.len().checked_sub(distance) {
+                Some(index) => output[index],
+                None => 0,
+            };
+            output.push(byte);
+        }
+    }
+
+    output.truncate(size);
+    Some(output)
+}
@@ -0,0 +1,111 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Conversion of ZIP's LZMA framing into the `.lzma` ("LZMA alone") framing expected by `async-compression`.
+//!
+//! Within a ZIP file, LZMA data is prefixed by the LZMA SDK's version (two bytes), the length of the properties (two
+//! bytes), and the properties themselves (five bytes), whereas the `.lzma` header holds the properties followed by the
+//! uncompressed size (eight bytes, or all ones if the data is terminated by an end marker instead).
+
+use crate::read::ZipEntry;
+
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+/// The length of the LZMA properties, which is the only length supported.
+const PROPERTIES_LENGTH: usize = 5;
+/// The length of the ZIP LZMA header (the version and properties length, followed by the properties).
+const ZIP_HEADER_LENGTH: usize = 4 + PROPERTIES_LENGTH;
+
+enum State {
+    // The ZIP header read from the inner reader so far.
+    Reading(Vec<u8>),
+    // The `.lzma` header and the amount of it consumed so far.
+    Emitting([u8; PROPERTIES_LENGTH + 8], usize),
+    Passing,
+}
+
+/// A reader which replaces the ZIP LZMA header of an inner reader's data with a `.lzma` header.
+pub(crate) struct LzmaHeaderReader<R> {
+    inner: R,
+    size: u64,
+    state: State,
+}
+
+impl<R: AsyncBufRead + Unpin> LzmaHeaderReader<R> {
+    pub(crate) fn new(inner: R, entry: &ZipEntry) -> Self {
+        // Bit 1 of the general purpose flag marks data terminated by an end marker, whose size isn't then relied upon.
+        let size = match entry.compression_options & 0b01 {
+            0 => entry.uncompressed_size.unwrap_or(u64::MAX),
+            _ => u64::MAX,
+        };
+
+        LzmaHeaderReader { inner, size, state: State::Reading(Vec::with_capacity(ZIP_HEADER_LENGTH)) }
+    }
+
+    pub(crate) fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncBufRead for LzmaHeaderReader<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<&[u8]>> {
+        let this = self.get_mut();
+
+        while let State::Reading(header) = &mut this.state {
+            let bytes = ready!(Pin::new(&mut this.inner).poll_fill_buf(c))?;
+            if bytes.is_empty() {
+                return Poll::Ready(Err(tokio::io::ErrorKind::UnexpectedEof.into()));
+            }
+
+            let length = std::cmp::min(ZIP_HEADER_LENGTH - header.len(), bytes.len());
+            header.extend_from_slice(&bytes[..length]);
+            Pin::new(&mut this.inner).consume(length);
+
+            if header.len() == ZIP_HEADER_LENGTH {
+                if u16::from_le_bytes([header[2], header[3]]) as usize != PROPERTIES_LENGTH {
+                    let error = "unsupported LZMA properties length";
+                    return Poll::Ready(Err(tokio::io::Error::new(tokio::io::ErrorKind::InvalidData, error)));
+                }
+
+                let mut lzma_header = [0; PROPERTIES_LENGTH + 8];
+                lzma_header[..PROPERTIES_LENGTH].copy_from_slice(&header[4..]);
+                lzma_header[PROPERTIES_LENGTH..].copy_from_slice(&this.size.to_le_bytes());
+                this.state = State::Emitting(lzma_header, 0);
+            }
+        }
+
+        match &this.state {
+            State::Emitting(header, position) => Poll::Ready(Ok(&header[*position..])),
+            _ => Pin::new(&mut this.inner).poll_fill_buf(c),
+        }
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        match &mut self.state {
+            State::Emitting(header, position) => {
+                *position += amt;
+                if *position == header.len() {
+                    self.state = State::Passing;
+                }
+            }
+            _ => Pin::new(&mut self.inner).consume(amt),
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncRead for LzmaHeaderReader<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let bytes = ready!(self.as_mut().poll_fill_buf(c))?;
+        let length = std::cmp::min(bytes.len(), b.remaining());
+        b.put_slice(&bytes[..length]);
+        self.consume(length);
+        Poll::Ready(Ok(()))
+    }
+}
@@ -4,11 +4,11 @@
 //! A module for reading ZIP file entries concurrently from an in-memory buffer.
 
 use crate::error::{Result, ZipError};
-use crate::read::{CompressionReader, ZipEntry, ZipEntryReader};
+use crate::read::{CompressionReader, EntryIndex, ReaderOptions, ZipEntry, ZipEntryReader};
 
-use std::io::{Cursor, SeekFrom};
+use std::io::Cursor;
 
-use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::io::AsyncReadExt;
 
 /// The type returned as an entry reader within this concurrent module.
 pub type ConcurrentReader<'b, 'a> = ZipEntryReader<'b, Cursor<&'a [u8]>>;
@@ -17,13 +17,22 @@ pub type ConcurrentReader<'b, 'a> = ZipEntryReader<'b, Cursor<&'a [u8]>>;
 pub struct ZipFileReader<'a> {
     pub(crate) data: &'a [u8],
     pub(crate) entries: Vec<ZipEntry>,
+    pub(crate) options: ReaderOptions,
+    pub(crate) index: EntryIndex,
 }
 
 impl<'a> ZipFileReader<'a> {
     /// Constructs a new ZIP file reader from an in-memory buffer.
     pub async fn new(data: &'a [u8]) -> Result<ZipFileReader<'a>> {
-        let entries = crate::read::seek::read_cd(&mut Cursor::new(data)).await?;
-        Ok(ZipFileReader { data, entries })
+        Self::with_options(data, ReaderOptions::default()).await
+    }
+
+    /// Constructs a new ZIP file reader from an in-memory buffer and a set of options.
+    pub async fn with_options(data: &'a [u8], options: ReaderOptions) -> Result<ZipFileReader<'a>> {
+        let mut cursor = Cursor::new(data);
+        let entries = crate::read::seek::read_cd(&mut cursor, options.parse_mode).await?;
+        crate::read::seek::validate_entries(&mut cursor, &entries, &options).await?;
+        Ok(ZipFileReader { data, entries, options, index: EntryIndex::default() })
     }
 
     crate::read::reader_entry_impl!();
@@ -32,16 +41,12 @@ impl<'a> ZipFileReader<'a> {
     pub async fn entry_reader<'b>(&'b mut self, index: usize) -> Result<ConcurrentReader<'b, 'a>> {
         let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
 
-        if entry.data_descriptor() {
-            return Err(ZipError::FeatureNotSupported("Entries with data descriptors"));
-        }
-
-        let mut cursor = Cursor::new(self.data.clone());
-        cursor.seek(SeekFrom::Start(entry.data_offset())).await?;
+        let mut cursor = Cursor::new(self.data);
+        entry.seek_to_data_offset(&mut cursor).await?;
 
-        let reader = cursor.take(entry.compressed_size.unwrap().into());
-        let reader = CompressionReader::from_reader(entry.compression(), reader);
+        let reader = cursor.take(entry.compressed_size.unwrap());
+        let reader = CompressionReader::from_reader(entry, reader, &self.options)?;
 
-        Ok(ZipEntryReader::from_raw(entry, reader, false))
+        Ok(ZipEntryReader::from_raw(entry, reader, &self.options))
     }
 }
@@ -3,53 +3,494 @@
 
 //! A module which supports reading ZIP files using various approaches.
 
+#[cfg(feature = "stream")]
+pub(crate) mod byte_stream;
+#[cfg(feature = "bytes")]
+pub mod bytes;
+pub(crate) mod crypto;
+pub(crate) mod deflate64;
+pub(crate) mod descriptor;
+#[cfg(feature = "stream")]
+pub(crate) mod entries;
 pub mod fs;
+#[cfg(feature = "legacy-compression")]
+pub(crate) mod legacy;
+#[cfg(feature = "lzma")]
+pub(crate) mod lzma;
 pub mod mem;
+pub(crate) mod offset_reader;
+pub mod pool;
+#[cfg(feature = "ppmd")]
+pub(crate) mod ppmd;
+pub mod prefetch;
+pub(crate) mod recovery;
+pub mod remote;
 pub mod seek;
+pub mod shared;
 pub mod stream;
 pub mod sync;
+pub mod verify;
+pub(crate) mod window;
 
+#[cfg(feature = "stream")]
+pub use byte_stream::ByteStream;
+#[cfg(feature = "stream")]
+pub use entries::{EntryData, EntryStream};
+pub use window::EntryWindow;
+
+use crate::backend::{BackendDecoder, Backends, CompressionBackend};
+use crate::crypto::Password;
 use crate::error::{Result, ZipError};
 use crate::spec::compression::Compression;
+use crate::spec::date::NtfsTimestamps;
+use crate::spec::encryption::{AesExtraField, Encryption};
+use crate::spec::header::{LocalFileHeader, TEXT_ATTRIBUTE};
+use crate::spec::string::ZipString;
+use crate::spec::version::CreatorOs;
+#[cfg(feature = "zstd")]
+use crate::zstd_dictionary::ZstdDictionary;
+use crypto::DecryptReader;
+use deflate64::Deflate64Decoder;
+use descriptor::{DataDescriptor, DescriptorScanner, DescriptorState};
+#[cfg(feature = "legacy-compression")]
+use legacy::LegacyDecoder;
+#[cfg(feature = "lzma")]
+use lzma::LzmaHeaderReader;
+use pool::{BufferPool, PooledBufReader, PooledBuffer, SharedBufReader};
+#[cfg(feature = "ppmd")]
+use ppmd::PpmdDecoder;
+use prefetch::PrefetchOptions;
 
-use std::convert::TryInto;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::PathBuf;
 use std::pin::Pin;
-use std::task::{Context, Poll};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::task::{ready, Context, Poll};
 
-use async_compression::tokio::bufread::{BzDecoder, DeflateDecoder, LzmaDecoder, XzDecoder, ZstdDecoder};
+#[cfg(feature = "bzip2")]
+use async_compression::tokio::bufread::BzDecoder;
+#[cfg(feature = "deflate")]
+use async_compression::tokio::bufread::DeflateDecoder;
+#[cfg(feature = "lzma")]
+use async_compression::tokio::bufread::LzmaDecoder;
+#[cfg(feature = "xz")]
+use async_compression::tokio::bufread::XzDecoder;
+#[cfg(feature = "zstd")]
+use async_compression::tokio::bufread::ZstdDecoder;
 use chrono::{DateTime, Utc};
 use crc32fast::Hasher;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, BufReader, ReadBuf, Take};
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, ReadBuf, Take};
+
+/// The maximum length of a symbolic link's target path which will be read (4 KiB, as is Linux's `PATH_MAX`).
+pub const MAX_SYMLINK_TARGET_LENGTH: u64 = 4 * 1024;
+
+/// The mask of the file type bits of a Unix file mode, and the file type of symbolic links.
+const UNIX_FILE_TYPE: u32 = 0o170000;
+const UNIX_SYMLINK: u32 = 0o120000;
+
+/// The default capacity of the buffer which sits between each decoder and its source (8 KiB).
+pub const DEFAULT_DECODER_BUFFER_SIZE: usize = 8 * 1024;
+
+/// The default maximum number of archives which may be nested within one another when opening nested archives.
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 4;
+
+/// How strictly the headers of an archive are parsed and validated.
+///
+/// No mode validates the version fields of headers, as many tools write them incorrectly. Seekable readers take each
+/// entry's values from its central directory record, so local file headers which conflict with it are only rejected
+/// by [`ParseMode::Strict`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Recoverable deviations from the specification, as are common in archives written by other tools, are tolerated.
+    ///
+    /// Specifically:
+    /// - Invalid MS-DOS dates and times are read as the start of 1980.
+    /// - Incomplete Zip64 extended information extra fields are ignored in favour of the 32-bit header values.
+    /// - Extra fields whose declared length runs past the end of their header's extra field data are ignored.
+    /// - The central directory is read up to its declared size, rather than as many records as the end of central
+    ///   directory header declares (which is often wrong for archives with more than 65,535 entries).
+    Lenient,
+    /// Headers which deviate from the specification are rejected.
+    #[default]
+    Standard,
+    /// As [`ParseMode::Standard`], but seekable readers also read the local file header of every entry when opening an
+    /// archive, and validate the archive's layout.
+    ///
+    /// Archives in which the data of any two entries overlap fail to open with [`ZipError::OverlappingEntries`]. Such
+    /// archives are never written legitimately, but may be crafted to amplify decompression by pointing many central
+    /// directory records at the same data. Archives in which an entry's local file header conflicts with its central
+    /// directory record (in its compression method or encryption, or in its CRC32 value and sizes if neither declares a
+    /// data descriptor) fail to open with [`ZipError::LocalHeaderMismatch`].
+    Strict,
+}
+
+impl ParseMode {
+    /// Returns the fallback value in place of an error if parsing leniently.
+    pub(crate) fn tolerate<T>(self, result: Result<T>, fallback: impl FnOnce() -> T) -> Result<T> {
+        match (self, result) {
+            (ParseMode::Lenient, Err(_)) => Ok(fallback()),
+            (_, result) => result,
+        }
+    }
+}
+
+/// A set of options which affect how ZIP files and their entries are read.
+#[derive(Debug, Clone)]
+pub struct ReaderOptions {
+    pub(crate) decoder_buffer_size: usize,
+    pub(crate) read_buffer_size: Option<usize>,
+    pub(crate) buffer_pool: Arc<BufferPool>,
+    pub(crate) max_nesting_depth: usize,
+    pub(crate) prefetch: Option<PrefetchOptions>,
+    pub(crate) password: Option<Password>,
+    pub(crate) verify_crc: bool,
+    pub(crate) max_entry_size: Option<u64>,
+    pub(crate) max_total_size: Option<u64>,
+    pub(crate) max_compression_ratio: Option<u64>,
+    pub(crate) max_decoder_memory: Option<u64>,
+    pub(crate) parse_mode: ParseMode,
+    pub(crate) detect_truncation: bool,
+    pub(crate) skip_prepended_data: bool,
+    pub(crate) backends: Backends,
+    #[cfg(feature = "zstd")]
+    pub(crate) zstd_dictionary: Option<ZstdDictionary>,
+
+    // The number of bytes decompressed so far by every entry reader using these options (or their clones).
+    pub(crate) decompressed: Arc<AtomicU64>,
+
+    // The depth of the archive these options belong to (zero for the outermost archive).
+    pub(crate) depth: usize,
+}
+
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        ReaderOptions {
+            decoder_buffer_size: DEFAULT_DECODER_BUFFER_SIZE,
+            read_buffer_size: None,
+            buffer_pool: Arc::new(BufferPool::default()),
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            prefetch: None,
+            password: None,
+            verify_crc: true,
+            max_entry_size: None,
+            max_total_size: None,
+            max_compression_ratio: None,
+            max_decoder_memory: None,
+            parse_mode: ParseMode::Standard,
+            detect_truncation: false,
+            skip_prepended_data: false,
+            backends: Backends::default(),
+            #[cfg(feature = "zstd")]
+            zstd_dictionary: None,
+            decompressed: Arc::new(AtomicU64::new(0)),
+            depth: 0,
+        }
+    }
+}
+
+impl ReaderOptions {
+    /// Construct a new set of options with their default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Consume the options and override the capacity of the buffer which compressed data is read into before being
+    /// passed to a decoder (defaults to [`DEFAULT_DECODER_BUFFER_SIZE`]).
+    ///
+    /// Smaller buffers reduce the memory held by each open entry reader, which matters when thousands are open at
+    /// once, whereas larger buffers (eg. 256 KiB) reduce the number of reads and improve throughput for large entries.
+    /// This option has no effect on Stored entries as they're read without an intermediate buffer.
+    ///
+    /// Entry readers which are used via [`AsyncBufRead`] also fill a buffer of this size with decompressed data.
+    pub fn decoder_buffer_size(mut self, size: usize) -> Self {
+        self.decoder_buffer_size = size;
+        self
+    }
+
+    /// Consume the options and override the capacity of the buffer through which a stream reader reads from its
+    /// source (defaults to the decoder buffer size).
+    ///
+    /// This buffer holds headers and compressed data alike, so its capacity is the most a stream reader ever requests
+    /// from its source at once. Sources with a high per-read cost (eg. network streams) benefit from larger buffers
+    /// (eg. 1 MiB) regardless of the size of the archive's entries. The capacity is never less than the length of the
+    /// largest data descriptor.
+    pub fn read_buffer_size(mut self, size: usize) -> Self {
+        self.read_buffer_size = Some(size);
+        self
+    }
+
+    /// Consume the options and override the pool from which decoder, copy, and skip buffers are taken.
+    ///
+    /// By default, each set of options owns a separate pool (shared only by clones of those options). Passing the same
+    /// pool to many readers allows buffers to be reused across all of them.
+    pub fn buffer_pool(mut self, pool: Arc<BufferPool>) -> Self {
+        self.buffer_pool = pool;
+        self
+    }
+
+    /// Consume the options and override how many archives deep nested archives may be opened (defaults to
+    /// [`DEFAULT_MAX_NESTING_DEPTH`]).
+    ///
+    /// Opening an archive nested any deeper fails with [`ZipError::NestingDepthExceeded`], which stops maliciously
+    /// recursive archives from being followed indefinitely. A depth of zero disallows opening nested archives at all.
+    pub fn max_nesting_depth(mut self, depth: usize) -> Self {
+        self.max_nesting_depth = depth;
+        self
+    }
+
+    /// Consume the options and enable prefetching of entries when they're read in order from a seekable reader (via
+    /// [`seek::ZipFileReader::sequential()`]).
+    ///
+    /// Whilst an entry is being read, the compressed data of the entries which follow it is fetched into memory, up to
+    /// the limits of the provided options. Entries opened by index or name are always read directly.
+    pub fn prefetch(mut self, options: PrefetchOptions) -> Self {
+        self.prefetch = Some(options);
+        self
+    }
+
+    /// Consume the options and provide the password with which encrypted entries are decrypted.
+    ///
+    /// Entries encrypted with the traditional PKWARE encryption scheme (ZipCrypto) are supported, as are those
+    /// encrypted with WinZip's AES encryption scheme when the `aes` feature is enabled. Opening an encrypted entry
+    /// without a password fails with [`ZipError::PasswordRequired`], whereas reading one with an incorrect password
+    /// fails with an I/O error wrapping [`ZipError::IncorrectPassword`].
+    pub fn password(mut self, password: impl AsRef<[u8]>) -> Self {
+        self.password = Some(Password::new(password.as_ref()));
+        self
+    }
+
+    /// Consume the options and override whether entries' CRC32 values are verified once they're read to their end
+    /// (defaults to `true`).
+    ///
+    /// When enabled, an entry reader which reaches the end of data not matching the entry's CRC32 value fails with an
+    /// I/O error wrapping [`ZipError::CrcMismatch`], so corrupt data can't pass silently. Disabling this avoids the
+    /// cost of hashing for callers which verify data by other means, though the `_crc` convenience methods (eg.
+    /// [`ZipEntryReader::read_to_end_crc()`]) still verify it.
+    pub fn verify_crc(mut self, verify: bool) -> Self {
+        self.verify_crc = verify;
+        self
+    }
+
+    /// Consume the options and limit the number of bytes any single entry may decompress to.
+    ///
+    /// Reading beyond the limit fails with an I/O error wrapping [`ZipError::DecompressionLimitExceeded`], regardless
+    /// of the entry's declared size. Along with the other limits, this guards against maliciously crafted archives
+    /// (zip bombs) when reading untrusted input.
+    pub fn max_entry_size(mut self, size: u64) -> Self {
+        self.max_entry_size = Some(size);
+        self
+    }
+
+    /// Consume the options and limit the total number of bytes decompressed across every entry read with these
+    /// options (or their clones, which includes those of nested archives).
+    pub fn max_total_size(mut self, size: u64) -> Self {
+        self.max_total_size = Some(size);
+        self
+    }
+
+    /// Consume the options and limit the ratio of any entry's decompressed size to its compressed size.
+    ///
+    /// The ratio is checked against the entry's compressed size as the data is read, or for entries whose compressed
+    /// size is held within a data descriptor, against the number of compressed bytes consumed so far.
+    pub fn max_compression_ratio(mut self, ratio: u64) -> Self {
+        self.max_compression_ratio = Some(ratio);
+        self
+    }
+
+    /// Consume the options and limit the memory which a decoder may allocate for state whose size is chosen by the
+    /// archive, which is currently the model of PPMd entries (of up to 256 MiB).
+    ///
+    /// Entries which require more fail to be read with an I/O error wrapping [`ZipError::DecompressionLimitExceeded`]
+    /// before anything is allocated.
+    pub fn max_decoder_memory(mut self, size: u64) -> Self {
+        self.max_decoder_memory = Some(size);
+        self
+    }
+
+    /// Consume the options and override how strictly the headers of archives are parsed and validated (defaults to
+    /// [`ParseMode::Standard`]).
+    pub fn parse_mode(mut self, mode: ParseMode) -> Self {
+        self.parse_mode = mode;
+        self
+    }
+
+    /// Consume the options and override whether archives which end part-way through an entry (eg. failed downloads)
+    /// are reported as such (defaults to `false`).
+    ///
+    /// When enabled, the stream reader still yields every complete entry, but the entry which the archive ends within
+    /// (whether within its header or its data) fails with [`ZipError::Truncated`] rather than a generic I/O error,
+    /// after which no more entries are yielded. Failures whilst reading an entry's data are I/O errors wrapping
+    /// [`ZipError::Truncated`] instead. This allows callers to distinguish an incomplete archive, of which the entries
+    /// already read are intact, from a corrupt one.
+    pub fn detect_truncation(mut self, detect: bool) -> Self {
+        self.detect_truncation = detect;
+        self
+    }
+
+    /// Consume the options and override whether the stream reader skips any data prepended to the archive (eg. the stub
+    /// of a self-extracting archive) by scanning forward for the first local file header (defaults to `false`).
+    ///
+    /// Seekable readers always account for prepended data, as the end of central directory header reveals its length.
+    pub fn skip_prepended_data(mut self, skip: bool) -> Self {
+        self.skip_prepended_data = skip;
+        self
+    }
+
+    /// Consume the options and register a backend through which entries compressed with its method are read.
+    ///
+    /// Backends are only consulted for methods without built-in support (ie. [`Compression::Custom`]), and replace any
+    /// previously registered for the same method. Reading an entry whose method has no backend fails with
+    /// [`ZipError::UnsupportedCompressionError`].
+    pub fn compression_backend(mut self, backend: impl CompressionBackend + 'static) -> Self {
+        self.backends.register(Arc::new(backend));
+        self
+    }
+
+    /// Consume the options and decompress every zstd entry with the provided dictionary.
+    ///
+    /// Entries compressed without a dictionary are unaffected, whereas those compressed with a different dictionary
+    /// fail to be read.
+    #[cfg(feature = "zstd")]
+    pub fn zstd_dictionary(mut self, dictionary: impl Into<Arc<[u8]>>) -> Self {
+        self.zstd_dictionary = Some(ZstdDictionary(dictionary.into()));
+        self
+    }
+
+    /// Returns the backend through which entries of the provided method are decompressed, if not by this crate itself.
+    pub(crate) fn backend(&self, compression: &Compression) -> Result<Option<&dyn CompressionBackend>> {
+        match compression {
+            Compression::Custom(method) => Ok(Some(&**self.backends.get(*method)?)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(self.zstd_dictionary.as_ref().map(|dictionary| dictionary as _)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the options to be used by an archive nested within the one these options belong to.
+    pub(crate) fn nested(&self) -> Result<ReaderOptions> {
+        if self.depth >= self.max_nesting_depth {
+            return Err(ZipError::NestingDepthExceeded(self.max_nesting_depth));
+        }
+
+        Ok(ReaderOptions { depth: self.depth + 1, ..self.clone() })
+    }
+}
 
 /// An entry within a larger ZIP file reader.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZipEntry {
-    pub(crate) name: String,
-    pub(crate) comment: Option<String>,
+    pub(crate) name: ZipString,
+    pub(crate) comment: Option<ZipString>,
     pub(crate) data_descriptor: bool,
+    pub(crate) encrypted: bool,
+    pub(crate) compression_options: u8,
+    pub(crate) aes: Option<AesExtraField>,
     pub(crate) crc32: Option<u32>,
-    pub(crate) uncompressed_size: Option<u32>,
-    pub(crate) compressed_size: Option<u32>,
+    pub(crate) uncompressed_size: Option<u64>,
+    pub(crate) compressed_size: Option<u64>,
     pub(crate) last_modified: DateTime<Utc>,
+    pub(crate) last_accessed: Option<DateTime<Utc>>,
+    pub(crate) created: Option<DateTime<Utc>>,
+    pub(crate) ntfs: Option<NtfsTimestamps>,
     pub(crate) extra: Option<Vec<u8>>,
+    pub(crate) local_extra: Option<Vec<u8>>,
     pub(crate) compression: Compression,
 
     // Additional fields from EOCDH.
-    pub(crate) offset: Option<u32>,
+    pub(crate) offset: Option<u64>,
+    pub(crate) made_by: Option<u16>,
+    pub(crate) exter_attr: Option<u32>,
+    pub(crate) inter_attr: Option<u16>,
+
+    // Whether the entry's data was found to extend beyond the end of a recovered archive.
+    pub(crate) partial: bool,
 }
 
 impl ZipEntry {
-    /// Returns a shared reference to the entry's name.
+    /// Returns a shared reference to the entry's name, decoded per its declared encoding (see [`ZipString::as_str()`]).
     pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Returns a shared reference to the entry's name, including its raw bytes.
+    pub fn filename(&self) -> &ZipString {
         &self.name
     }
 
-    /// Returns an optional shared reference to the entry's comment.
-    pub fn comment(&self) -> Option<&str> {
-        match &self.comment {
-            Some(comment) => Some(comment),
-            None => None,
+    /// Returns the raw bytes of the entry's name, exactly as stored within the archive.
+    ///
+    /// Names aren't guaranteed to be valid UTF-8, so these should be used wherever a name must be preserved exactly
+    /// (eg. when matching entries against those of another archive).
+    pub fn file_name_raw(&self) -> &[u8] {
+        self.name.as_bytes()
+    }
+
+    /// Returns the entry's name as a relative path if it stays within the directory it's extracted into, or `None` if
+    /// it doesn't.
+    ///
+    /// The name is rejected if it's absolute, contains a drive prefix (eg. `C:`) in any component, contains a NUL byte,
+    /// or contains `..` components which would escape the directory. Unlike [`ZipEntry::sanitized_name()`], a name is
+    /// never altered, so this should be preferred when extracting untrusted archives. Backslashes are treated as
+    /// separators, as some archivers write Windows paths as-is.
+    pub fn enclosed_name(&self) -> Option<PathBuf> {
+        let mut components = name_components(self.name())?;
+        let mut path = PathBuf::new();
+        let mut depth = 0usize;
+
+        if components.peek().is_some_and(|component| component.is_empty()) {
+            return None;
+        }
+
+        for component in components {
+            if drive_prefix(component).is_some() {
+                return None;
+            }
+
+            match component {
+                "" | "." => continue,
+                ".." => depth = depth.checked_sub(1)?,
+                _ => depth += 1,
+            }
+            path.push(component);
+        }
+
+        Some(path)
+    }
+
+    /// Returns the entry's name as a relative path which stays within the directory it's extracted into, with any
+    /// parts which would escape it stripped (ie. `..` components, leading separators, and the drive prefixes of any
+    /// components).
+    ///
+    /// `None` is returned if the name contains a NUL byte, or if nothing is left once stripped. As a stripped name may
+    /// collide with another entry's, [`ZipEntry::enclosed_name()`] should be preferred where names can be rejected.
+    pub fn sanitized_name(&self) -> Option<PathBuf> {
+        let components = name_components(self.name())?;
+        let mut path = PathBuf::new();
+
+        for component in components.map(|component| drive_prefix(component).unwrap_or(component)) {
+            if !matches!(component, "" | "." | "..") {
+                path.push(component);
+            }
         }
+
+        (!path.as_os_str().is_empty()).then_some(path)
+    }
+
+    /// Returns an optional shared reference to the entry's comment, decoded with its declared encoding.
+    ///
+    /// Comments are only held within the central directory, so this is always `None` for entries read by a stream
+    /// reader (or recovered from local file headers), and `Some` (though possibly empty) for all others.
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_ref().map(ZipString::as_str)
+    }
+
+    /// Returns an optional shared reference to the entry's comment, including its raw bytes (see
+    /// [`ZipEntry::comment()`]).
+    pub fn zip_comment(&self) -> Option<&ZipString> {
+        self.comment.as_ref()
     }
 
     /// Returns whether or not a data descriptor exists for the entry (ie. whether or not it was stream written).
@@ -57,9 +498,23 @@ impl ZipEntry {
         self.data_descriptor
     }
 
+    /// Returns whether or not the entry's data is encrypted.
+    pub fn encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    /// Returns the scheme with which the entry's data is encrypted, if it is.
+    pub fn encryption(&self) -> Option<Encryption> {
+        match (self.encrypted, self.aes) {
+            (_, Some(aes)) => Some(Encryption::Aes(aes.strength)),
+            (true, None) => Some(Encryption::ZipCrypto),
+            (false, None) => None,
+        }
+    }
+
     /// Returns whether or not the entry represents a directory.
     pub fn dir(&self) -> bool {
-        self.name.ends_with("/")
+        self.name.as_bytes().ends_with(b"/")
     }
 
     /// Returns an optional CRC32 value for the entry.
@@ -68,20 +523,54 @@ impl ZipEntry {
     }
 
     /// Returns an optional compressed file size for the entry.
-    pub fn compressed_size(&self) -> Option<u32> {
+    ///
+    /// Sizes held within a Zip64 extended information extra field are used in place of saturated 32-bit sizes.
+    pub fn compressed_size(&self) -> Option<u64> {
         self.compressed_size
     }
 
     /// Returns an optional uncompressed file size for the entry.
-    pub fn uncompressed_size(&self) -> Option<u32> {
+    ///
+    /// Sizes held within a Zip64 extended information extra field are used in place of saturated 32-bit sizes.
+    pub fn uncompressed_size(&self) -> Option<u64> {
         self.uncompressed_size
     }
 
     /// Returns a shared reference to the entry's last modification date.
+    ///
+    /// The date is taken from the entry's extended timestamp extra field (0x5455) if it has one, as it's held in UTC
+    /// with 1-second precision. Otherwise, it's taken from the MS-DOS date and time, which have 2-second precision and
+    /// are in an unknown timezone (so are interpreted as UTC).
     pub fn last_modified(&self) -> &DateTime<Utc> {
         &self.last_modified
     }
 
+    /// Returns an optional shared reference to the entry's last access date, held within its extended timestamp extra
+    /// field.
+    ///
+    /// As the central directory's copy of the field only holds the modification date, this is only ever known for
+    /// entries read by a stream reader.
+    pub fn last_accessed(&self) -> Option<&DateTime<Utc>> {
+        self.last_accessed.as_ref()
+    }
+
+    /// Returns an optional shared reference to the entry's creation date, held within its extended timestamp extra
+    /// field.
+    ///
+    /// As the central directory's copy of the field only holds the modification date, this is only ever known for
+    /// entries read by a stream reader.
+    pub fn created(&self) -> Option<&DateTime<Utc>> {
+        self.created.as_ref()
+    }
+
+    /// Returns an optional shared reference to the entry's NTFS timestamps, held within its NTFS extra field (0x000a).
+    ///
+    /// Archives created on Windows commonly hold these, which have 100-nanosecond precision. They're exposed alongside
+    /// (rather than replacing) the date returned by [`ZipEntry::last_modified()`].
+    pub fn ntfs_timestamps(&self) -> Option<&NtfsTimestamps> {
+        self.ntfs.as_ref()
+    }
+
     /// Returns an optional shared reference to the extra bytes for the entry.
     pub fn extra(&self) -> Option<&Vec<u8>> {
         self.extra.as_ref()
@@ -92,30 +581,184 @@ impl ZipEntry {
         &self.compression
     }
 
-    /// Returns the offset at which data for this entry starts.
-    pub(crate) fn data_offset(&self) -> u64 {
-        30 + self.offset.unwrap() as u64 + (self.name().len() + self.extra().unwrap().len()) as u64
+    /// Returns the operating system which created the entry, if the entry was read from a central directory.
+    pub fn made_by_os(&self) -> Option<CreatorOs> {
+        self.made_by.map(crate::spec::version::creator_os)
+    }
+
+    /// Returns the entry's internal file attributes, if the entry was read from a central directory.
+    pub fn internal_attributes(&self) -> Option<u16> {
+        self.inter_attr
+    }
+
+    /// Returns whether or not the entry is flagged as holding text (bit 0 of its internal file attributes), which some
+    /// extractors use to convert its line endings.
+    ///
+    /// The flag is only a hint set by the entry's creator, and is only held within the central directory.
+    pub fn is_text(&self) -> bool {
+        self.inter_attr.is_some_and(|attributes| attributes & TEXT_ATTRIBUTE != 0)
+    }
+
+    /// Returns the entry's external file attributes, if the entry was read from a central directory.
+    ///
+    /// Their meaning depends on the OS which created the entry (see [`ZipEntry::made_by_os()`]).
+    pub fn external_attributes(&self) -> Option<u32> {
+        self.exter_attr
+    }
+
+    /// Returns the entry's Unix file mode (its file type and permission bits), if it was created by an OS which stores
+    /// one within the upper 16 bits of the external file attributes (see [`CreatorOs::unix_attributes()`]).
+    pub fn unix_mode(&self) -> Option<u32> {
+        let mode = self.exter_attr? >> 16;
+        (self.made_by_os()?.unix_attributes() && mode != 0).then_some(mode)
+    }
+
+    /// Returns whether or not the entry represents a symbolic link (ie. its Unix file mode has the symbolic link file
+    /// type), in which case its data holds the link's target path.
+    ///
+    /// See [`ZipEntryReader::read_symlink_target()`].
+    pub fn is_symlink(&self) -> bool {
+        self.unix_mode().is_some_and(|mode| mode & UNIX_FILE_TYPE == UNIX_SYMLINK)
+    }
+
+    /// Returns the entry's Unix permission bits (eg. `0o755`, including the setuid, setgid, and sticky bits), if it
+    /// has a Unix file mode.
+    pub fn permissions(&self) -> Option<u32> {
+        self.unix_mode().map(|mode| mode & 0o7777)
     }
+
+    /// Returns whether or not the entry's data extends beyond the end of the archive it was recovered from.
+    ///
+    /// This is only ever true for entries of archives opened with recovery (eg.
+    /// [`seek::ZipFileReader::open_with_recovery()`]), as such entries can't be read in full.
+    pub fn partial(&self) -> bool {
+        self.partial
+    }
+
+    /// Returns whether or not the entry's extra field holds a Zip64 extended information extra field.
+    pub(crate) fn zip64(&self) -> bool {
+        let extra = self.extra.as_deref().unwrap_or_default();
+        crate::spec::extra::fields(extra).any(|(id, _)| id == crate::spec::extra::ZIP64_EXTRA_FIELD_ID)
+    }
+
+    /// Seeks a reader to the offset at which data for this entry starts, returning its local file header.
+    ///
+    /// The filename and extra field lengths are read from the local file header as they may differ from those stored
+    /// in the central directory.
+    pub(crate) async fn seek_to_data_offset<R: AsyncRead + AsyncSeek + Unpin>(
+        &self,
+        reader: &mut R,
+    ) -> Result<LocalFileHeader> {
+        reader.seek(SeekFrom::Start(self.offset.unwrap())).await?;
+        crate::utils::assert_delimiter(reader, crate::spec::delimiter::LFHD).await?;
+
+        let header = LocalFileHeader::from_reader(reader).await?;
+        let trailing = header.file_name_length as i64 + header.extra_field_length as i64;
+        reader.seek(SeekFrom::Current(trailing)).await?;
+
+        Ok(header)
+    }
+
+    /// Seeks to the entry's local file header and reads it, leaving the reader at the start of the entry's data and
+    /// returning the header's extra field data.
+    pub(crate) async fn read_local_extra<R: AsyncRead + AsyncSeek + Unpin>(&self, reader: &mut R) -> Result<Vec<u8>> {
+        reader.seek(SeekFrom::Start(self.offset.unwrap())).await?;
+        crate::utils::assert_delimiter(reader, crate::spec::delimiter::LFHD).await?;
+
+        let header = LocalFileHeader::from_reader(reader).await?;
+        reader.seek(SeekFrom::Current(header.file_name_length as i64)).await?;
+        crate::utils::read_bytes(reader, header.extra_field_length.into()).await
+    }
+}
+
+/// Splits an entry's name into its components (treating backslashes as separators), unless it contains a NUL byte.
+fn name_components(name: &str) -> Option<std::iter::Peekable<impl Iterator<Item = &str>>> {
+    (!name.contains('\0')).then(|| name.split(['/', '\\']).peekable())
 }
 
+/// Returns the remainder of a path component if it starts with a drive prefix (eg. `C:`).
+fn drive_prefix(component: &str) -> Option<&str> {
+    match component.as_bytes() {
+        [letter, b':', ..] if letter.is_ascii_alphabetic() => Some(&component[2..]),
+        _ => None,
+    }
+}
+
+/// An entry reader which owns its entry and source, and so borrows nothing from the ZIP file reader it was opened by.
+///
+/// Owned entry readers may be moved into spawned tasks or stored within other types.
+pub type OwnedEntryReader<R> = ZipEntryReader<'static, R>;
+
 /// A ZIP file entry reader which may implement decompression.
+///
+/// The entry's data is checked against its uncompressed size as it's read, failing with an I/O error wrapping
+/// [`ZipError::UncompressedSizeExceeded`] if there's more data than declared, or [`ZipError::DeclaredSizeMismatch`]
+/// (of kind [`UnexpectedEof`]) if there's less.
+///
+/// Entry readers also implement [`AsyncBufRead`] (for use with `tokio::io::copy_buf()`, `lines()`, and the like),
+/// filling a buffer taken from the reader's [`BufferPool`] on first use. Data is validated as it fills the buffer, so
+/// the two ways of reading may be freely mixed.
+///
+/// [`UnexpectedEof`]: std::io::ErrorKind::UnexpectedEof
 pub struct ZipEntryReader<'a, R: AsyncRead + Unpin> {
-    pub(crate) entry: &'a ZipEntry,
+    pub(crate) entry: Cow<'a, ZipEntry>,
     pub(crate) reader: CompressionReader<'a, R>,
     pub(crate) hasher: Hasher,
+    pub(crate) read: u64,
     pub(crate) consumed: bool,
-    pub(crate) stream: bool,
+    pub(crate) options: ReaderOptions,
+    pub(crate) descriptor: DescriptorState,
+
+    // The buffer filled via `AsyncBufRead`, and the range of it which is yet to be consumed.
+    buffer: Option<PooledBuffer>,
+    position: usize,
+    filled: usize,
 }
 
 impl<'a, R: AsyncRead + Unpin> ZipEntryReader<'a, R> {
     /// Construct an entry reader from its raw parts (a shared reference to the entry and an inner reader).
-    pub(crate) fn from_raw(entry: &'a ZipEntry, reader: CompressionReader<'a, R>, stream: bool) -> Self {
-        ZipEntryReader { entry, reader, stream, hasher: Hasher::new(), consumed: false }
+    pub(crate) fn from_raw(entry: &'a ZipEntry, reader: CompressionReader<'a, R>, options: &ReaderOptions) -> Self {
+        Self::from_entry(Cow::Borrowed(entry), reader, options)
+    }
+
+    /// Construct an entry reader which owns its entry from its raw parts.
+    pub(crate) fn from_owned(entry: ZipEntry, reader: CompressionReader<'a, R>, options: &ReaderOptions) -> Self {
+        Self::from_entry(Cow::Owned(entry), reader, options)
+    }
+
+    fn from_entry(entry: Cow<'a, ZipEntry>, reader: CompressionReader<'a, R>, options: &ReaderOptions) -> Self {
+        let options = options.clone();
+        let descriptor = DescriptorState::NotExpected;
+        ZipEntryReader {
+            entry,
+            reader,
+            hasher: Hasher::new(),
+            read: 0,
+            consumed: false,
+            options,
+            descriptor,
+            buffer: None,
+            position: 0,
+            filled: 0,
+        }
+    }
+
+    /// Consume this reader and read the data descriptor which follows the entry's data once it's read to its end.
+    ///
+    /// The inner reader must have been constructed via [`CompressionReader::from_shared()`] without a size.
+    pub(crate) fn expect_descriptor(mut self, zip64: bool) -> Self {
+        self.descriptor = DescriptorState::Expected { zip64, read: 0 };
+        self
     }
 
     /// Returns a reference to the inner entry's data.
     pub fn entry(&self) -> &ZipEntry {
-        self.entry
+        &self.entry
+    }
+
+    /// Returns the raw bytes of the inner entry's name (see [`ZipEntry::file_name_raw()`]).
+    pub fn file_name_raw(&self) -> &[u8] {
+        self.entry.file_name_raw()
     }
 
     ///  Returns whether or not this reader has been fully consumed.
@@ -124,16 +767,95 @@ impl<'a, R: AsyncRead + Unpin> ZipEntryReader<'a, R> {
     }
 
     /// Returns true if the computed CRC32 value of all bytes read so far matches the expected value.
+    ///
+    /// For an entry read by a stream reader whose CRC32 value is held in a data descriptor, the expected value is only
+    /// known once the entry has been read to its end.
+    ///
+    /// AES-encrypted entries of vendor version 2 (AE-2) omit their CRC32 value, as their data is authenticated instead.
+    /// For such entries, this returns true once the entry has been read to its end (and so authenticated).
     pub fn compare_crc(&mut self) -> bool {
+        if self.entry.aes.is_some_and(|aes| aes.version == 2) {
+            return self.consumed;
+        }
+
         let hasher = std::mem::take(&mut self.hasher);
-        self.entry.crc32().unwrap() == hasher.finalize()
+        self.expected_crc() == Some(hasher.finalize())
+    }
+
+    /// Returns the CRC32 value which the entry's data is expected to match, if it's known.
+    fn expected_crc(&self) -> Option<u32> {
+        match self.descriptor {
+            DescriptorState::NotExpected => self.entry.crc32(),
+            DescriptorState::Expected { .. } => None,
+            DescriptorState::Read(descriptor) => Some(descriptor.crc),
+        }
+    }
+
+    /// Returns the uncompressed size which the entry's data is expected to match, if it's known before the data has
+    /// been read (ie. if it isn't held within a data descriptor).
+    fn expected_size(&self) -> Option<u64> {
+        match self.descriptor {
+            DescriptorState::NotExpected => self.entry.uncompressed_size,
+            _ => None,
+        }
+    }
+
+    /// Checks that reading a further number of decompressed bytes wouldn't exceed any of the decompression limits.
+    fn check_limits(&self, read: u64) -> Result<()> {
+        let options = &self.options;
+        let entry_size = self.read + read;
+
+        if let Some(limit) = options.max_entry_size.filter(|limit| entry_size > *limit) {
+            return Err(ZipError::DecompressionLimitExceeded("entry's decompressed size", limit));
+        }
+        if let Some(limit) = options.max_total_size {
+            if options.decompressed.fetch_add(read, Ordering::Relaxed) + read > limit {
+                return Err(ZipError::DecompressionLimitExceeded("total decompressed size", limit));
+            }
+        }
+        if let Some(limit) = options.max_compression_ratio {
+            let compressed = match self.descriptor {
+                DescriptorState::NotExpected => self.entry.compressed_size,
+                _ => self.reader.compressed_read(),
+            };
+            if compressed.is_some_and(|compressed| entry_size > compressed.max(1).saturating_mul(limit)) {
+                return Err(ZipError::DecompressionLimitExceeded("entry's compression ratio", limit));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verifies the CRC32 value of the entry's data once it has been read to its end.
+    fn verify_crc(&self) -> tokio::io::Result<()> {
+        if self.entry.aes.is_some_and(|aes| aes.version == 2) {
+            return Ok(());
+        }
+
+        match (self.expected_crc(), self.hasher.clone().finalize()) {
+            (Some(expected), actual) if expected != actual => Err(tokio::io::Error::new(
+                tokio::io::ErrorKind::InvalidData,
+                ZipError::CrcMismatch { expected, actual },
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Compares the CRC32 value of the data read against the entry's (as with [`ZipEntryReader::compare_crc()`]),
+    /// failing with [`ZipError::CrcMismatch`] if they differ.
+    fn check_crc(&mut self) -> Result<()> {
+        let actual = self.hasher.clone().finalize();
+        match self.compare_crc() {
+            true => Ok(()),
+            false => Err(ZipError::CrcMismatch { expected: self.expected_crc().unwrap_or_default(), actual }),
+        }
     }
 
     /// A convenience method similar to `AsyncReadExt::read_to_end()` but with the final CRC32 check integrated.
     ///
     /// Reads all bytes until EOF and returns an owned vector of them.
     pub async fn read_to_end_crc(mut self) -> Result<Vec<u8>> {
-        let mut buffer = Vec::with_capacity(self.entry.uncompressed_size.unwrap().try_into().unwrap());
+        let mut buffer = Vec::with_capacity(crate::utils::speculative_capacity(self.entry.uncompressed_size.unwrap()));
         self.read_to_end(&mut buffer).await?;
 
         if self.compare_crc() {
@@ -147,7 +869,8 @@ impl<'a, R: AsyncRead + Unpin> ZipEntryReader<'a, R> {
     ///
     /// Reads all bytes until EOF and returns an owned string of them.
     pub async fn read_to_string_crc(mut self) -> Result<String> {
-        let mut buffer = String::with_capacity(self.entry.uncompressed_size.unwrap().try_into().unwrap());
+        let mut buffer =
+            String::with_capacity(crate::utils::speculative_capacity(self.entry.uncompressed_size.unwrap()));
         self.read_to_string(&mut buffer).await?;
 
         if self.compare_crc() {
@@ -157,6 +880,40 @@ impl<'a, R: AsyncRead + Unpin> ZipEntryReader<'a, R> {
         }
     }
 
+    /// A convenience method similar to [`ZipEntryReader::read_to_end_crc()`] which refuses entries larger than the
+    /// provided maximum size, failing with [`ZipError::EntryTooLarge`].
+    ///
+    /// Entries which declare a larger uncompressed size are refused before any data is read, and otherwise reading
+    /// stops as soon as more data than the maximum has been read (as entries followed by data descriptors declare no
+    /// size). Data left unread is skipped when a stream reader's next entry is opened, except for an entry followed by
+    /// a data descriptor, as its end can only be found by reading it. Data which doesn't match the entry's CRC32 value
+    /// fails with [`ZipError::CrcMismatch`].
+    pub async fn read_to_end_limited(mut self, max_size: u64) -> Result<Vec<u8>> {
+        let declared = self.expected_size().unwrap_or(0);
+        if declared > max_size {
+            return Err(ZipError::EntryTooLarge(declared, max_size));
+        }
+
+        let mut buffer = Vec::with_capacity(crate::utils::speculative_capacity(declared));
+        (&mut self).take(max_size.saturating_add(1)).read_to_end(&mut buffer).await?;
+
+        if buffer.len() as u64 > max_size {
+            return Err(ZipError::EntryTooLarge(buffer.len() as u64, max_size));
+        }
+
+        self.check_crc()?;
+        Ok(buffer)
+    }
+
+    /// A convenience method similar to [`ZipEntryReader::read_to_string_crc()`] which refuses entries larger than the
+    /// provided maximum size.
+    ///
+    /// See [`ZipEntryReader::read_to_end_limited()`] for details.
+    pub async fn read_to_string_limited(self, max_size: u64) -> Result<String> {
+        let buffer = self.read_to_end_limited(max_size).await?;
+        String::from_utf8(buffer).map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err).into())
+    }
+
     /// A convenience method for buffered copying of bytes to a writer with the final CRC32 check integrated.
     ///
     /// # Note
@@ -167,8 +924,10 @@ impl<'a, R: AsyncRead + Unpin> ZipEntryReader<'a, R> {
     /// modern systems), whereas, tokio's default implementation uses 2kb, so many more calls to read() have to take
     /// place.
     pub async fn copy_to_end_crc<W: AsyncWrite + Unpin>(mut self, writer: &mut W, buffer: usize) -> Result<()> {
-        let mut reader = BufReader::with_capacity(buffer, &mut self);
-        tokio::io::copy_buf(&mut reader, writer).await.unwrap();
+        let pooled = self.options.buffer_pool.take(buffer);
+        let mut reader = PooledBufReader::new(&mut self, pooled);
+        tokio::io::copy_buf(&mut reader, writer).await?;
+        drop(reader);
 
         if self.compare_crc() {
             Ok(())
@@ -176,10 +935,103 @@ impl<'a, R: AsyncRead + Unpin> ZipEntryReader<'a, R> {
             Err(ZipError::CRC32CheckError)
         }
     }
+
+    /// Reads the target path of a symbolic link entry (see [`ZipEntry::is_symlink()`]), which is held as its data,
+    /// with the CRC32 check integrated.
+    ///
+    /// Targets longer than [`MAX_SYMLINK_TARGET_LENGTH`] are refused. The target is returned as-is, so it may be
+    /// absolute or lead outside of the directory an archive is extracted into; extractors should check it before
+    /// creating the link.
+    pub async fn read_symlink_target(mut self) -> Result<PathBuf> {
+        let mut buffer = Vec::new();
+        (&mut self).take(MAX_SYMLINK_TARGET_LENGTH + 1).read_to_end(&mut buffer).await?;
+
+        if buffer.len() as u64 > MAX_SYMLINK_TARGET_LENGTH {
+            let size = self.entry.uncompressed_size.filter(|size| *size > MAX_SYMLINK_TARGET_LENGTH);
+            return Err(ZipError::EntryTooLarge(size.unwrap_or(buffer.len() as u64), MAX_SYMLINK_TARGET_LENGTH));
+        }
+        if !self.compare_crc() {
+            return Err(ZipError::CRC32CheckError);
+        }
+
+        #[cfg(unix)]
+        let target = PathBuf::from(<std::ffi::OsString as std::os::unix::ffi::OsStringExt>::from_vec(buffer));
+        #[cfg(not(unix))]
+        let target = PathBuf::from(String::from_utf8_lossy(&buffer).into_owned());
+
+        Ok(target)
+    }
+
+    /// Consumes this entry reader and returns a stream reader over the ZIP file stored within the entry.
+    ///
+    /// The nested reader never needs to read this entry's data to its end. If this entry was itself opened by a
+    /// stream reader, any data left unread is discarded when that reader's next entry is opened.
+    pub fn into_zip_reader(self) -> Result<crate::read::stream::ZipFileReader<Self>> {
+        let options = self.options.nested()?;
+
+        Ok(crate::read::stream::ZipFileReader::with_options(self, options))
+    }
 }
 
 impl<'a, R: AsyncRead + Unpin> AsyncRead for ZipEntryReader<'a, R> {
     fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        // Data which has filled the buffer (and so has already been validated) is returned first.
+        if self.position < self.filled {
+            let length = std::cmp::min(b.remaining(), self.filled - self.position);
+            let start = self.position;
+            b.put_slice(&self.buffer.as_ref().unwrap()[start..start + length]);
+            self.position += length;
+            return Poll::Ready(Ok(()));
+        }
+
+        // Only errors from the source itself are reported as truncation, rather than errors such as
+        // [`ZipError::DeclaredSizeMismatch`] which report data that doesn't match the entry's headers.
+        match self.as_mut().poll_read_entry(c, b) {
+            Poll::Ready(Err(error))
+                if self.options.detect_truncation
+                    && error.kind() == tokio::io::ErrorKind::UnexpectedEof
+                    && !error.get_ref().is_some_and(|inner| inner.is::<ZipError>()) =>
+            {
+                Poll::Ready(Err(tokio::io::Error::new(tokio::io::ErrorKind::UnexpectedEof, ZipError::Truncated)))
+            }
+            poll => poll,
+        }
+    }
+}
+
+impl<'a, R: AsyncRead + Unpin> AsyncBufRead for ZipEntryReader<'a, R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<&[u8]>> {
+        let this = self.get_mut();
+
+        if this.position >= this.filled {
+            let size = this.options.decoder_buffer_size.max(1);
+            let mut buffer = this.buffer.take().unwrap_or_else(|| this.options.buffer_pool.take(size));
+
+            let mut buf = ReadBuf::new(&mut buffer);
+            let poll = Pin::new(&mut *this).poll_read(c, &mut buf);
+            let filled = buf.filled().len();
+            this.buffer = Some(buffer);
+            ready!(poll)?;
+
+            this.position = 0;
+            this.filled = filled;
+        }
+
+        Poll::Ready(Ok(&this.buffer.as_ref().unwrap()[this.position..this.filled]))
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amount: usize) {
+        self.position = std::cmp::min(self.position + amount, self.filled);
+    }
+}
+
+impl<'a, R: AsyncRead + Unpin> ZipEntryReader<'a, R> {
+    /// Reads the entry's data, validating it against the entry's declared size, CRC32 value, and configured limits.
+    fn poll_read_entry(
+        mut self: Pin<&mut Self>,
+        c: &mut Context<'_>,
+        b: &mut ReadBuf<'_>,
+    ) -> Poll<tokio::io::Result<()>> {
         let prev_len = b.filled().len();
         let poll = Pin::new(&mut self.reader).poll_read(c, b);
 
@@ -188,20 +1040,51 @@ impl<'a, R: AsyncRead + Unpin> AsyncRead for ZipEntryReader<'a, R> {
             _ => {}
         };
 
-        if b.filled().len() - prev_len == 0 {
-            self.consumed = true;
+        let read = b.filled().len() - prev_len;
+
+        // Data beyond the expected size is never returned, as the entry's header (or decoder) can't be trusted. Reads
+        // which fail return nothing at all, so the whole read which overran the expected size is discarded.
+        if let Some(expected) = self.expected_size() {
+            if read as u64 > expected - self.read {
+                b.set_filled(prev_len);
+                let error = ZipError::UncompressedSizeExceeded;
+                return Poll::Ready(Err(tokio::io::Error::new(tokio::io::ErrorKind::InvalidData, error)));
+            }
+            if read == 0 && b.remaining() > 0 && self.read < expected {
+                let error = ZipError::DeclaredSizeMismatch(expected, self.read);
+                return Poll::Ready(Err(tokio::io::Error::new(tokio::io::ErrorKind::UnexpectedEof, error)));
+            }
+        }
+
+        if let Err(error) = self.check_limits(read as u64) {
+            b.set_filled(prev_len);
+            return Poll::Ready(Err(tokio::io::Error::new(tokio::io::ErrorKind::InvalidData, error)));
         }
 
+        self.read += read as u64;
+        match &mut self.descriptor {
+            DescriptorState::Expected { zip64, read: total } if read == 0 && b.remaining() > 0 => {
+                let (zip64, total) = (*zip64, *total);
+                match self.reader.poll_descriptor(c, total, zip64) {
+                    Poll::Ready(Ok(descriptor)) => self.descriptor = DescriptorState::Read(descriptor),
+                    Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                    Poll::Pending => return Poll::Pending,
+                };
+            }
+            DescriptorState::Expected { read: total, .. } => *total += read as u64,
+            _ => {}
+        };
+
         self.hasher.update(&b.filled()[prev_len..b.filled().len()]);
-        poll
-    }
-}
 
-impl<'a, R: AsyncRead + Unpin> Drop for ZipEntryReader<'a, R> {
-    fn drop(&mut self) {
-        if self.stream && !self.consumed {
-            panic!("Not all bytes of this reader were consumed before being dropped.");
+        if read == 0 && b.remaining() > 0 && !self.consumed {
+            self.consumed = true;
+            if self.options.verify_crc {
+                self.verify_crc()?;
+            }
         }
+
+        poll
     }
 }
 
@@ -210,18 +1093,58 @@ impl<'a, R: AsyncRead + Unpin> Drop for ZipEntryReader<'a, R> {
 ///
 /// This underpins entry reading functionality for all three sub-modules (stream, seek, and concurrent).
 pub(crate) enum CompressionReader<'a, R: AsyncRead + Unpin> {
-    Stored(Take<R>),
-    StoredBorrow(Take<&'a mut R>),
-    Deflate(DeflateDecoder<BufReader<Take<R>>>),
-    DeflateBorrow(DeflateDecoder<BufReader<Take<&'a mut R>>>),
-    Bz(BzDecoder<BufReader<Take<R>>>),
-    BzBorrow(BzDecoder<BufReader<Take<&'a mut R>>>),
-    Lzma(LzmaDecoder<BufReader<Take<R>>>),
-    LzmaBorrow(LzmaDecoder<BufReader<Take<&'a mut R>>>),
-    Zstd(ZstdDecoder<BufReader<Take<R>>>),
-    ZstdBorrow(ZstdDecoder<BufReader<Take<&'a mut R>>>),
-    Xz(XzDecoder<BufReader<Take<R>>>),
-    XzBorrow(XzDecoder<BufReader<Take<&'a mut R>>>),
+    Stored(DecryptReader<R>),
+    StoredBorrow(DecryptReader<&'a mut R>),
+    #[cfg(feature = "deflate")]
+    Deflate(DeflateDecoder<PooledBufReader<DecryptReader<R>>>),
+    #[cfg(feature = "deflate")]
+    DeflateBorrow(DeflateDecoder<PooledBufReader<DecryptReader<&'a mut R>>>),
+    Deflate64(Deflate64Decoder<PooledBufReader<DecryptReader<R>>>),
+    Deflate64Borrow(Deflate64Decoder<PooledBufReader<DecryptReader<&'a mut R>>>),
+    #[cfg(feature = "bzip2")]
+    Bz(BzDecoder<PooledBufReader<DecryptReader<R>>>),
+    #[cfg(feature = "bzip2")]
+    BzBorrow(BzDecoder<PooledBufReader<DecryptReader<&'a mut R>>>),
+    #[cfg(feature = "lzma")]
+    Lzma(LzmaDecoder<LzmaHeaderReader<PooledBufReader<DecryptReader<R>>>>),
+    #[cfg(feature = "lzma")]
+    LzmaBorrow(LzmaDecoder<LzmaHeaderReader<PooledBufReader<DecryptReader<&'a mut R>>>>),
+    #[cfg(feature = "zstd")]
+    Zstd(ZstdDecoder<PooledBufReader<DecryptReader<R>>>),
+    #[cfg(feature = "zstd")]
+    ZstdBorrow(ZstdDecoder<PooledBufReader<DecryptReader<&'a mut R>>>),
+    #[cfg(feature = "xz")]
+    Xz(XzDecoder<PooledBufReader<DecryptReader<R>>>),
+    #[cfg(feature = "xz")]
+    XzBorrow(XzDecoder<PooledBufReader<DecryptReader<&'a mut R>>>),
+    #[cfg(feature = "legacy-compression")]
+    Legacy(LegacyDecoder<DecryptReader<R>>),
+    #[cfg(feature = "legacy-compression")]
+    LegacyBorrow(LegacyDecoder<DecryptReader<&'a mut R>>),
+    #[cfg(feature = "ppmd")]
+    Ppmd(PpmdDecoder<DecryptReader<R>>),
+    #[cfg(feature = "ppmd")]
+    PpmdBorrow(PpmdDecoder<DecryptReader<&'a mut R>>),
+    Custom(BackendDecoder<PooledBufReader<DecryptReader<R>>>),
+    CustomBorrow(BackendDecoder<PooledBufReader<DecryptReader<&'a mut R>>>),
+    StoredShared(DecryptReader<SharedBufReader<'a, R>>),
+    StoredDescriptor(DescriptorScanner<'a, R>),
+    #[cfg(feature = "deflate")]
+    DeflateShared(DeflateDecoder<DecryptReader<SharedBufReader<'a, R>>>),
+    Deflate64Shared(Deflate64Decoder<DecryptReader<SharedBufReader<'a, R>>>),
+    #[cfg(feature = "bzip2")]
+    BzShared(BzDecoder<DecryptReader<SharedBufReader<'a, R>>>),
+    #[cfg(feature = "lzma")]
+    LzmaShared(LzmaDecoder<LzmaHeaderReader<DecryptReader<SharedBufReader<'a, R>>>>),
+    #[cfg(feature = "zstd")]
+    ZstdShared(ZstdDecoder<DecryptReader<SharedBufReader<'a, R>>>),
+    #[cfg(feature = "xz")]
+    XzShared(XzDecoder<DecryptReader<SharedBufReader<'a, R>>>),
+    #[cfg(feature = "legacy-compression")]
+    LegacyShared(LegacyDecoder<DecryptReader<SharedBufReader<'a, R>>>),
+    #[cfg(feature = "ppmd")]
+    PpmdShared(PpmdDecoder<DecryptReader<SharedBufReader<'a, R>>>),
+    CustomShared(BackendDecoder<DecryptReader<SharedBufReader<'a, R>>>),
 }
 
 impl<'a, R: AsyncRead + Unpin> AsyncRead for CompressionReader<'a, R> {
@@ -229,40 +1152,272 @@ impl<'a, R: AsyncRead + Unpin> AsyncRead for CompressionReader<'a, R> {
         match *self {
             CompressionReader::Stored(ref mut inner) => Pin::new(inner).poll_read(c, b),
             CompressionReader::StoredBorrow(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "deflate")]
             CompressionReader::Deflate(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "deflate")]
             CompressionReader::DeflateBorrow(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            CompressionReader::Deflate64(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            CompressionReader::Deflate64Borrow(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "bzip2")]
             CompressionReader::Bz(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "bzip2")]
             CompressionReader::BzBorrow(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "lzma")]
             CompressionReader::Lzma(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "lzma")]
             CompressionReader::LzmaBorrow(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "zstd")]
             CompressionReader::Zstd(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "zstd")]
             CompressionReader::ZstdBorrow(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "xz")]
             CompressionReader::Xz(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "xz")]
             CompressionReader::XzBorrow(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "legacy-compression")]
+            CompressionReader::Legacy(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "legacy-compression")]
+            CompressionReader::LegacyBorrow(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "ppmd")]
+            CompressionReader::Ppmd(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "ppmd")]
+            CompressionReader::PpmdBorrow(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            CompressionReader::Custom(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            CompressionReader::CustomBorrow(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            CompressionReader::StoredShared(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            CompressionReader::StoredDescriptor(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "deflate")]
+            CompressionReader::DeflateShared(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            CompressionReader::Deflate64Shared(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "bzip2")]
+            CompressionReader::BzShared(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "lzma")]
+            CompressionReader::LzmaShared(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "zstd")]
+            CompressionReader::ZstdShared(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "xz")]
+            CompressionReader::XzShared(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "legacy-compression")]
+            CompressionReader::LegacyShared(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            #[cfg(feature = "ppmd")]
+            CompressionReader::PpmdShared(ref mut inner) => Pin::new(inner).poll_read(c, b),
+            CompressionReader::CustomShared(ref mut inner) => Pin::new(inner).poll_read(c, b),
         }
     }
 }
 
 impl<'a, R: AsyncRead + Unpin> CompressionReader<'a, R> {
-    pub(crate) fn from_reader(compression: &Compression, reader: Take<R>) -> Self {
-        match compression {
-            Compression::Stored => CompressionReader::Stored(reader),
-            Compression::Deflate => CompressionReader::Deflate(DeflateDecoder::new(BufReader::new(reader))),
-            Compression::Bz => CompressionReader::Bz(BzDecoder::new(BufReader::new(reader))),
-            Compression::Lzma => CompressionReader::Lzma(LzmaDecoder::new(BufReader::new(reader))),
-            Compression::Zstd => CompressionReader::Zstd(ZstdDecoder::new(BufReader::new(reader))),
-            Compression::Xz => CompressionReader::Xz(XzDecoder::new(BufReader::new(reader))),
+    /// Constructs a reader over an entry's data, decrypting it first if the entry is encrypted.
+    pub(crate) fn from_reader(entry: &ZipEntry, reader: Take<R>, options: &ReaderOptions) -> Result<Self> {
+        let reader = DecryptReader::new(reader, entry, options)?;
+        let buffer = || options.buffer_pool.take(options.decoder_buffer_size);
+
+        if let Some(backend) = options.backend(entry.compression())? {
+            return Ok(CompressionReader::Custom(BackendDecoder::new(
+                PooledBufReader::new(reader, buffer()),
+                backend,
+            )?));
         }
+
+        Ok(match entry.compression() {
+            Compression::Stored => CompressionReader::Stored(reader),
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => {
+                CompressionReader::Deflate(DeflateDecoder::new(PooledBufReader::new(reader, buffer())))
+            }
+            Compression::Deflate64 => {
+                CompressionReader::Deflate64(Deflate64Decoder::new(PooledBufReader::new(reader, buffer())))
+            }
+            #[cfg(feature = "bzip2")]
+            Compression::Bz => CompressionReader::Bz(BzDecoder::new(PooledBufReader::new(reader, buffer()))),
+            #[cfg(feature = "lzma")]
+            Compression::Lzma => {
+                let reader = LzmaHeaderReader::new(PooledBufReader::new(reader, buffer()), entry);
+                CompressionReader::Lzma(LzmaDecoder::new(reader))
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => CompressionReader::Zstd(ZstdDecoder::new(PooledBufReader::new(reader, buffer()))),
+            #[cfg(feature = "xz")]
+            Compression::Xz => CompressionReader::Xz(XzDecoder::new(PooledBufReader::new(reader, buffer()))),
+            #[cfg(feature = "legacy-compression")]
+            Compression::Shrink | Compression::Implode => {
+                CompressionReader::Legacy(LegacyDecoder::new(reader, entry, options))
+            }
+            #[cfg(feature = "ppmd")]
+            Compression::Ppmd => CompressionReader::Ppmd(PpmdDecoder::new(reader, entry, options)),
+            // Methods whose Cargo feature is disabled (as custom methods are read via their backend).
+            compression => return Err(ZipError::UnsupportedCompressionError(compression.to_u16())),
+        })
     }
 
-    pub(crate) fn from_reader_borrow(compression: &Compression, reader: Take<&'a mut R>) -> Self {
-        match compression {
+    /// Constructs a reader over an entry's data from a mutable borrow of its source, decrypting it first if the entry
+    /// is encrypted.
+    pub(crate) fn from_reader_borrow(
+        entry: &ZipEntry,
+        reader: Take<&'a mut R>,
+        options: &ReaderOptions,
+    ) -> Result<Self> {
+        let reader = DecryptReader::new(reader, entry, options)?;
+        let buffer = || options.buffer_pool.take(options.decoder_buffer_size);
+
+        if let Some(backend) = options.backend(entry.compression())? {
+            let reader = PooledBufReader::new(reader, buffer());
+            return Ok(CompressionReader::CustomBorrow(BackendDecoder::new(reader, backend)?));
+        }
+
+        Ok(match entry.compression() {
             Compression::Stored => CompressionReader::StoredBorrow(reader),
-            Compression::Deflate => CompressionReader::DeflateBorrow(DeflateDecoder::new(BufReader::new(reader))),
-            Compression::Bz => CompressionReader::BzBorrow(BzDecoder::new(BufReader::new(reader))),
-            Compression::Lzma => CompressionReader::LzmaBorrow(LzmaDecoder::new(BufReader::new(reader))),
-            Compression::Zstd => CompressionReader::ZstdBorrow(ZstdDecoder::new(BufReader::new(reader))),
-            Compression::Xz => CompressionReader::XzBorrow(XzDecoder::new(BufReader::new(reader))),
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => {
+                CompressionReader::DeflateBorrow(DeflateDecoder::new(PooledBufReader::new(reader, buffer())))
+            }
+            Compression::Deflate64 => {
+                CompressionReader::Deflate64Borrow(Deflate64Decoder::new(PooledBufReader::new(reader, buffer())))
+            }
+            #[cfg(feature = "bzip2")]
+            Compression::Bz => CompressionReader::BzBorrow(BzDecoder::new(PooledBufReader::new(reader, buffer()))),
+            #[cfg(feature = "lzma")]
+            Compression::Lzma => {
+                let reader = LzmaHeaderReader::new(PooledBufReader::new(reader, buffer()), entry);
+                CompressionReader::LzmaBorrow(LzmaDecoder::new(reader))
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                CompressionReader::ZstdBorrow(ZstdDecoder::new(PooledBufReader::new(reader, buffer())))
+            }
+            #[cfg(feature = "xz")]
+            Compression::Xz => CompressionReader::XzBorrow(XzDecoder::new(PooledBufReader::new(reader, buffer()))),
+            #[cfg(feature = "legacy-compression")]
+            Compression::Shrink | Compression::Implode => {
+                CompressionReader::LegacyBorrow(LegacyDecoder::new(reader, entry, options))
+            }
+            #[cfg(feature = "ppmd")]
+            Compression::Ppmd => CompressionReader::PpmdBorrow(PpmdDecoder::new(reader, entry, options)),
+            // Methods whose Cargo feature is disabled (as custom methods are read via their backend).
+            compression => return Err(ZipError::UnsupportedCompressionError(compression.to_u16())),
+        })
+    }
+
+    /// Constructs a reader over an entry's data which is read through a stream reader's shared buffer.
+    ///
+    /// If the entry's size isn't known (ie. its data is followed by a data descriptor), compressed data is read until
+    /// its decoder reaches the end of the stream, whereas Stored data is scanned for the descriptor. Scanning for the
+    /// descriptor of an encrypted Stored entry isn't supported. Deflate64 data is decoded from an internal buffer (and
+    /// so may be read beyond the end of its stream), whilst Shrink, Implode, and PPMd data is decoded once it has been
+    /// read in full (and up to the uncompressed size), so none are supported for entries with data descriptors. Custom
+    /// data is read for as long as its backend's decoder consumes it.
+    pub(crate) fn from_shared(
+        entry: &ZipEntry,
+        reader: SharedBufReader<'a, R>,
+        size: Option<u64>,
+        options: &ReaderOptions,
+    ) -> Result<Self> {
+        let reader = match (entry.compression(), size) {
+            (Compression::Stored, None) if entry.encrypted() => {
+                return Err(ZipError::FeatureNotSupported("encrypted Stored entries with data descriptors"));
+            }
+            (Compression::Deflate64, None) => {
+                return Err(ZipError::FeatureNotSupported("Deflate64 entries with data descriptors"));
+            }
+            (Compression::Shrink | Compression::Implode, None) => {
+                return Err(ZipError::FeatureNotSupported("Shrink and Implode entries with data descriptors"));
+            }
+            (Compression::Ppmd, None) => {
+                return Err(ZipError::FeatureNotSupported("PPMd entries with data descriptors"));
+            }
+            (_, None) if matches!(entry.encryption(), Some(Encryption::Aes(_))) => {
+                return Err(ZipError::FeatureNotSupported("AES-encrypted entries with data descriptors"));
+            }
+            (Compression::Stored, None) => {
+                return Ok(CompressionReader::StoredDescriptor(DescriptorScanner::new(reader, entry.zip64())));
+            }
+            (_, size) => DecryptReader::new(reader.take(size.unwrap_or(u64::MAX)), entry, options)?,
+        };
+
+        if let Some(backend) = options.backend(entry.compression())? {
+            return Ok(CompressionReader::CustomShared(BackendDecoder::new(reader, backend)?));
+        }
+
+        Ok(match entry.compression() {
+            Compression::Stored => CompressionReader::StoredShared(reader),
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => CompressionReader::DeflateShared(DeflateDecoder::new(reader)),
+            Compression::Deflate64 => CompressionReader::Deflate64Shared(Deflate64Decoder::new(reader)),
+            #[cfg(feature = "bzip2")]
+            Compression::Bz => CompressionReader::BzShared(BzDecoder::new(reader)),
+            #[cfg(feature = "lzma")]
+            Compression::Lzma => CompressionReader::LzmaShared(LzmaDecoder::new(LzmaHeaderReader::new(reader, entry))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => CompressionReader::ZstdShared(ZstdDecoder::new(reader)),
+            #[cfg(feature = "xz")]
+            Compression::Xz => CompressionReader::XzShared(XzDecoder::new(reader)),
+            #[cfg(feature = "legacy-compression")]
+            Compression::Shrink | Compression::Implode => {
+                CompressionReader::LegacyShared(LegacyDecoder::new(reader, entry, options))
+            }
+            #[cfg(feature = "ppmd")]
+            Compression::Ppmd => CompressionReader::PpmdShared(PpmdDecoder::new(reader, entry, options)),
+            // Methods whose Cargo feature is disabled (as custom methods are read via their backend).
+            compression => return Err(ZipError::UnsupportedCompressionError(compression.to_u16())),
+        })
+    }
+
+    /// Returns the number of compressed bytes consumed so far by a reader constructed via
+    /// [`CompressionReader::from_shared()`] without a size, or `None` for other readers.
+    pub(crate) fn compressed_read(&self) -> Option<u64> {
+        let source = match self {
+            #[cfg(feature = "deflate")]
+            CompressionReader::DeflateShared(inner) => inner.get_ref().get_ref(),
+            #[cfg(feature = "bzip2")]
+            CompressionReader::BzShared(inner) => inner.get_ref().get_ref(),
+            #[cfg(feature = "lzma")]
+            CompressionReader::LzmaShared(inner) => inner.get_ref().get_ref().get_ref(),
+            #[cfg(feature = "zstd")]
+            CompressionReader::ZstdShared(inner) => inner.get_ref().get_ref(),
+            #[cfg(feature = "xz")]
+            CompressionReader::XzShared(inner) => inner.get_ref().get_ref(),
+            CompressionReader::CustomShared(inner) => inner.get_ref().get_ref(),
+            _ => return None,
+        };
+
+        // The source's limit started at u64::MAX, as with those passed to `descriptor::poll_read()`.
+        Some(u64::MAX - source.limit())
+    }
+
+    /// Reads the data descriptor which follows an entry's data, once a reader constructed via
+    /// [`CompressionReader::from_shared()`] without a size has been read to its end.
+    pub(crate) fn poll_descriptor(
+        &mut self,
+        c: &mut Context<'_>,
+        uncompressed_size: u64,
+        zip64: bool,
+    ) -> Poll<tokio::io::Result<DataDescriptor>> {
+        match self {
+            CompressionReader::StoredDescriptor(inner) => Poll::Ready(inner.descriptor()),
+            #[cfg(feature = "deflate")]
+            CompressionReader::DeflateShared(inner) => {
+                descriptor::poll_read(inner.get_mut().get_mut(), c, uncompressed_size, zip64)
+            }
+            #[cfg(feature = "bzip2")]
+            CompressionReader::BzShared(inner) => {
+                descriptor::poll_read(inner.get_mut().get_mut(), c, uncompressed_size, zip64)
+            }
+            #[cfg(feature = "lzma")]
+            CompressionReader::LzmaShared(inner) => {
+                descriptor::poll_read(inner.get_mut().get_mut().get_mut(), c, uncompressed_size, zip64)
+            }
+            #[cfg(feature = "zstd")]
+            CompressionReader::ZstdShared(inner) => {
+                descriptor::poll_read(inner.get_mut().get_mut(), c, uncompressed_size, zip64)
+            }
+            #[cfg(feature = "xz")]
+            CompressionReader::XzShared(inner) => {
+                descriptor::poll_read(inner.get_mut().get_mut(), c, uncompressed_size, zip64)
+            }
+            CompressionReader::CustomShared(inner) => {
+                descriptor::poll_read(inner.get_mut().get_mut(), c, uncompressed_size, zip64)
+            }
+            _ => unreachable!("only entries of unknown size are read up to a data descriptor"),
         }
     }
 }
@@ -274,16 +1429,62 @@ macro_rules! reader_entry_impl {
             &self.entries
         }
 
-        /// Searches for an entry with a specific filename.
+        /// Searches for an entry with a specific filename, comparing raw bytes (or for filenames of an unknown
+        /// encoding, also their decoded form).
+        ///
+        /// Entries are found via a map from their names to their indexes, which is built on the first lookup. Where
+        /// many entries share a name, the first is returned.
         pub fn entry(&self, name: &str) -> Option<(usize, &ZipEntry)> {
-            for (index, entry) in self.entries().iter().enumerate() {
-                if entry.name() == name {
-                    return Some((index, entry));
-                }
-            }
-            None
+            let index = self.entry_index(name)?;
+            Some((index, &self.entries[index]))
+        }
+
+        /// Returns the index of the entry with a specific filename (see [`Self::entry()`]).
+        pub fn entry_index(&self, name: &str) -> Option<usize> {
+            self.index.get(&self.entries, name)
+        }
+
+        /// Returns the entry with a specific filename (see [`Self::entry()`]).
+        pub fn entry_by_name(&self, name: &str) -> Option<&ZipEntry> {
+            self.entry(name).map(|(_, entry)| entry)
+        }
+
+        /// Returns an iterator over the entries whose names match a glob pattern (eg. `assets/**/*.png`), alongside
+        /// their indexes.
+        ///
+        /// See the [`glob`](crate::glob) module for the supported syntax.
+        pub fn entries_matching(
+            &self,
+            pattern: &str,
+        ) -> crate::error::Result<impl Iterator<Item = (usize, &ZipEntry)> + '_> {
+            let glob = crate::glob::Glob::new(pattern)?;
+            Ok(self.entries.iter().enumerate().filter(move |(_, entry)| glob.matches(entry.name())))
         }
     };
 }
 
+/// A map from entries' names to their indexes, built on the first lookup by name.
+///
+/// Entries are keyed by their raw filename bytes (and for filenames of an unknown encoding, also their decoded form),
+/// with the first of any entries sharing a key taking precedence.
+#[derive(Default)]
+pub(crate) struct EntryIndex(OnceLock<HashMap<Vec<u8>, usize>>);
+
+impl EntryIndex {
+    pub(crate) fn get(&self, entries: &[ZipEntry], name: &str) -> Option<usize> {
+        let map = self.0.get_or_init(|| {
+            let mut map = HashMap::with_capacity(entries.len());
+            for (index, entry) in entries.iter().enumerate() {
+                map.entry(entry.filename().as_bytes().to_vec()).or_insert(index);
+                if entry.filename().encoding() == crate::StringEncoding::Unknown {
+                    map.entry(entry.name().as_bytes().to_vec()).or_insert(index);
+                }
+            }
+            map
+        });
+
+        map.get(name.as_bytes()).copied()
+    }
+}
+
 pub(crate) use reader_entry_impl;
@@ -0,0 +1,46 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// An async reader wrapper which tracks the number of bytes read from its inner reader.
+///
+/// As every read made by a reader (headers, entry data, and skipped data alike) passes through this wrapper, the
+/// offset always reflects how far through the underlying source the reader has advanced.
+pub struct OffsetAsyncReader<R: AsyncRead + Unpin> {
+    reader: R,
+    offset: u64,
+}
+
+impl<R: AsyncRead + Unpin> OffsetAsyncReader<R> {
+    /// Constructs a new offset reader from a generic reader implementing AsyncRead.
+    pub fn from_raw(reader: R) -> Self {
+        Self { reader, offset: 0 }
+    }
+
+    /// Returns the number of bytes read from the inner reader so far.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Consumes this reader and returns its inner value.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for OffsetAsyncReader<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let prev_len = b.filled().len();
+        let poll = Pin::new(&mut self.reader).poll_read(c, b);
+
+        if let Poll::Ready(Ok(())) = poll {
+            self.offset += (b.filled().len() - prev_len) as u64;
+        }
+
+        poll
+    }
+}
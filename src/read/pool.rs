@@ -0,0 +1,274 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+/// The default maximum number of idle buffers retained by a pool.
+pub const DEFAULT_POOL_MAX_BUFFERS: usize = 16;
+
+/// The default maximum capacity of a buffer retained by a pool (1 MiB).
+pub const DEFAULT_POOL_MAX_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// A pool of reusable byte buffers from which readers take their decoding, copying, and skipping buffers.
+///
+/// Buffers are returned to the pool once the entry reader (or operation) using them completes, so reading many small
+/// entries doesn't allocate fresh buffers for each one. A single pool may be shared between many readers by wrapping
+/// it in an [`Arc`] and passing it to [`ReaderOptions::buffer_pool()`].
+///
+/// [`ReaderOptions::buffer_pool()`]: crate::read::ReaderOptions::buffer_pool
+#[derive(Debug)]
+pub struct BufferPool {
+    inner: Mutex<PoolInner>,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    buffers: Vec<Vec<u8>>,
+    max_buffers: usize,
+    max_buffer_size: usize,
+    allocations: usize,
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        BufferPool::new(DEFAULT_POOL_MAX_BUFFERS, DEFAULT_POOL_MAX_BUFFER_SIZE)
+    }
+}
+
+impl BufferPool {
+    /// Constructs a new pool which retains at most `max_buffers` idle buffers, each of at most `max_buffer_size` bytes.
+    ///
+    /// Buffers larger than the maximum size are still handed out when requested, but are freed rather than retained.
+    pub fn new(max_buffers: usize, max_buffer_size: usize) -> Self {
+        let inner = PoolInner { buffers: Vec::new(), max_buffers, max_buffer_size, allocations: 0 };
+        BufferPool { inner: Mutex::new(inner) }
+    }
+
+    /// Takes a buffer of exactly `size` bytes from the pool, allocating one if no idle buffer is large enough.
+    pub(crate) fn take(self: &Arc<Self>, size: usize) -> PooledBuffer {
+        let mut inner = self.inner.lock().unwrap();
+
+        let buffer = match inner.buffers.iter().position(|buffer| buffer.capacity() >= size) {
+            Some(index) => inner.buffers.swap_remove(index),
+            None => {
+                inner.allocations += 1;
+                Vec::with_capacity(size)
+            }
+        };
+        drop(inner);
+
+        let mut buffer = buffer;
+        buffer.resize(size, 0);
+        PooledBuffer { buffer, pool: self.clone() }
+    }
+
+    /// Returns the number of buffers this pool has had to allocate (ie. the number of times no idle buffer was reused).
+    pub fn allocations(&self) -> usize {
+        self.inner.lock().unwrap().allocations
+    }
+
+    fn release(&self, buffer: Vec<u8>) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.buffers.len() < inner.max_buffers && buffer.capacity() <= inner.max_buffer_size {
+            inner.buffers.push(buffer);
+        }
+    }
+}
+
+/// A buffer taken from a [`BufferPool`] which is returned to it when dropped.
+pub(crate) struct PooledBuffer {
+    buffer: Vec<u8>,
+    pool: Arc<BufferPool>,
+}
+
+impl Deref for PooledBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        self.pool.release(std::mem::take(&mut self.buffer));
+    }
+}
+
+/// An equivalent of tokio's `BufReader` whose buffer is taken from (and returned to) a [`BufferPool`].
+pub(crate) struct PooledBufReader<R: AsyncRead + Unpin> {
+    reader: R,
+    buffer: PooledBuffer,
+    position: usize,
+    filled: usize,
+}
+
+impl<R: AsyncRead + Unpin> PooledBufReader<R> {
+    pub(crate) fn new(reader: R, buffer: PooledBuffer) -> Self {
+        Self { reader, buffer, position: 0, filled: 0 }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncBufRead for PooledBufReader<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<&[u8]>> {
+        let this = self.get_mut();
+
+        if this.position >= this.filled {
+            let mut buf = ReadBuf::new(&mut this.buffer);
+            match Pin::new(&mut this.reader).poll_read(c, &mut buf) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            this.filled = buf.filled().len();
+            this.position = 0;
+        }
+
+        Poll::Ready(Ok(&this.buffer[this.position..this.filled]))
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amount: usize) {
+        self.position = std::cmp::min(self.position + amount, self.filled);
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for PooledBufReader<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        // Bypass our buffer entirely if it's empty and the caller's buffer is at least as large.
+        if self.position >= self.filled && b.remaining() >= self.buffer.len() {
+            return Pin::new(&mut self.reader).poll_read(c, b);
+        }
+
+        let available = match self.as_mut().poll_fill_buf(c) {
+            Poll::Ready(Ok(available)) => available,
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        let amount = std::cmp::min(available.len(), b.remaining());
+        b.put_slice(&available[..amount]);
+        self.consume(amount);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A buffer which outlives the readers borrowing it, so that bytes read ahead by one are seen by the next.
+///
+/// The stream reader reads every header and entry through this buffer. As decoders only consume the compressed data
+/// they decode, the bytes which follow an entry of unknown size (ie. its data descriptor) are never lost.
+pub(crate) struct SharedBuffer {
+    buffer: PooledBuffer,
+    position: usize,
+    filled: usize,
+    entry_ended: bool,
+}
+
+impl SharedBuffer {
+    pub(crate) fn new(buffer: PooledBuffer) -> Self {
+        Self { buffer, position: 0, filled: 0, entry_ended: false }
+    }
+
+    /// Returns the number of bytes which have been read from the inner reader but not yet consumed.
+    pub(crate) fn buffered(&self) -> usize {
+        self.filled - self.position
+    }
+
+    /// Returns whether the entry being read has been read up to and including its data descriptor.
+    pub(crate) fn entry_ended(&self) -> bool {
+        self.entry_ended
+    }
+
+    /// Sets whether the entry being read has been read up to and including its data descriptor.
+    pub(crate) fn set_entry_ended(&mut self, ended: bool) {
+        self.entry_ended = ended;
+    }
+}
+
+/// A buffered reader over a mutable borrow of an inner reader and of the [`SharedBuffer`] in front of it.
+pub(crate) struct SharedBufReader<'a, R: AsyncRead + Unpin> {
+    reader: &'a mut R,
+    shared: &'a mut SharedBuffer,
+}
+
+impl<'a, R: AsyncRead + Unpin> SharedBufReader<'a, R> {
+    pub(crate) fn new(reader: &'a mut R, shared: &'a mut SharedBuffer) -> Self {
+        Self { reader, shared }
+    }
+
+    /// Returns a mutable reference to the shared buffer.
+    pub(crate) fn shared_mut(&mut self) -> &mut SharedBuffer {
+        self.shared
+    }
+
+    /// Fills the buffer until at least `minimum` bytes are available (or the inner reader is exhausted), moving any
+    /// unconsumed bytes to its start if they'd otherwise not fit.
+    ///
+    /// `minimum` is capped to the capacity of the buffer.
+    pub(crate) fn poll_fill_min(&mut self, c: &mut Context<'_>, minimum: usize) -> Poll<tokio::io::Result<&[u8]>> {
+        let shared = &mut *self.shared;
+        let minimum = std::cmp::min(minimum, shared.buffer.len());
+
+        while shared.filled - shared.position < minimum {
+            if shared.position > 0 {
+                shared.buffer.copy_within(shared.position..shared.filled, 0);
+                shared.filled -= shared.position;
+                shared.position = 0;
+            }
+
+            let mut buf = ReadBuf::new(&mut shared.buffer[shared.filled..]);
+            match Pin::new(&mut *self.reader).poll_read(c, &mut buf) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            match buf.filled().len() {
+                0 => break,
+                read => shared.filled += read,
+            }
+        }
+
+        Poll::Ready(Ok(&shared.buffer[shared.position..shared.filled]))
+    }
+}
+
+impl<'a, R: AsyncRead + Unpin> AsyncBufRead for SharedBufReader<'a, R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<&[u8]>> {
+        self.get_mut().poll_fill_min(c, 1)
+    }
+
+    fn consume(self: Pin<&mut Self>, amount: usize) {
+        let shared = &mut *self.get_mut().shared;
+        shared.position = std::cmp::min(shared.position + amount, shared.filled);
+    }
+}
+
+impl<'a, R: AsyncRead + Unpin> AsyncRead for SharedBufReader<'a, R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let available = match self.as_mut().poll_fill_buf(c) {
+            Poll::Ready(Ok(available)) => available,
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        let amount = std::cmp::min(available.len(), b.remaining());
+        b.put_slice(&available[..amount]);
+        self.consume(amount);
+
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A decoder for PPMd (variant I, revision 1), as written by 7-Zip.
+//!
+//! The `ppmd-rust` decoder only implements the blocking [`Read`] trait, and can't resume decoding a symbol whose input
+//! ran out part-way through. So, compressed data is read ahead into a buffer, and until the inner reader is exhausted,
+//! only as many symbols are decoded as the buffered data is certain to hold. The data is prefixed by two bytes holding
+//! the model's order, memory size, and restore method, and isn't necessarily terminated by an end marker, so decoding
+//! stops once the entry's uncompressed size is reached.
+//!
+//! The model's memory size is chosen by the archive (up to 256 MiB), so it's checked against
+//! [`ReaderOptions::max_decoder_memory()`] before the model is allocated.
+
+use crate::error::ZipError;
+use crate::read::{ReaderOptions, ZipEntry};
+
+use std::io::Read;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use ppmd_rust::{Ppmd8Decoder, RestoreMethod, PPMD8_MAX_ORDER, PPMD8_MIN_ORDER};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// The most compressed bytes which decoding a single symbol may consume.
+///
+/// A symbol is decoded within at most one context of each order (and the order-(-1) context), and the range decoder
+/// reads at most four bytes for each.
+const SYMBOL_INPUT: usize = 4 * (PPMD8_MAX_ORDER as usize + 2);
+
+/// The length of the header (two bytes) and of the range decoder's initial code (four bytes).
+const HEADER_INPUT: usize = 6;
+
+/// The number of compressed bytes which are read ahead of the decoder.
+const READ_AHEAD: usize = 64 * 1024;
+
+/// Compressed data which has been read from the inner reader but not yet consumed by the decoder.
+#[derive(Default)]
+struct Input {
+    bytes: Vec<u8>,
+    position: usize,
+}
+
+impl Input {
+    fn available(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    fn extend(&mut self, bytes: &[u8]) {
+        self.bytes.drain(..self.position);
+        self.position = 0;
+        self.bytes.extend_from_slice(bytes);
+    }
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let length = std::cmp::min(buf.len(), self.available());
+        buf[..length].copy_from_slice(&self.bytes[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+/// A decoder which decompresses PPMd data read from an inner reader.
+pub(crate) struct PpmdDecoder<R> {
+    reader: R,
+    remaining: u64,
+    max_memory: Option<u64>,
+    exhausted: bool,
+    input: Input,
+
+    // The decoder (which owns the buffered input), once enough data has been read to construct it.
+    decoder: Option<Box<Ppmd8Decoder<Input>>>,
+}
+
+impl<R> PpmdDecoder<R> {
+    pub(crate) fn new(reader: R, entry: &ZipEntry, options: &ReaderOptions) -> Self {
+        let remaining = entry.uncompressed_size.unwrap_or_default();
+        let max_memory = options.max_decoder_memory;
+        PpmdDecoder { reader, remaining, max_memory, exhausted: false, input: Input::default(), decoder: None }
+    }
+
+    /// Returns the buffered compressed data.
+    fn input(&mut self) -> &mut Input {
+        match &mut self.decoder {
+            Some(decoder) => decoder.get_mut(),
+            None => &mut self.input,
+        }
+    }
+
+    /// Returns the number of symbols which can be decoded without the buffered data running out.
+    fn decodable(&mut self) -> usize {
+        let reserved = if self.decoder.is_some() { 0 } else { HEADER_INPUT };
+        match self.exhausted {
+            true => usize::MAX,
+            false => self.input().available().saturating_sub(reserved) / SYMBOL_INPUT,
+        }
+    }
+
+    /// Constructs a decoder over the buffered data (starting with its two-byte parameter header).
+    fn open(&mut self) -> tokio::io::Result<Ppmd8Decoder<Input>> {
+        let invalid = |reason| tokio::io::Error::new(tokio::io::ErrorKind::InvalidData, reason);
+
+        let mut input = std::mem::take(&mut self.input);
+        let mut header = [0; 2];
+        input.read_exact(&mut header)?;
+        let header = u16::from_le_bytes(header);
+
+        let order = (header & 0xF) as u32 + 1;
+        let memory = (((header >> 4) & 0xFF) as u32 + 1) << 20;
+        let restore_method = RestoreMethod::from(header >> 12);
+        if order < PPMD8_MIN_ORDER || restore_method == RestoreMethod::Unsupported {
+            return Err(invalid("invalid PPMd parameters"));
+        }
+
+        if let Some(limit) = self.max_memory.filter(|limit| memory as u64 > *limit) {
+            let error = ZipError::DecompressionLimitExceeded("PPMd model's memory size", limit);
+            return Err(tokio::io::Error::new(tokio::io::ErrorKind::InvalidData, error));
+        }
+
+        Ppmd8Decoder::new(input, order, memory, restore_method)
+            .map_err(|_| invalid("failed to initialise PPMd decoder"))
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for PpmdDecoder<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let this = &mut *self;
+        if this.remaining == 0 || b.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        // Read ahead of the decoder, only decoding what's already buffered if the inner reader isn't ready.
+        while !this.exhausted && this.input().available() < READ_AHEAD {
+            let mut chunk = [0; 8192];
+            let mut chunk = ReadBuf::new(&mut chunk);
+
+            match Pin::new(&mut this.reader).poll_read(c, &mut chunk)? {
+                Poll::Ready(()) if chunk.filled().is_empty() => this.exhausted = true,
+                Poll::Ready(()) => this.input().extend(chunk.filled()),
+                Poll::Pending if this.decodable() == 0 => return Poll::Pending,
+                Poll::Pending => break,
+            }
+        }
+
+        let decodable = this.decodable();
+        if this.decoder.is_none() {
+            this.decoder = Some(Box::new(this.open()?));
+        }
+
+        let limit = std::cmp::min(std::cmp::min(this.remaining, b.remaining() as u64) as usize, decodable);
+        let read = this.decoder.as_mut().unwrap().read(&mut b.initialize_unfilled()[..limit])?;
+        b.advance(read);
+        this.remaining -= read as u64;
+
+        Poll::Ready(Ok(()))
+    }
+}
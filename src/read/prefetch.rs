@@ -0,0 +1,346 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module for reading the entries of a seekable ZIP file in order, with optional prefetching.
+//!
+//! When reading from a high-latency source, waiting for an entry's data and then decompressing it leaves the source
+//! idle half of the time. With prefetching enabled via [`ReaderOptions::prefetch()`], the compressed data of upcoming
+//! entries is fetched (using the offsets known from the central directory) whilst the current entry is being read, so
+//! that the two overlap.
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::read::prefetch::PrefetchOptions;
+//! # use async_zip::read::seek::ZipFileReader;
+//! # use async_zip::read::ReaderOptions;
+//! # use tokio::fs::File;
+//! # use async_zip::error::ZipError;
+//! #
+//! # async fn run() -> Result<(), ZipError> {
+//! let options = ReaderOptions::new().prefetch(PrefetchOptions::new().max_entries(4));
+//! let mut zip = ZipFileReader::with_options(File::open("./Archive.zip").await?, options).await?;
+//! let mut entries = zip.sequential();
+//!
+//! while let Some(reader) = entries.next_entry().await? {
+//!     println!("{}", reader.read_to_string_crc().await?);
+//! }
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::error::{Result, ZipError};
+use crate::read::{CompressionReader, ReaderOptions, ZipEntry, ZipEntryReader};
+use crate::spec::header::LocalFileHeader;
+
+use std::collections::VecDeque;
+use std::io::{Cursor, SeekFrom};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, ReadBuf};
+
+/// The default maximum number of entries fetched ahead of the one being read.
+pub const DEFAULT_PREFETCH_ENTRIES: usize = 2;
+
+/// The default maximum number of compressed bytes held for entries fetched ahead of the one being read (16 MiB).
+pub const DEFAULT_PREFETCH_BYTES: u64 = 16 * 1024 * 1024;
+
+/// A set of options which bound how far ahead entries are prefetched.
+#[derive(Debug, Clone, Copy)]
+pub struct PrefetchOptions {
+    max_entries: usize,
+    max_bytes: u64,
+}
+
+impl Default for PrefetchOptions {
+    fn default() -> Self {
+        PrefetchOptions { max_entries: DEFAULT_PREFETCH_ENTRIES, max_bytes: DEFAULT_PREFETCH_BYTES }
+    }
+}
+
+impl PrefetchOptions {
+    /// Construct a new set of options with their default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Consume the options and override the maximum number of entries fetched ahead of the one being read (defaults to
+    /// [`DEFAULT_PREFETCH_ENTRIES`]).
+    pub fn max_entries(mut self, entries: usize) -> Self {
+        self.max_entries = entries;
+        self
+    }
+
+    /// Consume the options and override the maximum number of compressed bytes held for entries fetched ahead of the
+    /// one being read (defaults to [`DEFAULT_PREFETCH_BYTES`]).
+    ///
+    /// An entry larger than this limit is never fetched into memory; once it's the next to be read, it's read directly
+    /// from the inner reader instead (and nothing is fetched ahead of it until it has been).
+    pub fn max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = bytes;
+        self
+    }
+}
+
+/// A reader which yields the entries of a seekable ZIP file in central directory order.
+///
+/// This reader cannot be manually constructed; instead, use [`seek::ZipFileReader::sequential()`]. If prefetching
+/// is enabled in the reader's options, each entry's compressed data is fetched into memory ahead of time. Otherwise,
+/// entries are read directly from the inner reader as [`seek::ZipFileReader::entry_reader()`] would.
+///
+/// [`seek::ZipFileReader::sequential()`]: crate::read::seek::ZipFileReader::sequential
+/// [`seek::ZipFileReader::entry_reader()`]: crate::read::seek::ZipFileReader::entry_reader
+pub struct SequentialReader<'a, R: AsyncRead + AsyncSeek + Unpin> {
+    reader: &'a mut R,
+    entries: &'a [ZipEntry],
+    options: &'a ReaderOptions,
+    queue: Option<PrefetchQueue>,
+    index: usize,
+}
+
+impl<'a, R: AsyncRead + AsyncSeek + Unpin> SequentialReader<'a, R> {
+    pub(crate) fn from_raw(reader: &'a mut R, entries: &'a [ZipEntry], options: &'a ReaderOptions) -> Self {
+        let queue = options.prefetch.map(PrefetchQueue::new);
+        SequentialReader { reader, entries, options, queue, index: 0 }
+    }
+
+    /// Opens the next entry for reading, or returns `None` once every entry has been read.
+    ///
+    /// Entries which fail to be read don't stop the following entries from being opened.
+    pub async fn next_entry(&mut self) -> Result<Option<ZipEntryReader<'_, SequentialData<'_, R>>>> {
+        let entry = match self.entries.get(self.index) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        self.index += 1;
+
+        let data = match &mut self.queue {
+            Some(queue) if !queue.fetches(entry) => {
+                queue.skip();
+                if entry.partial() {
+                    return Err(ZipError::PartialEntry);
+                }
+
+                entry.seek_to_data_offset(self.reader).await?;
+                SequentialData(Source::Direct(&mut *self.reader))
+            }
+            Some(queue) => {
+                let (reader, entries) = (&mut *self.reader, self.entries);
+                let data = std::future::poll_fn(|cx| queue.poll_take(reader, entries, cx)).await?;
+                SequentialData(Source::Buffered { data, reader: &mut *self.reader, entries: self.entries, queue })
+            }
+            None if entry.partial() => return Err(ZipError::PartialEntry),
+            None => {
+                entry.seek_to_data_offset(self.reader).await?;
+                SequentialData(Source::Direct(&mut *self.reader))
+            }
+        };
+
+        let reader = data.take(entry.compressed_size.unwrap());
+        let reader = CompressionReader::from_reader(entry, reader, self.options)?;
+
+        Ok(Some(ZipEntryReader::from_raw(entry, reader, self.options)))
+    }
+}
+
+/// The source of an entry's compressed data when reading sequentially.
+///
+/// Reading prefetched data also drives the fetches of the entries which follow it.
+pub struct SequentialData<'b, R: AsyncRead + AsyncSeek + Unpin>(Source<'b, R>);
+
+enum Source<'b, R: AsyncRead + AsyncSeek + Unpin> {
+    Buffered { data: Cursor<Vec<u8>>, reader: &'b mut R, entries: &'b [ZipEntry], queue: &'b mut PrefetchQueue },
+    Direct(&'b mut R),
+}
+
+impl<'b, R: AsyncRead + AsyncSeek + Unpin> AsyncRead for SequentialData<'b, R> {
+    fn poll_read(self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        match &mut self.get_mut().0 {
+            Source::Buffered { data, reader, entries, queue } => {
+                // Only progress the fetches here; the data we're reading is already in memory so never waits on them.
+                let _ = queue.poll_fetch(reader, entries, false, c);
+                Pin::new(data).poll_read(c, b)
+            }
+            Source::Direct(reader) => Pin::new(reader).poll_read(c, b),
+        }
+    }
+}
+
+/// A bounded queue of the compressed data of entries fetched ahead of the one being read.
+struct PrefetchQueue {
+    options: PrefetchOptions,
+    ready: VecDeque<(Result<Cursor<Vec<u8>>>, u64)>,
+    current: Option<Fetch>,
+    buffered: u64,
+    taken: usize,
+    next: usize,
+}
+
+impl PrefetchQueue {
+    fn new(options: PrefetchOptions) -> Self {
+        PrefetchQueue { options, ready: VecDeque::new(), current: None, buffered: 0, taken: 0, next: 0 }
+    }
+
+    /// Returns whether the provided entry's data is fetched into memory, rather than read directly once it's reached.
+    fn fetches(&self, entry: &ZipEntry) -> bool {
+        entry.compressed_size.unwrap() <= self.options.max_bytes
+    }
+
+    /// Moves past the next entry without fetching it, as it's to be read directly.
+    ///
+    /// Fetches stop short of entries which aren't fetched, so nothing has been fetched beyond it.
+    fn skip(&mut self) {
+        self.next += 1;
+        self.taken += 1;
+    }
+
+    /// Returns whether the fetch of the next entry should be started.
+    ///
+    /// A fetch is forced when the entry being waited on hasn't been fetched yet, regardless of the number of entries
+    /// fetched ahead. As nothing else is buffered at that point, it still fits within the byte limit.
+    fn should_fetch(&self, entries: &[ZipEntry], force: bool) -> bool {
+        let Some(entry) = entries.get(self.next) else {
+            return false;
+        };
+        let ahead = self.next - self.taken;
+        let fits = self.buffered + entry.compressed_size.unwrap() <= self.options.max_bytes;
+
+        fits && ((force && ahead == 0) || ahead < self.options.max_entries)
+    }
+
+    /// Progresses the in-flight fetch and starts new ones until either a fetch is pending or the limits are reached.
+    fn poll_fetch<R: AsyncRead + AsyncSeek + Unpin>(
+        &mut self,
+        reader: &mut R,
+        entries: &[ZipEntry],
+        force: bool,
+        cx: &mut Context<'_>,
+    ) -> Poll<()> {
+        loop {
+            if self.current.is_none() {
+                if !self.should_fetch(entries, force) {
+                    return Poll::Ready(());
+                }
+
+                self.buffered += entries[self.next].compressed_size.unwrap();
+                self.current = Some(Fetch::Seek { started: false });
+            }
+
+            let entry = &entries[self.next];
+            let result = match entry.partial() {
+                true => Err(ZipError::PartialEntry),
+                false => ready!(self.current.as_mut().unwrap().poll(reader, entry, cx)),
+            };
+
+            self.ready.push_back((result, entry.compressed_size.unwrap()));
+            self.current = None;
+            self.next += 1;
+        }
+    }
+
+    /// Waits for the data of the next entry to be fetched and removes it from the queue.
+    fn poll_take<R: AsyncRead + AsyncSeek + Unpin>(
+        &mut self,
+        reader: &mut R,
+        entries: &[ZipEntry],
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Cursor<Vec<u8>>>> {
+        let fetched = self.ready.is_empty().then(|| self.poll_fetch(reader, entries, true, cx));
+
+        let (result, size) = match self.ready.pop_front() {
+            Some(ready) => ready,
+            None if matches!(fetched, Some(Poll::Pending)) => return Poll::Pending,
+            None => unreachable!("the entry being taken is always fetched"),
+        };
+
+        self.buffered -= size;
+        self.taken += 1;
+        Poll::Ready(result)
+    }
+}
+
+/// The stages of fetching a single entry's compressed data.
+enum Fetch {
+    Seek { started: bool },
+    Header { buffer: [u8; 30], filled: usize },
+    Data { buffer: Vec<u8>, filled: usize, length: usize, skip: usize },
+}
+
+impl Fetch {
+    fn poll<R: AsyncRead + AsyncSeek + Unpin>(
+        &mut self,
+        reader: &mut R,
+        entry: &ZipEntry,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Cursor<Vec<u8>>>> {
+        loop {
+            match self {
+                Fetch::Seek { started } => {
+                    if !*started {
+                        Pin::new(&mut *reader).start_seek(SeekFrom::Start(entry.offset.unwrap()))?;
+                        *started = true;
+                    }
+
+                    ready!(Pin::new(&mut *reader).poll_complete(cx))?;
+                    *self = Fetch::Header { buffer: [0; 30], filled: 0 };
+                }
+                Fetch::Header { buffer, filled } => {
+                    ready!(poll_fill(reader, buffer, filled, cx))?;
+
+                    let delimiter = u32::from_le_bytes(buffer[0..4].try_into().unwrap());
+                    if delimiter != crate::spec::delimiter::LFHD {
+                        return Poll::Ready(Err(ZipError::UnexpectedHeaderError(
+                            delimiter,
+                            crate::spec::delimiter::LFHD,
+                        )));
+                    }
+
+                    // The filename and extra field lengths may differ from those stored in the central directory.
+                    let header = LocalFileHeader::from(<[u8; 26]>::try_from(&buffer[4..]).unwrap());
+                    let skip = usize::from(header.file_name_length) + usize::from(header.extra_field_length);
+                    let length =
+                        usize::try_from(entry.compressed_size.unwrap()).ok().and_then(|size| size.checked_add(skip));
+                    let length = length.ok_or(ZipError::HeaderOutOfBounds("entry data"))?;
+
+                    // Grow the buffer as data arrives rather than trusting the declared size up front.
+                    let buffer = Vec::new();
+                    *self = Fetch::Data { buffer, filled: 0, length, skip };
+                }
+                Fetch::Data { buffer, filled, length, skip } => {
+                    while *filled < *length {
+                        if *filled == buffer.len() {
+                            let grown = std::cmp::max(buffer.len() * 2, crate::read::DEFAULT_DECODER_BUFFER_SIZE);
+                            buffer.resize(std::cmp::min(grown, *length), 0);
+                        }
+
+                        ready!(poll_fill(reader, buffer, filled, cx))?;
+                    }
+
+                    let mut data = Cursor::new(std::mem::take(buffer));
+                    data.set_position(*skip as u64);
+                    return Poll::Ready(Ok(data));
+                }
+            }
+        }
+    }
+}
+
+/// Reads from a reader until the provided buffer is full, tracking progress across polls.
+fn poll_fill<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buffer: &mut [u8],
+    filled: &mut usize,
+    cx: &mut Context<'_>,
+) -> Poll<std::io::Result<()>> {
+    while *filled < buffer.len() {
+        let mut read_buf = ReadBuf::new(&mut buffer[*filled..]);
+        ready!(Pin::new(&mut *reader).poll_read(cx, &mut read_buf))?;
+
+        match read_buf.filled().len() {
+            0 => return Poll::Ready(Err(std::io::ErrorKind::UnexpectedEof.into())),
+            read => *filled += read,
+        }
+    }
+
+    Poll::Ready(Ok(()))
+}
@@ -0,0 +1,137 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Reconstruction of entry metadata by sequentially scanning local file headers, for archives whose central directory
+//! is missing or corrupt (eg. because the archive was truncated).
+
+use crate::error::{Result, ZipError};
+use crate::read::{ParseMode, ZipEntry};
+
+use std::io::SeekFrom;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+/// The size of each chunk read whilst searching for a data descriptor.
+const SCAN_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The size of a data descriptor including its delimiter.
+const DESCRIPTOR_LENGTH: usize = 16;
+
+/// Scans a reader from its start for local file headers and reconstructs the entries they describe.
+///
+/// Scanning stops at the central directory or at the end of the reader. Anything else which isn't a valid local file
+/// header (eg. garbage or a corrupt header) is skipped by searching forward for the next local file header signature.
+/// Entries whose data extends beyond the end of the reader are marked as partial.
+pub(crate) async fn scan_entries<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    mode: ParseMode,
+) -> Result<Vec<ZipEntry>> {
+    let length = reader.seek(SeekFrom::End(0)).await?;
+    let mut entries = Vec::new();
+    let mut offset = 0;
+
+    while offset < length {
+        reader.seek(SeekFrom::Start(offset)).await?;
+
+        let mut entry = match crate::read::stream::read_lfh(reader, mode).await {
+            Ok(Some(entry)) => entry,
+            Ok(None) | Err(ZipError::UpstreamReadError(_)) => break,
+            Err(_) => match find_lfh(reader, offset + 1, length).await? {
+                Some(next) => {
+                    offset = next;
+                    continue;
+                }
+                None => break,
+            },
+        };
+
+        let data_start = reader.stream_position().await?;
+        entry.offset = Some(offset);
+
+        offset = if entry.data_descriptor {
+            match find_descriptor(reader, data_start, length).await? {
+                Some((descriptor_offset, crc, compressed_size, uncompressed_size)) => {
+                    entry.crc32 = Some(crc);
+                    entry.compressed_size = Some(compressed_size.into());
+                    entry.uncompressed_size = Some(uncompressed_size.into());
+                    descriptor_offset + DESCRIPTOR_LENGTH as u64
+                }
+                None => {
+                    entry.partial = true;
+                    length
+                }
+            }
+        } else {
+            let data_end = data_start.saturating_add(entry.compressed_size.unwrap());
+            entry.partial = data_end > length;
+            data_end
+        };
+
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Searches for the data descriptor of an entry whose data starts at the provided offset.
+///
+/// As the signature may also appear within compressed data by chance, a candidate is only accepted if its compressed
+/// size matches its distance from the start of the data. Returns the descriptor's offset, CRC32 value, compressed
+/// size, and uncompressed size.
+async fn find_descriptor<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    data_start: u64,
+    length: u64,
+) -> Result<Option<(u64, u32, u32, u32)>> {
+    find_signature(reader, data_start, length, crate::spec::delimiter::DDD, DESCRIPTOR_LENGTH, |offset, bytes| {
+        let field = |start: usize| u32::from_le_bytes(bytes[start..start + 4].try_into().unwrap());
+        (field(8) as u64 == offset - data_start).then(|| (offset, field(4), field(8), field(12)))
+    })
+    .await
+}
+
+/// Searches for the offset of the next local file header signature at or after the provided offset.
+async fn find_lfh<R: AsyncRead + AsyncSeek + Unpin>(reader: &mut R, start: u64, length: u64) -> Result<Option<u64>> {
+    find_signature(reader, start, length, crate::spec::delimiter::LFHD, 4, |offset, _| Some(offset)).await
+}
+
+/// Searches forward from the provided offset for a signature, returning the first value produced by the predicate.
+///
+/// The predicate is passed the offset of each occurrence, along with the bytes of the provided window length which
+/// start at it (including the signature itself).
+async fn find_signature<R, T>(
+    reader: &mut R,
+    start: u64,
+    length: u64,
+    signature: u32,
+    window: usize,
+    mut predicate: impl FnMut(u64, &[u8]) -> Option<T>,
+) -> Result<Option<T>>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    let signature = signature.to_le_bytes();
+    let mut buffer = vec![0; SCAN_CHUNK_SIZE + window - 1];
+    let mut chunk_start = start;
+
+    while chunk_start < length {
+        reader.seek(SeekFrom::Start(chunk_start)).await?;
+        let available = std::cmp::min(buffer.len() as u64, length - chunk_start) as usize;
+        reader.read_exact(&mut buffer[..available]).await?;
+
+        for index in 0..available.saturating_sub(window - 1) {
+            if buffer[index..index + 4] != signature {
+                continue;
+            }
+
+            if let Some(value) = predicate(chunk_start + index as u64, &buffer[index..index + window]) {
+                return Ok(Some(value));
+            }
+        }
+
+        // Overlap consecutive chunks so that windows straddling a boundary aren't missed.
+        chunk_start += SCAN_CHUNK_SIZE as u64;
+    }
+
+    Ok(None)
+}
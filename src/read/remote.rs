@@ -0,0 +1,325 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module for reading archives from sources which only support reads of byte ranges (eg. HTTP servers supporting
+//! `Range` requests, or cloud object storage).
+//!
+//! A source implements [`AsyncRandomAccess`], and is adapted into a seekable reader by [`RandomAccessReader`]. Ranges
+//! are only fetched as they're read, so opening an archive fetches little more than its end of central directory
+//! header and central directory, and reading an entry fetches little more than its local file header and data.
+//!
+//! With the `object-store` feature, [`ObjectStoreSource`] implements this trait for an object within any store of the
+//! `object_store` crate (eg. Amazon S3, Google Cloud Storage, or Azure Blob Storage).
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::read::remote::{AsyncRandomAccess, RangeFuture};
+//! # use async_zip::read::seek::ZipFileReader;
+//! # use async_zip::error::ZipError;
+//! #
+//! /// A source which fetches ranges from some remote store.
+//! struct Remote;
+//!
+//! impl AsyncRandomAccess for Remote {
+//!     fn size(&self) -> RangeFuture<u64> {
+//!         Box::pin(async { todo!("request the size of the archive") })
+//!     }
+//!
+//!     fn read_at(&self, offset: u64, length: usize) -> RangeFuture<Vec<u8>> {
+//!         Box::pin(async move { todo!("request the bytes from `offset` to `offset + length`") })
+//!     }
+//! }
+//!
+//! # async fn run() -> Result<(), ZipError> {
+//! let mut zip = ZipFileReader::from_random_access(Remote).await?;
+//! let data = zip.entry_reader(0).await?.read_to_end_crc().await?;
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::error::Result;
+use crate::read::seek::ZipFileReader;
+use crate::read::ReaderOptions;
+
+use std::future::Future;
+use std::io::SeekFrom;
+use std::pin::Pin;
+#[cfg(feature = "object-store")]
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+#[cfg(feature = "object-store")]
+use object_store::{path::Path, ObjectStore};
+
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+/// The default minimum number of bytes requested from a source at once (64 KiB).
+pub const DEFAULT_RANGE_SIZE: usize = 64 * 1024;
+
+/// A future returned by an [`AsyncRandomAccess`] source.
+pub type RangeFuture<T> = Pin<Box<dyn Future<Output = std::io::Result<T>> + Send + 'static>>;
+
+/// A source which supports reading ranges of bytes at arbitrary offsets.
+///
+/// The returned futures may not borrow from the source, so any handles they require (eg. an HTTP client) should be
+/// cloned into them.
+pub trait AsyncRandomAccess {
+    /// Returns the total size of the source in bytes.
+    fn size(&self) -> RangeFuture<u64>;
+
+    /// Reads `length` bytes starting at `offset`.
+    ///
+    /// Requested ranges never extend beyond the size of the source. Fewer bytes may be returned than were requested,
+    /// but at least one must be.
+    fn read_at(&self, offset: u64, length: usize) -> RangeFuture<Vec<u8>>;
+}
+
+/// A reader which adapts an [`AsyncRandomAccess`] source into a seekable reader.
+///
+/// Each fetch requests at least [`DEFAULT_RANGE_SIZE`] bytes (or as set via [`RandomAccessReader::range_size()`]), and
+/// the most recently fetched range is retained, so the small reads made whilst parsing headers are served from memory.
+/// Seeking never makes a request by itself, and no request is made ahead of a read unless enabled via
+/// [`RandomAccessReader::read_ahead()`].
+pub struct RandomAccessReader<T: AsyncRandomAccess> {
+    source: T,
+    size: u64,
+    position: u64,
+    range_size: usize,
+    read_ahead: u64,
+
+    // The most recently fetched range, and the offset it starts at.
+    buffer: Vec<u8>,
+    buffer_offset: u64,
+
+    // A fetch which is yet to complete, and the offset it starts at.
+    pending: Option<(u64, RangeFuture<Vec<u8>>)>,
+}
+
+impl<T: AsyncRandomAccess> RandomAccessReader<T> {
+    /// Constructs a new reader over a source, fetching its size.
+    pub async fn new(source: T) -> std::io::Result<Self> {
+        let size = source.size().await?;
+        Ok(RandomAccessReader {
+            source,
+            size,
+            position: 0,
+            range_size: DEFAULT_RANGE_SIZE,
+            read_ahead: 0,
+            buffer: Vec::new(),
+            buffer_offset: 0,
+            pending: None,
+        })
+    }
+
+    /// Consume the reader and override the minimum number of bytes requested from the source at once (defaults to
+    /// [`DEFAULT_RANGE_SIZE`]).
+    pub fn range_size(mut self, size: usize) -> Self {
+        self.range_size = size.max(1);
+        self
+    }
+
+    /// Consume the reader and enable fetching ahead of reads (disabled by default).
+    ///
+    /// Whenever a read leaves fewer than `bytes` bytes fetched beyond the current position, the next range is requested
+    /// immediately, so that it's fetched whilst the data already read is being processed (eg. decompressed) rather than
+    /// once it's needed. Each request made ahead is of at least `bytes` bytes. This is most effective when reading
+    /// large entries from sources with high latency.
+    pub fn read_ahead(mut self, bytes: usize) -> Self {
+        self.read_ahead = bytes as u64;
+        self
+    }
+
+    /// Returns the total size of the source in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns a shared reference to the inner source.
+    pub fn get_ref(&self) -> &T {
+        &self.source
+    }
+
+    /// Consumes this reader and returns the inner source.
+    pub fn into_inner(self) -> T {
+        self.source
+    }
+}
+
+impl<T: AsyncRandomAccess> RandomAccessReader<T> {
+    /// Requests the range following the fetched data if too little remains ahead of the current position, and
+    /// progresses any such request, appending its data once complete.
+    ///
+    /// Failed requests are discarded, as the range is requested again (and any error surfaced) once it's read.
+    fn poll_ahead(&mut self, c: &mut Context<'_>) {
+        loop {
+            let end = self.buffer_offset + self.buffer.len() as u64;
+            if self.read_ahead == 0 || end >= self.size {
+                return;
+            }
+
+            match &self.pending {
+                Some((offset, _)) if *offset != end => return,
+                Some(_) => {}
+                None if end - self.position >= self.read_ahead => return,
+                None => {
+                    let length = std::cmp::max(self.read_ahead, self.range_size as u64);
+                    let length = std::cmp::min(length, self.size - end) as usize;
+                    self.pending = Some((end, self.source.read_at(end, length)));
+                }
+            }
+
+            let data = match self.pending.as_mut().unwrap().1.as_mut().poll(c) {
+                Poll::Ready(data) => data,
+                Poll::Pending => return,
+            };
+            self.pending = None;
+
+            match data {
+                Ok(data) if !data.is_empty() => {
+                    // Only the data at or beyond the current position is retained.
+                    let consumed = self.position.saturating_sub(self.buffer_offset);
+                    let consumed = std::cmp::min(consumed, self.buffer.len() as u64);
+                    self.buffer.drain(..consumed as usize);
+                    self.buffer_offset += consumed;
+                    self.buffer.extend_from_slice(&data);
+                }
+                _ => return,
+            }
+        }
+    }
+}
+
+impl<T: AsyncRandomAccess + Unpin> AsyncRead for RandomAccessReader<T> {
+    fn poll_read(self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let this = self.get_mut();
+
+        loop {
+            let start = this.position.wrapping_sub(this.buffer_offset);
+            if this.position >= this.buffer_offset && start < this.buffer.len() as u64 {
+                let available = &this.buffer[start as usize..];
+                let length = std::cmp::min(available.len(), b.remaining());
+                b.put_slice(&available[..length]);
+                this.position += length as u64;
+
+                this.poll_ahead(c);
+                return Poll::Ready(Ok(()));
+            }
+            if this.position >= this.size || b.remaining() == 0 {
+                return Poll::Ready(Ok(()));
+            }
+
+            // Any fetch left over from before a seek is abandoned.
+            if !matches!(&this.pending, Some((offset, _)) if *offset == this.position) {
+                let length = std::cmp::max(b.remaining(), this.range_size) as u64;
+                let length = std::cmp::min(length, this.size - this.position) as usize;
+                this.pending = Some((this.position, this.source.read_at(this.position, length)));
+            }
+
+            let (offset, fetch) = this.pending.as_mut().unwrap();
+            let data = ready!(fetch.as_mut().poll(c));
+            let offset = *offset;
+            this.pending = None;
+
+            let data = data?;
+            if data.is_empty() {
+                return Poll::Ready(Err(tokio::io::ErrorKind::UnexpectedEof.into()));
+            }
+            this.buffer = data;
+            this.buffer_offset = offset;
+        }
+    }
+}
+
+impl<T: AsyncRandomAccess + Unpin> AsyncSeek for RandomAccessReader<T> {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        let target = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+        };
+
+        self.position = target.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "seek to a negative or overflowing position")
+        })?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        Poll::Ready(Ok(self.position))
+    }
+}
+
+impl<T: AsyncRandomAccess + Unpin> ZipFileReader<RandomAccessReader<T>> {
+    /// Constructs a new ZIP file reader over a source which supports reading ranges of bytes.
+    ///
+    /// See the [module-level documentation](crate::read::remote) for details.
+    pub async fn from_random_access(source: T) -> Result<Self> {
+        Self::from_random_access_with_options(source, ReaderOptions::default()).await
+    }
+
+    /// Constructs a new ZIP file reader over a source which supports reading ranges of bytes and a set of options.
+    pub async fn from_random_access_with_options(source: T, options: ReaderOptions) -> Result<Self> {
+        Self::with_options(RandomAccessReader::new(source).await?, options).await
+    }
+}
+
+/// A source over an object within an [`ObjectStore`], whose ranges are fetched via ranged GET requests.
+///
+/// # Example
+/// ```no_run
+/// # use async_zip::read::remote::ObjectStoreSource;
+/// # use async_zip::read::seek::ZipFileReader;
+/// # use async_zip::error::ZipError;
+/// # use object_store::{path::Path, ObjectStore};
+/// # use std::sync::Arc;
+/// #
+/// # async fn run(store: Arc<dyn ObjectStore>) -> Result<(), ZipError> {
+/// let source = ObjectStoreSource::new(store, Path::from("archives/Archive.zip"));
+/// let mut zip = ZipFileReader::from_random_access(source).await?;
+///
+/// for entry in zip.entries() {
+///     println!("{}", entry.filename());
+/// }
+/// #   Ok(())
+/// # }
+/// ```
+#[cfg(feature = "object-store")]
+#[derive(Debug, Clone)]
+pub struct ObjectStoreSource {
+    store: Arc<dyn ObjectStore>,
+    location: Path,
+}
+
+#[cfg(feature = "object-store")]
+impl ObjectStoreSource {
+    /// Constructs a new source over the object at the provided location within a store.
+    pub fn new(store: Arc<dyn ObjectStore>, location: Path) -> Self {
+        ObjectStoreSource { store, location }
+    }
+
+    /// Returns a shared reference to the store.
+    pub fn store(&self) -> &Arc<dyn ObjectStore> {
+        &self.store
+    }
+
+    /// Returns a shared reference to the location of the object within the store.
+    pub fn location(&self) -> &Path {
+        &self.location
+    }
+}
+
+#[cfg(feature = "object-store")]
+impl AsyncRandomAccess for ObjectStoreSource {
+    fn size(&self) -> RangeFuture<u64> {
+        let (store, location) = (self.store.clone(), self.location.clone());
+        Box::pin(async move { Ok(store.head(&location).await.map_err(std::io::Error::other)?.size) })
+    }
+
+    fn read_at(&self, offset: u64, length: usize) -> RangeFuture<Vec<u8>> {
+        let (store, location) = (self.store.clone(), self.location.clone());
+        Box::pin(async move {
+            let range = offset..offset + length as u64;
+            Ok(store.get_range(&location, range).await.map_err(std::io::Error::other)?.into())
+        })
+    }
+}
@@ -26,87 +26,802 @@
 //! ```
 
 use crate::error::{Result, ZipError};
-use crate::read::{CompressionReader, ZipEntry, ZipEntryReader};
+use crate::read::prefetch::SequentialReader;
+use crate::read::shared;
+use crate::read::verify::{self, EntryVerification, VerificationReport};
+use crate::read::{
+    CompressionReader, EntryIndex, EntryWindow, OwnedEntryReader, ParseMode, ReaderOptions, ZipEntry, ZipEntryReader,
+};
 use crate::spec::compression::Compression;
-use crate::spec::header::{CentralDirectoryHeader, EndOfCentralDirectoryHeader};
+use crate::spec::date::{ExtendedTimestamp, NtfsTimestamps};
+use crate::spec::encryption::{AesExtraField, AES_COMPRESSION_METHOD};
+use crate::spec::extra::{validate_fields, Zip64ExtendedInfo, ZIP64_SENTINEL};
+use crate::spec::header::{
+    CentralDirectoryHeader, EndOfCentralDirectoryHeader, LocalFileHeader, Zip64EndOfCentralDirectoryLocator,
+    Zip64EndOfCentralDirectoryRecord,
+};
+use crate::spec::string::{StringEncoding, ZipString};
 
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, BufReader, Take};
 
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+#[cfg(feature = "mmap")]
+use std::io::Cursor;
 use std::io::SeekFrom;
+use std::path::Path;
 
 /// A reader which acts over a seekable source.
-pub struct ZipFileReader<'a, R: AsyncRead + AsyncSeek + Unpin> {
-    pub(crate) reader: &'a mut R,
+pub struct ZipFileReader<R: AsyncRead + AsyncSeek + Unpin> {
+    pub(crate) reader: R,
     pub(crate) entries: Vec<ZipEntry>,
+    pub(crate) options: ReaderOptions,
+    pub(crate) recovered: bool,
+    pub(crate) comment: ZipString,
+    pub(crate) index: EntryIndex,
 }
 
-impl<'a, R: AsyncRead + AsyncSeek + Unpin> ZipFileReader<'a, R> {
-    /// Constructs a new ZIP file reader from a mutable reference to a reader.
-    pub async fn new(reader: &'a mut R) -> Result<ZipFileReader<'a, R>> {
-        let entries = read_cd(reader).await?;
-        Ok(ZipFileReader { reader, entries })
+impl<R: AsyncRead + AsyncSeek + Unpin> ZipFileReader<R> {
+    /// Constructs a new ZIP file reader from a reader (or a mutable reference to one).
+    pub async fn new(reader: R) -> Result<ZipFileReader<R>> {
+        Self::with_options(reader, ReaderOptions::default()).await
+    }
+
+    /// Constructs a new ZIP file reader from a reader (or a mutable reference to one) and a set of options.
+    pub async fn with_options(mut reader: R, options: ReaderOptions) -> Result<ZipFileReader<R>> {
+        let (entries, comment) = read_cd_and_comment(&mut reader, options.parse_mode).await?;
+        validate_entries(&mut reader, &entries, &options).await?;
+        Ok(ZipFileReader { reader, entries, options, recovered: false, comment, index: EntryIndex::default() })
+    }
+
+    /// Constructs a new ZIP file reader from a reader (or a mutable reference to one) and a set of options, falling
+    /// back to recovering entries from their local file headers if the central directory can't be read.
+    ///
+    /// Recovery scans the reader from its start for local file headers (skipping forward past anything which isn't
+    /// one), so it can salvage archives whose tail (including the central directory) was truncated or whose contents
+    /// were partly corrupted. Sizes of entries with data descriptors are resolved by
+    /// searching for the descriptors. Entries whose data extends beyond the end of the reader are reported via
+    /// [`ZipEntry::partial()`] and can't be opened. Recovered entries (and the archive itself) have no comments.
+    pub async fn with_recovery(mut reader: R, options: ReaderOptions) -> Result<ZipFileReader<R>> {
+        if let Ok((entries, comment)) = read_cd_and_comment(&mut reader, options.parse_mode).await {
+            validate_entries(&mut reader, &entries, &options).await?;
+            return Ok(ZipFileReader {
+                reader,
+                entries,
+                options,
+                recovered: false,
+                comment,
+                index: EntryIndex::default(),
+            });
+        }
+
+        Self::recover(reader, options).await
+    }
+
+    /// Constructs a new ZIP file reader from a reader (or a mutable reference to one) and a set of options, always
+    /// recovering entries from their local file headers and ignoring the central directory entirely.
+    ///
+    /// This is useful when the central directory can be read but is known (or suspected) to be damaged. See
+    /// [`ZipFileReader::with_recovery()`] for details of recovery, and [`ZipFileWriter::repair_from()`] for writing the
+    /// recovered entries out as a repaired archive.
+    ///
+    /// [`ZipFileWriter::repair_from()`]: crate::write::ZipFileWriter::repair_from
+    pub async fn recover(mut reader: R, options: ReaderOptions) -> Result<ZipFileReader<R>> {
+        let entries = crate::read::recovery::scan_entries(&mut reader, options.parse_mode).await?;
+        let comment = ZipString::default();
+        Ok(ZipFileReader { reader, entries, options, recovered: true, comment, index: EntryIndex::default() })
+    }
+
+    /// Returns whether or not the entries of this reader were recovered from local file headers because the central
+    /// directory couldn't be read.
+    pub fn recovered(&self) -> bool {
+        self.recovered
+    }
+
+    /// Returns a shared reference to the archive comment held within the end of central directory header (which is
+    /// empty if the archive has none).
+    ///
+    /// As the comment's encoding is never declared, it's treated as UTF-8 if valid (as written by this crate), and
+    /// otherwise as of an unknown encoding.
+    pub fn comment(&self) -> &ZipString {
+        &self.comment
+    }
+
+    /// Consumes this reader and returns its inner reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Consumes this reader and returns a reader over the same entries which opens them from a shared reference, so
+    /// that many may be read at once.
+    ///
+    /// See [`shared::ZipFileReader`] for details.
+    pub fn into_shared(self) -> shared::ZipFileReader<R> {
+        shared::ZipFileReader::from_raw(self.reader, self.entries, self.options)
     }
 
     crate::read::reader_entry_impl!();
 
     /// Opens an entry at the provided index for reading.
-    pub async fn entry_reader<'b>(&'b mut self, index: usize) -> Result<ZipEntryReader<'b, R>> {
+    pub async fn entry_reader(&mut self, index: usize) -> Result<ZipEntryReader<'_, R>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+
+        if entry.partial() {
+            return Err(ZipError::PartialEntry);
+        }
+
+        entry.seek_to_data_offset(&mut self.reader).await?;
+
+        let reader = (&mut self.reader).take(entry.compressed_size.unwrap());
+        let reader = CompressionReader::from_reader_borrow(entry, reader, &self.options)?;
+
+        Ok(ZipEntryReader::from_raw(entry, reader, &self.options))
+    }
+
+    /// Consumes this reader and opens the entry at the provided index for reading, returning a reader which owns both
+    /// the entry and the inner reader.
+    ///
+    /// Unlike [`ZipFileReader::entry_reader()`], the returned reader borrows nothing, and so may be moved into a
+    /// spawned task or stored within another type. To read many entries without re-reading the central directory, see
+    /// [`ZipFileReader::into_shared()`] instead.
+    pub async fn into_entry_reader(mut self, index: usize) -> Result<OwnedEntryReader<R>>
+    where
+        R: 'static,
+    {
+        if index >= self.entries.len() {
+            return Err(ZipError::EntryIndexOutOfBounds);
+        }
+
+        let entry = self.entries.swap_remove(index);
+        if entry.partial() {
+            return Err(ZipError::PartialEntry);
+        }
+
+        entry.seek_to_data_offset(&mut self.reader).await?;
+
+        let reader = self.reader.take(entry.compressed_size.unwrap());
+        let reader = CompressionReader::from_reader(&entry, reader, &self.options)?;
+
+        Ok(ZipEntryReader::from_owned(entry, reader, &self.options))
+    }
+
+    /// Opens the entry at the provided index for reading its data as stored, without decrypting or decompressing it.
+    ///
+    /// The returned reader yields exactly the entry's compressed size in bytes, and so can be passed directly to
+    /// [`ZipFileWriter::copy_entry_raw()`] along with the entry.
+    ///
+    /// [`ZipFileWriter::copy_entry_raw()`]: crate::write::ZipFileWriter::copy_entry_raw
+    pub async fn raw_entry_reader(&mut self, index: usize) -> Result<Take<&mut R>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+
+        if entry.partial() {
+            return Err(ZipError::PartialEntry);
+        }
+
+        entry.seek_to_data_offset(&mut self.reader).await?;
+        Ok((&mut self.reader).take(entry.compressed_size.unwrap()))
+    }
+
+    /// Opens the entry at the provided index for reading its data as stored (see
+    /// [`ZipFileReader::raw_entry_reader()`]), returning it along with a copy of the entry which also holds the extra
+    /// field data of its local file header.
+    ///
+    /// Passing both to [`ZipFileWriter::copy_entry_raw()`] keeps the entry's local extra fields in the copy.
+    ///
+    /// [`ZipFileWriter::copy_entry_raw()`]: crate::write::ZipFileWriter::copy_entry_raw
+    pub async fn raw_entry(&mut self, index: usize) -> Result<(ZipEntry, Take<&mut R>)> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+
+        if entry.partial() {
+            return Err(ZipError::PartialEntry);
+        }
+
+        let local_extra = entry.read_local_extra(&mut self.reader).await?;
+        let entry = ZipEntry { local_extra: Some(local_extra), ..entry.clone() };
+        let size = entry.compressed_size.unwrap();
+        Ok((entry, (&mut self.reader).take(size)))
+    }
+
+    /// Returns a reader which opens each entry in turn, in the order they appear within the central directory.
+    ///
+    /// If prefetching is enabled via [`ReaderOptions::prefetch()`], upcoming entries are fetched whilst the current
+    /// one is being read.
+    pub fn sequential(&mut self) -> SequentialReader<'_, R> {
+        SequentialReader::from_raw(&mut self.reader, &self.entries, &self.options)
+    }
+
+    /// Reads the full contents of the entry with the provided name, verifying its CRC32 value.
+    ///
+    /// If a maximum size is provided, entries with a larger declared uncompressed size are refused before any data
+    /// is read. Regardless of the limit, reading stops with an error if the entry yields more data than it declares.
+    pub async fn read_entry(&mut self, name: &str, max_size: Option<u64>) -> Result<Vec<u8>> {
+        let (index, entry) = self.entry(name).ok_or_else(|| ZipError::EntryNotFound(name.to_string()))?;
+        let declared = entry.uncompressed_size.unwrap();
+
+        match max_size {
+            Some(limit) if declared > limit => return Err(ZipError::EntryTooLarge(declared, limit)),
+            _ => {}
+        };
+
+        let mut reader = self.entry_reader(index).await?;
+        let mut buffer = Vec::with_capacity(crate::utils::speculative_capacity(declared));
+        (&mut reader).take(declared + 1).read_to_end(&mut buffer).await?;
+
+        if buffer.len() as u64 > declared {
+            return Err(ZipError::UncompressedSizeExceeded);
+        }
+
+        if reader.compare_crc() {
+            Ok(buffer)
+        } else {
+            Err(ZipError::CRC32CheckError)
+        }
+    }
+
+    /// Reads the archive's APK signing block, if it has one.
+    ///
+    /// Android's v2 (and later) APK signature schemes store signatures within a block between the data of the last
+    /// entry and the central directory, which ZIP readers otherwise ignore. The block is returned as-is so that it may
+    /// be carried into another archive via [`ZipFileWriter::signing_block()`].
+    ///
+    /// [`ZipFileWriter::signing_block()`]: crate::write::ZipFileWriter::signing_block
+    pub async fn signing_block(&mut self) -> Result<Option<Vec<u8>>> {
+        let location = locate_cd(&mut self.reader).await?;
+        Ok(read_signing_block(&mut self.reader, location.offset).await?.map(|(_, block)| block))
+    }
+
+    /// Tests the integrity of every entry (as with `unzip -t`), returning a report of the outcome for each.
+    ///
+    /// Each entry is decompressed in full (and decrypted, if a password was provided), and its data checked against
+    /// its declared size and CRC32 value. Failures are recorded within the report rather than stopping verification,
+    /// so one corrupt entry doesn't hide the state of the rest.
+    pub async fn verify(&mut self) -> VerificationReport {
+        let mut report = VerificationReport::default();
+
+        for index in 0..self.entries.len() {
+            let name = self.entries[index].filename().as_str().to_owned();
+            let result = match self.entry_reader(index).await {
+                Ok(reader) => verify::verify_entry(reader).await,
+                Err(error) => Err(error),
+            };
+            report.entries.push(EntryVerification { index, name, result });
+        }
+
+        report
+    }
+
+    /// Opens the Stored entry with the provided name as a nested ZIP file, with full seekable access to its entries.
+    ///
+    /// Offsets within the nested file are translated onto this reader, so nothing is buffered in memory. Compressed
+    /// entries can't be seeked within and so must instead be read via [`ZipEntryReader::into_zip_reader()`]. Opening
+    /// archives nested deeper than [`ReaderOptions::max_nesting_depth()`] fails.
+    pub async fn open_nested(&mut self, name: &str) -> Result<ZipFileReader<EntryWindow<&mut R>>> {
+        let entry = self.entries.iter().find(|entry| entry.filename() == name);
+        let entry = entry.ok_or_else(|| ZipError::EntryNotFound(name.to_string()))?;
+        let options = self.options.nested()?;
+
+        if entry.compression() != &Compression::Stored {
+            return Err(ZipError::FeatureNotSupported("Seekable access to compressed nested archives"));
+        } else if entry.partial() {
+            return Err(ZipError::PartialEntry);
+        }
+
+        entry.seek_to_data_offset(&mut self.reader).await?;
+        let start = self.reader.stream_position().await?;
+        let window = EntryWindow::from_raw(&mut self.reader, start, entry.compressed_size.unwrap());
+
+        ZipFileReader::with_options(window, options).await
+    }
+
+    /// Reads the full contents of the entry with the provided name as a UTF-8 string.
+    ///
+    /// See [`ZipFileReader::read_entry()`] for details.
+    pub async fn read_entry_to_string(&mut self, name: &str, max_size: Option<u64>) -> Result<String> {
+        let buffer = self.read_entry(name, max_size).await?;
+        String::from_utf8(buffer).map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err).into())
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<R: futures_io::AsyncRead + futures_io::AsyncSeek + Unpin> ZipFileReader<crate::compat::Compat<R>> {
+    /// Constructs a new ZIP file reader from a reader implementing the `futures-io` traits rather than tokio's.
+    pub async fn from_futures_io(reader: R) -> Result<Self> {
+        Self::new(crate::compat::Compat::new(reader)).await
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl ZipFileReader<Cursor<Mmap>> {
+    /// Constructs a new ZIP file reader over a memory-mapped file.
+    ///
+    /// The central directory is parsed from, and entries are read from, the mapping rather than via a seek and read
+    /// of the file for each, and the data of entries may be borrowed directly via [`ZipFileReader::entry_data()`].
+    /// As mapping a file is unsafe (the mapping's contents change if the file is modified by another process), the
+    /// file is mapped by the caller.
+    pub async fn from_mmap(mmap: Mmap) -> Result<Self> {
+        Self::with_options(Cursor::new(mmap), ReaderOptions::default()).await
+    }
+
+    /// Constructs a new ZIP file reader over a memory-mapped file and a set of options.
+    ///
+    /// See [`ZipFileReader::from_mmap()`] for details.
+    pub async fn from_mmap_with_options(mmap: Mmap, options: ReaderOptions) -> Result<Self> {
+        Self::with_options(Cursor::new(mmap), options).await
+    }
+
+    /// Returns the data of the entry at the provided index as stored (ie. without decrypting or decompressing it),
+    /// borrowed from the mapping without copying.
+    ///
+    /// For Stored entries which aren't encrypted, this is the entry's uncompressed data.
+    pub async fn entry_data(&self, index: usize) -> Result<&[u8]> {
         let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
 
-        if entry.data_descriptor() {
-            return Err(ZipError::FeatureNotSupported("Entries with data descriptors"));
+        if entry.partial() {
+            return Err(ZipError::PartialEntry);
         }
 
-        self.reader.seek(SeekFrom::Start(entry.data_offset())).await?;
+        let data = &self.reader.get_ref()[..];
+        let mut cursor = Cursor::new(data);
+        entry.seek_to_data_offset(&mut cursor).await?;
 
-        let reader = self.reader.take(entry.compressed_size.unwrap().into());
-        let reader = CompressionReader::from_reader_borrow(entry.compression(), reader);
+        let start = cursor.position() as usize;
+        let end = start.checked_add(entry.compressed_size.unwrap() as usize);
+        end.and_then(|end| data.get(start..end)).ok_or(ZipError::HeaderOutOfBounds("entry data"))
+    }
+}
+
+impl ZipFileReader<BufReader<File>> {
+    /// Opens the file at the provided path and constructs a new ZIP file reader which owns it, with buffering.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with_options(path, ReaderOptions::default()).await
+    }
+
+    /// Opens the file at the provided path and constructs a new ZIP file reader which owns it, with buffering and a
+    /// set of options.
+    pub async fn open_with_options(path: impl AsRef<Path>, options: ReaderOptions) -> Result<Self> {
+        let file = crate::utils::open_file(path.as_ref()).await?;
+        Self::with_options(BufReader::new(file), options).await
+    }
 
-        Ok(ZipEntryReader::from_raw(entry, reader, false))
+    /// Opens the file at the provided path with recovery, and constructs a new ZIP file reader which owns it.
+    ///
+    /// See [`ZipFileReader::with_recovery()`] for details.
+    pub async fn open_with_recovery(path: impl AsRef<Path>) -> Result<Self> {
+        let file = crate::utils::open_file(path.as_ref()).await?;
+        Self::with_recovery(BufReader::new(file), ReaderOptions::default()).await
     }
 }
 
-pub(crate) async fn read_cd<R: AsyncRead + AsyncSeek + Unpin>(reader: &mut R) -> Result<Vec<ZipEntry>> {
-    // Assume no ZIP comment exists for the moment so we can seek directly to EOCD header.
-    reader.seek(SeekFrom::End(-22)).await?;
-    crate::utils::assert_delimiter(reader, crate::spec::delimiter::EOCDD).await?;
+/// The length of a central directory file header (including its signature) with empty variable-length fields.
+const MIN_CDFH_LENGTH: u64 = 46;
+
+pub(crate) async fn read_cd<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    mode: ParseMode,
+) -> Result<Vec<ZipEntry>> {
+    Ok(read_cd_and_comment(reader, mode).await?.0)
+}
+
+/// Reads the central directory's entries, along with the archive comment which follows it.
+pub(crate) async fn read_cd_and_comment<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    mode: ParseMode,
+) -> Result<(Vec<ZipEntry>, ZipString)> {
+    let location = locate_cd(reader).await?;
+
+    reader.seek(SeekFrom::Start(location.offset)).await?;
+    let capacity = std::cmp::min(location.num_of_entries, location.size / MIN_CDFH_LENGTH);
+    let mut entries = Vec::with_capacity(capacity as usize);
+
+    match mode {
+        ParseMode::Lenient => {
+            let end = location.offset + location.size;
+            while reader.stream_position().await? < end {
+                entries.push(read_cd_entry(reader, mode).await?);
+            }
+        }
+        ParseMode::Standard | ParseMode::Strict => {
+            for _ in 0..location.num_of_entries {
+                entries.push(read_cd_entry(reader, mode).await?);
+            }
+        }
+    }
+
+    for entry in entries.iter_mut() {
+        entry.offset = entry.offset.map(|offset| offset.saturating_add(location.prepended));
+    }
+
+    Ok((entries, location.comment))
+}
+
+/// Validates the central directory's entries against their local file headers, and the archive's layout, if the
+/// provided options parse archives in [`ParseMode::Strict`].
+pub(crate) async fn validate_entries<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    entries: &[ZipEntry],
+    options: &ReaderOptions,
+) -> Result<()> {
+    if options.parse_mode != ParseMode::Strict {
+        return Ok(());
+    }
+
+    // Each entry spans from its local file header to the end of its data (and the shortest possible data descriptor).
+    let mut spans = Vec::with_capacity(entries.len());
+    for entry in entries.iter().filter(|entry| !entry.partial()) {
+        let header = entry.seek_to_data_offset(reader).await?;
+        if !local_header_matches(entry, &header) {
+            return Err(ZipError::LocalHeaderMismatch(entry.filename().as_str().to_owned()));
+        }
+
+        let descriptor_length = match (entry.data_descriptor(), entry.zip64()) {
+            (false, _) => 0,
+            (true, false) => 12,
+            (true, true) => 20,
+        };
+
+        let data_offset = reader.stream_position().await?;
+        let end =
+            data_offset.checked_add(entry.compressed_size.unwrap()).and_then(|end| end.checked_add(descriptor_length));
+        let end = end.ok_or(ZipError::HeaderOutOfBounds("entry data"))?;
+        spans.push((entry.offset.unwrap(), end, entry));
+    }
+
+    spans.sort_by_key(|&(start, _, _)| start);
+    for window in spans.windows(2) {
+        let ((_, end, previous), (start, _, next)) = (window[0], window[1]);
+        if start < end {
+            return Err(ZipError::OverlappingEntries(
+                previous.filename().as_str().to_owned(),
+                next.filename().as_str().to_owned(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns whether an entry's local file header agrees with the values taken from its central directory record.
+fn local_header_matches(entry: &ZipEntry, header: &LocalFileHeader) -> bool {
+    let method = match entry.aes {
+        Some(_) => AES_COMPRESSION_METHOD,
+        None => entry.compression.to_u16(),
+    };
+    if header.compression != method || header.flags.encrypted != entry.encrypted {
+        return false;
+    }
+    if header.flags.data_descriptor || entry.data_descriptor {
+        return true;
+    }
+
+    // Saturated sizes are held by the Zip64 extended information extra field instead.
+    let size_matches = |held: u32, size: Option<u64>| held == ZIP64_SENTINEL || Some(held as u64) == size;
+    Some(header.crc) == entry.crc32
+        && size_matches(header.compressed_size, entry.compressed_size)
+        && size_matches(header.uncompressed_size, entry.uncompressed_size)
+}
+
+/// The location of a central directory, as declared by the end of central directory header (or Zip64 record).
+pub(crate) struct CentralDirectoryLocation {
+    pub(crate) num_of_entries: u64,
+    pub(crate) size: u64,
+    pub(crate) offset: u64,
+    pub(crate) zip64: bool,
+    pub(crate) comment: ZipString,
+
+    // The length of any data prepended to the archive (eg. a self-extracting stub), which the declared offsets are
+    // relative to the end of. The offset above already accounts for it.
+    pub(crate) prepended: u64,
+}
+
+/// Reads the end of central directory header (and Zip64 record, if present) to locate the central directory.
+pub(crate) async fn locate_cd<R: AsyncRead + AsyncSeek + Unpin>(reader: &mut R) -> Result<CentralDirectoryLocation> {
+    let eocdh_offset = find_eocdh(reader).await?;
+    reader.seek(SeekFrom::Start(eocdh_offset + 4)).await?;
 
     let eocdh = EndOfCentralDirectoryHeader::from_reader(reader).await?;
+    let comment = crate::utils::read_bytes(reader, eocdh.file_comm_length.into()).await?;
 
     // Outdated feature so unlikely to ever make it into this crate.
     if eocdh.disk_num != eocdh.start_cent_dir_disk || eocdh.num_of_entries != eocdh.num_of_entries_disk {
         return Err(ZipError::FeatureNotSupported("Spanned/split files"));
     }
 
-    reader.seek(SeekFrom::Start(eocdh.cent_dir_offset.into())).await?;
-    let mut entries = Vec::with_capacity(eocdh.num_of_entries.into());
+    let (num_of_entries, size_cent_dir, cent_dir_offset, cd_limit, zip64) =
+        match read_zip64_eocdr(reader, &eocdh, eocdh_offset).await? {
+            Some((record, record_offset)) => {
+                if record.disk_num != record.start_cent_dir_disk || record.num_of_entries != record.num_of_entries_disk
+                {
+                    return Err(ZipError::FeatureNotSupported("Spanned/split files"));
+                }
+                (record.num_of_entries, record.size_cent_dir, record.cent_dir_offset, record_offset, true)
+            }
+            None => {
+                let (num, size, offset) =
+                    (eocdh.num_of_entries.into(), eocdh.size_cent_dir.into(), eocdh.cent_dir_offset.into());
+                (num, size, offset, eocdh_offset, false)
+            }
+        };
+
+    // The central directory must end before the EOCDH (or Zip64 record) starts, and can't hold more entries than
+    // would fit within it.
+    let cd_end = match cent_dir_offset.checked_add(size_cent_dir) {
+        Some(cd_end) if cd_end <= cd_limit => cd_end,
+        _ => return Err(ZipError::HeaderOutOfBounds("central directory")),
+    };
+
+    // Offsets within archives with prepended data are relative to the start of the archive itself, which shows as a
+    // gap between the end of the central directory and the record which should directly follow it.
+    let gap = cd_limit - cd_end;
+    let prepended = if gap > 0
+        && num_of_entries > 0
+        && !has_signature(reader, cent_dir_offset, crate::spec::delimiter::CDFHD).await?
+        && has_signature(reader, cent_dir_offset + gap, crate::spec::delimiter::CDFHD).await?
+    {
+        gap
+    } else {
+        0
+    };
+
+    // The archive comment's encoding is never declared, so it's assumed to be UTF-8 (as this crate writes) if valid.
+    let encoding = match std::str::from_utf8(&comment) {
+        Ok(_) => StringEncoding::Utf8,
+        Err(_) => StringEncoding::Unknown,
+    };
+    let comment = ZipString::new(comment, encoding);
+
+    Ok(CentralDirectoryLocation {
+        num_of_entries,
+        size: size_cent_dir,
+        offset: cent_dir_offset + prepended,
+        zip64,
+        comment,
+        prepended,
+    })
+}
 
-    for _ in 0..eocdh.num_of_entries {
-        entries.push(read_cd_entry(reader).await?);
+/// Returns whether the provided signature is found at an offset of the reader.
+async fn has_signature<R: AsyncRead + AsyncSeek + Unpin>(reader: &mut R, offset: u64, signature: u32) -> Result<bool> {
+    reader.seek(SeekFrom::Start(offset)).await?;
+    match reader.read_u32_le().await {
+        Ok(actual) => Ok(actual == signature),
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(error) => Err(error.into()),
     }
+}
+
+/// The length of the end of central directory header (including its signature) without a comment.
+const EOCDH_LENGTH: u64 = 22;
 
-    Ok(entries)
+/// Returns the offset of the end of central directory header.
+///
+/// Most archives have no comment, in which case the header ends the reader. Otherwise, the header is searched for
+/// backwards through the longest comment which could follow it. As the signature may also appear within the comment
+/// (or within data trailing the archive) by chance, candidates are accepted in order of preference: those whose
+/// comment ends the reader and whose central directory is plausible, then those whose central directory is plausible
+/// but which are followed by trailing data, and lastly those whose comment ends the reader.
+async fn find_eocdh<R: AsyncRead + AsyncSeek + Unpin>(reader: &mut R) -> Result<u64> {
+    let len = reader.seek(SeekFrom::End(0)).await?;
+    let search_len = std::cmp::min(len, EOCDH_LENGTH + u16::MAX as u64);
+    let (mut trailing, mut fallback) = (None, None);
+
+    for search_len in [std::cmp::min(len, EOCDH_LENGTH), search_len] {
+        let search_start = len - search_len;
+        reader.seek(SeekFrom::Start(search_start)).await?;
+        let mut buffer = vec![0; search_len as usize];
+        reader.read_exact(&mut buffer).await?;
+
+        let signature = crate::spec::delimiter::EOCDD.to_le_bytes();
+        let candidates = (0..buffer.len().saturating_sub(EOCDH_LENGTH as usize - 1)).rev();
+        for position in candidates.filter(|position| buffer[*position..*position + 4] == signature) {
+            let header = &buffer[position..position + EOCDH_LENGTH as usize];
+            let comment_end = position + EOCDH_LENGTH as usize + u16::from_le_bytes([header[20], header[21]]) as usize;
+            let exact = comment_end == buffer.len();
+            if comment_end > buffer.len() || (!exact && trailing.is_some()) {
+                continue;
+            }
+
+            let offset = search_start + position as u64;
+            match plausible_cd(reader, offset, header).await? {
+                true if exact => return Ok(offset),
+                true => trailing = Some(offset),
+                false if exact => fallback = fallback.or(Some(offset)),
+                false => {}
+            }
+        }
+    }
+
+    trailing.or(fallback).ok_or(ZipError::EndOfCentralDirectoryNotFound)
+}
+
+/// Returns whether the central directory declared by a candidate end of central directory header is plausible.
+///
+/// That's whether it's empty, or whether a central directory file header is found where it's declared to start (or
+/// for archives with prepended data, where it would start if it directly precedes the candidate). The values of Zip64
+/// archives are held elsewhere, so only the presence of the Zip64 locator before the candidate is checked.
+async fn plausible_cd<R: AsyncRead + AsyncSeek + Unpin>(reader: &mut R, offset: u64, header: &[u8]) -> Result<bool> {
+    let num_of_entries = u16::from_le_bytes([header[10], header[11]]);
+    let size = u32::from_le_bytes(header[12..16].try_into().unwrap());
+    let cd_offset = u32::from_le_bytes(header[16..20].try_into().unwrap());
+
+    if num_of_entries == u16::MAX || size == ZIP64_SENTINEL || cd_offset == ZIP64_SENTINEL {
+        return match offset.checked_sub(ZIP64_EOCDL_LENGTH) {
+            Some(locator_offset) => has_signature(reader, locator_offset, crate::spec::delimiter::ZIP64_EOCDLD).await,
+            None => Ok(false),
+        };
+    }
+    if num_of_entries == 0 {
+        return Ok(size == 0);
+    }
+
+    let (size, cd_offset) = (size as u64, cd_offset as u64);
+    if cd_offset + size > offset {
+        return Ok(false);
+    }
+
+    Ok(has_signature(reader, cd_offset, crate::spec::delimiter::CDFHD).await?
+        || has_signature(reader, offset - size, crate::spec::delimiter::CDFHD).await?)
+}
+
+/// The magic value which ends an APK signing block.
+const APK_SIGNING_BLOCK_MAGIC: &[u8; 16] = b"APK Sig Block 42";
+
+/// The length of an APK signing block without any ID-value pairs (its two size fields and its magic value).
+const MIN_APK_SIGNING_BLOCK_LENGTH: u64 = 32;
+
+/// Reads the APK signing block which directly precedes the central directory at the provided offset, if any, along
+/// with the offset it starts at.
+///
+/// The block starts and ends with its size (excluding the leading size field), the latter followed by its magic value.
+pub(crate) async fn read_signing_block<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    cd_offset: u64,
+) -> Result<Option<(u64, Vec<u8>)>> {
+    let Some(footer_offset) = cd_offset.checked_sub(24) else {
+        return Ok(None);
+    };
+
+    reader.seek(SeekFrom::Start(footer_offset)).await?;
+    let size = reader.read_u64_le().await?;
+    let mut magic = [0; 16];
+    reader.read_exact(&mut magic).await?;
+    if &magic != APK_SIGNING_BLOCK_MAGIC {
+        return Ok(None);
+    }
+
+    let start = match size.checked_add(8).and_then(|length| cd_offset.checked_sub(length)) {
+        Some(start) if size + 8 >= MIN_APK_SIGNING_BLOCK_LENGTH => start,
+        _ => return Err(ZipError::HeaderOutOfBounds("APK signing block")),
+    };
+    let length = usize::try_from(size + 8).map_err(|_| ZipError::HeaderOutOfBounds("APK signing block"))?;
+
+    reader.seek(SeekFrom::Start(start)).await?;
+    let block = crate::utils::read_bytes(reader, length).await?;
+    if block[..8] != size.to_le_bytes() {
+        return Err(ZipError::HeaderOutOfBounds("APK signing block"));
+    }
+
+    Ok(Some((start, block)))
 }
 
-pub(crate) async fn read_cd_entry<R: AsyncRead + Unpin>(reader: &mut R) -> Result<ZipEntry> {
+/// The length of the Zip64 end of central directory locator (including its signature).
+const ZIP64_EOCDL_LENGTH: u64 = 20;
+
+/// The length of the Zip64 end of central directory record (including its signature) without extensible data.
+const MIN_ZIP64_EOCDR_LENGTH: u64 = 56;
+
+/// Reads the Zip64 end of central directory record and its offset, if the EOCDH has saturated values and is preceded
+/// by a Zip64 locator.
+async fn read_zip64_eocdr<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: &mut R,
+    eocdh: &EndOfCentralDirectoryHeader,
+    eocdh_offset: u64,
+) -> Result<Option<(Zip64EndOfCentralDirectoryRecord, u64)>> {
+    let saturated = eocdh.num_of_entries == u16::MAX
+        || eocdh.size_cent_dir == ZIP64_SENTINEL
+        || eocdh.cent_dir_offset == ZIP64_SENTINEL;
+    let locator_offset = match eocdh_offset.checked_sub(ZIP64_EOCDL_LENGTH) {
+        Some(locator_offset) if saturated => locator_offset,
+        _ => return Ok(None),
+    };
+
+    // The values may genuinely be saturated in an archive written without Zip64 support.
+    reader.seek(SeekFrom::Start(locator_offset)).await?;
+    if reader.read_u32_le().await? != crate::spec::delimiter::ZIP64_EOCDLD {
+        return Ok(None);
+    }
+
+    let locator = Zip64EndOfCentralDirectoryLocator::from_reader(reader).await?;
+    if locator.total_disks > 1 {
+        return Err(ZipError::FeatureNotSupported("Spanned/split files"));
+    }
+    if locator.record_offset.checked_add(MIN_ZIP64_EOCDR_LENGTH).is_none_or(|end| end > locator_offset) {
+        return Err(ZipError::HeaderOutOfBounds("Zip64 end of central directory record"));
+    }
+
+    // Within archives with prepended data, the declared offset is relative to the start of the archive itself, so the
+    // record is instead assumed to directly precede the locator.
+    let mut record_offset = locator.record_offset;
+    if !has_signature(reader, record_offset, crate::spec::delimiter::ZIP64_EOCDRD).await? {
+        record_offset = locator_offset - MIN_ZIP64_EOCDR_LENGTH;
+    }
+
+    reader.seek(SeekFrom::Start(record_offset)).await?;
+    crate::utils::assert_delimiter(reader, crate::spec::delimiter::ZIP64_EOCDRD).await?;
+    let record = Zip64EndOfCentralDirectoryRecord::from_reader(reader).await?;
+
+    Ok(Some((record, record_offset)))
+}
+
+pub(crate) async fn read_cd_entry<R: AsyncRead + Unpin>(reader: &mut R, mode: ParseMode) -> Result<ZipEntry> {
+    let CentralDirectoryRecord { header, filename, extra, comment } = read_cd_record(reader).await?;
+    cd_record_entry(&header, filename, extra, comment, mode)
+}
+
+/// A central directory file header along with its variable-length fields, as read from the archive.
+pub(crate) struct CentralDirectoryRecord {
+    pub(crate) header: CentralDirectoryHeader,
+    pub(crate) filename: ZipString,
+    pub(crate) extra: Vec<u8>,
+    pub(crate) comment: ZipString,
+}
+
+/// Reads a central directory file header along with its variable-length fields.
+pub(crate) async fn read_cd_record<R: AsyncRead + Unpin>(reader: &mut R) -> Result<CentralDirectoryRecord> {
     crate::utils::assert_delimiter(reader, crate::spec::delimiter::CDFHD).await?;
 
     let header = CentralDirectoryHeader::from_reader(reader).await?;
-    let filename = crate::utils::read_string(reader, header.file_name_length.into()).await?;
+    let encoding = crate::utils::flag_encoding(&header.flags);
+    let filename = crate::utils::read_string(reader, header.file_name_length.into(), encoding).await?;
     let extra = crate::utils::read_bytes(reader, header.extra_field_length.into()).await?;
-    let comment = crate::utils::read_string(reader, header.file_comment_length.into()).await?;
+    let comment = crate::utils::read_string(reader, header.file_comment_length.into(), encoding).await?;
+
+    Ok(CentralDirectoryRecord { header, filename, extra, comment })
+}
+
+/// Constructs an entry from the fields of its central directory record.
+pub(crate) fn cd_record_entry(
+    header: &CentralDirectoryHeader,
+    filename: ZipString,
+    extra: Vec<u8>,
+    comment: ZipString,
+    mode: ParseMode,
+) -> Result<ZipEntry> {
+    mode.tolerate(validate_fields(&extra), || ())?;
+    let zip64 =
+        Zip64ExtendedInfo::parse(&extra, header.uncompressed_size, header.compressed_size, Some(header.lh_offset));
+    let zip64 = mode.tolerate(zip64, || None)?.unwrap_or_default();
+    let aes = AesExtraField::parse(&extra)?.filter(|_| header.compression == AES_COMPRESSION_METHOD);
+    let timestamp = ExtendedTimestamp::parse(&extra);
 
     let entry = ZipEntry {
         name: filename,
         comment: Some(comment),
         data_descriptor: header.flags.data_descriptor,
+        encrypted: header.flags.encrypted,
+        compression_options: header.flags.compression_options,
         crc32: Some(header.crc),
-        uncompressed_size: Some(header.uncompressed_size),
-        compressed_size: Some(header.compressed_size),
-        last_modified: crate::spec::date::zip_date_to_chrono(header.mod_date, header.mod_time),
+        uncompressed_size: Some(zip64.uncompressed_size.unwrap_or(header.uncompressed_size.into())),
+        compressed_size: Some(zip64.compressed_size.unwrap_or(header.compressed_size.into())),
+        last_modified: match timestamp.modified {
+            Some(modified) => modified,
+            None => mode.tolerate(
+                crate::spec::date::zip_date_to_chrono(header.mod_date, header.mod_time),
+                crate::spec::date::dos_epoch,
+            )?,
+        },
+        last_accessed: timestamp.accessed,
+        created: timestamp.created,
+        ntfs: NtfsTimestamps::parse(&extra),
         extra: Some(extra),
-        compression: Compression::from_u16(header.compression)?,
-        offset: Some(header.lh_offset),
+        local_extra: None,
+        compression: crate::spec::encryption::resolve_compression(header.compression, aes.as_ref())?,
+        aes,
+        offset: Some(zip64.lh_offset.unwrap_or(header.lh_offset.into())),
+        made_by: Some(header.v_made_by),
+        exter_attr: Some(header.exter_attr),
+        inter_attr: Some(header.inter_attr),
+        partial: false,
     };
 
     Ok(entry)
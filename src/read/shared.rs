@@ -0,0 +1,275 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module for reading ZIP file entries concurrently from a single seekable source.
+//!
+//! Each entry reader tracks its own position within the source, and seeks it there before each read. Reads from the
+//! source are still serialised, but entries are decompressed independently of one another.
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::read::shared::ZipFileReader;
+//! # use async_zip::error::ZipError;
+//! # use std::sync::Arc;
+//! # use tokio::fs::File;
+//! #
+//! # async fn run() -> Result<(), ZipError> {
+//! let file = File::open("./Archive.zip").await?;
+//! let zip = Arc::new(ZipFileReader::new(file).await?);
+//!
+//! let mut handles = Vec::new();
+//! for index in 0..zip.entries().len() {
+//!     let zip = zip.clone();
+//!     handles.push(tokio::spawn(async move { zip.entry_reader(index).await?.read_to_end_crc().await }));
+//! }
+//!
+//! for handle in handles {
+//!     let data = handle.await.unwrap()?;
+//! }
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::error::{Result, ZipError};
+use crate::read::{CompressionReader, EntryIndex, OwnedEntryReader, ReaderOptions, ZipEntry, ZipEntryReader};
+
+use std::io::SeekFrom;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{ready, Context, Poll, Waker};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, ReadBuf};
+
+/// The type returned as an entry reader within this concurrent module.
+pub type ConcurrentReader<'a, R> = ZipEntryReader<'a, PositionedReader<R>>;
+
+/// A reader which acts concurrently over a seekable source shared between its entry readers.
+pub struct ZipFileReader<R: AsyncRead + AsyncSeek + Unpin> {
+    pub(crate) source: Arc<Source<R>>,
+    pub(crate) entries: Vec<ZipEntry>,
+    pub(crate) options: ReaderOptions,
+    pub(crate) index: EntryIndex,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> ZipFileReader<R> {
+    /// Constructs a new ZIP file reader from a reader.
+    pub async fn new(reader: R) -> Result<ZipFileReader<R>> {
+        Self::with_options(reader, ReaderOptions::default()).await
+    }
+
+    /// Constructs a new ZIP file reader from a reader and a set of options.
+    pub async fn with_options(mut reader: R, options: ReaderOptions) -> Result<ZipFileReader<R>> {
+        let entries = crate::read::seek::read_cd(&mut reader, options.parse_mode).await?;
+        crate::read::seek::validate_entries(&mut reader, &entries, &options).await?;
+        Ok(Self::from_raw(reader, entries, options))
+    }
+
+    /// Constructs a new ZIP file reader from its raw parts (a reader and its already-read entries).
+    pub(crate) fn from_raw(reader: R, entries: Vec<ZipEntry>, options: ReaderOptions) -> Self {
+        ZipFileReader { source: Arc::new(Source::new(reader)), entries, options, index: EntryIndex::default() }
+    }
+
+    crate::read::reader_entry_impl!();
+
+    /// Opens an entry at the provided index for reading.
+    ///
+    /// As only a shared reference is required, any number of entries may be open and read at once (including from
+    /// separate tasks if this reader is wrapped within an [`Arc`]).
+    pub async fn entry_reader(&self, index: usize) -> Result<ConcurrentReader<'_, R>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        let reader = self.compression_reader(entry).await?;
+
+        Ok(ZipEntryReader::from_raw(entry, reader, &self.options))
+    }
+
+    /// Opens an entry at the provided index for reading, returning a reader which owns a copy of the entry and a
+    /// handle to the shared source.
+    ///
+    /// Unlike [`ZipFileReader::entry_reader()`], the returned reader doesn't borrow this reader, and so may be moved
+    /// into a spawned task or outlive this reader entirely.
+    pub async fn owned_entry_reader(&self, index: usize) -> Result<OwnedEntryReader<PositionedReader<R>>>
+    where
+        R: 'static,
+    {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+        let reader = self.compression_reader(entry).await?;
+
+        Ok(ZipEntryReader::from_owned(entry.clone(), reader, &self.options))
+    }
+
+    async fn compression_reader<'a>(&self, entry: &ZipEntry) -> Result<CompressionReader<'a, PositionedReader<R>>>
+    where
+        R: 'a,
+    {
+        if entry.partial() {
+            return Err(ZipError::PartialEntry);
+        }
+
+        let mut reader = PositionedReader::new(self.source.clone());
+        entry.seek_to_data_offset(&mut reader).await?;
+
+        let reader = reader.take(entry.compressed_size.unwrap());
+        CompressionReader::from_reader(entry, reader, &self.options)
+    }
+}
+
+/// A source shared between positioned readers, along with the state of whichever reader is currently using it.
+pub(crate) struct Source<R> {
+    state: Mutex<SourceState<R>>,
+}
+
+struct SourceState<R> {
+    reader: R,
+
+    // The position of the source if it's known, and the positioned reader (if any) whose seek or read is in progress.
+    position: Option<u64>,
+    owner: Option<usize>,
+    next_id: usize,
+    waiting: Vec<Waker>,
+}
+
+impl<R> Source<R> {
+    fn new(reader: R) -> Self {
+        let state = SourceState { reader, position: None, owner: None, next_id: 0, waiting: Vec::new() };
+        Source { state: Mutex::new(state) }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SourceState<R>> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<R> SourceState<R> {
+    /// Releases the source once a positioned reader's seek and read have completed, waking any others waiting on it.
+    fn release(&mut self) {
+        self.owner = None;
+        self.waiting.drain(..).for_each(Waker::wake);
+    }
+}
+
+/// The progress of a positioned reader's seek of the shared source.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SeekState {
+    Idle,
+    // Any seek left incomplete by a previous owner must be completed before another can be started.
+    Completing,
+    Started,
+}
+
+/// A reader over a seekable source shared with other positioned readers, which reads from its own position.
+///
+/// The source is seeked to the reader's position before each read, unless it's already positioned there. Whilst a
+/// seek or read is in progress, other positioned readers over the same source wait until it has completed.
+pub struct PositionedReader<R> {
+    source: Arc<Source<R>>,
+    id: usize,
+    position: u64,
+    seek: SeekState,
+}
+
+impl<R> PositionedReader<R> {
+    fn new(source: Arc<Source<R>>) -> Self {
+        let id = {
+            let mut state = source.lock();
+            state.next_id += 1;
+            state.next_id
+        };
+
+        PositionedReader { source, id, position: 0, seek: SeekState::Idle }
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncRead for PositionedReader<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let PositionedReader { source, id, position, seek } = &mut *self;
+        let mut state = source.lock();
+
+        match state.owner {
+            Some(owner) if owner != *id => {
+                state.waiting.push(c.waker().clone());
+                return Poll::Pending;
+            }
+            Some(_) => {}
+            None => {
+                state.owner = Some(*id);
+                *seek = SeekState::Idle;
+            }
+        };
+
+        let poll = poll_seek_and_read(&mut state, position, seek, c, b);
+        if let Poll::Ready(ref result) = poll {
+            if result.is_err() {
+                state.position = None;
+            }
+            state.release();
+        }
+
+        poll
+    }
+}
+
+/// Seeks the source to the reader's position (if it isn't already positioned there), and then reads from it.
+fn poll_seek_and_read<R: AsyncRead + AsyncSeek + Unpin>(
+    state: &mut SourceState<R>,
+    position: &mut u64,
+    seek: &mut SeekState,
+    c: &mut Context<'_>,
+    b: &mut ReadBuf<'_>,
+) -> Poll<tokio::io::Result<()>> {
+    if state.position != Some(*position) {
+        if *seek == SeekState::Idle {
+            *seek = SeekState::Completing;
+        }
+        if *seek == SeekState::Completing {
+            ready!(Pin::new(&mut state.reader).poll_complete(c))?;
+            Pin::new(&mut state.reader).start_seek(SeekFrom::Start(*position))?;
+            *seek = SeekState::Started;
+        }
+
+        state.position = Some(ready!(Pin::new(&mut state.reader).poll_complete(c))?);
+        *seek = SeekState::Idle;
+    }
+
+    let prev_len = b.filled().len();
+    ready!(Pin::new(&mut state.reader).poll_read(c, b))?;
+
+    *position += (b.filled().len() - prev_len) as u64;
+    state.position = Some(*position);
+    Poll::Ready(Ok(()))
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncSeek for PositionedReader<R> {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        let target = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "positioned readers can't seek relative to the end of their source",
+                ))
+            }
+        };
+
+        self.position = target.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "seek to a negative or overflowing position")
+        })?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        Poll::Ready(Ok(self.position))
+    }
+}
+
+impl<R> Drop for PositionedReader<R> {
+    fn drop(&mut self) {
+        let mut state = self.source.lock();
+
+        // The source's position is unknown if this reader was dropped part-way through seeking or reading it.
+        if state.owner == Some(self.id) {
+            state.position = None;
+            state.release();
+        }
+    }
+}
@@ -3,28 +3,67 @@
 
 //! A module for reading ZIP file from a non-seekable source.
 //!
+//! The reader is generic over (and takes ownership of) its source, so no dynamic dispatch takes place and the reader
+//! is only `Send` if its source is.
+//!
 //! # Example
-//! ```
+//! ```no_run
+//! # use async_zip::read::stream::ZipFileReader;
+//! # use async_zip::error::ZipError;
+//! # use tokio::fs::File;
+//! #
+//! # async fn run() -> Result<(), ZipError> {
+//! let file = File::open("./Archive.zip").await?;
+//! let mut zip = ZipFileReader::new(file);
+//!
+//! // Consume the entries in the order they appear within the archive.
+//! while let Some(reader) = zip.entry_reader().await? {
+//!     let name = reader.entry().name().to_string();
+//!     let contents = reader.read_to_string_crc().await?;
+//! }
+//! #   Ok(())
+//! # }
 //! ```
 
 use crate::error::{Result, ZipError};
-use crate::read::{CompressionReader, ZipEntry, ZipEntryReader};
-use crate::spec::compression::Compression;
+use crate::read::descriptor;
+use crate::read::offset_reader::OffsetAsyncReader;
+use crate::read::pool::{SharedBufReader, SharedBuffer};
+use crate::read::{CompressionReader, ParseMode, ReaderOptions, ZipEntry, ZipEntryReader};
+use crate::spec::date::{ExtendedTimestamp, NtfsTimestamps};
+use crate::spec::encryption::{AesExtraField, AES_COMPRESSION_METHOD};
+use crate::spec::extra::{validate_fields, Zip64ExtendedInfo};
 use crate::spec::header::LocalFileHeader;
 
-use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+
+use std::path::Path;
 
 /// A reader which acts over a non-seekable source.
-pub struct ZipFileReader<'a, R: AsyncRead + Unpin> {
-    pub(crate) reader: &'a mut R,
+pub struct ZipFileReader<R: AsyncRead + Unpin> {
+    pub(crate) reader: OffsetAsyncReader<R>,
+    pub(crate) buffer: SharedBuffer,
     pub(crate) entry: Option<ZipEntry>,
+    pub(crate) entry_start: u64,
+    pub(crate) entry_end: Option<u64>,
     pub(crate) finished: bool,
+    pub(crate) options: ReaderOptions,
 }
 
-impl<'a, R: AsyncRead + Unpin> ZipFileReader<'a, R> {
-    /// Constructs a new ZIP file reader from a mutable reference to a reader.
-    pub fn new(reader: &'a mut R) -> Self {
-        ZipFileReader { reader, entry: None, finished: false }
+impl<R: AsyncRead + Unpin> ZipFileReader<R> {
+    /// Constructs a new ZIP file reader from a reader (or a mutable reference to one).
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ReaderOptions::default())
+    }
+
+    /// Constructs a new ZIP file reader from a reader (or a mutable reference to one) and a set of options.
+    pub fn with_options(reader: R, options: ReaderOptions) -> Self {
+        let reader = OffsetAsyncReader::from_raw(reader);
+        let size = options.read_buffer_size.unwrap_or(options.decoder_buffer_size);
+        let size = std::cmp::max(size, crate::read::descriptor::MAX_DESCRIPTOR_LENGTH);
+        let buffer = SharedBuffer::new(options.buffer_pool.take(size));
+        ZipFileReader { reader, buffer, entry: None, entry_start: 0, entry_end: Some(0), finished: false, options }
     }
 
     /// Returns whether or not `entry_reader()` will yield more entries.
@@ -32,55 +71,291 @@ impl<'a, R: AsyncRead + Unpin> ZipFileReader<'a, R> {
         self.finished
     }
 
-    /// Opens the next entry for reading if the central directory hasn't already been reached.
-    pub async fn entry_reader<'b>(&'b mut self) -> Result<Option<ZipEntryReader<'b, R>>> {
-        // TODO: Ensure the previous entry has been fully read.
+    /// Returns the total number of bytes consumed from the inner reader so far.
+    ///
+    /// This includes all headers, entry data, and skipped data, so when the total size of the archive is known, it
+    /// can be used to compute the progress made through the archive as a whole.
+    pub fn bytes_consumed(&self) -> u64 {
+        self.reader.offset() - self.buffer.buffered() as u64
+    }
 
+    /// Opens the next entry for reading if the central directory hasn't already been reached.
+    ///
+    /// Any data of the previous entry which wasn't read is discarded without being decompressed.
+    pub async fn entry_reader(&mut self) -> Result<Option<ZipEntryReader<'_, OffsetAsyncReader<R>>>> {
         if self.finished {
             return Ok(None);
-        } else if let Some(inner) = read_lfh(self.reader).await? {
+        }
+
+        if let Some(inner) = self.next_lfh().await? {
             self.entry = Some(inner);
         } else {
             self.finished = true;
             return Ok(None);
         }
 
-        let entry_borrow = self.entry.as_ref().unwrap();
+        self.open_entry().map(Some)
+    }
+
+    /// Skips entries until one with the provided name is found, and opens it for reading.
+    ///
+    /// Skipped entries are never decompressed; their compressed data is discarded directly from the reader's buffer.
+    /// The data of an entry followed by a data descriptor is scanned for a descriptor whose compressed size matches it.
+    ///
+    /// `Ok(None)` is returned if the central directory is reached before a matching entry is found.
+    pub async fn skip_to(&mut self, name: &str) -> Result<Option<ZipEntryReader<'_, OffsetAsyncReader<R>>>> {
+        self.skip_to_matching(|entry| entry.filename() == name).await
+    }
+
+    /// Skips entries until one matching the provided predicate is found, and opens it for reading.
+    ///
+    /// See [`ZipFileReader::skip_to()`] for details.
+    pub async fn skip_to_matching<P>(
+        &mut self,
+        mut predicate: P,
+    ) -> Result<Option<ZipEntryReader<'_, OffsetAsyncReader<R>>>>
+    where
+        P: FnMut(&ZipEntry) -> bool,
+    {
+        while !self.finished {
+            match self.next_lfh().await? {
+                Some(entry) if predicate(&entry) => {
+                    self.entry = Some(entry);
+                    return self.open_entry().map(Some);
+                }
+                Some(entry) => {
+                    self.entry = Some(entry);
+                    self.set_entry_end()?;
+                }
+                None => self.finished = true,
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Reads the next entry's local file header and skips over its data without opening it for reading, returning
+    /// the entry (as described by its local file header).
+    ///
+    /// As with [`ZipFileReader::skip_to()`], the entry's compressed data is discarded directly from the reader's
+    /// buffer (once the next entry is opened or skipped) without constructing a decoder. `Ok(None)` is returned once
+    /// the central directory has been reached.
+    pub async fn skip_entry(&mut self) -> Result<Option<ZipEntry>> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        match self.next_lfh().await? {
+            Some(entry) => {
+                self.entry = Some(entry.clone());
+                self.set_entry_end()?;
+                Ok(Some(entry))
+            }
+            None => {
+                self.finished = true;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Discards any data of the previous entry which wasn't read, and then reads the next local file header (if the
+    /// central directory hasn't been reached).
+    async fn next_lfh(&mut self) -> Result<Option<ZipEntry>> {
+        let result = match self.skip_remaining().await {
+            Ok(()) if self.options.skip_prepended_data && self.bytes_consumed() == 0 => self.skip_prepended().await,
+            Ok(()) => {
+                read_lfh(&mut SharedBufReader::new(&mut self.reader, &mut self.buffer), self.options.parse_mode).await
+            }
+            Err(error) => Err(error),
+        };
+
+        result.map_err(|error| self.truncation(error))
+    }
+
+    /// Discards any data prepended to the archive by scanning forward for the signature of the first local file header,
+    /// and then reads that header.
+    async fn skip_prepended(&mut self) -> Result<Option<ZipEntry>> {
+        let signature = crate::spec::delimiter::LFHD.to_le_bytes();
+        let mut reader = SharedBufReader::new(&mut self.reader, &mut self.buffer);
+        let mut matched = 0;
+
+        while matched < signature.len() {
+            let buffer = reader.fill_buf().await?;
+            if buffer.is_empty() {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+
+            let mut consumed = 0;
+            for byte in buffer.iter() {
+                consumed += 1;
+                matched = match *byte {
+                    byte if byte == signature[matched] => matched + 1,
+                    byte if byte == signature[0] => 1,
+                    _ => 0,
+                };
+
+                if matched == signature.len() {
+                    break;
+                }
+            }
+
+            reader.consume(consumed);
+        }
+
+        read_lfh_fields(&mut reader, self.options.parse_mode).await.map(Some)
+    }
 
-        if entry_borrow.data_descriptor() {
-            return Err(ZipError::FeatureNotSupported("Entries with data descriptors"));
+    /// Replaces an error caused by the archive ending unexpectedly with [`ZipError::Truncated`] (and stops yielding
+    /// entries) if truncation is to be detected.
+    fn truncation(&mut self, error: ZipError) -> ZipError {
+        match error {
+            ZipError::UpstreamReadError(error)
+                if self.options.detect_truncation && error.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                self.finished = true;
+                ZipError::Truncated
+            }
+            error => error,
         }
+    }
 
-        let reader = self.reader.take(entry_borrow.compressed_size.unwrap().into());
-        let reader = CompressionReader::from_reader_borrow(entry_borrow.compression(), reader);
+    /// Records where the data of the entry whose local file header has just been read starts and ends.
+    ///
+    /// The end of an entry followed by a data descriptor isn't known until the descriptor has been found.
+    fn set_entry_end(&mut self) -> Result<()> {
+        let entry = self.entry.as_ref().unwrap();
+        let (descriptor, compressed_size) = (entry.data_descriptor(), entry.compressed_size);
+
+        self.entry_start = self.bytes_consumed();
+        if descriptor {
+            self.entry_end = None;
+            self.buffer.set_entry_ended(false);
+            return Ok(());
+        }
+
+        let end = self.bytes_consumed().checked_add(compressed_size.unwrap());
+        self.entry_end = Some(end.ok_or(ZipError::HeaderOutOfBounds("entry data"))?);
+        Ok(())
+    }
+
+    /// Discards any data of the previous entry which wasn't read.
+    ///
+    /// Skipped data is never decompressed; it's discarded directly from the reader's buffer. The rest of the data of
+    /// an entry followed by a data descriptor is scanned for the descriptor (see [`descriptor::poll_skip()`]).
+    async fn skip_remaining(&mut self) -> Result<()> {
+        let remaining = match self.entry_end {
+            Some(end) => end.saturating_sub(self.bytes_consumed()),
+            None if self.buffer.entry_ended() => 0,
+            None => {
+                let zip64 = self.entry.as_ref().is_some_and(ZipEntry::zip64);
+                let mut skipped = self.bytes_consumed() - self.entry_start;
+                let mut reader = SharedBufReader::new(&mut self.reader, &mut self.buffer);
+                std::future::poll_fn(|c| descriptor::poll_skip(&mut reader, c, &mut skipped, zip64)).await?;
+                return Ok(());
+            }
+        };
+
+        if remaining > 0 {
+            let mut reader = SharedBufReader::new(&mut self.reader, &mut self.buffer).take(remaining);
+            if tokio::io::copy_buf(&mut reader, &mut tokio::io::sink()).await? < remaining {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+        }
 
-        Ok(Some(ZipEntryReader::from_raw(entry_borrow, reader, true)))
+        Ok(())
+    }
+
+    /// Opens the most recently read entry for reading.
+    fn open_entry(&mut self) -> Result<ZipEntryReader<'_, OffsetAsyncReader<R>>> {
+        self.set_entry_end()?;
+        let entry_borrow = self.entry.as_mut().unwrap();
+
+        let (descriptor, zip64) = (entry_borrow.data_descriptor(), entry_borrow.zip64());
+        let size = (!descriptor).then(|| entry_borrow.compressed_size.unwrap());
+        let reader = SharedBufReader::new(&mut self.reader, &mut self.buffer);
+        let reader = CompressionReader::from_shared(entry_borrow, reader, size, &self.options)?;
+        let reader = ZipEntryReader::from_raw(entry_borrow, reader, &self.options);
+
+        Ok(match descriptor {
+            true => reader.expect_descriptor(zip64),
+            false => reader,
+        })
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<R: futures_io::AsyncRead + Unpin> ZipFileReader<crate::compat::Compat<R>> {
+    /// Constructs a new ZIP file reader from a reader implementing the `futures-io` traits rather than tokio's.
+    pub fn from_futures_io(reader: R) -> Self {
+        Self::new(crate::compat::Compat::new(reader))
     }
 }
 
-pub(crate) async fn read_lfh<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<ZipEntry>> {
+impl ZipFileReader<BufReader<File>> {
+    /// Opens the file at the provided path and constructs a new ZIP file reader which owns it, with buffering.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with_options(path, ReaderOptions::default()).await
+    }
+
+    /// Opens the file at the provided path and constructs a new ZIP file reader which owns it, with buffering and a
+    /// set of options.
+    pub async fn open_with_options(path: impl AsRef<Path>, options: ReaderOptions) -> Result<Self> {
+        let file = crate::utils::open_file(path.as_ref()).await?;
+        Ok(Self::with_options(BufReader::new(file), options))
+    }
+}
+
+pub(crate) async fn read_lfh<R: AsyncRead + Unpin>(reader: &mut R, mode: ParseMode) -> Result<Option<ZipEntry>> {
     match reader.read_u32_le().await? {
         crate::spec::delimiter::LFHD => {}
         crate::spec::delimiter::CDFHD => return Ok(None),
         actual => return Err(ZipError::UnexpectedHeaderError(actual, crate::spec::delimiter::LFHD)),
     };
 
+    read_lfh_fields(reader, mode).await.map(Some)
+}
+
+/// Reads a local file header whose signature has already been read, along with its variable-length fields.
+async fn read_lfh_fields<R: AsyncRead + Unpin>(reader: &mut R, mode: ParseMode) -> Result<ZipEntry> {
     let header = LocalFileHeader::from_reader(reader).await?;
-    let filename = crate::utils::read_string(reader, header.file_name_length.into()).await?;
+    let encoding = crate::utils::flag_encoding(&header.flags);
+    let filename = crate::utils::read_string(reader, header.file_name_length.into(), encoding).await?;
     let extra = crate::utils::read_bytes(reader, header.extra_field_length.into()).await?;
+    mode.tolerate(validate_fields(&extra), || ())?;
+    let zip64 = Zip64ExtendedInfo::parse(&extra, header.uncompressed_size, header.compressed_size, None);
+    let zip64 = mode.tolerate(zip64, || None)?.unwrap_or_default();
+    let aes = AesExtraField::parse(&extra)?.filter(|_| header.compression == AES_COMPRESSION_METHOD);
+    let timestamp = ExtendedTimestamp::parse(&extra);
 
     let entry = ZipEntry {
         name: filename,
         comment: None,
         data_descriptor: header.flags.data_descriptor,
+        encrypted: header.flags.encrypted,
+        compression_options: header.flags.compression_options,
         crc32: Some(header.crc),
-        uncompressed_size: Some(header.uncompressed_size),
-        compressed_size: Some(header.compressed_size),
-        last_modified: crate::spec::date::zip_date_to_chrono(header.mod_date, header.mod_time),
+        uncompressed_size: Some(zip64.uncompressed_size.unwrap_or(header.uncompressed_size.into())),
+        compressed_size: Some(zip64.compressed_size.unwrap_or(header.compressed_size.into())),
+        last_modified: match timestamp.modified {
+            Some(modified) => modified,
+            None => mode.tolerate(
+                crate::spec::date::zip_date_to_chrono(header.mod_date, header.mod_time),
+                crate::spec::date::dos_epoch,
+            )?,
+        },
+        last_accessed: timestamp.accessed,
+        created: timestamp.created,
+        ntfs: NtfsTimestamps::parse(&extra),
+        local_extra: Some(extra.clone()),
         extra: Some(extra),
-        compression: Compression::from_u16(header.compression)?,
+        compression: crate::spec::encryption::resolve_compression(header.compression, aes.as_ref())?,
+        aes,
         offset: None,
+        made_by: None,
+        exter_attr: None,
+        inter_attr: None,
+        partial: false,
     };
 
-    Ok(Some(entry))
+    Ok(entry)
 }
@@ -15,7 +15,7 @@
 //! Feel free to open an issue/PR if you have a good approach for this.
 
 use crate::error::{Result, ZipError};
-use crate::read::{CompressionReader, ZipEntry, ZipEntryReader};
+use crate::read::{CompressionReader, EntryIndex, ReaderOptions, ZipEntry, ZipEntryReader};
 
 use std::io::SeekFrom;
 use std::ops::DerefMut;
@@ -23,12 +23,14 @@ use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, ReadBuf};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, ReadBuf};
 
 /// A reader which acts concurrently over an in-memory buffer.
 pub struct ZipFileReader<R: AsyncRead + AsyncSeek + Unpin> {
     pub(crate) reader: Arc<Mutex<R>>,
     pub(crate) entries: Vec<ZipEntry>,
+    pub(crate) options: ReaderOptions,
+    pub(crate) index: EntryIndex,
 }
 
 #[allow(unreachable_code, unused_variables)]
@@ -37,8 +39,9 @@ impl<R: AsyncRead + AsyncSeek + Unpin> ZipFileReader<R> {
     pub async fn new(reader: R) -> Result<ZipFileReader<R>> {
         unimplemented!();
 
-        let entries = crate::read::seek::read_cd(&mut reader).await?;
-        Ok(ZipFileReader { reader: Arc::new(Mutex::new(reader)), entries })
+        let entries = crate::read::seek::read_cd(&mut reader, crate::read::ParseMode::Standard).await?;
+        let (reader, options) = (Arc::new(Mutex::new(reader)), ReaderOptions::default());
+        Ok(ZipFileReader { reader, entries, options, index: EntryIndex::default() })
     }
 
     crate::read::reader_entry_impl!();
@@ -49,12 +52,12 @@ impl<R: AsyncRead + AsyncSeek + Unpin> ZipFileReader<R> {
 
         let mut guarded_reader = GuardedReader { reader: self.reader.clone() };
 
-        guarded_reader.seek(SeekFrom::Start(entry.data_offset())).await?;
+        entry.seek_to_data_offset(&mut guarded_reader).await?;
 
-        let reader = guarded_reader.take(entry.compressed_size.unwrap().into());
-        let reader = CompressionReader::from_reader(entry.compression(), reader);
+        let reader = guarded_reader.take(entry.compressed_size.unwrap());
+        let reader = CompressionReader::from_reader(entry, reader, &self.options)?;
 
-        Ok(ZipEntryReader::from_raw(entry, reader, false))
+        Ok(ZipEntryReader::from_raw(entry, reader, &self.options))
     }
 }
 
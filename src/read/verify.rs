@@ -0,0 +1,109 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A module for testing the integrity of an archive's entries (as with `unzip -t`).
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::read::seek::ZipFileReader;
+//! # use tokio::fs::File;
+//! # use async_zip::error::ZipError;
+//! #
+//! # async fn run() -> Result<(), ZipError> {
+//! let mut zip = ZipFileReader::new(File::open("./Archive.zip").await?).await?;
+//! let report = zip.verify().await;
+//!
+//! for failure in report.failures() {
+//!     println!("{}: {}", failure.name(), failure.error().unwrap());
+//! }
+//! #   Ok(())
+//! # }
+//! ```
+
+use crate::error::{Result, ZipError};
+use crate::read::ZipEntryReader;
+
+use tokio::io::AsyncRead;
+
+/// The outcome of verifying every entry of an archive, as returned by [`seek::ZipFileReader::verify()`].
+///
+/// [`seek::ZipFileReader::verify()`]: crate::read::seek::ZipFileReader::verify
+#[derive(Debug, Default)]
+pub struct VerificationReport {
+    pub(crate) entries: Vec<EntryVerification>,
+}
+
+impl VerificationReport {
+    /// Returns whether every entry was verified successfully.
+    pub fn is_ok(&self) -> bool {
+        self.entries.iter().all(EntryVerification::is_ok)
+    }
+
+    /// Returns the outcome of verifying each entry, in the order they appear within the central directory.
+    pub fn entries(&self) -> &[EntryVerification] {
+        &self.entries
+    }
+
+    /// Returns an iterator over the outcomes of the entries which failed verification.
+    pub fn failures(&self) -> impl Iterator<Item = &EntryVerification> {
+        self.entries.iter().filter(|entry| !entry.is_ok())
+    }
+}
+
+/// The outcome of verifying a single entry.
+#[derive(Debug)]
+pub struct EntryVerification {
+    pub(crate) index: usize,
+    pub(crate) name: String,
+    pub(crate) result: Result<u64>,
+}
+
+impl EntryVerification {
+    /// Returns the index of the entry within the archive.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the entry's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns whether the entry's data was decompressed in full and matched its declared size and CRC32 value.
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// Returns the number of bytes the entry decompressed to, if it was verified successfully.
+    pub fn size(&self) -> Option<u64> {
+        self.result.as_ref().ok().copied()
+    }
+
+    /// Returns the reason the entry failed verification, if it did.
+    pub fn error(&self) -> Option<&ZipError> {
+        self.result.as_ref().err()
+    }
+}
+
+/// Reads an entry to its end, verifying its data against its declared size and CRC32 value.
+pub(crate) async fn verify_entry<R: AsyncRead + Unpin>(mut reader: ZipEntryReader<'_, R>) -> Result<u64> {
+    let declared = reader.entry().uncompressed_size.unwrap();
+    let read = tokio::io::copy(&mut reader, &mut tokio::io::sink()).await.map_err(unwrap_io)?;
+
+    if read != declared {
+        return Err(ZipError::DeclaredSizeMismatch(declared, read));
+    }
+    if !reader.compare_crc() {
+        return Err(ZipError::CRC32CheckError);
+    }
+
+    Ok(read)
+}
+
+/// Returns the error an entry reader's I/O error wraps, if any, so that reports hold the underlying cause.
+fn unwrap_io(error: std::io::Error) -> ZipError {
+    match error.get_ref().is_some_and(|inner| inner.is::<ZipError>()) {
+        true => *error.into_inner().unwrap().downcast::<ZipError>().unwrap(),
+        false => error.into(),
+    }
+}
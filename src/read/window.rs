@@ -0,0 +1,85 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use std::io::SeekFrom;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+/// A seekable reader over a fixed range of its inner reader, such as the data of a Stored entry.
+///
+/// All offsets are translated so that the start of the range appears as the start of the reader, and reads stop at
+/// the end of the range. This allows an archive stored within another to be read as if it were a standalone file.
+pub struct EntryWindow<R: AsyncRead + AsyncSeek + Unpin> {
+    reader: R,
+    start: u64,
+    length: u64,
+    position: u64,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> EntryWindow<R> {
+    /// Constructs a new window from a reader already positioned at the start of the range, and the range's length.
+    pub(crate) fn from_raw(reader: R, start: u64, length: u64) -> Self {
+        Self { reader, start, length, position: 0 }
+    }
+
+    /// Returns the length of the range this window covers.
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    /// Returns whether or not the range this window covers is empty.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncRead for EntryWindow<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let remaining = self.length.saturating_sub(self.position);
+        let limit = std::cmp::min(remaining, b.remaining() as u64) as usize;
+
+        if limit == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let mut limited = ReadBuf::new(b.initialize_unfilled_to(limit));
+        match Pin::new(&mut self.reader).poll_read(c, &mut limited) {
+            Poll::Ready(Ok(())) => {}
+            poll => return poll,
+        };
+
+        let read = limited.filled().len();
+        b.advance(read);
+        self.position += read as u64;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncSeek for EntryWindow<R> {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        let target = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.length.checked_add_signed(offset),
+        };
+
+        let absolute = target.and_then(|target| self.start.checked_add(target)).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "seek to a negative or overflowing position")
+        })?;
+
+        Pin::new(&mut self.reader).start_seek(SeekFrom::Start(absolute))
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        match Pin::new(&mut self.reader).poll_complete(c) {
+            Poll::Ready(Ok(absolute)) => {
+                self.position = absolute.saturating_sub(self.start);
+                Poll::Ready(Ok(self.position))
+            }
+            poll => poll,
+        }
+    }
+}
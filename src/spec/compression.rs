@@ -1,17 +1,29 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
-use crate::error::{Result, ZipError};
+use crate::error::Result;
 
 /// A compression method supported by this crate.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Compression {
     Stored,
+    /// Shrink, which may only be read (with the `legacy-compression` feature).
+    Shrink,
+    /// Implode, which may only be read (with the `legacy-compression` feature).
+    Implode,
     Deflate,
+    /// Deflate64 ("enhanced deflate"), which may only be read.
+    Deflate64,
     Bz,
     Lzma,
     Zstd,
     Xz,
+    /// PPMd (variant I, revision 1), which may only be read (with the `ppmd` feature).
+    Ppmd,
+    /// A method without built-in support, which may be read or written via a registered [`CompressionBackend`].
+    ///
+    /// [`CompressionBackend`]: crate::backend::CompressionBackend
+    Custom(u16),
 }
 
 impl Compression {
@@ -19,24 +31,38 @@ impl Compression {
     pub fn to_u16(&self) -> u16 {
         match self {
             Compression::Stored => 0,
+            Compression::Shrink => 1,
+            Compression::Implode => 6,
             Compression::Deflate => 8,
+            Compression::Deflate64 => 9,
             Compression::Bz => 12,
             Compression::Lzma => 14,
             Compression::Zstd => 93,
             Compression::Xz => 95,
+            Compression::Ppmd => 98,
+            Compression::Custom(value) => *value,
         }
     }
 
-    /// Convert a u16 stored with little endianness into a supported compression method.
+    /// Convert a u16 stored with little endianness into a compression method.
+    ///
+    /// Methods without built-in support are converted into [`Compression::Custom`], and only fail to be read (with
+    /// [`ZipError::UnsupportedCompressionError`]) if no backend has been registered for them.
+    ///
+    /// [`ZipError::UnsupportedCompressionError`]: crate::error::ZipError::UnsupportedCompressionError
     pub fn from_u16(value: u16) -> Result<Compression> {
         match value {
             0 => Ok(Compression::Stored),
+            1 => Ok(Compression::Shrink),
+            6 => Ok(Compression::Implode),
             8 => Ok(Compression::Deflate),
+            9 => Ok(Compression::Deflate64),
             12 => Ok(Compression::Bz),
             14 => Ok(Compression::Lzma),
             93 => Ok(Compression::Zstd),
             95 => Ok(Compression::Xz),
-            _ => Err(ZipError::UnsupportedCompressionError(value)),
+            98 => Ok(Compression::Ppmd),
+            _ => Ok(Compression::Custom(value)),
         }
     }
 }
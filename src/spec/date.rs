@@ -1,42 +1,147 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
+use crate::error::{Result, ZipError};
+
 use chrono::{DateTime, Datelike, TimeZone, Timelike, Utc};
 
 //  4.4.6 date and time fields: (2 bytes each)
-// 
+//
 // The date and time are encoded in standard MS-DOS format.
 // If input came from standard input, the date and time are
-// those at which compression was started for this data. 
-// If encrypting the central directory and general purpose bit 
-// flag 13 is set indicating masking, the value stored in the 
+// those at which compression was started for this data.
+// If encrypting the central directory and general purpose bit
+// flag 13 is set indicating masking, the value stored in the
 // Local Header will be zero. MS-DOS time format is different
-// from more commonly used computer time formats such as 
+// from more commonly used computer time formats such as
 // UTC. For example, MS-DOS uses year values relative to 1980
 // and 2 second precision.
 
 // Converts a date and time stored within ZIP headers into a `chrono` structure.
-pub fn zip_date_to_chrono(date: u16, time: u16) -> DateTime<Utc> {
+pub fn zip_date_to_chrono(date: u16, time: u16) -> Result<DateTime<Utc>> {
     let years = (((date & 0xFE00) >> 9) + 1980).into();
     let months = ((date & 0x1E0) >> 5).into();
     let days = (date & 0x1F).into();
 
-    let hours = ((time & 0x1F) >> 11).into();
+    let hours = ((time & 0xF800) >> 11).into();
     let mins = ((time & 0x7E0) >> 5).into();
     let secs = ((time & 0x1F) << 1).into();
 
-    Utc.ymd(years, months, days).and_hms(hours, mins, secs)
+    Utc.with_ymd_and_hms(years, months, days, hours, mins, secs).single().ok_or(ZipError::InvalidDateTime(date, time))
+}
+
+// Returns the earliest date and time which can be stored in ZIP headers (the start of 1980).
+pub(crate) fn dos_epoch() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(1980, 1, 1, 0, 0, 0).unwrap()
 }
 
 // Converts a `chrono` structure into a date and time stored in ZIP headers.
+//
+// Dates outside of the representable range (1980 to 2107) are clamped to its nearest end, so timestamps such as a
+// SOURCE_DATE_EPOCH of zero still produce valid headers.
 pub fn chrono_to_zip_time(dt: &DateTime<Utc>) -> (u16, u16) {
-    let year: u16 = (((dt.date().year() - 1980) << 9) & 0xFE00).try_into().unwrap();
-    let month: u16 = ((dt.date().month() << 5) & 0x1E0).try_into().unwrap();
-    let day: u16 = (dt.date().day() & 0x1F).try_into().unwrap();
+    let min = dos_epoch();
+    let max = Utc.with_ymd_and_hms(2107, 12, 31, 23, 59, 58).unwrap();
+    let dt = &(*dt).clamp(min, max);
+
+    let year: u16 = (((dt.date_naive().year() - 1980) << 9) & 0xFE00).try_into().unwrap();
+    let month: u16 = ((dt.date_naive().month() << 5) & 0x1E0).try_into().unwrap();
+    let day: u16 = (dt.date_naive().day() & 0x1F).try_into().unwrap();
 
-    let hour: u16 = ((dt.time().hour() << 11) & 0x1F).try_into().unwrap();
+    let hour: u16 = ((dt.time().hour() << 11) & 0xF800).try_into().unwrap();
     let min: u16 = ((dt.time().minute() << 5) & 0x7E0).try_into().unwrap();
     let second: u16 = ((dt.time().second() >> 1) & 0x1F).try_into().unwrap();
 
     (hour | min | second, year | month | day)
 }
+
+/// The header ID of Info-ZIP's extended timestamp extra field.
+pub(crate) const EXTENDED_TIMESTAMP_FIELD_ID: u16 = 0x5455;
+
+/// The Unix timestamps (with 1-second precision) held within Info-ZIP's extended timestamp extra field.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ExtendedTimestamp {
+    pub(crate) modified: Option<DateTime<Utc>>,
+    pub(crate) accessed: Option<DateTime<Utc>>,
+    pub(crate) created: Option<DateTime<Utc>>,
+}
+
+impl ExtendedTimestamp {
+    /// Parses the extended timestamp extra field held within an extra field, if one exists.
+    ///
+    /// The field's flags declare which timestamps the local file header's copy holds, whereas the central directory's
+    /// copy only ever holds the modification time. So any timestamp which is flagged but missing is ignored, as is the
+    /// field if it's empty.
+    pub(crate) fn parse(extra: &[u8]) -> ExtendedTimestamp {
+        let field = crate::spec::extra::fields(extra).find(|(id, _)| *id == EXTENDED_TIMESTAMP_FIELD_ID);
+        let (flags, mut values) = match field.and_then(|(_, data)| data.split_first()) {
+            Some((flags, values)) => (*flags, values),
+            None => return ExtendedTimestamp::default(),
+        };
+
+        let mut next = |flag: u8| {
+            if flags & flag == 0 {
+                return None;
+            }
+
+            let (value, remaining) = values.split_first_chunk::<4>()?;
+            values = remaining;
+            Utc.timestamp_opt(i32::from_le_bytes(*value).into(), 0).single()
+        };
+
+        ExtendedTimestamp { modified: next(0x01), accessed: next(0x02), created: next(0x04) }
+    }
+}
+
+/// The header ID of the NTFS extra field.
+pub(crate) const NTFS_FIELD_ID: u16 = 0x000a;
+
+/// The tag of the NTFS extra field's attribute which holds the file times.
+const NTFS_TIMES_TAG: u16 = 0x0001;
+
+/// The number of seconds between the NTFS epoch (1601-01-01) and the Unix epoch.
+const NTFS_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// The NTFS timestamps (with 100-nanosecond precision) held within an entry's NTFS extra field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NtfsTimestamps {
+    pub(crate) modified: DateTime<Utc>,
+    pub(crate) accessed: DateTime<Utc>,
+    pub(crate) created: DateTime<Utc>,
+}
+
+impl NtfsTimestamps {
+    /// Parses the NTFS extra field held within an extra field, if one exists and holds the file times.
+    pub(crate) fn parse(extra: &[u8]) -> Option<NtfsTimestamps> {
+        let (_, data) = crate::spec::extra::fields(extra).find(|(id, _)| *id == NTFS_FIELD_ID)?;
+
+        // The attributes follow four reserved bytes, and are laid out in the same way as the extra fields themselves.
+        let (_, times) = crate::spec::extra::fields(data.get(4..)?).find(|(tag, _)| *tag == NTFS_TIMES_TAG)?;
+        let time = |index: usize| {
+            ntfs_time_to_chrono(u64::from_le_bytes(times.get(index * 8..index * 8 + 8)?.try_into().unwrap()))
+        };
+
+        Some(NtfsTimestamps { modified: time(0)?, accessed: time(1)?, created: time(2)? })
+    }
+
+    /// Returns a shared reference to the entry's last modification date.
+    pub fn modified(&self) -> &DateTime<Utc> {
+        &self.modified
+    }
+
+    /// Returns a shared reference to the entry's last access date.
+    pub fn accessed(&self) -> &DateTime<Utc> {
+        &self.accessed
+    }
+
+    /// Returns a shared reference to the entry's creation date.
+    pub fn created(&self) -> &DateTime<Utc> {
+        &self.created
+    }
+}
+
+/// Converts an NTFS time (the number of 100-nanosecond intervals since 1601-01-01) to a chrono datetime.
+fn ntfs_time_to_chrono(time: u64) -> Option<DateTime<Utc>> {
+    let seconds = (time / 10_000_000) as i64 - NTFS_EPOCH_OFFSET;
+    Utc.timestamp_opt(seconds, (time % 10_000_000) as u32 * 100).single()
+}
@@ -12,3 +12,9 @@ pub const DDD: u32 = 0x8074b50;
 
 /// End of central directory delimiter.
 pub const EOCDD: u32 = 0x6054b50;
+
+/// Zip64 end of central directory record delimiter.
+pub const ZIP64_EOCDRD: u32 = 0x6064b50;
+
+/// Zip64 end of central directory locator delimiter.
+pub const ZIP64_EOCDLD: u32 = 0x7064b50;
@@ -0,0 +1,131 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::error::{Result, ZipError};
+use crate::spec::compression::Compression;
+
+/// The compression method stored within the headers of entries encrypted with WinZip's AES encryption scheme.
+pub(crate) const AES_COMPRESSION_METHOD: u16 = 99;
+
+/// The header ID of the AES extra data field.
+pub(crate) const AES_EXTRA_FIELD_ID: u16 = 0x9901;
+
+/// The vendor version of the AES extra data fields written by this crate (AE-2, which omits CRC32 values).
+#[cfg(feature = "aes")]
+pub(crate) const AES_VENDOR_VERSION: u16 = 2;
+
+/// The length of the password verification value which follows the salt of an AES-encrypted entry.
+#[cfg(feature = "aes")]
+pub(crate) const AES_VERIFICATION_LENGTH: usize = 2;
+
+/// The length of the authentication code which follows the data of an AES-encrypted entry.
+#[cfg(feature = "aes")]
+pub(crate) const AES_AUTH_CODE_LENGTH: usize = 10;
+
+/// A scheme with which an entry's data may be encrypted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encryption {
+    /// The traditional PKWARE encryption scheme (ZipCrypto).
+    ZipCrypto,
+    /// WinZip's AES encryption scheme, with the provided key strength.
+    Aes(AesStrength),
+}
+
+/// The key strength of an entry encrypted with WinZip's AES encryption scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AesStrength {
+    Aes128,
+    Aes192,
+    Aes256,
+}
+
+impl AesStrength {
+    /// Convert the strength stored within an AES extra data field into a key strength.
+    pub(crate) fn from_u8(value: u8) -> Option<AesStrength> {
+        match value {
+            1 => Some(AesStrength::Aes128),
+            2 => Some(AesStrength::Aes192),
+            3 => Some(AesStrength::Aes256),
+            _ => None,
+        }
+    }
+
+    /// Convert a key strength into the value stored within an AES extra data field.
+    #[cfg(feature = "aes")]
+    pub(crate) fn to_u8(self) -> u8 {
+        match self {
+            AesStrength::Aes128 => 1,
+            AesStrength::Aes192 => 2,
+            AesStrength::Aes256 => 3,
+        }
+    }
+
+    /// Returns the length of the AES key (and of the HMAC-SHA1 key) in bytes.
+    #[cfg(feature = "aes")]
+    pub(crate) fn key_length(&self) -> usize {
+        match self {
+            AesStrength::Aes128 => 16,
+            AesStrength::Aes192 => 24,
+            AesStrength::Aes256 => 32,
+        }
+    }
+
+    /// Returns the length of the salt which precedes an entry's data in bytes.
+    #[cfg(feature = "aes")]
+    pub(crate) fn salt_length(&self) -> usize {
+        self.key_length() / 2
+    }
+}
+
+/// The values of an AES extra data field, which describes how an AES-encrypted entry's data was produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AesExtraField {
+    /// The vendor version, where entries of version 2 (AE-2) omit their CRC32 value.
+    pub(crate) version: u16,
+    pub(crate) strength: AesStrength,
+    /// The compression method applied to the entry's data before it was encrypted.
+    pub(crate) compression: u16,
+}
+
+impl AesExtraField {
+    /// Parses the AES extra data field held within an extra field, if one exists.
+    pub(crate) fn parse(extra: &[u8]) -> Result<Option<AesExtraField>> {
+        let data = match crate::spec::extra::fields(extra).find(|(id, _)| *id == AES_EXTRA_FIELD_ID) {
+            Some((_, data)) => data,
+            None => return Ok(None),
+        };
+
+        let strength = match data {
+            [_, _, b'A', b'E', strength, _, _] => AesStrength::from_u8(*strength),
+            _ => None,
+        };
+        let strength = strength.ok_or(ZipError::InvalidExtraField(AES_EXTRA_FIELD_ID))?;
+
+        Ok(Some(AesExtraField {
+            version: u16::from_le_bytes([data[0], data[1]]),
+            strength,
+            compression: u16::from_le_bytes([data[5], data[6]]),
+        }))
+    }
+
+    /// Serialises the field, including its header ID and size.
+    #[cfg(feature = "aes")]
+    pub(crate) fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(11);
+        bytes.extend_from_slice(&AES_EXTRA_FIELD_ID.to_le_bytes());
+        bytes.extend_from_slice(&7u16.to_le_bytes());
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(b"AE");
+        bytes.push(self.strength.to_u8());
+        bytes.extend_from_slice(&self.compression.to_le_bytes());
+        bytes
+    }
+}
+
+/// Resolves the compression method of an entry, which is held within its AES extra data field if it's AES-encrypted.
+pub(crate) fn resolve_compression(method: u16, aes: Option<&AesExtraField>) -> Result<Compression> {
+    match (method, aes) {
+        (AES_COMPRESSION_METHOD, Some(aes)) => Compression::from_u16(aes.compression),
+        (method, _) => Compression::from_u16(method),
+    }
+}
@@ -0,0 +1,269 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::error::{Result, ZipError};
+use crate::utils::field_length;
+
+use chrono::{DateTime, Utc};
+
+/// The header ID of the Zip64 extended information extra field.
+pub(crate) const ZIP64_EXTRA_FIELD_ID: u16 = 0x0001;
+
+/// The value of a 32-bit size or offset field which indicates that the real value is held in the Zip64 extended
+/// information extra field.
+pub(crate) const ZIP64_SENTINEL: u32 = u32::MAX;
+
+/// Returns whether a 64-bit value can't be stored in its 32-bit header field (including values which would be mistaken
+/// for the sentinel).
+pub(crate) fn exceeds_u32(value: u64) -> bool {
+    value >= ZIP64_SENTINEL as u64
+}
+
+/// Returns the value to store in a 32-bit header field, given the value held in the Zip64 extra field (if any).
+pub(crate) fn header_value(held: Option<u64>, value: u64) -> u32 {
+    match held {
+        Some(_) => ZIP64_SENTINEL,
+        None => value as u32,
+    }
+}
+
+/// Iterates over the `(header ID, data)` pairs of an extra field, stopping at the first which is truncated.
+pub(crate) fn fields(extra: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    let mut remaining = extra;
+
+    std::iter::from_fn(move || {
+        let id = u16::from_le_bytes(remaining.get(0..2)?.try_into().unwrap());
+        let size = u16::from_le_bytes(remaining.get(2..4)?.try_into().unwrap()) as usize;
+        let data = remaining.get(4..4 + size)?;
+
+        remaining = &remaining[4 + size..];
+        Some((id, data))
+    })
+}
+
+/// Checks that every field within an extra field ends within it, failing with the header ID of the first which doesn't.
+///
+/// Up to three trailing bytes (too few to hold a field's header) are allowed, as some tools pad extra fields with them.
+pub(crate) fn validate_fields(extra: &[u8]) -> Result<()> {
+    let mut remaining = extra;
+
+    while let (Some(id), Some(size)) = (remaining.get(0..2), remaining.get(2..4)) {
+        let id = u16::from_le_bytes(id.try_into().unwrap());
+        let size = u16::from_le_bytes(size.try_into().unwrap()) as usize;
+        remaining = remaining.get(4 + size..).ok_or(ZipError::InvalidExtraField(id))?;
+    }
+
+    Ok(())
+}
+
+/// Splits an extra field into the fields with the provided header ID, and all other fields (each in their original
+/// order).
+pub(crate) fn split_fields(extra: &[u8], id: u16) -> Result<(Vec<u8>, Vec<u8>)> {
+    let (mut matching, mut rest) = (Vec::new(), Vec::with_capacity(extra.len()));
+
+    for (field_id, data) in fields(extra) {
+        let split = if field_id == id { &mut matching } else { &mut rest };
+        split.extend_from_slice(&field_id.to_le_bytes());
+        split.extend_from_slice(&field_length(data.len())?.to_le_bytes());
+        split.extend_from_slice(data);
+    }
+
+    Ok((matching, rest))
+}
+
+/// The values of a Zip64 extended information extra field which replace saturated 32-bit header fields.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Zip64ExtendedInfo {
+    pub(crate) uncompressed_size: Option<u64>,
+    pub(crate) compressed_size: Option<u64>,
+    pub(crate) lh_offset: Option<u64>,
+}
+
+impl Zip64ExtendedInfo {
+    /// Parses the Zip64 extended information extra field (if one exists) for a header with the provided 32-bit values.
+    ///
+    /// The field only holds values whose 32-bit counterparts are saturated, in a fixed order, so which values are read
+    /// depends on the header. Pass `None` for values which the header doesn't have (ie. the local file header offset
+    /// for a local file header).
+    pub(crate) fn parse(
+        extra: &[u8],
+        uncompressed_size: u32,
+        compressed_size: u32,
+        lh_offset: Option<u32>,
+    ) -> Result<Option<Zip64ExtendedInfo>> {
+        let data = match fields(extra).find(|(id, _)| *id == ZIP64_EXTRA_FIELD_ID) {
+            Some((_, data)) => data,
+            None => return Ok(None),
+        };
+
+        let mut values = data.chunks_exact(8).map(|value| u64::from_le_bytes(value.try_into().unwrap()));
+        let mut next_if = |saturated: bool| match saturated {
+            true => values.next().map(Some).ok_or(ZipError::Zip64ExtraFieldIncomplete),
+            false => Ok(None),
+        };
+
+        Ok(Some(Zip64ExtendedInfo {
+            uncompressed_size: next_if(uncompressed_size == ZIP64_SENTINEL)?,
+            compressed_size: next_if(compressed_size == ZIP64_SENTINEL)?,
+            lh_offset: next_if(lh_offset == Some(ZIP64_SENTINEL))?,
+        }))
+    }
+
+    /// Constructs the field for a local file header, which must hold both sizes if either is held.
+    pub(crate) fn local(uncompressed_size: u64, compressed_size: u64, force: bool) -> Zip64ExtendedInfo {
+        match force || exceeds_u32(uncompressed_size) || exceeds_u32(compressed_size) {
+            true => Zip64ExtendedInfo {
+                uncompressed_size: Some(uncompressed_size),
+                compressed_size: Some(compressed_size),
+                lh_offset: None,
+            },
+            false => Zip64ExtendedInfo::default(),
+        }
+    }
+
+    /// Constructs the field for a central directory header, which only holds the values that don't fit in their
+    /// 32-bit header fields (or every value if forced).
+    pub(crate) fn central(
+        uncompressed_size: u64,
+        compressed_size: u64,
+        lh_offset: u64,
+        force: bool,
+    ) -> Zip64ExtendedInfo {
+        let held = |value: u64| (force || exceeds_u32(value)).then_some(value);
+
+        Zip64ExtendedInfo {
+            uncompressed_size: held(uncompressed_size),
+            compressed_size: held(compressed_size),
+            lh_offset: held(lh_offset),
+        }
+    }
+
+    /// Returns whether the field holds no values, in which case it shouldn't be written.
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == Zip64ExtendedInfo::default()
+    }
+
+    /// Encodes the field (including its header ID and size), or returns an empty buffer if it holds no values.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let values: Vec<u64> =
+            [self.uncompressed_size, self.compressed_size, self.lh_offset].into_iter().flatten().collect();
+        if values.is_empty() {
+            return Vec::new();
+        }
+
+        let mut bytes = Vec::with_capacity(4 + values.len() * 8);
+        bytes.extend_from_slice(&ZIP64_EXTRA_FIELD_ID.to_le_bytes());
+        bytes.extend_from_slice(&((values.len() * 8) as u16).to_le_bytes());
+        values.iter().for_each(|value| bytes.extend_from_slice(&value.to_le_bytes()));
+        bytes
+    }
+}
+
+/// The header ID of Info-ZIP's Unix extra field which holds the owner's UID and GID.
+pub(crate) const UNIX_IDS_FIELD_ID: u16 = 0x7875;
+
+/// The header ID of Info-ZIP's Unicode path extra field.
+pub(crate) const UNICODE_PATH_FIELD_ID: u16 = 0x7075;
+
+/// The header ID of the extra field with which Android's `zipalign` and `apksigner` pad local file headers.
+pub(crate) const ALIGNMENT_FIELD_ID: u16 = 0xd935;
+
+/// Encodes an alignment extra field (holding the alignment followed by zeroed padding) for a local file header, such
+/// that the data which follows it starts at a multiple of the alignment, given the offset the field would start at.
+pub(crate) fn alignment_field(alignment: u16, offset: usize) -> Result<Vec<u8>> {
+    let padding = (alignment as usize - (offset + 6) % alignment as usize) % alignment as usize;
+
+    let mut bytes = Vec::with_capacity(6 + padding);
+    bytes.extend_from_slice(&ALIGNMENT_FIELD_ID.to_le_bytes());
+    bytes.extend_from_slice(&field_length(2 + padding)?.to_le_bytes());
+    bytes.extend_from_slice(&alignment.to_le_bytes());
+    bytes.resize(6 + padding, 0);
+    Ok(bytes)
+}
+
+/// A typed extra field to be written for an entry, which is encoded appropriately for both its local file header and
+/// central directory header.
+///
+/// # Example
+/// ```
+/// # use async_zip::{Compression, ExtraField, write::EntryOptions};
+/// # use chrono::Utc;
+/// #
+/// let opts = EntryOptions::new("foo.txt".to_string(), Compression::Deflate)
+///     .extra_field(ExtraField::ExtendedTimestamp { modified: Some(Utc::now()), accessed: None, created: None })
+///     .extra_field(ExtraField::UnixIds { uid: 1000, gid: 1000 });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtraField {
+    /// A Zip64 extended information field, which the writer fills in with the entry's sizes (and offset).
+    ///
+    /// The writer already adds this field whenever it's required, so this only forces it to be written for entries
+    /// which would otherwise fit within the 32-bit header fields.
+    Zip64,
+    /// Info-ZIP's extended timestamp field (0x5455), holding Unix timestamps with 1-second precision.
+    ///
+    /// The central directory's copy only holds the modification time. Timestamps which don't fit within its signed
+    /// 32-bit values are omitted.
+    ExtendedTimestamp {
+        modified: Option<DateTime<Utc>>,
+        accessed: Option<DateTime<Utc>>,
+        created: Option<DateTime<Utc>>,
+    },
+    /// Info-ZIP's Unix field (0x7875), holding the UID and GID of the entry's owner.
+    UnixIds { uid: u32, gid: u32 },
+    /// Info-ZIP's Unicode path field (0x7075), holding a UTF-8 copy of the entry's filename for archives whose
+    /// filenames are stored in another encoding.
+    ///
+    /// The field is tied to the filename stored in the entry's headers via its CRC32 value, which the writer computes.
+    UnicodePath(String),
+    /// Any other field, written as-is to both headers. Its data must be no longer than 65,535 bytes.
+    Custom { id: u16, data: Vec<u8> },
+}
+
+impl ExtraField {
+    /// Encodes the field (including its header ID and size) for an entry with the provided filename, within either its
+    /// local file header or central directory header.
+    ///
+    /// The Zip64 field is generated by the writer itself, so isn't encoded here.
+    pub(crate) fn write(&self, filename: &[u8], central: bool, buf: &mut Vec<u8>) -> Result<()> {
+        let (id, data) = match self {
+            ExtraField::Zip64 => return Ok(()),
+            ExtraField::ExtendedTimestamp { modified, accessed, created } => {
+                let (mut flags, mut data) = (0u8, vec![0]);
+                for (flag, time) in [(0x01, modified), (0x02, accessed), (0x04, created)] {
+                    let time = match time.map(|time| i32::try_from(time.timestamp())) {
+                        Some(Ok(time)) => time,
+                        _ => continue,
+                    };
+
+                    flags |= flag;
+                    if !central || flag == 0x01 {
+                        data.extend_from_slice(&time.to_le_bytes());
+                    }
+                }
+
+                data[0] = flags;
+                (crate::spec::date::EXTENDED_TIMESTAMP_FIELD_ID, data)
+            }
+            ExtraField::UnixIds { uid, gid } => {
+                let mut data = vec![1, 4];
+                data.extend_from_slice(&uid.to_le_bytes());
+                data.push(4);
+                data.extend_from_slice(&gid.to_le_bytes());
+                (UNIX_IDS_FIELD_ID, data)
+            }
+            ExtraField::UnicodePath(path) => {
+                let mut data = vec![1];
+                data.extend_from_slice(&crc32fast::hash(filename).to_le_bytes());
+                data.extend_from_slice(path.as_bytes());
+                (UNICODE_PATH_FIELD_ID, data)
+            }
+            ExtraField::Custom { id, data } => (*id, data.clone()),
+        };
+
+        buf.extend_from_slice(&id.to_le_bytes());
+        buf.extend_from_slice(&field_length(data.len())?.to_le_bytes());
+        buf.extend_from_slice(&data);
+        Ok(())
+    }
+}
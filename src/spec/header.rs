@@ -114,7 +114,9 @@ pub struct LocalFileHeader {
 #[derive(Copy, Clone)]
 pub struct GeneralPurposeFlag {
     pub encrypted: bool,
+    pub compression_options: u8,
     pub data_descriptor: bool,
+    pub filename_unicode: bool,
 }
 
 // central file header signature   4 bytes  (0x02014b50)
@@ -157,6 +159,9 @@ pub struct CentralDirectoryHeader {
     pub lh_offset: u32,
 }
 
+/// The bit of a central directory header's internal file attributes which flags the entry as holding text.
+pub(crate) const TEXT_ATTRIBUTE: u16 = 0x1;
+
 // end of central dir signature    4 bytes  (0x06054b50)
 // number of this disk             2 bytes
 // number of the disk with the
@@ -180,3 +185,47 @@ pub struct EndOfCentralDirectoryHeader {
     pub(crate) cent_dir_offset: u32,
     pub(crate) file_comm_length: u16,
 }
+
+// zip64 end of central dir
+// signature                       4 bytes  (0x06064b50)
+// size of zip64 end of central
+// directory record                8 bytes
+// version made by                 2 bytes
+// version needed to extract       2 bytes
+// number of this disk             4 bytes
+// number of the disk with the
+// start of the central directory  4 bytes
+// total number of entries in the
+// central directory on this disk  8 bytes
+// total number of entries in the
+// central directory               8 bytes
+// size of the central directory   8 bytes
+// offset of start of central
+// directory with respect to
+// the starting disk number        8 bytes
+// zip64 extensible data sector    (variable size)
+pub struct Zip64EndOfCentralDirectoryRecord {
+    pub(crate) size_of_record: u64,
+    pub(crate) v_made_by: u16,
+    pub(crate) v_needed: u16,
+    pub(crate) disk_num: u32,
+    pub(crate) start_cent_dir_disk: u32,
+    pub(crate) num_of_entries_disk: u64,
+    pub(crate) num_of_entries: u64,
+    pub(crate) size_cent_dir: u64,
+    pub(crate) cent_dir_offset: u64,
+}
+
+// zip64 end of central dir locator
+// signature                       4 bytes  (0x07064b50)
+// number of the disk with the
+// start of the zip64 end of
+// central directory               4 bytes
+// relative offset of the zip64
+// end of central directory record 8 bytes
+// total number of disks           4 bytes
+pub struct Zip64EndOfCentralDirectoryLocator {
+    pub(crate) start_record_disk: u32,
+    pub(crate) record_offset: u64,
+    pub(crate) total_disks: u32,
+}
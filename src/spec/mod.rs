@@ -4,5 +4,9 @@
 pub(crate) mod compression;
 pub(crate) mod date;
 pub(crate) mod delimiter;
+pub(crate) mod encryption;
+pub(crate) mod extra;
 pub(crate) mod header;
 pub(crate) mod parse;
+pub(crate) mod string;
+pub(crate) mod version;
@@ -2,7 +2,10 @@
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
 use crate::error::Result;
-use crate::spec::header::{CentralDirectoryHeader, EndOfCentralDirectoryHeader, GeneralPurposeFlag, LocalFileHeader};
+use crate::spec::header::{
+    CentralDirectoryHeader, EndOfCentralDirectoryHeader, GeneralPurposeFlag, LocalFileHeader,
+    Zip64EndOfCentralDirectoryLocator, Zip64EndOfCentralDirectoryRecord,
+};
 
 use tokio::io::{AsyncRead, AsyncReadExt};
 
@@ -27,17 +30,22 @@ impl LocalFileHeader {
 }
 
 impl GeneralPurposeFlag {
-    pub fn to_slice(&self) -> [u8; 2] {
+    pub fn to_slice(self) -> [u8; 2] {
         let encrypted: u16 = match self.encrypted {
             false => 0x0,
-            true => 0b1 << 14,
+            true => 0b1,
         };
+        let compression_options = ((self.compression_options & 0b11) as u16) << 1;
         let data_descriptor: u16 = match self.data_descriptor {
             false => 0x0,
-            true => 0b1 << 12,
+            true => 0b1 << 3,
+        };
+        let filename_unicode: u16 = match self.filename_unicode {
+            false => 0x0,
+            true => 0b1 << 11,
         };
 
-        (encrypted | data_descriptor).to_le_bytes()
+        (encrypted | compression_options | data_descriptor | filename_unicode).to_le_bytes()
     }
 }
 
@@ -84,6 +92,38 @@ impl EndOfCentralDirectoryHeader {
     }
 }
 
+impl Zip64EndOfCentralDirectoryRecord {
+    pub fn to_slice(&self) -> [u8; 52] {
+        let mut array = [0; 52];
+        let mut cursor = 0;
+
+        array_push!(array, cursor, self.size_of_record.to_le_bytes());
+        array_push!(array, cursor, self.v_made_by.to_le_bytes());
+        array_push!(array, cursor, self.v_needed.to_le_bytes());
+        array_push!(array, cursor, self.disk_num.to_le_bytes());
+        array_push!(array, cursor, self.start_cent_dir_disk.to_le_bytes());
+        array_push!(array, cursor, self.num_of_entries_disk.to_le_bytes());
+        array_push!(array, cursor, self.num_of_entries.to_le_bytes());
+        array_push!(array, cursor, self.size_cent_dir.to_le_bytes());
+        array_push!(array, cursor, self.cent_dir_offset.to_le_bytes());
+
+        array
+    }
+}
+
+impl Zip64EndOfCentralDirectoryLocator {
+    pub fn to_slice(&self) -> [u8; 16] {
+        let mut array = [0; 16];
+        let mut cursor = 0;
+
+        array_push!(array, cursor, self.start_record_disk.to_le_bytes());
+        array_push!(array, cursor, self.record_offset.to_le_bytes());
+        array_push!(array, cursor, self.total_disks.to_le_bytes());
+
+        array
+    }
+}
+
 impl From<[u8; 26]> for LocalFileHeader {
     fn from(value: [u8; 26]) -> LocalFileHeader {
         LocalFileHeader {
@@ -103,16 +143,12 @@ impl From<[u8; 26]> for LocalFileHeader {
 
 impl From<u16> for GeneralPurposeFlag {
     fn from(value: u16) -> GeneralPurposeFlag {
-        let encrypted = match value & 0x1 {
-            0 => false,
-            _ => true,
-        };
-        let data_descriptor = match (value & 0x8) >> 3 {
-            0 => false,
-            _ => true,
-        };
+        let encrypted = !matches!(value & 0x1, 0);
+        let compression_options = ((value & 0x6) >> 1) as u8;
+        let data_descriptor = !matches!((value & 0x8) >> 3, 0);
+        let filename_unicode = !matches!((value & 0x800) >> 11, 0);
 
-        GeneralPurposeFlag { encrypted, data_descriptor }
+        GeneralPurposeFlag { encrypted, compression_options, data_descriptor, filename_unicode }
     }
 }
 
@@ -153,10 +189,36 @@ impl From<[u8; 18]> for EndOfCentralDirectoryHeader {
     }
 }
 
+impl From<[u8; 52]> for Zip64EndOfCentralDirectoryRecord {
+    fn from(value: [u8; 52]) -> Zip64EndOfCentralDirectoryRecord {
+        Zip64EndOfCentralDirectoryRecord {
+            size_of_record: u64::from_le_bytes(value[0..8].try_into().unwrap()),
+            v_made_by: u16::from_le_bytes(value[8..10].try_into().unwrap()),
+            v_needed: u16::from_le_bytes(value[10..12].try_into().unwrap()),
+            disk_num: u32::from_le_bytes(value[12..16].try_into().unwrap()),
+            start_cent_dir_disk: u32::from_le_bytes(value[16..20].try_into().unwrap()),
+            num_of_entries_disk: u64::from_le_bytes(value[20..28].try_into().unwrap()),
+            num_of_entries: u64::from_le_bytes(value[28..36].try_into().unwrap()),
+            size_cent_dir: u64::from_le_bytes(value[36..44].try_into().unwrap()),
+            cent_dir_offset: u64::from_le_bytes(value[44..52].try_into().unwrap()),
+        }
+    }
+}
+
+impl From<[u8; 16]> for Zip64EndOfCentralDirectoryLocator {
+    fn from(value: [u8; 16]) -> Zip64EndOfCentralDirectoryLocator {
+        Zip64EndOfCentralDirectoryLocator {
+            start_record_disk: u32::from_le_bytes(value[0..4].try_into().unwrap()),
+            record_offset: u64::from_le_bytes(value[4..12].try_into().unwrap()),
+            total_disks: u32::from_le_bytes(value[12..16].try_into().unwrap()),
+        }
+    }
+}
+
 impl LocalFileHeader {
     pub async fn from_reader<R: AsyncRead + Unpin>(reader: &mut R) -> Result<LocalFileHeader> {
         let mut buffer: [u8; 26] = [0; 26];
-        reader.read(&mut buffer).await?;
+        reader.read_exact(&mut buffer).await?;
         Ok(LocalFileHeader::from(buffer))
     }
 }
@@ -164,7 +226,7 @@ impl LocalFileHeader {
 impl EndOfCentralDirectoryHeader {
     pub async fn from_reader<R: AsyncRead + Unpin>(reader: &mut R) -> Result<EndOfCentralDirectoryHeader> {
         let mut buffer: [u8; 18] = [0; 18];
-        reader.read(&mut buffer).await?;
+        reader.read_exact(&mut buffer).await?;
         Ok(EndOfCentralDirectoryHeader::from(buffer))
     }
 }
@@ -172,11 +234,27 @@ impl EndOfCentralDirectoryHeader {
 impl CentralDirectoryHeader {
     pub async fn from_reader<R: AsyncRead + Unpin>(reader: &mut R) -> Result<CentralDirectoryHeader> {
         let mut buffer: [u8; 42] = [0; 42];
-        reader.read(&mut buffer).await?;
+        reader.read_exact(&mut buffer).await?;
         Ok(CentralDirectoryHeader::from(buffer))
     }
 }
 
+impl Zip64EndOfCentralDirectoryRecord {
+    pub async fn from_reader<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Zip64EndOfCentralDirectoryRecord> {
+        let mut buffer: [u8; 52] = [0; 52];
+        reader.read_exact(&mut buffer).await?;
+        Ok(Zip64EndOfCentralDirectoryRecord::from(buffer))
+    }
+}
+
+impl Zip64EndOfCentralDirectoryLocator {
+    pub async fn from_reader<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Zip64EndOfCentralDirectoryLocator> {
+        let mut buffer: [u8; 16] = [0; 16];
+        reader.read_exact(&mut buffer).await?;
+        Ok(Zip64EndOfCentralDirectoryLocator::from(buffer))
+    }
+}
+
 /// Replace elements of an array at a given cursor index for use with a zero-initialised array.
 macro_rules! array_push {
     ($arr:ident, $cursor:ident, $value:expr) => {{
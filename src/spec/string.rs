@@ -0,0 +1,190 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use std::borrow::Cow;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+
+/// The encoding of a ZIP string, as declared by the language encoding flag (bit 11) of an entry or chosen by a user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StringEncoding {
+    /// The string is declared to be UTF-8.
+    Utf8,
+    /// The string's encoding wasn't declared, so it's decoded as IBM Code Page 437 (as the specification prescribes),
+    /// though in practice some tools write their platform's encoding instead.
+    Unknown,
+}
+
+/// A filename or comment stored within a ZIP file, holding its exact raw bytes alongside their encoding.
+///
+/// Keeping the raw bytes means strings which aren't valid UTF-8 can be inspected and written back out faithfully,
+/// whilst [`ZipString::as_str()`] still provides a convenient decoded view of them. Equality and hashing are defined on
+/// the raw bytes alone.
+///
+/// # Example
+/// ```
+/// # use async_zip::{StringEncoding, ZipString};
+/// #
+/// let name = ZipString::new(b"caf\x82.txt".to_vec(), StringEncoding::Unknown);
+///
+/// assert_eq!(b"caf\x82.txt", name.as_bytes());
+/// assert_eq!(None, name.to_str());
+/// assert_eq!("caf\u{E9}.txt", name.as_str());
+/// assert_eq!("caf\u{FFFD}.txt", name.decode_as(StringEncoding::Utf8));
+/// ```
+#[derive(Clone)]
+pub struct ZipString {
+    raw: Vec<u8>,
+    encoding: StringEncoding,
+    decoded: OnceLock<String>,
+}
+
+impl ZipString {
+    /// Constructs a new ZIP string from its raw bytes and their encoding.
+    pub fn new(raw: Vec<u8>, encoding: StringEncoding) -> Self {
+        ZipString { raw, encoding, decoded: OnceLock::new() }
+    }
+
+    /// Returns the raw bytes of this string.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// Consumes this string and returns its raw bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.raw
+    }
+
+    /// Returns the encoding of this string.
+    pub fn encoding(&self) -> StringEncoding {
+        self.encoding
+    }
+
+    /// Returns the length of this string in bytes.
+    pub fn len(&self) -> usize {
+        self.raw.len()
+    }
+
+    /// Returns whether or not this string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+
+    /// Returns a view of this string if its raw bytes can be viewed as-is (ie. if they're valid UTF-8 and declared as
+    /// such, or if they're ASCII).
+    pub fn to_str(&self) -> Option<&str> {
+        match self.encoding {
+            StringEncoding::Utf8 => std::str::from_utf8(&self.raw).ok(),
+            StringEncoding::Unknown => self.raw.is_ascii().then(|| std::str::from_utf8(&self.raw).unwrap()),
+        }
+    }
+
+    /// Returns a view of this string decoded with its encoding (see [`ZipString::decode_as()`]).
+    ///
+    /// The decoded form is only computed (and then cached) if the raw bytes can't be viewed as-is.
+    pub fn as_str(&self) -> &str {
+        match self.to_str() {
+            Some(string) => string,
+            None => self.decoded.get_or_init(|| self.decode_as(self.encoding).into_owned()),
+        }
+    }
+
+    /// Returns a view of this string decoded with the provided encoding, rather than the one it was declared with.
+    ///
+    /// Strings decoded as UTF-8 have any invalid sequences replaced with U+FFFD, whereas those of an unknown encoding
+    /// are decoded as IBM Code Page 437 (in which every byte is valid). This is useful for archives written by tools
+    /// which store UTF-8 filenames without declaring them as such.
+    pub fn decode_as(&self, encoding: StringEncoding) -> Cow<'_, str> {
+        match encoding {
+            StringEncoding::Utf8 => String::from_utf8_lossy(&self.raw),
+            StringEncoding::Unknown if self.raw.is_ascii() => Cow::Borrowed(std::str::from_utf8(&self.raw).unwrap()),
+            StringEncoding::Unknown => Cow::Owned(self.raw.iter().map(|byte| cp437_char(*byte)).collect()),
+        }
+    }
+}
+
+impl Default for ZipString {
+    fn default() -> Self {
+        ZipString::new(Vec::new(), StringEncoding::Utf8)
+    }
+}
+
+impl From<String> for ZipString {
+    fn from(value: String) -> Self {
+        ZipString::new(value.into_bytes(), StringEncoding::Utf8)
+    }
+}
+
+impl From<&str> for ZipString {
+    fn from(value: &str) -> Self {
+        ZipString::new(value.as_bytes().to_vec(), StringEncoding::Utf8)
+    }
+}
+
+impl PartialEq for ZipString {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl Eq for ZipString {}
+
+impl PartialEq<str> for ZipString {
+    fn eq(&self, other: &str) -> bool {
+        self.raw == other.as_bytes()
+    }
+}
+
+impl PartialEq<&str> for ZipString {
+    fn eq(&self, other: &&str) -> bool {
+        self.raw == other.as_bytes()
+    }
+}
+
+impl Hash for ZipString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state);
+    }
+}
+
+impl Debug for ZipString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl Display for ZipString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self.as_str(), f)
+    }
+}
+
+/// The characters of IBM Code Page 437 which don't coincide with ASCII (bytes 0x80 to 0xFF).
+#[rustfmt::skip]
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç',
+    'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù',
+    'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º',
+    '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖',
+    '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟',
+    '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫',
+    '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ',
+    'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈',
+    '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{00A0}',
+];
+
+/// Decodes a single byte of IBM Code Page 437.
+fn cp437_char(byte: u8) -> char {
+    match byte {
+        0x00..=0x7f => byte as char,
+        _ => CP437_HIGH[(byte - 0x80) as usize],
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+// 4.4.2 version made by (2 bytes)
+//
+// The upper byte indicates the compatibility of the file attribute information. If the external file attributes are
+// compatible with MS-DOS and can be read by PKZIP for DOS version 2.04g then this value will be zero. The lower byte
+// indicates the ZIP specification version (the version of this document) supported by the software used to encode
+// the file.
+
+use crate::spec::compression::Compression;
+
+/// The version of the specification this crate writes against (6.3).
+pub(crate) const SPEC_VERSION: u8 = 63;
+
+/// The operating system (or file system) which created an entry, as stored in the upper byte of `version made by`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreatorOs {
+    MsDos,
+    Amiga,
+    OpenVms,
+    Unix,
+    VmCms,
+    AtariSt,
+    Os2,
+    Macintosh,
+    ZSystem,
+    Cpm,
+    Ntfs,
+    Mvs,
+    Vse,
+    AcornRisc,
+    Vfat,
+    AlternateMvs,
+    BeOs,
+    Tandem,
+    Os400,
+    MacOsX,
+    Unknown(u8),
+}
+
+impl CreatorOs {
+    /// Convert a creator OS into its relevant u8 stored in the upper byte of `version made by`.
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            CreatorOs::MsDos => 0,
+            CreatorOs::Amiga => 1,
+            CreatorOs::OpenVms => 2,
+            CreatorOs::Unix => 3,
+            CreatorOs::VmCms => 4,
+            CreatorOs::AtariSt => 5,
+            CreatorOs::Os2 => 6,
+            CreatorOs::Macintosh => 7,
+            CreatorOs::ZSystem => 8,
+            CreatorOs::Cpm => 9,
+            CreatorOs::Ntfs => 10,
+            CreatorOs::Mvs => 11,
+            CreatorOs::Vse => 12,
+            CreatorOs::AcornRisc => 13,
+            CreatorOs::Vfat => 14,
+            CreatorOs::AlternateMvs => 15,
+            CreatorOs::BeOs => 16,
+            CreatorOs::Tandem => 17,
+            CreatorOs::Os400 => 18,
+            CreatorOs::MacOsX => 19,
+            CreatorOs::Unknown(value) => *value,
+        }
+    }
+
+    /// Convert the upper byte of `version made by` into a creator OS.
+    pub fn from_u8(value: u8) -> CreatorOs {
+        match value {
+            0 => CreatorOs::MsDos,
+            1 => CreatorOs::Amiga,
+            2 => CreatorOs::OpenVms,
+            3 => CreatorOs::Unix,
+            4 => CreatorOs::VmCms,
+            5 => CreatorOs::AtariSt,
+            6 => CreatorOs::Os2,
+            7 => CreatorOs::Macintosh,
+            8 => CreatorOs::ZSystem,
+            9 => CreatorOs::Cpm,
+            10 => CreatorOs::Ntfs,
+            11 => CreatorOs::Mvs,
+            12 => CreatorOs::Vse,
+            13 => CreatorOs::AcornRisc,
+            14 => CreatorOs::Vfat,
+            15 => CreatorOs::AlternateMvs,
+            16 => CreatorOs::BeOs,
+            17 => CreatorOs::Tandem,
+            18 => CreatorOs::Os400,
+            19 => CreatorOs::MacOsX,
+            _ => CreatorOs::Unknown(value),
+        }
+    }
+
+    /// Returns whether or not external attributes written by this OS carry Unix mode bits in their upper 16 bits.
+    pub fn unix_attributes(&self) -> bool {
+        matches!(self, CreatorOs::Unix | CreatorOs::MacOsX)
+    }
+}
+
+/// Compose a `version made by` value from a creator OS and the specification version this crate writes against.
+pub(crate) fn made_by(os: CreatorOs) -> u16 {
+    (os.to_u8() as u16) << 8 | SPEC_VERSION as u16
+}
+
+/// Split the creator OS out of a `version made by` value.
+pub(crate) fn creator_os(made_by: u16) -> CreatorOs {
+    CreatorOs::from_u8((made_by >> 8) as u8)
+}
+
+/// The `version needed to extract` of entries which use no features beyond those of the earliest specification (1.0).
+pub(crate) const DEFAULT_VERSION_NEEDED: u16 = 10;
+
+/// The `version needed to extract` of directories, Deflate-compressed entries, and entries encrypted with traditional
+/// PKWARE encryption (ZipCrypto) (2.0).
+pub(crate) const V20_VERSION_NEEDED: u16 = 20;
+
+/// The `version needed to extract` of entries and archives which use Zip64 structures (4.5).
+pub(crate) const ZIP64_VERSION_NEEDED: u16 = 45;
+
+/// The `version needed to extract` of entries encrypted with WinZip's AES encryption scheme (5.1).
+pub(crate) const AES_VERSION_NEEDED: u16 = 51;
+
+/// Returns the `version needed to extract` of entries compressed with the provided method (4.4.3.2).
+///
+/// Methods which the specification doesn't assign a version to (eg. zstd and xz, or custom methods) are given the
+/// version of the specification which lists them.
+pub(crate) fn compression_version_needed(compression: &Compression) -> u16 {
+    match compression {
+        Compression::Stored | Compression::Shrink | Compression::Implode => DEFAULT_VERSION_NEEDED,
+        Compression::Deflate => V20_VERSION_NEEDED,
+        Compression::Deflate64 => 21,
+        Compression::Bz => 46,
+        _ => SPEC_VERSION as u16,
+    }
+}
@@ -5,8 +5,51 @@ use crate::spec::compression::Compression;
 use crate::write::{EntryOptions, ZipFileWriter};
 
 use std::io::Cursor;
+use std::path::{Path, PathBuf};
 use std::vec::Vec;
 
+/// A directory within the system's temporary directory which is unique to a test, and removed once dropped.
+struct TestDir(PathBuf);
+
+impl TestDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("async_zip_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&path).expect("failed to create test directory");
+        TestDir(path)
+    }
+}
+
+impl std::ops::Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TestDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Writes an archive holding each of the provided entries in whole, returning its data.
+async fn write_archive<D: AsRef<[u8]>>(entries: impl IntoIterator<Item = (EntryOptions, D)>) -> Vec<u8> {
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    for (options, data) in entries {
+        zip_writer.write_entry_whole(options, data.as_ref()).await.expect("failed to write entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+    archive.into_inner()
+}
+
 #[tokio::test]
 async fn empty() {
     use crate::read::seek::ZipFileReader;
@@ -54,6 +97,7 @@ async fn single_entry_no_data() {
     assert_eq!(0, zip_reader.entry("foo.bar").unwrap().1.compressed_size().expect("no compressed size"));
     assert_eq!(0, zip_reader.entry("foo.bar").unwrap().1.uncompressed_size().expect("no uncompressed size"));
     assert_eq!(Compression::Stored, *zip_reader.entry("foo.bar").unwrap().1.compression());
+    assert_eq!(Some(crate::CreatorOs::MsDos), zip_reader.entry("foo.bar").unwrap().1.made_by_os());
 }
 
 macro_rules! single_entry_gen {
@@ -80,7 +124,7 @@ macro_rules! single_entry_gen {
             let entry = zip_reader.entry("foo.bar").expect("no 'foo.bar' entry");
             assert_eq!(0, entry.0);
             assert!(entry.1.compressed_size().is_some());
-            assert_eq!(data.len() as u32, entry.1.uncompressed_size().expect("no uncompressed size"));
+            assert_eq!(data.len() as u64, entry.1.uncompressed_size().expect("no uncompressed size"));
             assert_eq!($typ, *entry.1.compression());
 
             let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
@@ -92,8 +136,4011 @@ macro_rules! single_entry_gen {
 }
 
 single_entry_gen!(single_entry_stored, Compression::Stored);
+#[cfg(feature = "deflate")]
 single_entry_gen!(single_entry_deflate, Compression::Deflate);
+#[cfg(feature = "bzip2")]
 single_entry_gen!(single_entry_bz, Compression::Bz);
+#[cfg(feature = "lzma")]
 single_entry_gen!(single_entry_lzma, Compression::Lzma);
+#[cfg(feature = "zstd")]
 single_entry_gen!(single_entry_zstd, Compression::Zstd);
+#[cfg(feature = "xz")]
 single_entry_gen!(single_entry_xz, Compression::Xz);
+
+#[tokio::test]
+async fn stream_entry_progress() {
+    use tokio::io::AsyncWriteExt;
+    use tokio::sync::watch;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let (sender, receiver) = watch::channel(crate::write::EntryProgress::default());
+
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    zip_writer.progress(sender, 1024);
+
+    let data = vec![0u8; 4096];
+    let open_opts = EntryOptions::new("foo.bar".to_string(), Compression::Stored);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+
+    entry_writer.write_all(&data[..2048]).await.expect("failed to write entry data");
+    assert_eq!(2048, receiver.borrow().uncompressed_written);
+    assert!(!receiver.borrow().finished);
+
+    entry_writer.write_all(&data[2048..]).await.expect("failed to write entry data");
+    entry_writer.close().await.expect("failed to close entry");
+
+    let progress = receiver.borrow().clone();
+    assert_eq!("foo.bar", progress.entry_name);
+    assert_eq!(4096, progress.uncompressed_written);
+    assert_eq!(4096, progress.compressed_written);
+    assert!(progress.finished);
+
+    zip_writer.close().await.expect("failed to close writer");
+}
+
+#[test]
+fn compression_policy_extensions() {
+    use crate::write::CompressionPolicy;
+
+    let policy = CompressionPolicy::store_compressed(Compression::Deflate).extension("TXT", Compression::Zstd);
+
+    assert_eq!(Compression::Stored, policy.compression_for("photos/IMG_0001.JPG"));
+    assert_eq!(Compression::Stored, policy.compression_for("backup.tar.gz"));
+    assert_eq!(Compression::Zstd, policy.compression_for("notes.txt"));
+    assert_eq!(Compression::Deflate, policy.compression_for("Makefile"));
+    assert_eq!(Compression::Deflate, policy.compression_for(".png"));
+    assert_eq!(Compression::Deflate, policy.compression_for("trailing."));
+    assert_eq!(Compression::Deflate, policy.compression_for("dir.png/file"));
+}
+
+#[tokio::test]
+async fn sharded_writer_parts() {
+    use crate::read::seek::ZipFileReader;
+    use crate::write::{OversizedEntry, ShardedZipWriter};
+
+    let dir = TestDir::new("sharded");
+
+    let factory = |part: usize| {
+        let path = dir.join(format!("part{}.zip", part));
+        async move { Ok(tokio::fs::File::create(path).await?) }
+    };
+    let mut writer = ShardedZipWriter::new(factory, 512);
+
+    for index in 0..5 {
+        let opts = EntryOptions::new(format!("{}.bin", index), Compression::Stored);
+        writer.write_entry_whole(opts, &[index as u8; 150]).await.expect("failed to write entry");
+    }
+
+    let summary = writer.close().await.expect("failed to close writer");
+    assert_eq!(3, summary.parts);
+    assert_eq!(vec![0, 0, 1, 1, 2], summary.entries.iter().map(|(_, part)| *part).collect::<Vec<_>>());
+
+    for part in 0..summary.parts {
+        let mut file = tokio::fs::File::open(dir.join(format!("part{}.zip", part))).await.expect("missing part");
+        assert!(file.metadata().await.unwrap().len() <= 512);
+
+        let mut zip_reader = ZipFileReader::new(&mut file).await.expect("failed to open part");
+        let count = zip_reader.entries().len();
+        for index in 0..count {
+            let data = zip_reader.entry_reader(index).await.unwrap().read_to_end_crc().await.unwrap();
+            assert_eq!(150, data.len());
+        }
+    }
+
+    let factory = |_| async { Ok(Cursor::new(Vec::<u8>::new())) };
+    let mut writer = ShardedZipWriter::new(factory, 128).oversized(OversizedEntry::Error);
+    let opts = EntryOptions::new("big.bin".to_string(), Compression::Stored);
+    assert!(writer.write_entry_whole(opts, &[0; 256]).await.is_err());
+}
+
+#[tokio::test]
+async fn sharded_writer_generated_fields() {
+    use crate::read::seek::ZipFileReader;
+    use crate::write::ShardedZipWriter;
+    use crate::ExtraField;
+
+    let dir = TestDir::new("sharded_fields");
+
+    // Zip64 (and AES) extra fields are generated by the writer, and count towards each part's size.
+    let variants: Vec<fn() -> EntryOptions> = vec![
+        || EntryOptions::new("zip64.bin", Compression::Stored).extra_field(ExtraField::Zip64),
+        #[cfg(feature = "aes")]
+        || EntryOptions::new("aes.bin", Compression::Stored).password("password"),
+    ];
+
+    for (variant, options) in variants.into_iter().enumerate() {
+        let factory = |part: usize| {
+            let path = dir.join(format!("part{}_{}.zip", variant, part));
+            async move { Ok(tokio::fs::File::create(path).await?) }
+        };
+        let mut writer = ShardedZipWriter::new(factory, 560);
+        for _ in 0..4 {
+            writer.write_entry_whole(options(), &[0; 150]).await.expect("failed to write entry");
+        }
+
+        let summary = writer.close().await.expect("failed to close writer");
+        assert_eq!(4, summary.parts);
+        for part in 0..summary.parts {
+            let path = dir.join(format!("part{}_{}.zip", variant, part));
+            let mut file = tokio::fs::File::open(path).await.expect("missing part");
+            assert!(file.metadata().await.unwrap().len() <= 560);
+            assert_eq!(1, ZipFileReader::new(&mut file).await.expect("failed to open part").entries().len());
+        }
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn path_constructors() {
+    use crate::error::ZipError;
+    use crate::read::{seek, stream};
+
+    let dir = TestDir::new("paths");
+    let path = dir.join("archive.zip");
+
+    // The destination only appears once the atomic writer has been closed.
+    let mut writer = ZipFileWriter::create_atomic(&path).await.expect("failed to create writer");
+    let opts = EntryOptions::new("foo.txt".to_string(), Compression::Deflate);
+    writer.write_entry_whole(opts, b"foo").await.expect("failed to write entry");
+    assert!(!path.exists());
+    writer.close().await.expect("failed to close writer");
+    assert!(path.exists());
+
+    match ZipFileWriter::create_new(&path).await {
+        Err(ZipError::PathError(error_path, _)) => assert_eq!(path, error_path),
+        _ => panic!("expected an existing file to be refused"),
+    };
+
+    let mut zip_reader = seek::ZipFileReader::open(&path).await.expect("failed to open reader");
+    assert_eq!("foo", zip_reader.read_entry_to_string("foo.txt", None).await.expect("failed to read entry"));
+
+    let mut writer = ZipFileWriter::create(&path).await.expect("failed to create writer");
+    let opts = EntryOptions::new("bar.txt".to_string(), Compression::Deflate);
+    writer.write_entry_whole(opts, b"bar").await.expect("failed to write entry");
+    writer.close().await.expect("failed to close writer");
+
+    let mut zip_reader = stream::ZipFileReader::open(&path).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no 'bar.txt' entry");
+    assert_eq!("bar", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+
+    let missing = dir.join("missing.zip");
+    match seek::ZipFileReader::open(&missing).await {
+        Err(ZipError::PathError(error_path, _)) => assert_eq!(missing, error_path),
+        _ => panic!("expected a missing file to be reported with its path"),
+    };
+}
+
+#[cfg(feature = "zstd")]
+#[tokio::test]
+async fn writer_default_options() {
+    use crate::read::seek::ZipFileReader;
+    use crate::write::{CompressionLevel, EntryDefaults, TimestampPolicy};
+    use chrono::{TimeZone, Utc};
+
+    let timestamp = Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 6).unwrap();
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    zip_writer.default_options(EntryDefaults {
+        compression: Compression::Zstd,
+        level: CompressionLevel::Precise(19),
+        timestamp_policy: TimestampPolicy::Fixed(timestamp),
+        permissions: Some(0o755),
+        compression_policy: None,
+    });
+
+    let open_opts = EntryOptions::named("inherited.txt".to_string());
+    zip_writer.write_entry_whole(open_opts, b"inherited").await.expect("failed to write entry");
+    let open_opts = EntryOptions::named("overridden.txt".to_string()).compression(Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, b"overridden").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    let compressions: Vec<_> = zip_reader.entries().iter().map(|entry| *entry.compression()).collect();
+    assert_eq!(vec![Compression::Zstd, Compression::Stored], compressions);
+
+    for entry in zip_reader.entries() {
+        assert_eq!(&timestamp, entry.last_modified());
+        assert_eq!(Some(crate::CreatorOs::Unix), entry.made_by_os());
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn recover_truncated_archive() {
+    use crate::error::ZipError;
+    use crate::read::{seek::ZipFileReader, ReaderOptions};
+    use tokio::io::AsyncWriteExt;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    let open_opts = EntryOptions::new("whole.txt".to_string(), Compression::Deflate);
+    zip_writer.write_entry_whole(open_opts, "whole".repeat(64).as_bytes()).await.expect("failed to write entry");
+
+    let open_opts = EntryOptions::new("stream.txt".to_string(), Compression::Deflate);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+    entry_writer.write_all("stream".repeat(64).as_bytes()).await.expect("failed to write entry data");
+    entry_writer.close().await.expect("failed to close entry");
+
+    let open_opts = EntryOptions::new("truncated.bin".to_string(), Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, &[0xAB; 1024]).await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let mut archive = input_stream.into_inner();
+    let mut intact = Cursor::new(archive.clone());
+    let zip_reader = ZipFileReader::with_recovery(&mut intact, ReaderOptions::new()).await.expect("failed to open");
+    assert!(!zip_reader.recovered());
+    assert_eq!(3, zip_reader.entries().len());
+
+    // Cut the archive off part-way through the final entry's data.
+    let truncated_len = archive.len() - 512;
+    archive.truncate(truncated_len);
+    let mut input_stream = Cursor::new(archive);
+    assert!(ZipFileReader::new(&mut input_stream).await.is_err());
+
+    let mut zip_reader =
+        ZipFileReader::with_recovery(&mut input_stream, ReaderOptions::new()).await.expect("failed to recover");
+    assert!(zip_reader.recovered());
+
+    let names: Vec<_> = zip_reader.entries().iter().map(|entry| (entry.name(), entry.partial())).collect();
+    assert_eq!(vec![("whole.txt", false), ("stream.txt", false), ("truncated.bin", true)], names);
+
+    let data = zip_reader.read_entry_to_string("whole.txt", None).await.expect("failed to read entry");
+    assert_eq!("whole".repeat(64), data);
+    let data = zip_reader.read_entry_to_string("stream.txt", None).await.expect("failed to read entry");
+    assert_eq!("stream".repeat(64), data);
+    assert!(matches!(zip_reader.entry_reader(2).await, Err(ZipError::PartialEntry)));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn decoder_buffer_size() {
+    use crate::read::{mem::ZipFileReader, ReaderOptions};
+
+    let data =
+        "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...".repeat(64);
+    let open_opts = EntryOptions::new("foo.bar".to_string(), Compression::Deflate);
+    let archive = write_archive([(open_opts, data.as_bytes())]).await;
+
+    let options = ReaderOptions::new().decoder_buffer_size(1);
+    let mut zip_reader = ZipFileReader::with_options(&archive, options).await.expect("failed to open reader");
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert_eq!(data, entry_reader.read_to_string_crc().await.expect("failed to read entry to string"));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn buffer_pool_reuse() {
+    use crate::read::pool::BufferPool;
+    use crate::read::{mem, stream, ReaderOptions};
+    use std::sync::Arc;
+
+    let archive = write_archive((0..64).map(|index| {
+        (EntryOptions::new(format!("{}.txt", index), Compression::Deflate), index.to_string().repeat(16))
+    }))
+    .await;
+    let pool = Arc::new(BufferPool::new(4, 64 * 1024));
+
+    // Every entry reader is dropped before the next is opened, so a single decoder buffer is reused throughout.
+    for _ in 0..2 {
+        let options = ReaderOptions::new().buffer_pool(pool.clone());
+        let mut zip_reader = mem::ZipFileReader::with_options(&archive, options).await.expect("failed to open reader");
+
+        for index in 0..64 {
+            let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry reader");
+            entry_reader.read_to_end_crc().await.expect("failed to read entry");
+        }
+    }
+    assert_eq!(1, pool.allocations());
+
+    // Skipped entries reuse the same buffer, whereas copying holds a second buffer alongside the decoder's.
+    let options = ReaderOptions::new().buffer_pool(pool.clone());
+    let mut input_stream = Cursor::new(archive);
+    let mut zip_reader = stream::ZipFileReader::with_options(&mut input_stream, options);
+
+    let entry_reader = zip_reader.skip_to("63.txt").await.expect("failed to skip").expect("no '63.txt' entry");
+    entry_reader.copy_to_end_crc(&mut tokio::io::sink(), 1024).await.expect("failed to copy entry");
+    assert_eq!(2, pool.allocations());
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn nested_archives() {
+    use crate::error::ZipError;
+    use crate::read::{seek, stream, ReaderOptions};
+
+    let inner = write_archive(
+        ["a.txt", "b.txt"].map(|name| (EntryOptions::new(name.to_string(), Compression::Deflate), name.repeat(32))),
+    )
+    .await;
+
+    let outer = write_archive([
+        (EntryOptions::new("stored.zip".to_string(), Compression::Stored), inner.as_slice()),
+        (EntryOptions::new("deflated.zip".to_string(), Compression::Deflate), inner.as_slice()),
+        (EntryOptions::new("after.txt".to_string(), Compression::Stored), b"after".as_slice()),
+    ])
+    .await;
+    let mut outer = Cursor::new(outer);
+
+    // Stored inner archives are read in place with full seekable access.
+    let mut zip_reader = seek::ZipFileReader::new(&mut outer).await.expect("failed to open reader");
+    let mut nested = zip_reader.open_nested("stored.zip").await.expect("failed to open nested archive");
+    assert_eq!(2, nested.entries().len());
+    assert_eq!("b.txt".repeat(32), nested.read_entry_to_string("b.txt", None).await.expect("failed to read entry"));
+    assert!(matches!(nested.open_nested("a.txt").await, Err(ZipError::FeatureNotSupported(_))));
+    assert!(matches!(zip_reader.open_nested("deflated.zip").await, Err(ZipError::FeatureNotSupported(_))));
+
+    let options = ReaderOptions::new().max_nesting_depth(0);
+    let mut zip_reader = seek::ZipFileReader::with_options(&mut outer, options).await.expect("failed to open reader");
+    assert!(matches!(zip_reader.open_nested("stored.zip").await, Err(ZipError::NestingDepthExceeded(0))));
+
+    // Any inner archive can be streamed, and data left unread by the nested reader is skipped by the outer reader.
+    outer.set_position(0);
+    let mut zip_reader = stream::ZipFileReader::new(&mut outer);
+
+    for name in ["stored.zip", "deflated.zip"] {
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        assert_eq!(name, entry_reader.entry().name());
+
+        let mut nested = entry_reader.into_zip_reader().expect("failed to open nested archive");
+        let entry_reader = nested.entry_reader().await.expect("failed to open entry").expect("no 'a.txt' entry");
+        assert_eq!("a.txt".repeat(32), entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no 'after.txt' entry");
+    assert_eq!("after", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn stream_skip_to() {
+    use crate::read::stream::ZipFileReader;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        let open_opts = EntryOptions::new(name.to_string(), Compression::Deflate);
+        zip_writer.write_entry_whole(open_opts, name.repeat(32).as_bytes()).await.expect("failed to write entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream);
+
+    let entry_reader = zip_reader.skip_to("b.txt").await.expect("failed to skip").expect("no 'b.txt' entry");
+    assert_eq!("b.txt".repeat(32), entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no 'c.txt' entry");
+    assert_eq!("c.txt", entry_reader.entry().name());
+    assert_eq!("c.txt".repeat(32), entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+
+    assert!(zip_reader.skip_to("a.txt").await.expect("failed to skip").is_none());
+    assert!(zip_reader.finished());
+}
+
+#[tokio::test]
+async fn stream_bytes_consumed() {
+    use crate::read::stream::ZipFileReader;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        let open_opts = EntryOptions::new(name.to_string(), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, &[0; 100]).await.expect("failed to write entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream);
+    assert_eq!(0, zip_reader.bytes_consumed());
+
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    entry_reader.read_to_end_crc().await.expect("failed to read entry");
+    assert_eq!(135, zip_reader.bytes_consumed());
+
+    zip_reader.skip_to("c.txt").await.expect("failed to skip").expect("no entry").read_to_end_crc().await.unwrap();
+    assert_eq!(405, zip_reader.bytes_consumed());
+
+    assert!(zip_reader.entry_reader().await.expect("failed to read header").is_none());
+    assert_eq!(409, zip_reader.bytes_consumed());
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn seek_read_entry() {
+    use crate::error::ZipError;
+    use crate::read::seek::ZipFileReader;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    let open_opts = EntryOptions::new("foo.bar".to_string(), Compression::Deflate);
+    zip_writer.write_entry_whole(open_opts, data.as_bytes()).await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+
+    assert_eq!(data.as_bytes(), zip_reader.read_entry("foo.bar", None).await.expect("failed to read entry"));
+    assert_eq!(data, zip_reader.read_entry_to_string("foo.bar", Some(1024)).await.expect("failed to read entry"));
+
+    match zip_reader.read_entry("foo.bar", Some(16)).await {
+        Err(ZipError::EntryTooLarge(size, 16)) => assert_eq!(data.len() as u64, size),
+        _ => panic!("expected the size limit to be enforced"),
+    }
+    assert!(matches!(zip_reader.read_entry("bar.foo", None).await, Err(ZipError::EntryNotFound(_))));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn rollback_open_entry() {
+    use crate::error::ZipError;
+    use crate::read::seek::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = EntryOptions::new("abandoned.txt".to_string(), Compression::Deflate);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+    entry_writer.write_all(b"Some data before the source failed.").await.expect("failed to write entry data");
+    drop(entry_writer);
+
+    let open_opts = EntryOptions::new("foo.bar".to_string(), Compression::Stored);
+    let result = zip_writer.write_entry_whole(open_opts, b"data").await;
+    assert!(matches!(result, Err(ZipError::EntryStillOpen)));
+
+    zip_writer.rollback_open_entry().await.expect("failed to roll back entry");
+
+    let open_opts = EntryOptions::new("foo.bar".to_string(), Compression::Stored);
+    zip_writer.write_entry_whole(open_opts, b"data").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    assert_eq!(1, zip_reader.entries().len());
+    assert_eq!(b"data".to_vec(), zip_reader.read_entry("foo.bar", None).await.expect("failed to read entry"));
+}
+
+#[tokio::test]
+async fn rollback_seekable_entry() {
+    use crate::error::ZipError;
+    use crate::read::mem;
+    use tokio::io::AsyncWriteExt;
+
+    let timestamp = chrono::TimeZone::with_ymd_and_hms(&chrono::Utc, 2022, 1, 1, 0, 0, 0).unwrap();
+    let write = |abandoned: Option<usize>| async move {
+        let mut input_stream = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new_seekable(&mut input_stream);
+        zip_writer.deterministic(Some(timestamp));
+        zip_writer.write_entry_whole(EntryOptions::new("a.txt", Compression::Stored), b"aaa").await.unwrap();
+
+        if let Some(length) = abandoned {
+            let open_opts = EntryOptions::new("abandoned.txt", Compression::Stored);
+            let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+            entry_writer.write_all(&vec![b'x'; length]).await.expect("failed to write entry data");
+            drop(entry_writer);
+            zip_writer.rollback_open_entry().await.expect("failed to roll back entry");
+        }
+
+        zip_writer.write_entry_whole(EntryOptions::new("c.txt", Compression::Stored), b"ccc").await.unwrap();
+        let result = zip_writer.close().await.map(drop);
+        (result, input_stream.into_inner())
+    };
+
+    let (result, expected) = write(None).await;
+    result.expect("failed to close writer");
+
+    // The next entry is written over the abandoned entry's local file header and partial data.
+    let (result, archive) = write(Some(4)).await;
+    result.expect("failed to close writer");
+    assert_eq!(expected, archive);
+
+    // Abandoned data which extends beyond the end of the finished archive must be truncated.
+    let (result, mut archive) = write(Some(4096)).await;
+    match result {
+        Err(ZipError::TruncationRequired(len)) => archive.truncate(len as usize),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    assert_eq!(expected, archive);
+
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let names: Vec<_> = zip_reader.entries().iter().map(|entry| entry.name().to_string()).collect();
+    assert_eq!(vec!["a.txt", "c.txt"], names);
+}
+
+/// A writer which returns an error once a fixed number of bytes have been written.
+struct FailingWriter {
+    remaining: usize,
+}
+
+impl tokio::io::AsyncWrite for FailingWriter {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        if self.remaining == 0 {
+            return std::task::Poll::Ready(Err(std::io::Error::other("out of space")));
+        }
+
+        let written = std::cmp::min(self.remaining, buf.len());
+        self.remaining -= written;
+        std::task::Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn rollback_poisoned_writer() {
+    use crate::error::ZipError;
+
+    let mut zip_writer = ZipFileWriter::new(FailingWriter { remaining: 64 });
+
+    let open_opts = EntryOptions::new("foo.bar".to_string(), Compression::Stored);
+    assert!(zip_writer.write_entry_whole(open_opts, &[0; 128]).await.is_err());
+
+    assert!(matches!(zip_writer.rollback_open_entry().await, Err(ZipError::WriterPoisoned)));
+    assert!(matches!(zip_writer.close().await, Err(ZipError::WriterPoisoned)));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn amend_pending_entry() {
+    use crate::read::seek::ZipFileReader;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let data = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    let open_opts = EntryOptions::new("foo.bar".to_string(), Compression::Deflate);
+    zip_writer.write_entry_whole(open_opts, data.as_bytes()).await.expect("failed to write entry");
+
+    let mut pending = zip_writer.pending_entry_by_name_mut("foo.bar").expect("no pending 'foo.bar' entry");
+    pending.set_comment("amended".to_string()).expect("failed to set comment");
+    pending.set_extra(vec![0xfe, 0xca, 0x00, 0x00]).expect("failed to set extra field");
+    pending.set_external_attributes(0o100755 << 16);
+    assert!(pending.set_comment("a".repeat(70_000)).is_err());
+    let cp437 = crate::ZipString::new(b"caf\x82".to_vec(), crate::StringEncoding::Unknown);
+    assert!(matches!(pending.set_comment(cp437), Err(crate::error::ZipError::CommentEncodingMismatch)));
+
+    assert!(zip_writer.pending_entry_mut(1).is_none());
+    zip_writer.close().await.expect("failed to close writer");
+    // The central directory's general purpose flags still match those of the local file header.
+    let archive = input_stream.get_ref();
+    let cdfh_offset = archive.windows(4).position(|window| window == b"PK\x01\x02").unwrap();
+    assert_eq!(archive[6..8], archive[cdfh_offset + 8..cdfh_offset + 10]);
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    let entry = zip_reader.entry("foo.bar").expect("no 'foo.bar' entry").1;
+
+    assert_eq!(Some("amended"), entry.comment());
+    assert_eq!(Some(&vec![0xfe, 0xca, 0x00, 0x00]), entry.extra());
+
+    assert_eq!(data, zip_reader.read_entry_to_string("foo.bar", None).await.expect("failed to read entry"));
+}
+
+#[tokio::test]
+async fn stream_entry_with_sizes() {
+    use crate::error::ZipError;
+    use crate::read::stream::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    let data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt...";
+    let crc = crc32fast::hash(data);
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+
+    let open_opts = EntryOptions::new("foo.bar".to_string(), Compression::Stored);
+    let mut entry_writer = zip_writer.write_entry_stream_with_sizes(open_opts, data.len() as u32, crc).await.unwrap();
+    entry_writer.write_all(&data[..32]).await.expect("failed to write entry data");
+    entry_writer.write_all(&data[32..]).await.expect("failed to write entry data");
+    entry_writer.close().await.expect("failed to close entry");
+
+    let open_opts = EntryOptions::new("bar.foo".to_string(), Compression::Stored);
+    let mut entry_writer = zip_writer.write_entry_stream_with_sizes(open_opts, data.len() as u32, crc).await.unwrap();
+    entry_writer.write_all(&data[..32]).await.expect("failed to write entry data");
+    assert!(matches!(entry_writer.close().await, Err(ZipError::DeclaredSizeMismatch(_, 32))));
+    zip_writer.rollback_open_entry().await.expect("failed to roll back entry");
+
+    let open_opts = EntryOptions::new("bar.foo".to_string(), Compression::Deflate);
+    assert!(zip_writer.write_entry_stream_with_sizes(open_opts, 0, 0).await.is_err());
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream);
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    assert!(!entry_reader.entry().data_descriptor());
+    assert_eq!(data.to_vec(), entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+}
+
+#[tokio::test]
+async fn non_utf8_filenames() {
+    use crate::read::mem::ZipFileReader;
+    use crate::{StringEncoding, ZipString};
+
+    let raw_name = b"caf\x82.txt".to_vec();
+    let name = ZipString::new(raw_name.clone(), StringEncoding::Unknown);
+    assert_eq!(name, ZipString::new(raw_name.clone(), StringEncoding::Utf8));
+    assert_ne!(name, ZipString::from("caf\u{FFFD}.txt"));
+
+    let data = write_archive([
+        (EntryOptions::new(name, Compression::Stored), b"raw".as_slice()),
+        (EntryOptions::new("plain.txt", Compression::Stored).comment("caf\u{E9}"), b"utf8".as_slice()),
+    ])
+    .await;
+    let zip_reader = ZipFileReader::new(&data).await.expect("failed to open zip");
+    let entries = zip_reader.entries();
+
+    assert_eq!(raw_name, entries[0].filename().as_bytes());
+    assert_eq!(raw_name, entries[0].file_name_raw());
+    assert_eq!(StringEncoding::Unknown, entries[0].filename().encoding());
+    assert_eq!("caf\u{E9}.txt", entries[0].name());
+    assert_eq!("caf\u{FFFD}.txt", entries[0].filename().decode_as(StringEncoding::Utf8));
+    assert_eq!(None, entries[0].filename().to_str());
+
+    assert_eq!(StringEncoding::Utf8, entries[1].filename().encoding());
+    assert_eq!(Some("caf\u{E9}"), entries[1].comment());
+    assert!(zip_reader.entry("plain.txt").is_some());
+    assert!(zip_reader.entry("caf\u{FFFD}.txt").is_none());
+    assert_eq!(Some(0), zip_reader.entry("caf\u{E9}.txt").map(|(index, _)| index));
+
+    let mut zip_reader = crate::read::stream::ZipFileReader::new(data.as_slice());
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    assert_eq!(raw_name, entry_reader.file_name_raw());
+    assert_eq!(None, entry_reader.entry().comment());
+}
+
+#[cfg(all(feature = "sink", feature = "deflate"))]
+#[tokio::test]
+async fn sink_adapters() {
+    use crate::read::mem::ZipFileReader;
+    use crate::write::{ZipSink, ZipStreamSink};
+    use bytes::Bytes;
+    use futures_util::{stream, SinkExt, StreamExt};
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let zip_sink = ZipSink::new(ZipFileWriter::new(&mut input_stream));
+
+    let items = vec![("foo.txt", "foo"), ("bar.txt", "bar")];
+    let items =
+        items.into_iter().map(|(name, data)| Ok((EntryOptions::new(name, Compression::Deflate), Bytes::from(data))));
+    stream::iter(items).forward(zip_sink).await.expect("failed to forward entries");
+
+    let data = input_stream.into_inner();
+    let mut zip_reader = ZipFileReader::new(&data).await.expect("failed to open zip");
+    assert_eq!(2, zip_reader.entries().len());
+    let entry = zip_reader.entry_reader(1).await.expect("failed to open entry");
+    assert_eq!("bar", entry.read_to_string_crc().await.expect("failed to read entry"));
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_sink = ZipStreamSink::new(ZipFileWriter::new(&mut input_stream));
+
+    let chunks = stream::iter(vec![Bytes::from("Lorem ipsum "), Bytes::from("dolor sit amet")]);
+    zip_sink.send((EntryOptions::new("lorem.txt", Compression::Deflate), chunks)).await.expect("failed to send entry");
+    zip_sink.close().await.expect("failed to close sink");
+    assert!(matches!(zip_sink.flush().await, Err(crate::error::ZipError::SinkUnavailable)));
+    drop(zip_sink);
+
+    let data = input_stream.into_inner();
+    let mut zip_reader = ZipFileReader::new(&data).await.expect("failed to open zip");
+    let entry = zip_reader.entry_reader(0).await.expect("failed to open entry");
+    assert_eq!("Lorem ipsum dolor sit amet", entry.read_to_string_crc().await.expect("failed to read entry"));
+}
+
+#[tokio::test]
+async fn prefetch_reads_ahead() {
+    use crate::read::prefetch::PrefetchOptions;
+    use crate::read::seek::ZipFileReader;
+    use crate::read::ReaderOptions;
+    use std::sync::atomic::Ordering;
+
+    let sizes = [1024, 1024, 1024, 8192, 1024];
+    let entries = sizes.into_iter().enumerate().map(|(index, size)| {
+        (EntryOptions::new(format!("{}.bin", index), Compression::Stored), vec![index as u8; size])
+    });
+    let data = write_archive(entries).await;
+
+    let (reader, read) = CountingReader::new(Cursor::new(data));
+    let options = ReaderOptions::new().prefetch(PrefetchOptions::new().max_entries(2).max_bytes(4096));
+    let mut zip_reader = ZipFileReader::with_options(reader, options).await.expect("failed to open zip");
+    let mut entries = zip_reader.sequential();
+    let mut opened = Vec::new();
+
+    for (index, size) in sizes.into_iter().enumerate() {
+        let before = read.load(Ordering::Relaxed);
+        let reader = entries.next_entry().await.expect("failed to open entry").expect("missing entry");
+        opened.push(read.load(Ordering::Relaxed) - before);
+        assert_eq!(vec![index as u8; size], reader.read_to_end_crc().await.expect("failed to read entry"));
+    }
+    assert!(entries.next_entry().await.expect("failed to open entry").is_none());
+
+    // Opening the first entry fetches the one after it as well, so the second is already in memory once it's opened.
+    assert!(opened[0] >= 2 * 1024, "{:?}", opened);
+    assert!(opened[1] < 1024, "{:?}", opened);
+    // The entry larger than the byte limit is read directly once it's opened, rather than fetched into memory.
+    assert!(opened[3] < 1024, "{:?}", opened);
+    assert!(opened[4] >= 1024, "{:?}", opened);
+}
+
+#[tokio::test]
+async fn malformed_headers() {
+    use crate::error::ZipError;
+    use crate::read::{mem, stream};
+
+    let data = write_archive([(EntryOptions::new("foo.bar", Compression::Stored), b"foo")]).await;
+
+    // A zero month within the local file header's date.
+    let mut invalid_date = data.clone();
+    invalid_date[12..14].copy_from_slice(&0u16.to_le_bytes());
+    let mut zip_reader = stream::ZipFileReader::new(invalid_date.as_slice());
+    assert!(matches!(zip_reader.entry_reader().await, Err(ZipError::InvalidDateTime(0, _))));
+
+    // A central directory which claims to extend past the end of the archive.
+    let mut invalid_size = data.clone();
+    let eocdh = invalid_size.len() - 22;
+    invalid_size[eocdh + 12..eocdh + 16].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(mem::ZipFileReader::new(&invalid_size).await, Err(ZipError::HeaderOutOfBounds(_))));
+}
+
+#[tokio::test]
+async fn zip64_extra_field_sizes() {
+    use crate::read::{mem, stream};
+
+    // A Zip64 extended information extra field holding the uncompressed and compressed sizes.
+    let mut extra = vec![0x01, 0x00, 16, 0x00];
+    extra.extend_from_slice(&3u64.to_le_bytes());
+    extra.extend_from_slice(&3u64.to_le_bytes());
+
+    let mut data = write_archive([(EntryOptions::new("foo.bar", Compression::Stored).extra(extra), b"foo")]).await;
+
+    // Saturate the 32-bit sizes of both the local file header and central directory header.
+    let cdh = data.len() - 22 - (46 + 7 + 20);
+    for sizes in [18..26, cdh + 20..cdh + 28] {
+        data[sizes].fill(0xFF);
+    }
+
+    let mut zip_reader = stream::ZipFileReader::new(data.as_slice());
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    assert_eq!(Some(3), entry_reader.entry().compressed_size());
+    assert_eq!(Some(3), entry_reader.entry().uncompressed_size());
+    assert_eq!("foo", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+
+    let mut zip_reader = mem::ZipFileReader::new(&data).await.expect("failed to open zip");
+    assert_eq!(Some(3), zip_reader.entries()[0].compressed_size());
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry");
+    assert_eq!("foo", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn zip64_forced_round_trip() {
+    use crate::read::{mem, stream};
+    use tokio::io::AsyncWriteExt;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    zip_writer.force_zip64(true);
+
+    let open_opts = EntryOptions::new("foo.bar", Compression::Deflate);
+    zip_writer.write_entry_whole(open_opts, b"foo").await.expect("failed to write entry");
+    let open_opts = EntryOptions::new("bar.foo", Compression::Stored);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+    entry_writer.write_all(b"barbar").await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+    let data = input_stream.into_inner();
+
+    // The EOCDH is preceded by a Zip64 locator, and holds only sentinel values.
+    let locator = data.len() - 22 - 20;
+    assert_eq!(&crate::spec::delimiter::ZIP64_EOCDLD.to_le_bytes(), &data[locator..locator + 4]);
+    assert_eq!(&[0xFF; 12], &data[data.len() - 14..data.len() - 2]);
+
+    let mut zip_reader = mem::ZipFileReader::new(&data).await.expect("failed to open zip");
+    assert_eq!(2, zip_reader.entries().len());
+    assert_eq!(Some(6), zip_reader.entries()[1].uncompressed_size());
+    assert_eq!(Some(6), zip_reader.entries()[1].compressed_size());
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry");
+    assert_eq!("foo", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    let entry_reader = zip_reader.entry_reader(1).await.expect("failed to open entry");
+    assert_eq!("barbar", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+
+    let mut zip_reader = stream::ZipFileReader::new(data.as_slice());
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    assert_eq!(Some(3), entry_reader.entry().uncompressed_size());
+    assert_eq!("foo", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+}
+
+#[cfg(all(feature = "deflate", feature = "zstd"))]
+#[tokio::test]
+async fn stream_data_descriptors() {
+    use crate::read::stream;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let entries = [
+        ("stored.txt", Compression::Stored, "stored ".repeat(2048)),
+        ("deflate.txt", Compression::Deflate, "deflate ".repeat(2048)),
+        ("empty.txt", Compression::Stored, String::new()),
+        ("zstd.txt", Compression::Zstd, "zstd ".repeat(64)),
+    ];
+
+    for force_zip64 in [false, true] {
+        let mut input_stream = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+        zip_writer.force_zip64(force_zip64);
+
+        for (name, compression, data) in &entries {
+            let open_opts = EntryOptions::new(*name, *compression);
+            let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+            entry_writer.write_all(data.as_bytes()).await.expect("failed to write entry");
+            entry_writer.close().await.expect("failed to close entry");
+        }
+        zip_writer.write_entry_whole(EntryOptions::new("whole.txt", Compression::Deflate), b"whole").await.unwrap();
+        zip_writer.close().await.expect("failed to close writer");
+        let data = input_stream.into_inner();
+
+        let mut zip_reader = stream::ZipFileReader::new(data.as_slice());
+        for (name, _, expected) in &entries {
+            let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+            assert_eq!(*name, entry_reader.entry().name());
+            assert_eq!(*expected, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+        }
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        assert_eq!("whole", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+        assert!(zip_reader.entry_reader().await.expect("failed to read header").is_none());
+
+        // Entries followed by data descriptors are scanned for their descriptors in order to skip them.
+        let mut zip_reader = stream::ZipFileReader::new(data.as_slice());
+        let entry_reader = zip_reader.skip_to("whole.txt").await.expect("failed to skip").expect("no entry");
+        assert_eq!("whole", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+
+        // The unread data of an entry followed by a data descriptor is skipped when the next is opened.
+        let mut zip_reader = stream::ZipFileReader::new(data.as_slice());
+        let mut entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        entry_reader.read_exact(&mut [0; 16]).await.expect("failed to read entry");
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        assert_eq!(entries[1].2, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+
+    // Data descriptors may also be written without their signature.
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    let mut entry_writer =
+        zip_writer.write_entry_stream(EntryOptions::new("foo.bar", Compression::Stored)).await.unwrap();
+    entry_writer.write_all(b"unsigned").await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+    let mut data = input_stream.into_inner();
+
+    let descriptor = 30 + 7 + 8;
+    assert_eq!(crate::spec::delimiter::DDD.to_le_bytes(), data[descriptor..descriptor + 4]);
+    data.drain(descriptor..descriptor + 4);
+
+    let mut zip_reader = stream::ZipFileReader::new(data.as_slice());
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    assert_eq!("unsigned", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    assert!(zip_reader.entry_reader().await.expect("failed to read header").is_none());
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn zipcrypto_decryption() {
+    use crate::error::ZipError;
+    use crate::read::{mem, stream, ReaderOptions};
+
+    // Written by Info-ZIP's `zip -P secret`, with the second entry read from stdin.
+    let data = include_bytes!("fixtures/zipcrypto.zip");
+    let expected = [
+        ("a.txt", "Hello from a ZipCrypto-encrypted entry, hello hello hello.\n"),
+        ("-", "streamed from stdin, streamed from stdin.\n"),
+    ];
+
+    let options = ReaderOptions::new().password("secret");
+    let mut zip_reader = mem::ZipFileReader::with_options(data, options.clone()).await.expect("failed to open");
+    for (index, (name, contents)) in expected.iter().enumerate() {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+        assert!(entry_reader.entry().encrypted());
+        assert_eq!(*name, entry_reader.entry().name());
+        assert_eq!(*contents, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+
+    let mut zip_reader = stream::ZipFileReader::with_options(data.as_slice(), options);
+    for (name, contents) in expected {
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        assert_eq!(name, entry_reader.entry().name());
+        assert_eq!(contents, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+    assert!(zip_reader.entry_reader().await.expect("failed to read header").is_none());
+
+    let mut zip_reader = mem::ZipFileReader::new(data).await.expect("failed to open");
+    assert!(matches!(zip_reader.entry_reader(0).await, Err(ZipError::PasswordRequired)));
+
+    let options = ReaderOptions::new().password("incorrect");
+    let mut zip_reader = mem::ZipFileReader::with_options(data, options).await.expect("failed to open");
+    let error =
+        zip_reader.entry_reader(0).await.unwrap().read_to_end_crc().await.expect_err("read with wrong password");
+    let ZipError::UpstreamReadError(error) = error else { panic!("unexpected error: {error:?}") };
+    assert!(matches!(error.into_inner().unwrap().downcast_ref(), Some(ZipError::IncorrectPassword)));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn aes_decryption() {
+    use crate::error::ZipError;
+    use crate::read::{mem, ReaderOptions};
+    use crate::{AesStrength, Encryption};
+
+    // Written by an independent implementation of WinZip's AES specification: an AE-2 Deflate entry encrypted with
+    // AES-256, followed by an AE-1 Stored entry encrypted with AES-128.
+    let data = include_bytes!("fixtures/aes.zip");
+    let options = ReaderOptions::new().password("secret");
+    let mut zip_reader = mem::ZipFileReader::with_options(data, options.clone()).await.expect("failed to open");
+
+    let encryption: Vec<_> = zip_reader.entries().iter().map(|entry| entry.encryption()).collect();
+    assert_eq!(
+        vec![Some(Encryption::Aes(AesStrength::Aes256)), Some(Encryption::Aes(AesStrength::Aes128))],
+        encryption
+    );
+    assert_eq!(&Compression::Deflate, zip_reader.entries()[0].compression());
+
+    #[cfg(not(feature = "aes"))]
+    assert!(matches!(zip_reader.entry_reader(0).await, Err(ZipError::FeatureNotSupported(_))));
+
+    #[cfg(feature = "aes")]
+    {
+        use crate::read::stream;
+
+        let expected = [
+            ("aes256.txt", "Deflated and encrypted with AES-256. ".repeat(8)),
+            ("aes128.txt", "Stored and encrypted with AES-128 (AE-1).".to_string()),
+        ];
+        for (index, (name, contents)) in expected.iter().enumerate() {
+            let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+            assert_eq!(*name, entry_reader.entry().name());
+            assert_eq!(*contents, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+        }
+
+        let mut zip_reader = stream::ZipFileReader::with_options(data.as_slice(), options.clone());
+        for (_, contents) in &expected {
+            let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+            assert_eq!(*contents, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+        }
+
+        let inner_error = |error: ZipError| match error {
+            ZipError::UpstreamReadError(error) => error.into_inner().unwrap().downcast::<ZipError>().unwrap(),
+            error => panic!("unexpected error: {error:?}"),
+        };
+
+        let incorrect = ReaderOptions::new().password("incorrect");
+        let mut zip_reader = mem::ZipFileReader::with_options(data, incorrect).await.expect("failed to open");
+        let error =
+            zip_reader.entry_reader(0).await.unwrap().read_to_end_crc().await.expect_err("read with wrong password");
+        assert!(matches!(*inner_error(error), ZipError::IncorrectPassword));
+
+        // Flip a bit of the second entry's encrypted data, which follows its header, name, extra field, salt, and
+        // password verification value.
+        let mut tampered = data.to_vec();
+        let offset = zip_reader.entries()[1].offset.unwrap() as usize;
+        tampered[offset + 30 + 10 + 11 + 8 + 2] ^= 1;
+        let mut zip_reader = mem::ZipFileReader::with_options(&tampered, options).await.expect("failed to open");
+        let error = zip_reader.entry_reader(1).await.unwrap().read_to_end_crc().await.expect_err("read tampered entry");
+        assert!(matches!(*inner_error(error), ZipError::AuthenticationCodeMismatch));
+    }
+}
+
+#[cfg(all(feature = "aes", feature = "deflate"))]
+#[tokio::test]
+async fn aes_encryption_round_trip() {
+    use crate::error::ZipError;
+    use crate::read::{mem, ReaderOptions};
+    use crate::{AesStrength, Encryption};
+    use tokio::io::AsyncWriteExt;
+
+    let data = "Encrypted with AES-256. ".repeat(64);
+    let mut output = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+
+    for compression in [Compression::Stored, Compression::Deflate] {
+        let opts = EntryOptions::new(format!("whole-{compression:?}"), compression).password("secret");
+        zip_writer.write_entry_whole(opts, data.as_bytes()).await.expect("failed to write entry");
+
+        let opts = EntryOptions::new(format!("stream-{compression:?}"), compression).password("secret");
+        let mut entry_writer = zip_writer.write_entry_stream(opts).await.expect("failed to open entry");
+        entry_writer.write_all(data.as_bytes()).await.expect("failed to write entry data");
+        entry_writer.close().await.expect("failed to close entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = output.into_inner();
+    let options = ReaderOptions::new().password("secret");
+    let mut zip_reader = mem::ZipFileReader::with_options(&archive, options).await.expect("failed to open");
+    assert_eq!(4, zip_reader.entries().len());
+
+    for index in 0..zip_reader.entries().len() {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+        assert_eq!(Some(Encryption::Aes(AesStrength::Aes256)), entry_reader.entry().encryption());
+        assert_eq!(data, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+
+    let incorrect = ReaderOptions::new().password("incorrect");
+    let mut zip_reader = mem::ZipFileReader::with_options(&archive, incorrect).await.expect("failed to open");
+    let error =
+        zip_reader.entry_reader(0).await.unwrap().read_to_end_crc().await.expect_err("read with wrong password");
+    assert!(matches!(error, ZipError::UpstreamReadError(_)));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn shared_concurrent_entry_readers() {
+    use crate::read::{seek, shared};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, BufReader};
+
+    let contents: Vec<_> = (0..8).map(|index| format!("Entry number {index}. ").repeat(2048)).collect();
+    let entries = contents.iter().enumerate();
+    let archive = write_archive(
+        entries.map(|(index, data)| (EntryOptions::new(format!("{index}.txt"), Compression::Deflate), data)),
+    )
+    .await;
+
+    let source = BufReader::with_capacity(64, Cursor::new(archive));
+    let zip_reader = Arc::new(shared::ZipFileReader::new(source).await.expect("failed to open"));
+
+    let handles: Vec<_> = (0..contents.len())
+        .map(|index| {
+            let zip_reader = zip_reader.clone();
+            tokio::spawn(async move { zip_reader.entry_reader(index).await?.read_to_string_crc().await })
+        })
+        .collect();
+
+    for (handle, expected) in handles.into_iter().zip(&contents) {
+        assert_eq!(*expected, handle.await.unwrap().expect("failed to read entry"));
+    }
+
+    // Interleave small reads of two entries from the same task.
+    let mut first = zip_reader.entry_reader(0).await.expect("failed to open entry");
+    let mut second = zip_reader.entry_reader(7).await.expect("failed to open entry");
+    let (mut first_data, mut second_data) = (Vec::new(), Vec::new());
+    let mut buffer = [0; 100];
+
+    while !first.consumed() || !second.consumed() {
+        let read = first.read(&mut buffer).await.expect("failed to read entry");
+        first_data.extend_from_slice(&buffer[..read]);
+        let read = second.read(&mut buffer).await.expect("failed to read entry");
+        second_data.extend_from_slice(&buffer[..read]);
+    }
+    assert_eq!(contents[0].as_bytes(), first_data);
+    assert_eq!(contents[7].as_bytes(), second_data);
+    assert!(first.compare_crc() && second.compare_crc());
+
+    let archive = write_archive([(EntryOptions::new("foo.txt".to_string(), Compression::Stored), b"foo")]).await;
+
+    let zip_reader = seek::ZipFileReader::new(Cursor::new(archive)).await.expect("failed to open").into_shared();
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry");
+    assert_eq!("foo", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn owned_entry_readers() {
+    use crate::read::{fs, seek, shared, OwnedEntryReader};
+
+    let archive = write_archive(
+        [("foo.txt", "foo"), ("bar.txt", "bar")]
+            .map(|(name, data)| (EntryOptions::new(name.to_string(), Compression::Deflate), data)),
+    )
+    .await;
+
+    // Owned readers can be stored and spawned after the reader which opened them has been dropped.
+    struct Pending(Vec<OwnedEntryReader<shared::PositionedReader<Cursor<Vec<u8>>>>>);
+
+    let zip_reader = shared::ZipFileReader::new(Cursor::new(archive.clone())).await.expect("failed to open");
+    let mut pending = Pending(Vec::new());
+    for index in 0..zip_reader.entries().len() {
+        pending.0.push(zip_reader.owned_entry_reader(index).await.expect("failed to open entry"));
+    }
+    drop(zip_reader);
+
+    let handles: Vec<_> = pending.0.into_iter().map(|reader| tokio::spawn(reader.read_to_string_crc())).collect();
+    let mut contents = Vec::new();
+    for handle in handles {
+        contents.push(handle.await.unwrap().expect("failed to read entry"));
+    }
+    assert_eq!(vec!["foo", "bar"], contents);
+
+    let zip_reader = seek::ZipFileReader::new(Cursor::new(archive.clone())).await.expect("failed to open");
+    let entry_reader = zip_reader.into_entry_reader(1).await.expect("failed to open entry");
+    assert_eq!("bar.txt", entry_reader.entry().name());
+    let handle = tokio::spawn(entry_reader.read_to_string_crc());
+    assert_eq!("bar", handle.await.unwrap().expect("failed to read entry"));
+
+    let dir = TestDir::new("owned");
+    let path = dir.join("archive.zip");
+    tokio::fs::write(&path, &archive).await.expect("failed to write archive");
+
+    let zip_reader = fs::ZipFileReader::new(path.to_string_lossy().into_owned()).await.expect("failed to open");
+    let entry_reader = zip_reader.owned_entry_reader(0).await.expect("failed to open entry");
+    drop(zip_reader);
+    let handle = tokio::spawn(entry_reader.read_to_string_crc());
+    assert_eq!("foo", handle.await.unwrap().expect("failed to read entry"));
+}
+
+#[cfg(all(feature = "futures-io", feature = "deflate"))]
+#[test]
+fn futures_io_round_trip() {
+    use crate::read::{seek, stream};
+    use futures_util::io::{AsyncReadExt, AsyncWriteExt, Cursor};
+
+    // Driven by a non-tokio executor, without a tokio runtime.
+    futures_executor::block_on(async {
+        let mut output = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::from_futures_io(&mut output);
+
+        let opts = EntryOptions::new("whole.txt".to_string(), Compression::Deflate);
+        zip_writer.write_entry_whole(opts, b"Written whole.").await.expect("failed to write entry");
+
+        let opts = EntryOptions::new("stream.txt".to_string(), Compression::Stored);
+        let mut entry_writer = zip_writer.write_entry_stream(opts).await.expect("failed to open entry");
+        entry_writer.write_all(b"Written as a stream.").await.expect("failed to write entry data");
+        entry_writer.close().await.expect("failed to close entry");
+        zip_writer.close().await.expect("failed to close writer");
+
+        let archive = output.into_inner();
+        let expected = ["Written whole.", "Written as a stream."];
+
+        let mut zip_reader = seek::ZipFileReader::from_futures_io(Cursor::new(&archive)).await.expect("failed to open");
+        for (index, expected) in expected.iter().enumerate() {
+            let mut entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+            let mut contents = String::new();
+            AsyncReadExt::read_to_string(&mut entry_reader, &mut contents).await.expect("failed to read entry");
+            assert_eq!(*expected, contents);
+            assert!(entry_reader.compare_crc());
+        }
+
+        let mut zip_reader = stream::ZipFileReader::from_futures_io(Cursor::new(&archive));
+        for expected in expected {
+            let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+            assert_eq!(expected, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+        }
+    });
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn stream_reader_generic_source() {
+    use crate::read::stream::ZipFileReader;
+    use std::rc::Rc;
+    use tokio::io::{AsyncRead, ReadBuf};
+
+    // A source which isn't Send, read through without any dynamic dispatch.
+    struct LocalSource(Rc<Vec<u8>>, usize);
+
+    impl AsyncRead for LocalSource {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+            b: &mut ReadBuf<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            let remaining = &self.0[self.1..];
+            let read = std::cmp::min(remaining.len(), std::cmp::min(b.remaining(), 7));
+            b.put_slice(&remaining[..read]);
+            self.1 += read;
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    let archive = write_archive(
+        [("foo.txt", "foo"), ("bar.txt", "bar")]
+            .map(|(name, data)| (EntryOptions::new(name.to_string(), Compression::Deflate), data)),
+    )
+    .await;
+
+    let mut zip_reader = ZipFileReader::new(LocalSource(Rc::new(archive), 0));
+    let mut contents = Vec::new();
+    while let Some(entry_reader) = zip_reader.entry_reader().await.expect("failed to open entry") {
+        contents.push(entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+    assert_eq!(vec!["foo", "bar"], contents);
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn raw_entry_copy() {
+    use crate::read::{mem, seek, stream, ReaderOptions};
+
+    // The access time of the extended timestamp field is only held within the local file header.
+    let accessed = chrono::TimeZone::timestamp_opt(&chrono::Utc, 1_600_000_000, 0).unwrap();
+    let timestamp = crate::ExtraField::ExtendedTimestamp { modified: None, accessed: Some(accessed), created: None };
+    let source = write_archive([("keep.txt", "Kept as-is. "), ("drop.txt", "Dropped. ")].map(|(name, data)| {
+        let opts = EntryOptions::new(name.to_string(), Compression::Deflate).comment("A comment.".to_string());
+        (opts.extra_field(timestamp.clone()), data.repeat(64))
+    }))
+    .await;
+
+    let mut output = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(source)).await.expect("failed to open");
+    let (entry, raw_reader) = zip_reader.raw_entry(0).await.expect("failed to open entry");
+    zip_writer.copy_entry_raw(&entry, raw_reader).await.expect("failed to copy entry");
+
+    // Encrypted entries (here with data descriptors) are copied without needing their password.
+    let fixture = include_bytes!("fixtures/zipcrypto.zip");
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(fixture)).await.expect("failed to open");
+    for index in 0..zip_reader.entries().len() {
+        let entry = zip_reader.entries()[index].clone();
+        let raw_reader = zip_reader.raw_entry_reader(index).await.expect("failed to open entry");
+        zip_writer.copy_entry_raw(&entry, raw_reader).await.expect("failed to copy entry");
+    }
+
+    let opts = EntryOptions::new("added.txt".to_string(), Compression::Stored);
+    zip_writer.write_entry_whole(opts, b"Added.").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = output.into_inner();
+    let options = ReaderOptions::new().password("secret");
+    let mut zip_reader = mem::ZipFileReader::with_options(&archive, options.clone()).await.expect("failed to open");
+    let names: Vec<_> = zip_reader.entries().iter().map(|entry| entry.name().to_string()).collect();
+    assert_eq!(vec!["keep.txt", "a.txt", "-", "added.txt"], names);
+    assert_eq!(Some("A comment."), zip_reader.entries()[0].comment());
+    assert_eq!(&Compression::Deflate, zip_reader.entries()[0].compression());
+
+    let expected = [
+        "Kept as-is. ".repeat(64),
+        "Hello from a ZipCrypto-encrypted entry, hello hello hello.\n".to_string(),
+        "streamed from stdin, streamed from stdin.\n".to_string(),
+        "Added.".to_string(),
+    ];
+    for (index, contents) in expected.iter().enumerate() {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+        assert_eq!(*contents, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+
+    let mut zip_reader = stream::ZipFileReader::with_options(archive.as_slice(), options);
+    for contents in &expected {
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        if contents.starts_with("Kept") {
+            assert_eq!(Some(&accessed), entry_reader.entry().last_accessed());
+        }
+        assert_eq!(*contents, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn append_entries() {
+    use crate::error::ZipError;
+    use crate::read::{mem, stream};
+    use tokio::io::AsyncWriteExt;
+
+    for force_zip64 in [false, true] {
+        let mut archive = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut archive);
+        zip_writer.force_zip64(force_zip64);
+        let opts = EntryOptions::new("first.txt".to_string(), Compression::Deflate).comment("Kept.".to_string());
+        zip_writer.write_entry_whole(opts, b"First.").await.expect("failed to write entry");
+        zip_writer.close().await.expect("failed to close writer");
+
+        let mut zip_writer = ZipFileWriter::append(&mut archive).await.expect("failed to open for appending");
+        let opts = EntryOptions::new("second.txt".to_string(), Compression::Stored);
+        zip_writer.write_entry_whole(opts, b"Second.").await.expect("failed to write entry");
+        let opts = EntryOptions::new("third.txt".to_string(), Compression::Deflate);
+        let mut entry_writer = zip_writer.write_entry_stream(opts).await.expect("failed to open entry");
+        entry_writer.write_all(b"Third.").await.expect("failed to write entry");
+        entry_writer.close().await.expect("failed to close entry");
+        zip_writer.close().await.expect("failed to close writer");
+
+        let archive = archive.into_inner();
+        let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+        assert_eq!(3, zip_reader.entries().len());
+        assert_eq!(Some("Kept."), zip_reader.entries()[0].comment());
+        for (index, contents) in ["First.", "Second.", "Third."].iter().enumerate() {
+            let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+            assert_eq!(*contents, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+        }
+
+        let mut zip_reader = stream::ZipFileReader::new(archive.as_slice());
+        for contents in ["First.", "Second.", "Third."] {
+            let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+            assert_eq!(contents, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+        }
+        assert!(zip_reader.entry_reader().await.expect("failed to read").is_none());
+
+        // Shortening an existing entry's comment leaves the archive needing truncation.
+        let mut archive = Cursor::new(archive);
+        let mut zip_writer = ZipFileWriter::append(&mut archive).await.expect("failed to open for appending");
+        zip_writer.pending_entry_mut(0).expect("no entry").set_comment("").expect("failed to set comment");
+        let len = match zip_writer.close().await {
+            Err(ZipError::TruncationRequired(len)) => len,
+            result => panic!("unexpected result: {:?}", result.map(|_| ())),
+        };
+
+        let mut archive = archive.into_inner();
+        archive.truncate(len as usize);
+        let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+        assert_eq!(3, zip_reader.entries().len());
+        assert_eq!(Some(""), zip_reader.entries()[0].comment());
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn replace_existing_entries() {
+    use crate::read::mem;
+
+    let archive = write_archive(
+        [("a.txt", "Old A."), ("b.txt", "Old B.")]
+            .map(|(name, data)| (EntryOptions::new(name.to_string(), Compression::Deflate), data)),
+    )
+    .await;
+    let mut archive = Cursor::new(archive);
+
+    let mut zip_writer = ZipFileWriter::append(&mut archive).await.expect("failed to open for appending");
+    zip_writer.replace_existing(true);
+    for (name, data) in [("a.txt", "New A, which is longer."), ("c.txt", "New C.")] {
+        let opts = EntryOptions::new(name.to_string(), Compression::Stored);
+        zip_writer.write_entry_whole(opts, data.as_bytes()).await.expect("failed to write entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = archive.into_inner();
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let names: Vec<_> = zip_reader.entries().iter().map(|entry| entry.name().to_string()).collect();
+    assert_eq!(vec!["a.txt", "b.txt", "c.txt"], names);
+    assert_eq!(&Compression::Stored, zip_reader.entries()[0].compression());
+
+    for (index, contents) in ["New A, which is longer.", "Old B.", "New C."].iter().enumerate() {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+        assert_eq!(*contents, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn remove_entries() {
+    use crate::read::{mem, seek};
+
+    let source = write_archive(
+        ["a.txt", "b.txt", "c.txt", "d.txt"]
+            .map(|name| (EntryOptions::new(name.to_string(), Compression::Deflate), name.repeat(32))),
+    )
+    .await;
+
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(source)).await.expect("failed to open");
+    let mut output = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut output);
+    zip_writer.remove_entries(&mut zip_reader, &["b.txt", "d.txt"]).await.expect("failed to copy entries");
+    zip_writer.copy_entries(&mut zip_reader, |entry| entry.name() == "d.txt").await.expect("failed to copy entries");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = output.into_inner();
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let names: Vec<_> = zip_reader.entries().iter().map(|entry| entry.name().to_string()).collect();
+    assert_eq!(vec!["a.txt", "c.txt", "d.txt"], names);
+
+    for (index, name) in names.iter().enumerate() {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+        assert_eq!(name.repeat(32), entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn extract_to_dir() {
+    use crate::error::ZipError;
+    use crate::read::seek;
+
+    let entries = [("empty/", ""), ("nested/dir/a.txt", "A."), ("./b.txt", "B."), ("c/../c.txt", "C.")];
+    let archive =
+        write_archive(entries.map(|(name, data)| (EntryOptions::new(name.to_string(), Compression::Deflate), data)))
+            .await;
+
+    let dir = TestDir::new("extract");
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(archive)).await.expect("failed to open");
+    crate::fs::extract(&mut zip_reader, &dir).await.expect("failed to extract");
+
+    assert!(dir.join("empty").is_dir());
+    for (path, contents) in [("nested/dir/a.txt", "A."), ("b.txt", "B."), ("c.txt", "C.")] {
+        assert_eq!(contents, tokio::fs::read_to_string(dir.join(path)).await.expect("failed to read file"));
+    }
+
+    // Nothing is extracted from an archive containing any entry which would escape the destination.
+    for name in ["../escaped.txt", "/absolute.txt", "a/../../escaped.txt", "..\\escaped.txt"] {
+        let entries = ["safe.txt", name].map(|name| (EntryOptions::new(name.to_string(), Compression::Stored), b""));
+        let archive = write_archive(entries).await;
+
+        let mut zip_reader = seek::ZipFileReader::new(Cursor::new(archive)).await.expect("failed to open");
+        match crate::fs::extract(&mut zip_reader, dir.join("unsafe")).await {
+            Err(ZipError::UnsafeEntryPath(unsafe_name)) => assert_eq!(name, unsafe_name),
+            result => panic!("unexpected result: {:?}", result),
+        };
+        assert!(!dir.join("unsafe").exists());
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn write_dir_tree() {
+    use crate::fs::DirOptions;
+    use crate::read::mem;
+
+    let dir = TestDir::new("write_dir");
+    tokio::fs::create_dir_all(dir.join("sub/empty")).await.expect("failed to create input directory");
+    tokio::fs::write(dir.join("a.txt"), "A.".repeat(32)).await.expect("failed to write file");
+    tokio::fs::write(dir.join("sub/b.txt"), "B.").await.expect("failed to write file");
+
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    let options = DirOptions::new().compression(Compression::Deflate).prefix("pkg/");
+    let manifest = crate::fs::write_dir(&mut zip_writer, &dir, options).await.expect("failed to write directory");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = archive.into_inner();
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let names: Vec<_> = zip_reader.entries().iter().map(|entry| entry.name().to_string()).collect();
+    assert_eq!(vec!["pkg/a.txt", "pkg/sub/", "pkg/sub/b.txt", "pkg/sub/empty/"], names);
+    assert_eq!(names, manifest.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>());
+    assert_eq!(Compression::Stored, manifest[1].1);
+
+    let modified = std::fs::metadata(dir.join("a.txt")).and_then(|metadata| metadata.modified()).unwrap();
+    let modified = chrono::DateTime::<chrono::Utc>::from(modified);
+    assert!((*zip_reader.entries()[0].last_modified() - modified).num_seconds().abs() <= 2);
+    #[cfg(unix)]
+    assert_eq!(Some(crate::CreatorOs::Unix), zip_reader.entries()[0].made_by_os());
+
+    for (index, contents) in [(0, "A.".repeat(32)), (2, "B.".to_string())] {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+        assert_eq!(contents, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[tokio::test]
+async fn entry_path_sanitisation() {
+    use crate::read::stream;
+
+    let cases: [(&str, Option<&str>, Option<&str>); 12] = [
+        ("a/b.txt", Some("a/b.txt"), Some("a/b.txt")),
+        ("dir/", Some("dir"), Some("dir")),
+        ("./a/../b.txt", Some("a/../b.txt"), Some("a/b.txt")),
+        ("../b.txt", None, Some("b.txt")),
+        ("a/../../b.txt", None, Some("a/b.txt")),
+        ("/etc/passwd", None, Some("etc/passwd")),
+        ("C:\\Windows\\b.txt", None, Some("Windows/b.txt")),
+        ("C:b.txt", None, Some("b.txt")),
+        ("a/C:evil.txt", None, Some("a/evil.txt")),
+        ("a\\C:\\evil.txt", None, Some("a/evil.txt")),
+        ("..", None, None),
+        ("a\0b", None, None),
+    ];
+
+    let archive =
+        write_archive(cases.map(|(name, _, _)| (EntryOptions::new(name.to_string(), Compression::Stored), b""))).await;
+    let zip_reader = crate::read::mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let mut stream_reader = stream::ZipFileReader::new(archive.as_slice());
+
+    for (index, (name, enclosed, sanitized)) in cases.into_iter().enumerate() {
+        let entry = &zip_reader.entries()[index];
+        assert_eq!(enclosed.map(PathBuf::from), entry.enclosed_name(), "enclosed name of {:?}", name);
+        assert_eq!(sanitized.map(PathBuf::from), entry.sanitized_name(), "sanitized name of {:?}", name);
+
+        let entry_reader = stream_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        assert_eq!(sanitized.map(PathBuf::from), entry_reader.entry().sanitized_name());
+    }
+}
+
+#[tokio::test]
+async fn unix_permissions() {
+    use crate::read::seek;
+    use crate::write::EntryDefaults;
+
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    let opts = EntryOptions::new("plain.txt".to_string(), Compression::Stored);
+    zip_writer.write_entry_whole(opts, b"Plain.").await.expect("failed to write entry");
+    zip_writer.default_options(EntryDefaults { permissions: Some(0o100755), ..Default::default() });
+    let opts = EntryOptions::new("script.sh".to_string(), Compression::Stored);
+    zip_writer.write_entry_whole(opts, b"#!/bin/sh\n").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(archive.into_inner())).await.expect("failed to open");
+    let (plain, script) = (&zip_reader.entries()[0], &zip_reader.entries()[1]);
+    assert_eq!((Some(0), None, None), (plain.external_attributes(), plain.unix_mode(), plain.permissions()));
+    assert_eq!((Some(0o100755), Some(0o755)), (script.unix_mode(), script.permissions()));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TestDir::new("permissions");
+        crate::fs::extract(&mut zip_reader, &dir).await.expect("failed to extract");
+        let metadata = tokio::fs::metadata(dir.join("script.sh")).await.expect("failed to read metadata");
+        assert_eq!(0o755, metadata.permissions().mode() & 0o777);
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn entry_permissions() {
+    use crate::read::mem;
+    use crate::write::EntryDefaults;
+    use tokio::io::AsyncWriteExt;
+
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    zip_writer.default_options(EntryDefaults { permissions: Some(0o644), ..Default::default() });
+
+    let opts = EntryOptions::new("data.txt".to_string(), Compression::Deflate);
+    zip_writer.write_entry_whole(opts, b"Data.").await.expect("failed to write entry");
+    let opts = EntryOptions::new("bin/".to_string(), Compression::Stored).permissions(0o755);
+    zip_writer.write_entry_whole(opts, b"").await.expect("failed to write entry");
+    let opts = EntryOptions::new("bin/run.sh".to_string(), Compression::Deflate).permissions(0o755);
+    let mut entry_writer = zip_writer.write_entry_stream(opts).await.expect("failed to open entry");
+    entry_writer.write_all(b"#!/bin/sh\n").await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = archive.into_inner();
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let modes: Vec<_> = zip_reader.entries().iter().map(|entry| entry.unix_mode()).collect();
+    assert_eq!(vec![Some(0o100644), Some(0o040755), Some(0o100755)], modes);
+    assert_eq!(Some(0o040755 << 16 | 0x10), zip_reader.entries()[1].external_attributes());
+    assert!(zip_reader.entries().iter().all(|entry| entry.made_by_os() == Some(crate::CreatorOs::Unix)));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn symlink_entries() {
+    use crate::error::ZipError;
+    use crate::read::{mem, stream, MAX_SYMLINK_TARGET_LENGTH};
+
+    let target = "a/".repeat(MAX_SYMLINK_TARGET_LENGTH as usize);
+    let archive = write_archive([
+        (EntryOptions::new("file.txt".to_string(), Compression::Deflate).permissions(0o644), b"File.".as_slice()),
+        (EntryOptions::new("link".to_string(), Compression::Deflate).permissions(0o120777), b"dir/file.txt"),
+        (EntryOptions::new("long".to_string(), Compression::Deflate).permissions(0o120777), target.as_bytes()),
+    ])
+    .await;
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let symlinks: Vec<_> = zip_reader.entries().iter().map(|entry| entry.is_symlink()).collect();
+    assert_eq!(vec![false, true, true], symlinks);
+
+    let entry_reader = zip_reader.entry_reader(1).await.expect("failed to open entry");
+    assert_eq!(PathBuf::from("dir/file.txt"), entry_reader.read_symlink_target().await.expect("failed to read target"));
+    let entry_reader = zip_reader.entry_reader(2).await.expect("failed to open entry");
+    match entry_reader.read_symlink_target().await {
+        Err(ZipError::EntryTooLarge(size, limit)) => {
+            assert_eq!((target.len() as u64, MAX_SYMLINK_TARGET_LENGTH), (size, limit))
+        }
+        result => panic!("unexpected result: {:?}", result),
+    };
+
+    // Stream-read entries have no external attributes, but their targets can still be read.
+    let mut zip_reader = stream::ZipFileReader::new(archive.as_slice());
+    zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    assert!(!entry_reader.entry().is_symlink());
+    assert_eq!(PathBuf::from("dir/file.txt"), entry_reader.read_symlink_target().await.expect("failed to read target"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn extract_symlinks() {
+    use crate::error::ZipError;
+    use crate::fs::{ExtractOptions, SymlinkPolicy};
+    use crate::read::seek;
+
+    async fn archive(entries: &[(&str, &str, u16)]) -> seek::ZipFileReader<Cursor<Vec<u8>>> {
+        let archive = write_archive(entries.iter().map(|(name, data, permissions)| {
+            (EntryOptions::new(name.to_string(), Compression::Stored).permissions(*permissions), data)
+        }))
+        .await;
+        seek::ZipFileReader::new(Cursor::new(archive)).await.expect("failed to open")
+    }
+
+    let dir = TestDir::new("symlinks");
+    let entries =
+        [("link", "dir/file.txt", 0o120777), ("dir/up", "../dir", 0o120777), ("dir/file.txt", "File.", 0o644)];
+
+    for (policy, subdir) in [(SymlinkPolicy::Materialize, "materialize"), (SymlinkPolicy::Skip, "skip")] {
+        let mut zip_reader = archive(&entries).await;
+        let options = ExtractOptions::new().symlinks(policy);
+        crate::fs::extract_with_options(&mut zip_reader, dir.join(subdir), options).await.expect("failed to extract");
+        let link = tokio::fs::symlink_metadata(dir.join(subdir).join("link")).await;
+        assert_eq!(policy == SymlinkPolicy::Materialize, link.is_ok_and(|metadata| metadata.is_file()));
+    }
+
+    let mut zip_reader = archive(&entries).await;
+    let options = ExtractOptions::new().symlinks(SymlinkPolicy::Create);
+    crate::fs::extract_with_options(&mut zip_reader, dir.join("create"), options).await.expect("failed to extract");
+    let metadata = tokio::fs::symlink_metadata(dir.join("create/link")).await.expect("failed to read metadata");
+    assert!(metadata.is_symlink());
+    assert_eq!("File.", tokio::fs::read_to_string(dir.join("create/link")).await.expect("failed to read link"));
+    assert_eq!("File.", tokio::fs::read_to_string(dir.join("create/dir/up/file.txt")).await.expect("failed to read"));
+
+    // Links may neither point outside of the destination, nor be passed through by entries or other links.
+    let unsafe_archives: [&[(&str, &str, u16)]; 4] = [
+        &[("link", "../outside", 0o120777)],
+        &[("link", "/etc/passwd", 0o120777)],
+        &[("link", ".", 0o120777), ("escape", "link/..", 0o120777)],
+        &[("link", "dir", 0o120777), ("link/file.txt", "File.", 0o644)],
+    ];
+    for entries in unsafe_archives {
+        let mut zip_reader = archive(entries).await;
+        let options = ExtractOptions::new().symlinks(SymlinkPolicy::Create);
+        match crate::fs::extract_with_options(&mut zip_reader, dir.join("unsafe"), options).await {
+            Err(ZipError::UnsafeSymlinkTarget(_)) | Err(ZipError::UnsafeEntryPath(_)) => {}
+            result => panic!("unexpected result for {:?}: {:?}", entries, result),
+        };
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn extended_timestamps() {
+    use crate::read::{mem, stream};
+    use chrono::{TimeZone, Utc};
+
+    let (modified, accessed, created) = (1_600_000_001i32, 1_600_000_003i32, 1_500_000_005i32);
+    let timestamp = |flags: u8, times: &[i32]| {
+        let mut extra = vec![0x55, 0x54];
+        extra.extend_from_slice(&(1 + times.len() as u16 * 4).to_le_bytes());
+        extra.push(flags);
+        times.iter().for_each(|time| extra.extend_from_slice(&time.to_le_bytes()));
+        extra
+    };
+
+    let full = EntryOptions::new("full.txt".to_string(), Compression::Deflate)
+        .extra(timestamp(0x07, &[modified, accessed, created]));
+    // The central directory's copy of the field flags every timestamp, but only holds the modification time.
+    let central =
+        EntryOptions::new("central.txt".to_string(), Compression::Deflate).extra(timestamp(0x07, &[modified]));
+    let archive = write_archive([(full, b"Full.".as_slice()), (central, b"Central.")]).await;
+
+    let time = |seconds: i32| Utc.timestamp_opt(seconds.into(), 0).unwrap();
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let (full, central) = (&zip_reader.entries()[0], &zip_reader.entries()[1]);
+    assert_eq!(
+        (&time(modified), Some(&time(accessed)), Some(&time(created))),
+        (full.last_modified(), full.last_accessed(), full.created())
+    );
+    assert_eq!((&time(modified), None, None), (central.last_modified(), central.last_accessed(), central.created()));
+
+    let mut zip_reader = stream::ZipFileReader::new(archive.as_slice());
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    let entry = entry_reader.entry();
+    assert_eq!((&time(modified), Some(&time(accessed))), (entry.last_modified(), entry.last_accessed()));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn ntfs_timestamps() {
+    use crate::read::{mem, stream};
+
+    let (modified, accessed, created) =
+        (132_500_000_001_234_567u64, 132_500_000_020_000_000u64, 116_444_736_000_000_000);
+    let mut extra = vec![0x0a, 0x00, 32, 0, 0, 0, 0, 0, 0x01, 0x00, 24, 0];
+    [modified, accessed, created].iter().for_each(|time| extra.extend_from_slice(&time.to_le_bytes()));
+
+    let archive = write_archive([
+        (EntryOptions::new("ntfs.txt".to_string(), Compression::Deflate).extra(extra), b"NTFS.".as_slice()),
+        (EntryOptions::new("plain.txt".to_string(), Compression::Deflate), b"Plain."),
+    ])
+    .await;
+
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let timestamps = zip_reader.entries()[0].ntfs_timestamps().expect("no NTFS timestamps");
+    assert_eq!(
+        (1_605_526_400, 123_456_700),
+        (timestamps.modified().timestamp(), timestamps.modified().timestamp_subsec_nanos())
+    );
+    assert_eq!((1_605_526_402, 0), (timestamps.accessed().timestamp(), timestamps.accessed().timestamp_subsec_nanos()));
+    assert_eq!(0, timestamps.created().timestamp());
+    assert!(zip_reader.entries()[1].ntfs_timestamps().is_none());
+
+    let mut zip_reader = stream::ZipFileReader::new(archive.as_slice());
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    assert_eq!(Some(timestamps), entry_reader.entry().ntfs_timestamps());
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn typed_extra_fields() {
+    use crate::error::ZipError;
+    use crate::read::{mem, stream};
+    use crate::spec::extra::fields;
+    use crate::ExtraField;
+    use chrono::{TimeZone, Utc};
+
+    let (modified, accessed) =
+        (Utc.timestamp_opt(1_600_000_001, 0).unwrap(), Utc.timestamp_opt(1_600_000_003, 0).unwrap());
+    let opts = EntryOptions::new("typed.txt".to_string(), Compression::Deflate)
+        .extra_field(ExtraField::Zip64)
+        .extra_field(ExtraField::ExtendedTimestamp {
+            modified: Some(modified),
+            accessed: Some(accessed),
+            created: None,
+        })
+        .extra_field(ExtraField::UnixIds { uid: 1000, gid: 100 })
+        .extra_field(ExtraField::UnicodePath("typed.txt".to_string()))
+        .extra_field(ExtraField::Custom { id: 0xcafe, data: vec![1, 2, 3] });
+
+    let archive = write_archive([(opts, b"Typed.")]).await;
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let entry = &zip_reader.entries()[0];
+    let ids: Vec<_> = fields(entry.extra().unwrap()).map(|(id, _)| id).collect();
+    assert_eq!(vec![0x0001, 0x5455, 0x7875, 0x7075, 0xcafe], ids);
+    assert_eq!((&modified, None), (entry.last_modified(), entry.last_accessed()));
+
+    let field = |id: u16| fields(entry.extra().unwrap()).find(|(field_id, _)| *field_id == id).unwrap().1.to_vec();
+    assert_eq!(vec![1, 4, 0xe8, 0x03, 0, 0, 4, 100, 0, 0, 0], field(0x7875));
+    assert_eq!(crc32fast::hash(b"typed.txt").to_le_bytes(), field(0x7075)[1..5]);
+    assert_eq!(vec![1, 2, 3], field(0xcafe));
+
+    let mut zip_reader = stream::ZipFileReader::new(archive.as_slice());
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    assert_eq!(
+        (&modified, Some(&accessed)),
+        (entry_reader.entry().last_modified(), entry_reader.entry().last_accessed())
+    );
+
+    let opts = EntryOptions::new("large.txt".to_string(), Compression::Stored)
+        .extra_field(ExtraField::Custom { id: 0xcafe, data: vec![0; 65_536] });
+    let mut zip_writer = ZipFileWriter::new(Cursor::new(Vec::<u8>::new()));
+    assert!(matches!(zip_writer.write_entry_whole(opts, b"Large.").await, Err(ZipError::FieldTooLong(65_536))));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn archive_comments() {
+    use crate::error::ZipError;
+    use crate::read::seek;
+
+    // The comment holds the EOCDH signature, which mustn't be mistaken for the header itself.
+    let comment = "Archive comment. PK\u{5}\u{6}";
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    let opts = EntryOptions::new("foo.txt".to_string(), Compression::Deflate);
+    zip_writer.write_entry_whole(opts, b"Foo.").await.expect("failed to write entry");
+    zip_writer.close_with_comment(comment).await.expect("failed to close writer");
+
+    let zip_reader = seek::ZipFileReader::new(Cursor::new(archive.get_ref().clone())).await.expect("failed to open");
+    assert_eq!(comment, zip_reader.comment().as_str());
+    assert_eq!(1, zip_reader.entries().len());
+
+    archive.set_position(0);
+    let mut zip_writer = ZipFileWriter::append(&mut archive).await.expect("failed to open writer");
+    let opts = EntryOptions::new("bar.txt".to_string(), Compression::Deflate);
+    zip_writer.write_entry_whole(opts, b"Bar.").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let zip_reader = seek::ZipFileReader::new(Cursor::new(archive.into_inner())).await.expect("failed to open");
+    assert_eq!((comment, 2), (zip_reader.comment().as_str(), zip_reader.entries().len()));
+
+    let zip_writer = ZipFileWriter::new(Cursor::new(Vec::<u8>::new()));
+    assert!(matches!(zip_writer.close_with_comment("a".repeat(70_000)).await, Err(ZipError::FieldTooLong(70_000))));
+}
+
+#[tokio::test]
+async fn crc_verification() {
+    use crate::error::ZipError;
+    use crate::read::{mem, stream, ReaderOptions};
+    use tokio::io::AsyncReadExt;
+
+    let opts = EntryOptions::new("data.txt".to_string(), Compression::Stored);
+    let mut archive = write_archive([(opts, b"Uncorrupted data.")]).await;
+    let position = archive.windows(11).position(|window| window == b"Uncorrupted").unwrap();
+    archive[position] = b'u';
+    let expected = crc32fast::hash(b"Uncorrupted data.");
+    let actual = crc32fast::hash(b"uncorrupted data.");
+
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let mut entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry");
+    let error = entry_reader.read_to_end(&mut Vec::new()).await.expect_err("corrupt data was read");
+    let error = error.into_inner().unwrap().downcast::<ZipError>().unwrap();
+    assert!(matches!(*error, ZipError::CrcMismatch { expected: e, actual: a } if (e, a) == (expected, actual)));
+
+    let mut zip_reader = stream::ZipFileReader::new(archive.as_slice());
+    let mut entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    assert!(entry_reader.read_to_end(&mut Vec::new()).await.is_err());
+
+    let options = ReaderOptions::new().verify_crc(false);
+    let mut zip_reader = mem::ZipFileReader::with_options(&archive, options).await.expect("failed to open");
+    let mut entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry");
+    let mut data = Vec::new();
+    entry_reader.read_to_end(&mut data).await.expect("failed to read entry");
+    assert_eq!(b"uncorrupted data.".to_vec(), data);
+    assert!(!entry_reader.compare_crc());
+
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry");
+    let error = entry_reader.read_to_end_limited(1024).await.expect_err("corrupt data was read");
+    assert!(matches!(error, ZipError::CrcMismatch { expected: e, actual: a } if (e, a) == (expected, actual)));
+}
+
+#[tokio::test]
+async fn size_validation() {
+    use crate::error::ZipError;
+    use crate::read::{mem, ReaderOptions};
+    use tokio::io::AsyncReadExt;
+
+    let opts = EntryOptions::new("data.txt".to_string(), Compression::Stored);
+    let archive = write_archive([(opts, b"Sixteen bytes!!!")]).await;
+    let cdh = archive.windows(4).position(|window| window == crate::spec::delimiter::CDFHD.to_le_bytes()).unwrap();
+
+    // Data shorter than declared isn't mistaken for truncation, as the archive itself is complete.
+    let cases = [(10u32, std::io::ErrorKind::InvalidData), (20, std::io::ErrorKind::UnexpectedEof)];
+    for ((declared, kind), detect_truncation) in cases.into_iter().flat_map(|case| [(case, false), (case, true)]) {
+        let mut archive = archive.clone();
+        archive[cdh + 24..cdh + 28].copy_from_slice(&declared.to_le_bytes());
+
+        let options = ReaderOptions::new().detect_truncation(detect_truncation);
+        let mut zip_reader = mem::ZipFileReader::with_options(&archive, options).await.expect("failed to open");
+        let mut entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry");
+        let mut data = Vec::new();
+        let error = entry_reader.read_to_end(&mut data).await.expect_err("mis-sized data was read");
+        assert_eq!(kind, error.kind());
+        assert!(data.len() <= declared as usize);
+
+        match *error.into_inner().unwrap().downcast::<ZipError>().unwrap() {
+            ZipError::UncompressedSizeExceeded => assert_eq!(10, declared),
+            ZipError::DeclaredSizeMismatch(expected, actual) => assert_eq!((20, 16), (expected, actual)),
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn decompression_limits() {
+    use crate::error::ZipError;
+    use crate::read::{mem, stream, ReaderOptions};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let zeros = vec![0; 64 * 1024];
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    for name in ["a.bin", "b.bin"] {
+        let opts = EntryOptions::new(name.to_string(), Compression::Deflate);
+        zip_writer.write_entry_whole(opts, &zeros).await.expect("failed to write entry");
+    }
+    let opts = EntryOptions::new("streamed.bin".to_string(), Compression::Deflate);
+    let mut entry_writer = zip_writer.write_entry_stream(opts).await.expect("failed to open entry");
+    entry_writer.write_all(&zeros).await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = archive.into_inner();
+
+    fn limit(error: std::io::Error) -> (&'static str, u64) {
+        match *error.into_inner().unwrap().downcast::<ZipError>().unwrap() {
+            ZipError::DecompressionLimitExceeded(limit, value) => (limit, value),
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+
+    let options = ReaderOptions::new().max_entry_size(1000);
+    let mut zip_reader = mem::ZipFileReader::with_options(&archive, options).await.expect("failed to open");
+    let error = zip_reader.entry_reader(0).await.unwrap().read_to_end(&mut Vec::new()).await.unwrap_err();
+    assert_eq!(("entry's decompressed size", 1000), limit(error));
+
+    let options = ReaderOptions::new().max_total_size(100 * 1024);
+    let mut zip_reader = mem::ZipFileReader::with_options(&archive, options).await.expect("failed to open");
+    zip_reader.entry_reader(0).await.unwrap().read_to_end(&mut Vec::new()).await.expect("failed to read entry");
+    let error = zip_reader.entry_reader(1).await.unwrap().read_to_end(&mut Vec::new()).await.unwrap_err();
+    assert_eq!(("total decompressed size", 100 * 1024), limit(error));
+
+    let options = ReaderOptions::new().max_compression_ratio(10);
+    let mut zip_reader = mem::ZipFileReader::with_options(&archive, options.clone()).await.expect("failed to open");
+    let error = zip_reader.entry_reader(0).await.unwrap().read_to_end(&mut Vec::new()).await.unwrap_err();
+    assert_eq!(("entry's compression ratio", 10), limit(error));
+
+    // The streamed entry's compressed size is only held within its data descriptor.
+    let mut zip_reader = stream::ZipFileReader::with_options(archive.as_slice(), options);
+    for _ in 0..2 {
+        zip_reader.entry_reader().await.unwrap().unwrap().read_to_end(&mut Vec::new()).await.unwrap_err();
+    }
+    let mut entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    assert!(entry_reader.entry().data_descriptor());
+    assert_eq!(("entry's compression ratio", 10), limit(entry_reader.read_to_end(&mut Vec::new()).await.unwrap_err()));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn overlapping_entries() {
+    use crate::error::ZipError;
+    use crate::read::{fs, mem, ParseMode, ReaderOptions};
+    use tokio::io::AsyncWriteExt;
+
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    for name in ["a.txt", "b.txt"] {
+        let opts = EntryOptions::new(name.to_string(), Compression::Stored);
+        zip_writer.write_entry_whole(opts, b"Hello, world!").await.expect("failed to write entry");
+    }
+    let opts = EntryOptions::new("streamed.txt".to_string(), Compression::Deflate);
+    let mut entry_writer = zip_writer.write_entry_stream(opts).await.expect("failed to open entry");
+    entry_writer.write_all(b"Hello, world!").await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+    let mut archive = archive.into_inner();
+
+    let strict = ReaderOptions::new().parse_mode(ParseMode::Strict);
+    mem::ZipFileReader::with_options(&archive, strict.clone()).await.expect("failed to open strictly");
+
+    // Declare a different compression method within the first entry's local file header.
+    let mut mismatched = archive.clone();
+    mismatched[8..10].copy_from_slice(&8u16.to_le_bytes());
+    mem::ZipFileReader::new(&mismatched).await.expect("failed to open");
+    match mem::ZipFileReader::with_options(&mismatched, strict.clone()).await {
+        Err(ZipError::LocalHeaderMismatch(name)) => assert_eq!("a.txt", name),
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+
+    // Point the second central directory record at the data of the first entry.
+    let cd_offsets: Vec<usize> =
+        archive.windows(4).enumerate().filter(|(_, w)| w == b"PK\x01\x02").map(|(i, _)| i).collect();
+    archive[cd_offsets[1] + 42..cd_offsets[1] + 46].copy_from_slice(&0u32.to_le_bytes());
+
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    assert_eq!(2, zip_reader.entries().iter().filter(|entry| entry.offset == Some(0)).count());
+
+    match mem::ZipFileReader::with_options(&archive, strict.clone()).await {
+        Err(ZipError::OverlappingEntries(first, second)) => assert_eq!(("a.txt", "b.txt"), (&*first, &*second)),
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+
+    let dir = TestDir::new("overlapping");
+    let path = dir.join("archive.zip");
+    tokio::fs::write(&path, &archive).await.expect("failed to write archive");
+
+    match fs::ZipFileReader::with_options(path.to_string_lossy().into_owned(), strict).await {
+        Err(ZipError::OverlappingEntries(first, second)) => assert_eq!(("a.txt", "b.txt"), (&*first, &*second)),
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+
+    // Declare a Zip64 compressed size whose end lies beyond the largest possible offset.
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    zip_writer.force_zip64(true);
+    let opts = EntryOptions::new("a.txt".to_string(), Compression::Stored);
+    zip_writer.write_entry_whole(opts, b"Hello, world!").await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+    let mut archive = archive.into_inner();
+
+    let cdfh_offset = archive.windows(4).position(|window| window == b"PK\x01\x02").unwrap();
+    let extra_offset = cdfh_offset + 46 + "a.txt".len();
+    assert_eq!(&archive[extra_offset..extra_offset + 2], &1u16.to_le_bytes());
+    archive[extra_offset + 12..extra_offset + 20].copy_from_slice(&(u64::MAX - 8).to_le_bytes());
+
+    match mem::ZipFileReader::with_options(&archive, ReaderOptions::new().parse_mode(ParseMode::Strict)).await {
+        Err(ZipError::HeaderOutOfBounds("entry data")) => {}
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn lenient_parsing() {
+    use crate::error::ZipError;
+    use crate::read::{mem, stream, ParseMode, ReaderOptions};
+
+    let mut archive = write_archive(["a.txt", "b.txt"].map(|name| {
+        let opts = EntryOptions::new(name.to_string(), Compression::Stored)
+            .extra_field(crate::ExtraField::Custom { id: 0xcafe, data: vec![1, 2, 3] });
+        (opts, b"Hello, world!")
+    }))
+    .await;
+
+    // Overstate the length of the second entry's extra field (within both of its headers), so that it runs past the
+    // end of the headers' extra field data.
+    let fields: Vec<_> =
+        archive.windows(4).enumerate().filter(|(_, w)| w == &[0xfe, 0xca, 3, 0]).map(|(i, _)| i).collect();
+    assert_eq!(4, fields.len());
+    for offset in [fields[1], fields[3]] {
+        archive[offset + 2] = 4;
+    }
+    assert!(matches!(mem::ZipFileReader::new(&archive).await, Err(ZipError::InvalidExtraField(0xcafe))));
+
+    // Zero the first entry's dates (which are invalid), and understate the number of entries.
+    let find = |archive: &[u8], signature: &[u8]| archive.windows(4).position(|window| window == signature).unwrap();
+    let cdfh_offset = find(&archive, b"PK\x01\x02");
+    let eocdh_offset = find(&archive, b"PK\x05\x06");
+    archive[12..14].copy_from_slice(&[0, 0]);
+    archive[cdfh_offset + 14..cdfh_offset + 16].copy_from_slice(&[0, 0]);
+    archive[eocdh_offset + 8..eocdh_offset + 12].copy_from_slice(&[1, 0, 1, 0]);
+
+    assert!(matches!(mem::ZipFileReader::new(&archive).await, Err(ZipError::InvalidDateTime(0, _))));
+    let mut zip_reader = stream::ZipFileReader::new(archive.as_slice());
+    assert!(matches!(zip_reader.entry_reader().await, Err(ZipError::InvalidDateTime(0, _))));
+
+    let options = ReaderOptions::new().parse_mode(ParseMode::Lenient);
+    let zip_reader = mem::ZipFileReader::with_options(&archive, options.clone()).await.expect("failed to open");
+    assert_eq!(2, zip_reader.entries().len());
+    assert_eq!(crate::spec::date::dos_epoch(), *zip_reader.entries()[0].last_modified());
+
+    assert_eq!(0, crate::spec::extra::fields(zip_reader.entries()[1].extra().unwrap()).count());
+
+    let mut zip_reader = stream::ZipFileReader::with_options(archive.as_slice(), options);
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    assert_eq!(crate::spec::date::dos_epoch(), *entry_reader.entry().last_modified());
+    entry_reader.read_to_end_crc().await.expect("failed to read entry");
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    assert_eq!("Hello, world!", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn repair_archive() {
+    use crate::read::{mem, seek, ReaderOptions};
+
+    let mut archive = write_archive(
+        ["a.txt", "b.txt", "c.txt"]
+            .map(|name| (EntryOptions::new(name.to_string(), Compression::Deflate), name.repeat(64))),
+    )
+    .await;
+
+    // Corrupt the second entry's local file header with an invalid date, and prefix some garbage.
+    let lfh_offsets: Vec<_> =
+        archive.windows(4).enumerate().filter(|(_, w)| w == b"PK\x03\x04").map(|(i, _)| i).collect();
+    archive[lfh_offsets[1] + 12..lfh_offsets[1] + 14].copy_from_slice(&[0, 0]);
+    archive.splice(0..0, [0xAB; 100]);
+
+    let mut zip_reader = seek::ZipFileReader::recover(Cursor::new(archive), ReaderOptions::new()).await.unwrap();
+    assert!(zip_reader.recovered());
+    let names: Vec<_> = zip_reader.entries().iter().map(|entry| entry.name()).collect();
+    assert_eq!(vec!["a.txt", "c.txt"], names);
+
+    let mut repaired = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut repaired);
+    assert_eq!(2, zip_writer.repair_from(&mut zip_reader).await.expect("failed to repair archive"));
+    zip_writer.close().await.expect("failed to close writer");
+
+    let repaired = repaired.into_inner();
+    let mut zip_reader = mem::ZipFileReader::new(&repaired).await.expect("failed to open repaired archive");
+    assert_eq!(2, zip_reader.entries().len());
+    let data = zip_reader.entry_reader(1).await.unwrap().read_to_string_crc().await.expect("failed to read entry");
+    assert_eq!("c.txt".repeat(64), data);
+}
+
+#[tokio::test]
+async fn truncated_stream() {
+    use crate::error::ZipError;
+    use crate::read::{stream, ReaderOptions};
+    use tokio::io::AsyncReadExt;
+
+    let archive = write_archive(
+        ["a.txt", "b.txt", "c.txt"]
+            .map(|name| (EntryOptions::new(name.to_string(), Compression::Stored), name.repeat(64))),
+    )
+    .await;
+
+    let lfh_offset = archive.windows(4).enumerate().filter(|(_, w)| w == b"PK\x03\x04").nth(2).unwrap().0;
+    let options = ReaderOptions::new().detect_truncation(true);
+
+    // Cut the archive off part-way through the final entry's data, and then part-way through its header.
+    for truncated_len in [lfh_offset + 100, lfh_offset + 10] {
+        let mut zip_reader = stream::ZipFileReader::with_options(&archive[..truncated_len], options.clone());
+        for name in ["a.txt", "b.txt"] {
+            let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+            assert_eq!(name.repeat(64), entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+        }
+
+        if truncated_len > lfh_offset + 30 {
+            let mut entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+            let error = entry_reader.read_to_end(&mut Vec::new()).await.unwrap_err();
+            assert!(matches!(*error.into_inner().unwrap().downcast::<ZipError>().unwrap(), ZipError::Truncated));
+        }
+        assert!(matches!(zip_reader.entry_reader().await, Err(ZipError::Truncated)));
+        assert!(zip_reader.entry_reader().await.expect("failed to finish").is_none());
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn prepended_data() {
+    use crate::read::{mem, stream, ReaderOptions};
+
+    let mut stub = b"MZ stub PK\x03 ".to_vec();
+    stub.resize(1000, 0xAB);
+
+    for force_zip64 in [false, true] {
+        let mut archive = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut archive);
+        zip_writer.force_zip64(force_zip64);
+        for name in ["a.txt", "b.txt"] {
+            let opts = EntryOptions::new(name.to_string(), Compression::Deflate);
+            zip_writer.write_entry_whole(opts, name.repeat(64).as_bytes()).await.expect("failed to write entry");
+        }
+        zip_writer.close().await.expect("failed to close writer");
+        let archive = [stub.as_slice(), &archive.into_inner()].concat();
+
+        let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+        assert_eq!(Some(stub.len() as u64), zip_reader.entries()[0].offset);
+        let data = zip_reader.entry_reader(1).await.unwrap().read_to_string_crc().await.expect("failed to read entry");
+        assert_eq!("b.txt".repeat(64), data);
+
+        // Entries appended to the archive are also written with offsets relative to the start of the archive itself.
+        let mut appended = Cursor::new(archive.clone());
+        let mut zip_writer = ZipFileWriter::append(&mut appended).await.expect("failed to append");
+        let opts = EntryOptions::new("c.txt".to_string(), Compression::Stored);
+        zip_writer.write_entry_whole(opts, b"appended").await.expect("failed to write entry");
+        zip_writer.close().await.expect("failed to close writer");
+
+        let appended = appended.into_inner();
+        let mut zip_reader = mem::ZipFileReader::new(&appended).await.expect("failed to open appended archive");
+        let data = zip_reader.entry_reader(2).await.unwrap().read_to_string_crc().await.expect("failed to read entry");
+        assert_eq!("appended", data);
+
+        let mut zip_reader = stream::ZipFileReader::new(archive.as_slice());
+        assert!(zip_reader.entry_reader().await.is_err());
+
+        let options = ReaderOptions::new().skip_prepended_data(true);
+        let mut zip_reader = stream::ZipFileReader::with_options(archive.as_slice(), options);
+        for name in ["a.txt", "b.txt"] {
+            let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+            assert_eq!(name.repeat(64), entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+        }
+        assert!(zip_reader.entry_reader().await.expect("failed to finish").is_none());
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn eocdh_discovery() {
+    use crate::read::mem;
+
+    // The first comment is as long as possible, and ends with a complete (but bogus) EOCDH which ends the archive.
+    let bogus = b"PK\x05\x06\x00\x00\x00\x00\x05\x00\x05\x00\x64\x00\x00\x00\x07\x00\x00\x00\x00\x00";
+    let mut long_comment = "c".repeat(u16::MAX as usize - bogus.len());
+    long_comment.push_str(std::str::from_utf8(bogus).unwrap());
+
+    // Data trailing an archive is skipped over, even if it holds the signature itself.
+    let trailing: &[u8] = b"trailing junk PK\x05\x06 and more junk";
+
+    for (comment, trailing) in [(long_comment.as_str(), &[][..]), ("Short comment.", trailing)] {
+        let mut archive = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut archive);
+        let opts = EntryOptions::new("foo.txt".to_string(), Compression::Deflate);
+        zip_writer.write_entry_whole(opts, b"Foo.").await.expect("failed to write entry");
+        zip_writer.close_with_comment(comment).await.expect("failed to close writer");
+        let archive = [archive.into_inner().as_slice(), trailing].concat();
+
+        let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+        assert_eq!(1, zip_reader.entries().len());
+        let data = zip_reader.entry_reader(0).await.unwrap().read_to_string_crc().await.expect("failed to read entry");
+        assert_eq!("Foo.", data);
+    }
+}
+
+#[tokio::test]
+async fn verify_archive() {
+    use crate::error::ZipError;
+    use crate::read::seek;
+
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        let opts = EntryOptions::new(name.to_string(), Compression::Stored);
+        zip_writer.write_entry_whole(opts, name.repeat(16).as_bytes()).await.expect("failed to write entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+    let mut archive = archive.into_inner();
+
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(archive.clone())).await.expect("failed to open");
+    let report = zip_reader.verify().await;
+    assert!(report.is_ok());
+    assert_eq!(vec![Some(80); 3], report.entries().iter().map(|entry| entry.size()).collect::<Vec<_>>());
+
+    // Corrupt the second entry's data.
+    let position = archive.windows(10).position(|window| window == b"b.txtb.txt").unwrap() + 5;
+    archive[position] = b'X';
+
+    let mut zip_reader = seek::ZipFileReader::new(Cursor::new(archive)).await.expect("failed to open");
+    let report = zip_reader.verify().await;
+    assert!(!report.is_ok());
+
+    let failures: Vec<_> = report.failures().collect();
+    assert_eq!((1, "b.txt"), (failures.len(), failures[0].name()));
+    assert_eq!(1, failures[0].index());
+    assert!(matches!(failures[0].error(), Some(ZipError::CrcMismatch { .. })));
+}
+
+/// Packs values into bytes from their least to most significant bit, as compressed data is read by most methods.
+#[cfg(any(feature = "deflate", feature = "legacy-compression"))]
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: usize,
+}
+
+#[cfg(any(feature = "deflate", feature = "legacy-compression"))]
+impl BitWriter {
+    fn put(&mut self, value: u32, count: usize) {
+        for bit in 0..count {
+            if self.bits.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            *self.bytes.last_mut().unwrap() |= (((value >> bit) & 1) as u8) << (self.bits % 8);
+            self.bits += 1;
+        }
+    }
+
+    /// Puts a Huffman code, whose bits are stored from most to least significant.
+    fn put_code(&mut self, code: u32, count: usize) {
+        self.put(code.reverse_bits() >> (32 - count), count);
+    }
+
+    fn put_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+        self.bits = self.bytes.len() * 8;
+    }
+}
+
+/// Writes an archive holding a single entry of already-compressed data, patching the compression method, its flag
+/// bits, and the CRC32 value and uncompressed size of the expected data into both of the entry's headers.
+async fn raw_method_archive(method: u16, options: u16, compressed: &[u8], expected: &[u8]) -> Vec<u8> {
+    let opts = EntryOptions::new("data.bin".to_string(), Compression::Stored);
+    let mut archive = write_archive([(opts, compressed)]).await;
+
+    let crc = crc32fast::hash(expected).to_le_bytes();
+    let size = (expected.len() as u32).to_le_bytes();
+    let cdh = archive.windows(4).position(|window| window == b"PK\x01\x02").unwrap();
+    for (offset, flags, crc_offset, size_offset) in [(0, 6, 14, 22), (cdh, 8, 16, 24)] {
+        archive[offset + flags] |= (options << 1) as u8;
+        archive[offset + flags + 2..offset + flags + 4].copy_from_slice(&method.to_le_bytes());
+        archive[offset + crc_offset..offset + crc_offset + 4].copy_from_slice(&crc);
+        archive[offset + size_offset..offset + size_offset + 4].copy_from_slice(&size);
+    }
+
+    archive
+}
+
+/// A reader which counts the bytes read from it.
+struct CountingReader<R> {
+    inner: R,
+    read: std::sync::Arc<std::sync::atomic::AtomicU64>,
+}
+
+impl<R> CountingReader<R> {
+    fn new(inner: R) -> (Self, std::sync::Arc<std::sync::atomic::AtomicU64>) {
+        let read = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+        (CountingReader { inner, read: read.clone() }, read)
+    }
+}
+
+impl<R: tokio::io::AsyncRead + Unpin> tokio::io::AsyncRead for CountingReader<R> {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        c: &mut std::task::Context<'_>,
+        b: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let prev_len = b.filled().len();
+        let poll = std::pin::Pin::new(&mut self.inner).poll_read(c, b);
+        self.read.fetch_add((b.filled().len() - prev_len) as u64, std::sync::atomic::Ordering::Relaxed);
+        poll
+    }
+}
+
+impl<R: tokio::io::AsyncSeek + Unpin> tokio::io::AsyncSeek for CountingReader<R> {
+    fn start_seek(mut self: std::pin::Pin<&mut Self>, position: std::io::SeekFrom) -> std::io::Result<()> {
+        std::pin::Pin::new(&mut self.inner).start_seek(position)
+    }
+
+    fn poll_complete(
+        mut self: std::pin::Pin<&mut Self>,
+        c: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<u64>> {
+        std::pin::Pin::new(&mut self.inner).poll_complete(c)
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn deflate64_decompression() {
+    use crate::error::ZipError;
+    use crate::read::{mem, stream};
+    use crate::write::entry_whole::compress_data;
+
+    let mut seed = 0x2545F491u32;
+    let mut random = move || {
+        seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        (seed >> 24) as u8
+    };
+
+    // Deflate data is valid Deflate64 data as long as it holds no matches of 258 bytes (which are unlikely here).
+    let deflate_expected: Vec<u8> = (0..100_000).map(|_| b"ACGT"[(random() % 4) as usize]).collect();
+    let opts = EntryOptions::new("data.bin", Compression::Deflate).resolve(&Default::default()).unwrap();
+    let deflate = compress_data(&opts, &deflate_expected, &Default::default()).await.unwrap();
+
+    // Two stored blocks, followed by a block with fixed codes using the lengths and distances unique to Deflate64.
+    let mut writer = BitWriter::default();
+    let mut deflate64_expected = Vec::new();
+    for _ in 0..2 {
+        let block: Vec<u8> = (0..60_000).map(|_| random()).collect();
+        writer.put(0, 3);
+        writer.put_bytes(&60_000u16.to_le_bytes());
+        writer.put_bytes(&(!60_000u16).to_le_bytes());
+        writer.put_bytes(&block);
+        deflate64_expected.extend_from_slice(&block);
+    }
+
+    writer.put(0b011, 3);
+    for (length, distance_code, distance_extra, distance) in [(100, 31, 0, 49153), (10, 30, 5, 32774)] {
+        writer.put_code(0xC5, 8);
+        writer.put(length - 3, 16);
+        writer.put_code(distance_code, 5);
+        writer.put(distance_extra, 14);
+
+        let start = deflate64_expected.len() - distance;
+        deflate64_expected.extend_from_within(start..start + length as usize);
+    }
+    writer.put_code(0x30 + b'a' as u32, 8);
+    writer.put_code(0xC5, 8);
+    writer.put(u16::MAX as u32, 16);
+    writer.put_code(0, 5);
+    writer.put_code(0, 7);
+    deflate64_expected.extend(std::iter::repeat_n(b'a', 1 + u16::MAX as usize + 3));
+
+    for (compressed, expected) in
+        [(deflate.as_ref(), &deflate_expected), (writer.bytes.as_slice(), &deflate64_expected)]
+    {
+        let archive = raw_method_archive(9, 0, compressed, expected).await;
+
+        let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+        assert_eq!(&Compression::Deflate64, zip_reader.entries()[0].compression());
+        let data = zip_reader.entry_reader(0).await.unwrap().read_to_end_crc().await.expect("failed to read entry");
+        assert!(data == *expected);
+
+        let mut zip_reader = stream::ZipFileReader::new(archive.as_slice());
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        assert!(entry_reader.read_to_end_crc().await.expect("failed to read entry") == *expected);
+    }
+
+    let mut zip_writer = ZipFileWriter::new(Cursor::new(Vec::<u8>::new()));
+    let opts = EntryOptions::new("data.bin".to_string(), Compression::Deflate64);
+    let result = zip_writer.write_entry_whole(opts, b"data").await;
+    assert!(matches!(result, Err(ZipError::FeatureNotSupported(_))));
+}
+
+#[cfg(feature = "legacy-compression")]
+#[tokio::test]
+async fn legacy_decompression() {
+    use crate::error::ZipError;
+    use crate::read::{mem, stream, ReaderOptions};
+    use tokio::io::AsyncReadExt;
+
+    // Shrink codes exercising the KwKwK case, a partial clear (after which a code's string is unknown until its prefix
+    // is reused), and an increase of the code size.
+    let mut shrink = BitWriter::default();
+    [65, 66, 257, 259, 256, 2, 257, 66, 258, 256, 1].iter().for_each(|code| shrink.put(*code, 9));
+    shrink.put(260, 10);
+    let shrink_expected = [b"ABABABA".as_slice(), b"AB", b"B", b"ABBA", b"BA"].concat();
+
+    // Implode data with a 4 KiB window and no literal tree, where each length and distance code is six bits long.
+    let mut implode = BitWriter::default();
+    let implode_expected = [b"abcabcabc".as_slice(), &[b'c'; 75]].concat();
+    for _ in 0..2 {
+        implode.put_bytes(&[3, 0xF5, 0xF5, 0xF5, 0xF5]);
+    }
+    for literal in b"abc" {
+        implode.put(1, 1);
+        implode.put(*literal as u32, 8);
+    }
+    for (length, distance) in [(6, 3), (75, 1)] {
+        implode.put(0, 1);
+        implode.put((distance - 1) & 0x3F, 6);
+        implode.put_code((distance - 1) >> 6, 6);
+        implode.put_code(std::cmp::min(length - 2, 63), 6);
+        if length - 2 >= 63 {
+            implode.put(length - 2 - 63, 8);
+        }
+    }
+
+    let cases = [
+        (1, Compression::Shrink, &shrink.bytes, &shrink_expected),
+        (6, Compression::Implode, &implode.bytes, &implode_expected),
+    ];
+    for (method, compression, compressed, expected) in cases {
+        let archive = raw_method_archive(method, 0, compressed, expected).await;
+
+        let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+        assert_eq!(&compression, zip_reader.entries()[0].compression());
+        let data = zip_reader.entry_reader(0).await.unwrap().read_to_end_crc().await.expect("failed to read entry");
+        assert_eq!(*expected, data);
+
+        let mut zip_reader = stream::ZipFileReader::new(archive.as_slice());
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        assert_eq!(*expected, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+
+        // Entries declared larger than the maximum size are refused before any of their data is buffered.
+        let options = ReaderOptions::new().max_entry_size(expected.len() as u64 - 1);
+        let mut zip_reader = mem::ZipFileReader::with_options(&archive, options).await.expect("failed to open");
+        let mut entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry");
+        let error = entry_reader.read_to_end(&mut Vec::new()).await.expect_err("entry was read");
+        match *error.into_inner().unwrap().downcast::<ZipError>().unwrap() {
+            ZipError::DecompressionLimitExceeded(limit, _) => assert_eq!("entry's declared size", limit),
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+}
+
+#[cfg(feature = "ppmd")]
+#[tokio::test]
+async fn ppmd_decompression() {
+    use crate::error::ZipError;
+    use crate::read::{mem, stream, ReaderOptions};
+    use ppmd_rust::{Ppmd8Encoder, RestoreMethod};
+    use std::io::Write;
+    use tokio::io::AsyncReadExt;
+
+    // Enough data (which compresses poorly) that it's decoded as it's read, rather than once it has all been buffered.
+    let mut seed = 0x2545F491u32;
+    let expected: Vec<u8> = (0..65_536)
+        .flat_map(|_| {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            format!("{:08x}\n", seed).into_bytes()
+        })
+        .collect();
+
+    // An order of six with 16 MiB of memory and the restart method, written without an end marker (as with 7-Zip).
+    let (order, memory) = (6u16, 16u16);
+    let header = ((order - 1) | (memory - 1) << 4).to_le_bytes();
+    let mut encoder = Ppmd8Encoder::new(header.to_vec(), order as u32, (memory as u32) << 20, RestoreMethod::Restart)
+        .expect("failed to construct encoder");
+    encoder.write_all(&expected).expect("failed to encode");
+    let compressed = encoder.finish(false).expect("failed to finish encoding");
+
+    let archive = raw_method_archive(98, 0, &compressed, &expected).await;
+
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    assert_eq!(&Compression::Ppmd, zip_reader.entries()[0].compression());
+    let data = zip_reader.entry_reader(0).await.unwrap().read_to_end_crc().await.expect("failed to read entry");
+    assert_eq!(expected, data);
+
+    let mut zip_reader = stream::ZipFileReader::new(archive.as_slice());
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    assert_eq!(expected, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+
+    // Data is decoded as it's read, rather than once all of it has been.
+    let (reader, read) = CountingReader::new(archive.as_slice());
+    let mut zip_reader = stream::ZipFileReader::new(reader);
+    let mut entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+    let mut start = [0; 64];
+    entry_reader.read_exact(&mut start).await.expect("failed to read entry");
+    assert_eq!(expected[..64], start);
+    assert!(read.load(std::sync::atomic::Ordering::Relaxed) < compressed.len() as u64 / 2);
+
+    // The model's memory size is checked before it's allocated.
+    let options = ReaderOptions::new().max_decoder_memory(8 << 20);
+    let mut zip_reader = mem::ZipFileReader::with_options(&archive, options).await.expect("failed to open");
+    let mut entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry");
+    let error = entry_reader.read_to_end(&mut Vec::new()).await.expect_err("entry was read");
+    match *error.into_inner().unwrap().downcast::<ZipError>().unwrap() {
+        ZipError::DecompressionLimitExceeded(limit, _) => assert_eq!("PPMd model's memory size", limit),
+        error => panic!("unexpected error: {:?}", error),
+    }
+}
+
+#[tokio::test]
+async fn custom_compression_backend() {
+    use crate::backend::{BackendReader, BackendWriter, CompressionBackend};
+    use crate::error::{Result, ZipError};
+    use crate::read::{mem, stream, ReaderOptions};
+    use crate::write::CompressionLevel;
+    use async_compression::tokio::{bufread::DeflateDecoder, write::DeflateEncoder};
+    use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+
+    /// Deflate, under a method id without built-in support.
+    struct Custom;
+
+    impl CompressionBackend for Custom {
+        fn method(&self) -> u16 {
+            200
+        }
+
+        fn decoder(&self, reader: BackendReader) -> Result<Box<dyn AsyncRead + Unpin + Send>> {
+            Ok(Box::new(DeflateDecoder::new(reader)))
+        }
+
+        fn encoder(&self, writer: BackendWriter, _: CompressionLevel) -> Result<Box<dyn AsyncWrite + Unpin + Send>> {
+            Ok(Box::new(DeflateEncoder::new(writer)))
+        }
+    }
+
+    let expected = "Compressed by a user-supplied backend. ".repeat(256).into_bytes();
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+
+    let opts = || EntryOptions::new("whole.txt".to_string(), Compression::Custom(200));
+    let error = zip_writer.write_entry_whole(opts(), &expected).await.unwrap_err();
+    assert!(matches!(error, ZipError::UnsupportedCompressionError(200)));
+
+    zip_writer.compression_backend(Custom);
+    zip_writer.write_entry_whole(opts(), &expected).await.expect("failed to write entry");
+    let opts = EntryOptions::new("stream.txt".to_string(), Compression::Custom(200));
+    let mut entry_writer = zip_writer.write_entry_stream(opts).await.expect("failed to open entry");
+    entry_writer.write_all(&expected).await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = archive.into_inner();
+
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    assert_eq!(&Compression::Custom(200), zip_reader.entries()[0].compression());
+    assert!(matches!(zip_reader.entry_reader(0).await, Err(ZipError::UnsupportedCompressionError(200))));
+
+    let options = ReaderOptions::new().compression_backend(Custom);
+    let mut zip_reader = mem::ZipFileReader::with_options(&archive, options.clone()).await.expect("failed to open");
+    for index in 0..2 {
+        let data = zip_reader.entry_reader(index).await.unwrap().read_to_end_crc().await.expect("failed to read entry");
+        assert_eq!(expected, data);
+    }
+
+    // The streamed entry's data is followed by a data descriptor, which is read once the backend's decoder ends.
+    let mut zip_reader = stream::ZipFileReader::with_options(archive.as_slice(), options);
+    for name in ["whole.txt", "stream.txt"] {
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        assert_eq!(name, entry_reader.entry().name());
+        assert_eq!(expected, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    }
+    assert!(zip_reader.entry_reader().await.expect("failed to read").is_none());
+}
+
+#[cfg(not(feature = "bzip2"))]
+#[tokio::test]
+async fn disabled_compression_method() {
+    use crate::error::ZipError;
+    use crate::read::{mem, stream};
+
+    let mut zip_writer = ZipFileWriter::new(Cursor::new(Vec::<u8>::new()));
+    let opts = EntryOptions::new("whole.txt".to_string(), Compression::Bz);
+    let error = zip_writer.write_entry_whole(opts, b"data").await.unwrap_err();
+    assert!(matches!(error, ZipError::UnsupportedCompressionError(12)));
+    let opts = EntryOptions::new("stream.txt".to_string(), Compression::Bz);
+    assert!(matches!(zip_writer.write_entry_stream(opts).await, Err(ZipError::UnsupportedCompressionError(12))));
+
+    let archive = raw_method_archive(12, 0, b"data", b"data").await;
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    assert_eq!(&Compression::Bz, zip_reader.entries()[0].compression());
+    assert!(matches!(zip_reader.entry_reader(0).await, Err(ZipError::UnsupportedCompressionError(12))));
+
+    let mut zip_reader = stream::ZipFileReader::new(archive.as_slice());
+    assert!(matches!(zip_reader.entry_reader().await, Err(ZipError::UnsupportedCompressionError(12))));
+}
+
+#[cfg(feature = "zstd")]
+#[tokio::test]
+async fn zstd_dictionary() {
+    use crate::read::{mem, stream, ReaderOptions};
+    use tokio::io::AsyncWriteExt;
+
+    let dictionary = "{\"level\": \"info\", \"service\": \"archive\", \"message\": \"entry written\"}\n".repeat(16);
+    let expected = b"{\"level\": \"info\", \"service\": \"archive\", \"message\": \"entry read\"}\n".to_vec();
+
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    let opts = EntryOptions::new("plain.json", Compression::Zstd);
+    zip_writer.write_entry_whole(opts, &expected).await.expect("failed to write entry");
+    let opts = EntryOptions::new("whole.json", Compression::Zstd).zstd_dictionary(dictionary.as_bytes());
+    zip_writer.write_entry_whole(opts, &expected).await.expect("failed to write entry");
+    let opts = EntryOptions::new("stream.json", Compression::Zstd).zstd_dictionary(dictionary.as_bytes());
+    let mut entry_writer = zip_writer.write_entry_stream(opts).await.expect("failed to open entry");
+    entry_writer.write_all(&expected).await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = archive.into_inner();
+
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let entries = zip_reader.entries();
+    assert!(entries[1].compressed_size().unwrap() < entries[0].compressed_size().unwrap());
+    assert!(zip_reader.entry_reader(1).await.unwrap().read_to_end_crc().await.is_err());
+
+    // Entries compressed without a dictionary are still read as before.
+    let options = ReaderOptions::new().zstd_dictionary(dictionary.as_bytes());
+    let mut zip_reader = mem::ZipFileReader::with_options(&archive, options.clone()).await.expect("failed to open");
+    for index in 0..3 {
+        let data = zip_reader.entry_reader(index).await.unwrap().read_to_end_crc().await.expect("failed to read entry");
+        assert_eq!(expected, data);
+    }
+
+    let mut zip_reader = stream::ZipFileReader::with_options(archive.as_slice(), options);
+    for name in ["plain.json", "whole.json", "stream.json"] {
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        assert_eq!(name, entry_reader.entry().name());
+        assert_eq!(expected, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[cfg(feature = "lzma")]
+#[tokio::test]
+async fn lzma_zip_framing() {
+    use crate::read::{mem, stream};
+    use async_compression::tokio::write::LzmaEncoder;
+    use tokio::io::AsyncWriteExt;
+
+    let expected = "LZMA entries carry their properties within their data. ".repeat(64).into_bytes();
+
+    // As written by 7-Zip: the SDK version, the properties' length, and the properties, in place of a `.lzma` header.
+    let mut encoder = LzmaEncoder::new(Vec::new());
+    encoder.write_all(&expected).await.expect("failed to encode");
+    encoder.shutdown().await.expect("failed to finish encoding");
+    let lzma = encoder.into_inner();
+    let mut compressed = vec![16, 2, 5, 0];
+    compressed.extend_from_slice(&lzma[..5]);
+    compressed.extend_from_slice(&lzma[13..]);
+
+    let archive = raw_method_archive(14, 1, &compressed, &expected).await;
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let data = zip_reader.entry_reader(0).await.unwrap().read_to_end_crc().await.expect("failed to read entry");
+    assert_eq!(expected, data);
+
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    zip_writer.write_entry_whole(EntryOptions::new("whole.txt", Compression::Lzma), &expected).await.unwrap();
+    let mut entry_writer =
+        zip_writer.write_entry_stream(EntryOptions::new("stream.txt", Compression::Lzma)).await.unwrap();
+    entry_writer.write_all(&expected).await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+    let archive = archive.into_inner();
+
+    // Both entries are flagged as ending with an end marker, and their data starts with the ZIP LZMA header.
+    let data_start = 30 + "whole.txt".len();
+    assert_eq!(0b10, archive[6] & 0b110);
+    assert_eq!([9, 20, 5, 0], archive[data_start..data_start + 4]);
+    assert_eq!(lzma[..5], archive[data_start + 4..data_start + 9]);
+
+    let mut zip_reader = stream::ZipFileReader::new(archive.as_slice());
+    for name in ["whole.txt", "stream.txt"] {
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        assert_eq!(name, entry_reader.entry().name());
+        assert_eq!(0b01, entry_reader.entry().compression_options);
+        assert_eq!(expected, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn store_if_larger() {
+    use crate::read::mem;
+
+    let mut seed = 0x9E3779B9u32;
+    let random: Vec<u8> = (0..4096)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as u8
+        })
+        .collect();
+    let text = "Compression helps this entry. ".repeat(64).into_bytes();
+
+    for store in [false, true] {
+        let mut archive = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new(&mut archive);
+        zip_writer.store_if_larger(store);
+        zip_writer.write_entry_whole(EntryOptions::new("random.bin", Compression::Deflate), &random).await.unwrap();
+        zip_writer.write_entry_whole(EntryOptions::new("text.txt", Compression::Deflate), &text).await.unwrap();
+        zip_writer.close().await.expect("failed to close writer");
+        let archive = archive.into_inner();
+
+        let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+        let compressions: Vec<_> = zip_reader.entries().iter().map(|entry| *entry.compression()).collect();
+        let random_compression = if store { Compression::Stored } else { Compression::Deflate };
+        assert_eq!(vec![random_compression, Compression::Deflate], compressions);
+        assert_eq!(!store, zip_reader.entries()[0].compressed_size().unwrap() > random.len() as u64);
+
+        for (index, expected) in [&random, &text].into_iter().enumerate() {
+            let data = zip_reader.entry_reader(index).await.unwrap().read_to_end_crc().await.unwrap();
+            assert_eq!(*expected, data);
+        }
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn compression_policy_defaults() {
+    use crate::fs::DirOptions;
+    use crate::read::mem;
+    use crate::write::{CompressionPolicy, EntryDefaults};
+
+    let dir = TestDir::new("compression_policy");
+    tokio::fs::write(dir.join("photo.jpg"), [0xFF, 0xD8, 0xFF]).await.expect("failed to write file");
+    tokio::fs::write(dir.join("notes.txt"), "Notes.").await.expect("failed to write file");
+
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    let policy = CompressionPolicy::store_compressed(Compression::Deflate);
+    zip_writer.default_options(EntryDefaults { compression_policy: Some(policy), ..Default::default() });
+
+    zip_writer.write_entry_whole(EntryOptions::named("image.PNG".to_string()), b"PNG").await.unwrap();
+    zip_writer
+        .write_entry_whole(EntryOptions::new("forced.png".to_string(), Compression::Deflate), b"PNG")
+        .await
+        .unwrap();
+    let manifest =
+        crate::fs::write_dir(&mut zip_writer, &dir, DirOptions::new()).await.expect("failed to write directory");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let expected = [("notes.txt", Compression::Deflate), ("photo.jpg", Compression::Stored)];
+    assert_eq!(expected.map(|(name, compression)| (name.to_string(), compression)).to_vec(), manifest);
+
+    let archive = archive.into_inner();
+    let zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let entries: Vec<_> =
+        zip_reader.entries().iter().map(|entry| (entry.name().to_string(), *entry.compression())).collect();
+    let expected = [
+        ("image.PNG", Compression::Stored),
+        ("forced.png", Compression::Deflate),
+        ("notes.txt", Compression::Deflate),
+        ("photo.jpg", Compression::Stored),
+    ];
+    assert_eq!(expected.map(|(name, compression)| (name.to_string(), compression)).to_vec(), entries);
+}
+
+#[cfg(all(feature = "parallel", feature = "deflate"))]
+#[tokio::test(flavor = "multi_thread")]
+async fn write_entries_parallel() {
+    use crate::read::mem;
+
+    let entries: Vec<_> = (0..16)
+        .map(|index| {
+            let compression = if index % 4 == 0 { Compression::Stored } else { Compression::Deflate };
+            let data = format!("Entry number {}. ", index).repeat(index * 64 + 1).into_bytes();
+            (format!("entry{}.txt", index), compression, data)
+        })
+        .collect();
+
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    let jobs =
+        entries.iter().map(|(name, compression, data)| (EntryOptions::new(name.clone(), *compression), data.clone()));
+    zip_writer.write_entries_parallel(jobs, 4).await.expect("failed to write entries");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = archive.into_inner();
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    assert_eq!(entries.len(), zip_reader.entries().len());
+
+    for (index, (name, compression, data)) in entries.iter().enumerate() {
+        assert_eq!(
+            (name.as_str(), compression),
+            (zip_reader.entries()[index].name(), zip_reader.entries()[index].compression())
+        );
+        let entry_data = zip_reader.entry_reader(index).await.unwrap().read_to_end_crc().await.unwrap();
+        assert_eq!(*data, entry_data);
+    }
+}
+
+#[cfg(all(feature = "parallel", feature = "deflate", feature = "zstd"))]
+#[tokio::test]
+async fn offload_compression() {
+    use crate::backend::{BackendReader, BackendWriter, CompressionBackend};
+    use crate::read::mem;
+    use crate::write::CompressionLevel;
+    use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+
+    /// A backend whose encoder fails on its first write.
+    struct Failing;
+
+    impl CompressionBackend for Failing {
+        fn method(&self) -> u16 {
+            200
+        }
+
+        fn decoder(&self, reader: BackendReader) -> crate::error::Result<Box<dyn AsyncRead + Unpin + Send>> {
+            Ok(Box::new(reader))
+        }
+
+        fn encoder(
+            &self,
+            _: BackendWriter,
+            _: CompressionLevel,
+        ) -> crate::error::Result<Box<dyn AsyncWrite + Unpin + Send>> {
+            Ok(Box::new(FailingWriter { remaining: 0 }))
+        }
+    }
+
+    let data: Vec<u8> = (0..1024 * 1024u32).map(|index| (index % 251) as u8 ^ (index / 4096) as u8).collect();
+
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    zip_writer.offload_compression(true);
+
+    for compression in [Compression::Deflate, Compression::Stored, Compression::Zstd] {
+        let options = EntryOptions::new(format!("{:?}.bin", compression), compression);
+        let mut entry_writer = zip_writer.write_entry_stream(options).await.expect("failed to open entry");
+        for chunk in data.chunks(100_000) {
+            entry_writer.write_all(chunk).await.expect("failed to write entry");
+            entry_writer.flush().await.expect("failed to flush entry");
+        }
+        entry_writer.close().await.expect("failed to close entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = archive.into_inner();
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    for index in 0..3 {
+        let entry_data = zip_reader.entry_reader(index).await.unwrap().read_to_end_crc().await.unwrap();
+        assert_eq!(data, entry_data);
+    }
+    assert!(zip_reader.entries()[0].compressed_size().unwrap() < data.len() as u64);
+
+    // Once the encoder has failed, every later call returns its error rather than closing the entry.
+    let mut zip_writer = ZipFileWriter::new(Cursor::new(Vec::<u8>::new()));
+    zip_writer.offload_compression(true);
+    zip_writer.compression_backend(Failing);
+    let options = EntryOptions::new("failing.bin".to_string(), Compression::Custom(200));
+    let mut entry_writer = zip_writer.write_entry_stream(options).await.expect("failed to open entry");
+    entry_writer.write_all(&data[..1024]).await.expect("failed to write entry");
+    assert!(entry_writer.shutdown().await.is_err());
+    assert!(entry_writer.write_all(&data[..1024]).await.is_err());
+    assert!(entry_writer.close().await.is_err());
+}
+
+#[cfg(all(feature = "parallel", feature = "deflate"))]
+#[tokio::test(flavor = "multi_thread")]
+async fn parallel_deflate() {
+    use crate::read::mem;
+    use tokio::io::AsyncWriteExt;
+
+    let data: Vec<u8> = (0..1_500_000u32).map(|index| (index % 251) as u8 ^ (index / 4096) as u8).collect();
+
+    let mut archive = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    let options = EntryOptions::new("whole.bin".to_string(), Compression::Deflate).parallel(4);
+    zip_writer.write_entry_whole(options, &data).await.expect("failed to write entry");
+
+    let options = EntryOptions::new("stream.bin".to_string(), Compression::Deflate).parallel(4);
+    let mut entry_writer = zip_writer.write_entry_stream(options).await.expect("failed to open entry");
+    for chunk in data.chunks(100_000) {
+        entry_writer.write_all(chunk).await.expect("failed to write entry");
+    }
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = archive.into_inner();
+    let mut zip_reader = mem::ZipFileReader::new(&archive).await.expect("failed to open");
+    let (whole, stream) = (&zip_reader.entries()[0], &zip_reader.entries()[1]);
+    assert_eq!(whole.compressed_size(), stream.compressed_size());
+    assert!(whole.compressed_size().unwrap() < data.len() as u64 / 2);
+
+    for index in 0..2 {
+        let entry_data = zip_reader.entry_reader(index).await.unwrap().read_to_end_crc().await.unwrap();
+        assert_eq!(data, entry_data);
+    }
+}
+
+/// A writer which supports vectored writes, accepting up to a fixed number of bytes per call and counting the calls.
+struct VectoredWriter {
+    data: Vec<u8>,
+    limit: usize,
+    calls: usize,
+}
+
+impl tokio::io::AsyncWrite for VectoredWriter {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.poll_write_vectored(cx, &[std::io::IoSlice::new(buf)])
+    }
+
+    fn poll_write_vectored(
+        mut self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.calls += 1;
+
+        let mut written = 0;
+        for buf in bufs {
+            let length = std::cmp::min(self.limit - written, buf.len());
+            self.data.extend_from_slice(&buf[..length]);
+            written += length;
+        }
+        std::task::Poll::Ready(Ok(written))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn vectored_writes() {
+    use crate::read::mem;
+    use std::io::IoSlice;
+
+    async fn write_archive(limit: usize) -> VectoredWriter {
+        let mut writer = VectoredWriter { data: Vec::new(), limit, calls: 0 };
+        let mut zip_writer = ZipFileWriter::new(&mut writer);
+
+        let options = EntryOptions::new("whole.txt".to_string(), Compression::Stored);
+        zip_writer.write_entry_whole(options, b"hello world").await.expect("failed to write entry");
+
+        let options = EntryOptions::new("stream.txt".to_string(), Compression::Stored);
+        let mut entry_writer = zip_writer.write_entry_stream(options).await.expect("failed to open entry");
+        let data = &mut [IoSlice::new(b"hello "), IoSlice::new(b""), IoSlice::new(b"world")];
+        crate::utils::write_all_vectored(&mut entry_writer, data).await.expect("failed to write entry");
+        entry_writer.close().await.expect("failed to close entry");
+
+        zip_writer.close().await.expect("failed to close writer");
+        writer
+    }
+
+    // Each record (and the whole entry's data alongside its header) reaches the writer in a single call.
+    let writer = write_archive(usize::MAX).await;
+    assert_eq!(7, writer.calls);
+
+    let limited = write_archive(7).await;
+    assert_eq!(writer.data, limited.data);
+
+    let mut zip_reader = mem::ZipFileReader::new(&writer.data).await.expect("failed to open");
+    for index in 0..2 {
+        let entry_data = zip_reader.entry_reader(index).await.unwrap().read_to_end_crc().await.unwrap();
+        assert_eq!(b"hello world".to_vec(), entry_data);
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn entry_reader_buf_read() {
+    use crate::read::{mem::ZipFileReader, ReaderOptions};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+    let lines: Vec<String> = (0..500).map(|index| format!("line {index}")).collect();
+    let data = lines.join("\n");
+
+    let open_opts = EntryOptions::new("lines.txt".to_string(), Compression::Deflate);
+    let archive = write_archive([(open_opts, data.as_bytes())]).await;
+
+    let options = ReaderOptions::new().decoder_buffer_size(16);
+    let mut zip_reader = ZipFileReader::with_options(&archive, options).await.expect("failed to open reader");
+
+    let mut entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    let mut read_lines = Vec::new();
+    let mut line_reader = (&mut entry_reader).lines();
+    while let Some(line) = line_reader.next_line().await.expect("failed to read line") {
+        read_lines.push(line);
+    }
+    assert_eq!(lines, read_lines);
+    assert!(entry_reader.compare_crc());
+
+    // Buffered and unbuffered reads may be mixed without losing or repeating data.
+    let mut entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry reader");
+    assert_eq!(b"line 0\nline 1\nli", entry_reader.fill_buf().await.expect("failed to fill buffer"));
+    entry_reader.consume(7);
+
+    let mut rest = Vec::new();
+    entry_reader.read_to_end(&mut rest).await.expect("failed to read entry");
+    assert_eq!(data.as_bytes()[7..], rest[..]);
+    assert!(entry_reader.compare_crc());
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn stream_skip_descriptor_entries() {
+    use crate::read::stream;
+    use tokio::io::AsyncWriteExt;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    for index in 0..16 {
+        let open_opts = EntryOptions::new(format!("{}.txt", index), Compression::Deflate);
+        let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+        entry_writer.write_all(index.to_string().repeat(4096).as_bytes()).await.expect("failed to write entry");
+        entry_writer.close().await.expect("failed to close entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+    let mut data = input_stream.into_inner();
+
+    // Skipped entries are never decoded, so the corrupt data of the first doesn't prevent it from being skipped.
+    let data_start = |data: &[u8], name: &str| {
+        let offset = data.windows(name.len()).position(|window| window == name.as_bytes()).unwrap();
+        offset + name.len() + u16::from_le_bytes([data[offset - 2], data[offset - 1]]) as usize
+    };
+    let start = data_start(&data, "0.txt");
+    data[start..start + 8].fill(0xFF);
+
+    let mut zip_reader = stream::ZipFileReader::new(data.as_slice());
+    for index in 0..16 {
+        let entry = zip_reader.skip_entry().await.expect("failed to skip").expect("no entry");
+        assert_eq!(format!("{}.txt", index), entry.name());
+    }
+    assert_eq!(data_start(&data, "15.txt") as u64, zip_reader.bytes_consumed());
+    assert!(zip_reader.skip_entry().await.expect("failed to skip").is_none());
+
+    let mut zip_reader = stream::ZipFileReader::new(data.as_slice());
+    let entry_reader = zip_reader.skip_to("15.txt").await.expect("failed to skip").expect("no '15.txt' entry");
+    assert_eq!("15".repeat(4096), entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn stream_skip_entry() {
+    use crate::read::stream::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    for name in ["a.txt", "b.txt"] {
+        let open_opts = EntryOptions::new(name.to_string(), Compression::Deflate);
+        zip_writer.write_entry_whole(open_opts, name.repeat(32).as_bytes()).await.expect("failed to write entry");
+    }
+    let open_opts = EntryOptions::new("c.txt".to_string(), Compression::Deflate);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+    entry_writer.write_all("c.txt".repeat(32).as_bytes()).await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    let open_opts = EntryOptions::new("d.txt".to_string(), Compression::Deflate);
+    zip_writer.write_entry_whole(open_opts, "d.txt".repeat(32).as_bytes()).await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream);
+
+    let entry = zip_reader.skip_entry().await.expect("failed to skip entry").expect("no 'a.txt' entry");
+    assert_eq!(("a.txt", Some(160)), (entry.name(), entry.uncompressed_size()));
+
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no 'b.txt' entry");
+    assert_eq!("b.txt", entry_reader.entry().name());
+
+    // The unread data of 'b.txt' is discarded, and 'c.txt' (followed by a data descriptor) is read through.
+    let entry = zip_reader.skip_entry().await.expect("failed to skip entry").expect("no 'c.txt' entry");
+    assert_eq!("c.txt", entry.name());
+
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no 'd.txt' entry");
+    assert_eq!("d.txt".repeat(32), entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+
+    assert!(zip_reader.skip_entry().await.expect("failed to skip entry").is_none());
+    assert!(zip_reader.finished());
+}
+
+#[cfg(all(feature = "stream", feature = "deflate"))]
+#[tokio::test]
+async fn entry_streams() {
+    use crate::error::ZipError;
+    use crate::read::{seek, stream, ReaderOptions};
+    use futures_util::{StreamExt, TryStreamExt};
+    use std::sync::Mutex;
+
+    let archive = write_archive(
+        ["a.txt", "b.txt", "c.txt"]
+            .map(|name| (EntryOptions::new(name.to_string(), Compression::Deflate), name.repeat(32))),
+    )
+    .await;
+
+    let read = Mutex::new(Vec::new());
+    let zip_reader = stream::ZipFileReader::new(Cursor::new(archive.clone()));
+    zip_reader
+        .into_stream()
+        .try_for_each_concurrent(2, |(entry, data)| {
+            read.lock().unwrap().push((entry.name().to_string(), data));
+            async { Ok(()) }
+        })
+        .await
+        .expect("failed to read entries");
+
+    let mut read = read.into_inner().unwrap();
+    read.sort();
+    let expected = ["a.txt", "b.txt", "c.txt"].map(|name| (name.to_string(), name.repeat(32).into_bytes()));
+    assert_eq!(expected.to_vec(), read);
+
+    let zip_reader = seek::ZipFileReader::new(Cursor::new(archive.clone())).await.expect("failed to open reader");
+    let names: Vec<_> = zip_reader.into_stream().map(|item| item.unwrap().0.name().to_string()).collect().await;
+    assert_eq!(["a.txt", "b.txt", "c.txt"].to_vec(), names);
+
+    // Entries larger than the maximum entry size are refused before their data is buffered.
+    let options = ReaderOptions::new().max_entry_size(100);
+    let zip_reader = stream::ZipFileReader::with_options(Cursor::new(archive), options);
+    let items: Vec<_> = zip_reader.into_stream().collect().await;
+    assert_eq!(1, items.len());
+    assert!(matches!(items[0], Err(ZipError::EntryTooLarge(160, 100))));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn read_entry_limited() {
+    use crate::error::ZipError;
+    use crate::read::stream::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    for (name, size) in [("a.txt", 16), ("b.txt", 4096)] {
+        let open_opts = EntryOptions::new(name.to_string(), Compression::Deflate);
+        zip_writer.write_entry_whole(open_opts, "a".repeat(size).as_bytes()).await.expect("failed to write entry");
+    }
+    let open_opts = EntryOptions::new("c.txt".to_string(), Compression::Deflate);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+    entry_writer.write_all("c".repeat(4096).as_bytes()).await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream);
+
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no 'a.txt' entry");
+    assert_eq!("a".repeat(16), entry_reader.read_to_string_limited(16).await.expect("failed to read entry"));
+
+    // The declared size is refused up front, and the stream reader still moves on to the next entry.
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no 'b.txt' entry");
+    assert!(matches!(entry_reader.read_to_end_limited(1024).await, Err(ZipError::EntryTooLarge(4096, 1024))));
+
+    // An entry followed by a data descriptor declares no size, so reading stops once the limit is passed.
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no 'c.txt' entry");
+    assert!(matches!(entry_reader.read_to_end_limited(1024).await, Err(ZipError::EntryTooLarge(1025, 1024))));
+}
+
+#[tokio::test]
+async fn entry_lookup_by_name() {
+    use crate::read::seek::ZipFileReader;
+
+    let archive = write_archive(
+        (0..100).map(|index| (EntryOptions::new(format!("dir/{index}.txt"), Compression::Stored), index.to_string())),
+    )
+    .await;
+
+    let mut input_stream = Cursor::new(archive);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+
+    assert_eq!(Some(42), zip_reader.entry_index("dir/42.txt"));
+    assert_eq!("dir/99.txt", zip_reader.entry_by_name("dir/99.txt").expect("no 'dir/99.txt' entry").name());
+    assert_eq!(None, zip_reader.entry_index("dir/100.txt"));
+    assert!(zip_reader.entry_by_name("42.txt").is_none());
+
+    assert_eq!(b"7".to_vec(), zip_reader.read_entry("dir/7.txt", None).await.expect("failed to read entry"));
+}
+
+#[tokio::test]
+async fn glob_entries_matching() {
+    use crate::error::ZipError;
+    use crate::glob::Glob;
+    use crate::read::mem::ZipFileReader;
+
+    let glob = Glob::new("assets/**/*.png").expect("failed to compile pattern");
+    assert!(glob.matches("assets/logo.png"));
+    assert!(glob.matches("assets/icons/small/logo.png"));
+    assert!(!glob.matches("assets/logo.jpg"));
+    assert!(!glob.matches("other/assets/logo.png"));
+
+    let glob = Glob::new("src/*.[ch]").expect("failed to compile pattern");
+    assert!(glob.matches("src/main.c") && glob.matches("src/main.h") && !glob.matches("src/main.rs"));
+    assert!(!glob.matches("src/nested/main.c"));
+
+    let glob = Glob::new("log-??[!a-z0-]/**").expect("failed to compile pattern");
+    assert!(glob.matches("log-01X/") && glob.matches("log-01X/today.txt"));
+    assert!(!glob.matches("log-01x/today.txt") && !glob.matches("log-01-/today.txt"));
+
+    assert!(matches!(Glob::new("assets/[a-z"), Err(ZipError::InvalidGlob(_))));
+
+    let names = ["assets/", "assets/a.png", "assets/b.txt", "assets/icons/c.png", "readme.png"];
+    let archive =
+        write_archive(names.map(|name| (EntryOptions::new(name.to_string(), Compression::Stored), b""))).await;
+
+    let zip_reader = ZipFileReader::new(&archive).await.expect("failed to open reader");
+    let matching: Vec<_> = zip_reader.entries_matching("assets/**/*.png").unwrap().map(|(index, _)| index).collect();
+    assert_eq!(vec![1, 3], matching);
+    let matching: Vec<_> = zip_reader.entries_matching("assets/*").unwrap().map(|(_, entry)| entry.name()).collect();
+    assert_eq!(vec!["assets/a.png", "assets/b.txt"], matching);
+}
+
+#[cfg(all(feature = "bytes", feature = "deflate"))]
+#[tokio::test]
+async fn bytes_reader() {
+    use crate::read::bytes::ZipFileReader;
+
+    fn assert_shareable<T: Clone + Send + Sync + 'static>(_: &T) {}
+
+    let archive = write_archive(
+        ["a.txt", "b.txt", "c.txt"]
+            .map(|name| (EntryOptions::new(name.to_string(), Compression::Deflate), name.repeat(32))),
+    )
+    .await;
+
+    let zip_reader = ZipFileReader::new(archive.into()).await.expect("failed to open reader");
+    assert_shareable(&zip_reader);
+
+    let mut handles = Vec::new();
+    for index in 0..zip_reader.entries().len() {
+        let zip_reader = zip_reader.clone();
+        handles.push(tokio::spawn(async move { zip_reader.entry_reader(index).await?.read_to_string_crc().await }));
+    }
+
+    for (handle, name) in handles.into_iter().zip(["a.txt", "b.txt", "c.txt"]) {
+        assert_eq!(name.repeat(32), handle.await.unwrap().expect("failed to read entry"));
+    }
+    assert_eq!(Some(1), zip_reader.entry_index("b.txt"));
+}
+
+#[cfg(all(feature = "mmap", feature = "deflate"))]
+#[tokio::test]
+async fn mmap_reader() {
+    use crate::read::seek::ZipFileReader;
+
+    let data = write_archive([
+        (EntryOptions::new("stored.txt".to_string(), Compression::Stored), b"Stored data".to_vec()),
+        (EntryOptions::new("deflated.txt".to_string(), Compression::Deflate), b"Deflated data".repeat(16)),
+    ])
+    .await;
+
+    let mut mmap = memmap2::MmapOptions::new().len(data.len()).map_anon().expect("failed to map memory");
+    mmap.copy_from_slice(&data);
+    let mmap = mmap.make_read_only().expect("failed to make mapping read-only");
+
+    let mut zip_reader = ZipFileReader::from_mmap(mmap).await.expect("failed to open reader");
+    assert_eq!(b"Stored data", zip_reader.entry_data(0).await.expect("failed to borrow entry data"));
+    assert!(matches!(zip_reader.entry_data(2).await, Err(crate::error::ZipError::EntryIndexOutOfBounds)));
+
+    let entry_reader = zip_reader.entry_reader(1).await.expect("failed to open entry");
+    assert_eq!(b"Deflated data".repeat(16), entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+}
+
+#[tokio::test]
+async fn random_access_reader() {
+    use crate::read::remote::{AsyncRandomAccess, RangeFuture};
+    use crate::read::seek::ZipFileReader;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// A source which counts the requests made of it and the bytes fetched.
+    struct Counted {
+        data: Arc<Vec<u8>>,
+        requests: Arc<AtomicUsize>,
+        fetched: Arc<AtomicUsize>,
+    }
+
+    impl AsyncRandomAccess for Counted {
+        fn size(&self) -> RangeFuture<u64> {
+            let size = self.data.len() as u64;
+            Box::pin(async move { Ok(size) })
+        }
+
+        fn read_at(&self, offset: u64, length: usize) -> RangeFuture<Vec<u8>> {
+            let (data, requests, fetched) = (self.data.clone(), self.requests.clone(), self.fetched.clone());
+            Box::pin(async move {
+                requests.fetch_add(1, Ordering::SeqCst);
+                fetched.fetch_add(length, Ordering::SeqCst);
+                Ok(data[offset as usize..offset as usize + length].to_vec())
+            })
+        }
+    }
+
+    let entry_data: Vec<Vec<u8>> = (0..4u8).map(|index| vec![index; 256 * 1024]).collect();
+    let archive = write_archive(
+        entry_data
+            .iter()
+            .enumerate()
+            .map(|(index, data)| (EntryOptions::new(format!("{}.bin", index), Compression::Stored), data)),
+    )
+    .await;
+
+    let data = Arc::new(archive);
+    let (requests, fetched) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+    let source = Counted { data: data.clone(), requests: requests.clone(), fetched: fetched.clone() };
+
+    let mut zip_reader = ZipFileReader::from_random_access(source).await.expect("failed to open reader");
+    assert_eq!(4, zip_reader.entries().len());
+    assert!(requests.load(Ordering::SeqCst) <= 3);
+    assert!(fetched.load(Ordering::SeqCst) < 128 * 1024);
+
+    let entry_reader = zip_reader.entry_reader(2).await.expect("failed to open entry");
+    assert_eq!(entry_data[2], entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    assert!(fetched.load(Ordering::SeqCst) < data.len() / 2);
+}
+
+#[cfg(all(feature = "object-store", feature = "deflate"))]
+#[tokio::test]
+async fn object_store_source() {
+    use crate::read::remote::ObjectStoreSource;
+    use crate::read::seek::ZipFileReader;
+    use object_store::{memory::InMemory, path::Path, ObjectStore};
+    use std::sync::Arc;
+
+    let archive = write_archive(
+        ["a.txt", "b.txt"].map(|name| (EntryOptions::new(name.to_string(), Compression::Deflate), name.repeat(64))),
+    )
+    .await;
+
+    let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+    let location = Path::from("archives/test.zip");
+    store.put(&location, archive.into()).await.expect("failed to put object");
+
+    let source = ObjectStoreSource::new(store, location);
+    let mut zip_reader = ZipFileReader::from_random_access(source).await.expect("failed to open reader");
+    let index = zip_reader.entry_index("b.txt").expect("entry not found");
+    let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+    assert_eq!("b.txt".repeat(64), entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+
+    let missing = ObjectStoreSource::new(Arc::new(InMemory::new()), Path::from("missing.zip"));
+    assert!(ZipFileReader::from_random_access(missing).await.is_err());
+}
+
+#[tokio::test]
+async fn read_buffering_options() {
+    use crate::read::remote::{AsyncRandomAccess, RandomAccessReader, RangeFuture};
+    use crate::read::{seek, stream, ReaderOptions};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::AsyncReadExt;
+
+    /// A reader which records the largest read requested of it.
+    struct Recorded {
+        inner: Cursor<Vec<u8>>,
+        largest: Arc<AtomicUsize>,
+    }
+
+    impl tokio::io::AsyncRead for Recorded {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            self.largest.fetch_max(buf.remaining(), Ordering::SeqCst);
+            std::pin::Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    /// A source which counts the requests made of it.
+    struct Counted {
+        data: Arc<Vec<u8>>,
+        requests: Arc<AtomicUsize>,
+    }
+
+    impl AsyncRandomAccess for Counted {
+        fn size(&self) -> RangeFuture<u64> {
+            let size = self.data.len() as u64;
+            Box::pin(async move { Ok(size) })
+        }
+
+        fn read_at(&self, offset: u64, length: usize) -> RangeFuture<Vec<u8>> {
+            let (data, requests) = (self.data.clone(), self.requests.clone());
+            Box::pin(async move {
+                requests.fetch_add(1, Ordering::SeqCst);
+                Ok(data[offset as usize..offset as usize + length].to_vec())
+            })
+        }
+    }
+
+    let data: Vec<u8> = (0..64 * 1024).map(|index| (index % 251) as u8).collect();
+    let open_opts = EntryOptions::new("data.bin".to_string(), Compression::Stored);
+    let archive = Arc::new(write_archive([(open_opts, &data)]).await);
+
+    // The stream reader never requests more than its read buffer's capacity at once.
+    let largest = Arc::new(AtomicUsize::new(0));
+    let source = Recorded { inner: Cursor::new(archive.to_vec()), largest: largest.clone() };
+    let mut zip_reader = stream::ZipFileReader::with_options(source, ReaderOptions::new().read_buffer_size(1024));
+    let entry_reader = zip_reader.entry_reader().await.expect("failed to read entry").expect("no entry");
+    assert_eq!(data, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    assert_eq!(1024, largest.load(Ordering::SeqCst));
+
+    // Reading ahead requests the ranges which follow the data read before they're needed.
+    let mut requests_after_first_byte = Vec::new();
+    for read_ahead in [0, 16 * 1024] {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let source = Counted { data: archive.clone(), requests: requests.clone() };
+        let source = RandomAccessReader::new(source).await.expect("failed to open source");
+        let source = source.range_size(4096).read_ahead(read_ahead);
+
+        let mut zip_reader = seek::ZipFileReader::new(source).await.expect("failed to open reader");
+        let mut entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry");
+        let mut first = [0; 1];
+        entry_reader.read_exact(&mut first).await.expect("failed to read entry");
+        requests_after_first_byte.push(requests.load(Ordering::SeqCst));
+
+        let mut remaining = Vec::new();
+        entry_reader.read_to_end(&mut remaining).await.expect("failed to read entry");
+        assert_eq!(data[1..], remaining[..]);
+    }
+    assert!(requests_after_first_byte[1] > requests_after_first_byte[0]);
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn output_buffering() {
+    use crate::read::seek::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    /// A writer which counts the writes made to it.
+    #[derive(Default)]
+    struct CountingWriter {
+        data: Vec<u8>,
+        writes: usize,
+    }
+
+    impl tokio::io::AsyncWrite for CountingWriter {
+        fn poll_write(
+            mut self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+            buf: &[u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            self.writes += 1;
+            self.data.extend_from_slice(buf);
+            std::task::Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(
+            self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(
+            self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    async fn write_archive(buffer_size: usize) -> CountingWriter {
+        let mut output = CountingWriter::default();
+        let mut zip_writer = ZipFileWriter::new(&mut output);
+        zip_writer.output_buffer_size(buffer_size);
+
+        for index in 0..50 {
+            let open_opts = EntryOptions::new(format!("whole-{}.txt", index), Compression::Deflate);
+            zip_writer.write_entry_whole(open_opts, b"Some small entry").await.expect("failed to write entry");
+        }
+        let open_opts = EntryOptions::new("stream.txt".to_string(), Compression::Deflate);
+        let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+        entry_writer.write_all(b"Some streamed entry").await.expect("failed to write entry");
+        entry_writer.close().await.expect("failed to close entry");
+        zip_writer.close().await.expect("failed to close writer");
+
+        output
+    }
+
+    let unbuffered = write_archive(0).await;
+    let buffered = write_archive(64 * 1024).await;
+    assert_eq!(unbuffered.data, buffered.data);
+
+    // Each entry is drained once written, and the central directory once the archive is closed.
+    assert!(unbuffered.writes > 200, "writes: {}", unbuffered.writes);
+    assert!(buffered.writes <= 53, "writes: {}", buffered.writes);
+
+    let zip_reader = ZipFileReader::new(Cursor::new(buffered.data)).await.expect("failed to open reader");
+    assert_eq!(51, zip_reader.entries().len());
+}
+
+#[cfg(all(feature = "uring", feature = "deflate", target_os = "linux"))]
+#[test]
+fn uring_round_trip() {
+    use crate::read::seek::ZipFileReader;
+    use crate::uring::{UringReader, UringWriter};
+    use tokio::io::AsyncWriteExt;
+
+    let dir = TestDir::new("uring");
+    let path = dir.join("archive.zip");
+    let data: Vec<u8> = (0..300 * 1024).map(|index| (index % 251) as u8).collect();
+
+    tokio_uring::start(async {
+        let mut output = UringWriter::create(&path).await.expect("failed to create archive").block_size(4096);
+        let mut zip_writer = ZipFileWriter::new(&mut output);
+        let open_opts = EntryOptions::new("stored.bin".to_string(), Compression::Stored);
+        zip_writer.write_entry_whole(open_opts, &data).await.expect("failed to write entry");
+        let open_opts = EntryOptions::new("deflated.txt".to_string(), Compression::Deflate);
+        let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+        entry_writer.write_all(&b"Some deflated data".repeat(512)).await.expect("failed to write entry");
+        entry_writer.close().await.expect("failed to close entry");
+        zip_writer.close().await.expect("failed to close writer");
+
+        let reader = UringReader::open(&path).expect("failed to open archive").block_size(4096);
+        let mut zip_reader = ZipFileReader::new(reader).await.expect("failed to open reader");
+
+        let entry_reader = zip_reader.entry_reader(1).await.expect("failed to open entry");
+        let deflated = entry_reader.read_to_end_crc().await.expect("failed to read entry");
+        assert_eq!(b"Some deflated data".repeat(512), deflated);
+
+        let stored = zip_reader.entry_data_owned(0, Vec::new()).await.expect("failed to read entry data");
+        assert_eq!(data, stored);
+    });
+}
+
+#[cfg(all(feature = "stream", feature = "deflate"))]
+#[tokio::test]
+async fn entry_byte_stream() {
+    use crate::read::seek::ZipFileReader;
+    use futures_util::TryStreamExt;
+
+    let data: Vec<u8> = (0..5000).map(|index| (index % 7) as u8).collect();
+    let open_opts = EntryOptions::new("data.bin".to_string(), Compression::Deflate);
+    let archive = write_archive([(open_opts, &data)]).await;
+
+    // Owned entry readers yield streams which may be moved into spawned tasks (eg. as response bodies).
+    let zip_reader = ZipFileReader::new(Cursor::new(archive)).await.expect("failed to open reader");
+    let entry_reader = zip_reader.into_entry_reader(0).await.expect("failed to open entry");
+    let stream = entry_reader.into_byte_stream(1000);
+    let chunks: Vec<bytes::Bytes> = tokio::spawn(stream.try_collect()).await.unwrap().expect("failed to read entry");
+
+    assert!(chunks.iter().all(|chunk| !chunk.is_empty() && chunk.len() <= 1000));
+    assert_eq!(data, chunks.concat());
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn streaming_writer() {
+    use crate::read::seek::ZipFileReader;
+    use futures_util::StreamExt;
+
+    let data: Vec<u8> = (0..256 * 1024).map(|index| (index % 251) as u8).collect();
+    let (mut zip_writer, mut stream) = ZipFileWriter::new_streaming(4096);
+
+    let expected = data.clone();
+    let handle = tokio::spawn(async move {
+        for name in ["a.bin", "b.bin"] {
+            let open_opts = EntryOptions::new(name.to_string(), Compression::Stored);
+            zip_writer.write_entry_whole(open_opts, &expected).await?;
+        }
+        zip_writer.close().await.map(drop)
+    });
+
+    // Chunks never exceed the capacity held between the writer and the stream.
+    let mut archive = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.expect("failed to read chunk");
+        assert!(chunk.len() <= 4096);
+        archive.extend_from_slice(&chunk);
+    }
+    handle.await.unwrap().expect("failed to write archive");
+
+    let mut zip_reader = ZipFileReader::new(Cursor::new(archive)).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(1).await.expect("failed to open entry");
+    assert_eq!(data, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn seekable_writer() {
+    use crate::read::{seek, stream};
+    use tokio::io::AsyncWriteExt;
+
+    let entries = [
+        ("stored.txt", Compression::Stored, "stored ".repeat(2048)),
+        ("deflate.txt", Compression::Deflate, "deflate ".repeat(2048)),
+        ("empty.txt", Compression::Stored, String::new()),
+    ];
+
+    for force_zip64 in [false, true] {
+        let mut input_stream = Cursor::new(Vec::<u8>::new());
+        let mut zip_writer = ZipFileWriter::new_seekable(&mut input_stream);
+        zip_writer.force_zip64(force_zip64);
+        zip_writer.output_buffer_size(64);
+
+        for (name, compression, data) in &entries {
+            let open_opts = EntryOptions::new(*name, *compression);
+            let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+            entry_writer.write_all(data.as_bytes()).await.expect("failed to write entry");
+            entry_writer.close().await.expect("failed to close entry");
+        }
+        zip_writer.write_entry_whole(EntryOptions::new("whole.txt", Compression::Deflate), b"whole").await.unwrap();
+        zip_writer.close().await.expect("failed to close writer");
+        let data = input_stream.into_inner();
+
+        // Local file headers hold the CRC32 values and sizes, so entries needn't be read to their end.
+        let mut zip_reader = stream::ZipFileReader::new(data.as_slice());
+        for (name, _, expected) in &entries {
+            let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+            assert_eq!(*name, entry_reader.entry().name());
+            assert!(!entry_reader.entry().data_descriptor());
+            assert_eq!(Some(expected.len() as u64), entry_reader.entry().uncompressed_size());
+            assert_eq!(Some(crc32fast::hash(expected.as_bytes())), entry_reader.entry().crc32());
+        }
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        assert_eq!("whole", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+
+        let mut zip_reader = seek::ZipFileReader::new(Cursor::new(data)).await.expect("failed to open reader");
+        for (index, (_, _, expected)) in entries.iter().enumerate() {
+            assert!(!zip_reader.entries()[index].data_descriptor());
+            let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+            assert_eq!(*expected, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+        }
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn whole_entries_without_descriptors() {
+    use crate::read::stream;
+
+    let data = "whole ".repeat(1024);
+
+    for force_zip64 in [false, true] {
+        // A Vec is written to without seeking, so the local file headers must be complete when written.
+        let mut output = Vec::<u8>::new();
+        let mut zip_writer = ZipFileWriter::new(&mut output);
+        zip_writer.force_zip64(force_zip64);
+        for (name, compression) in [("stored.txt", Compression::Stored), ("deflate.txt", Compression::Deflate)] {
+            zip_writer.write_entry_whole(EntryOptions::new(name, compression), data.as_bytes()).await.unwrap();
+        }
+        zip_writer.close().await.expect("failed to close writer");
+
+        let mut zip_reader = stream::ZipFileReader::new(output.as_slice());
+        for _ in 0..2 {
+            let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+            assert!(!entry_reader.entry().data_descriptor());
+            assert_eq!(Some(data.len() as u64), entry_reader.entry().uncompressed_size());
+            assert_eq!(Some(crc32fast::hash(data.as_bytes())), entry_reader.entry().crc32());
+        }
+        assert!(zip_reader.entry_reader().await.expect("failed to read header").is_none());
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn write_entry_from_reader() {
+    use crate::read::seek::ZipFileReader;
+
+    let data: Vec<u8> = (0..64 * 1024).map(|index| (index % 251) as u8).collect();
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    let open_opts = EntryOptions::new("foo.bin", Compression::Deflate);
+    let copied = zip_writer.write_entry_from_reader(open_opts, &mut data.as_slice()).await.expect("failed to write");
+    assert_eq!(data.len() as u64, copied);
+    zip_writer.write_entry_from_reader(EntryOptions::named("empty.bin"), &mut tokio::io::empty()).await.unwrap();
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry");
+    assert_eq!(data, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    assert_eq!(Some(0), zip_reader.entries()[1].uncompressed_size());
+}
+
+#[cfg(all(feature = "bytes", feature = "deflate"))]
+#[tokio::test]
+async fn write_entry_whole_buf() {
+    use crate::read::seek::ZipFileReader;
+    use bytes::{Buf, Bytes};
+
+    let data = Bytes::from("buffered ".repeat(1024));
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    let open_opts = EntryOptions::new("bytes.txt", Compression::Deflate);
+    zip_writer.write_entry_whole_buf(open_opts, data.clone()).await.expect("failed to write entry");
+    let chained = data.slice(..100).chain(data.slice(100..));
+    zip_writer.write_entry_whole_buf(EntryOptions::named("chain.txt"), chained).await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    for index in 0..2 {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+        assert_eq!(data, entry_reader.read_to_end_crc().await.expect("failed to read entry"));
+    }
+}
+
+#[tokio::test]
+async fn write_dir_entry() {
+    use crate::read::seek::ZipFileReader;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    zip_writer.write_dir_entry(EntryOptions::new("empty", Compression::Deflate)).await.expect("failed to write entry");
+    let open_opts = EntryOptions::named("slashed/").permissions(0o755);
+    zip_writer.write_dir_entry(open_opts).await.expect("failed to write entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    let names: Vec<_> = zip_reader.entries().iter().map(|entry| entry.name()).collect();
+    assert_eq!(vec!["empty/", "slashed/"], names);
+
+    for entry in zip_reader.entries() {
+        assert!(entry.dir());
+        assert_eq!(Compression::Stored, *entry.compression());
+        assert_eq!(Some(0), entry.uncompressed_size());
+        assert_eq!(Some(0x10), entry.external_attributes().map(|attributes| attributes & 0x10));
+    }
+    assert_eq!(Some(0o40755), zip_reader.entries()[1].unix_mode());
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn deterministic_archives() {
+    use crate::read::seek::ZipFileReader;
+    use crate::write::EntryDefaults;
+    use chrono::TimeZone;
+
+    let timestamp = chrono::Utc.with_ymd_and_hms(2001, 2, 3, 4, 5, 6).unwrap();
+
+    let mut source = Vec::<u8>::new();
+    let mut zip_writer = ZipFileWriter::new(&mut source);
+    zip_writer.write_entry_whole(EntryOptions::named("d.txt").permissions(0o600), b"ddd").await.unwrap();
+    zip_writer.close().await.expect("failed to close writer");
+    let mut source = ZipFileReader::new(Cursor::new(source)).await.expect("failed to open reader");
+
+    let mut archives = Vec::new();
+    for _ in 0..2 {
+        let mut archive = Vec::<u8>::new();
+        let mut zip_writer = ZipFileWriter::new(&mut archive);
+        zip_writer.default_options(EntryDefaults { permissions: Some(0o644), ..Default::default() });
+        zip_writer.deterministic(Some(timestamp));
+
+        zip_writer.write_entry_whole(EntryOptions::named("b.txt").permissions(0o755), b"bbb").await.unwrap();
+        zip_writer.write_dir_entry(EntryOptions::named("a")).await.expect("failed to write entry");
+        zip_writer.write_entry_from_reader(EntryOptions::named("c.txt"), &mut &b"ccc"[..]).await.unwrap();
+        zip_writer.copy_entries(&mut source, |_| true).await.expect("failed to copy entries");
+        zip_writer.close().await.expect("failed to close writer");
+        archives.push(archive);
+    }
+    assert_eq!(archives[0], archives[1]);
+
+    let zip_reader = ZipFileReader::new(Cursor::new(&archives[0])).await.expect("failed to open reader");
+    let names: Vec<_> = zip_reader.entries().iter().map(|entry| entry.name()).collect();
+    assert_eq!(vec!["a/", "b.txt", "c.txt", "d.txt"], names);
+
+    for entry in zip_reader.entries() {
+        assert_eq!(timestamp, *entry.last_modified());
+        assert_eq!(None, entry.unix_mode());
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn entry_last_modified() {
+    use crate::read::stream::ZipFileReader;
+    use chrono::TimeZone;
+    use tokio::io::AsyncWriteExt;
+
+    let whole = chrono::Utc.with_ymd_and_hms(1999, 12, 31, 23, 59, 58).unwrap();
+    let streamed = chrono::Utc.with_ymd_and_hms(2020, 6, 15, 12, 30, 0).unwrap();
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    zip_writer.write_entry_whole(EntryOptions::named("whole.txt").last_modified(whole), b"whole").await.unwrap();
+    let open_opts = EntryOptions::named("stream.txt").last_modified(streamed);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+    entry_writer.write_all(b"stream").await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream);
+    for expected in [whole, streamed] {
+        let entry_reader = zip_reader.entry_reader().await.expect("failed to open entry").expect("no entry");
+        assert_eq!(expected, *entry_reader.entry().last_modified());
+        entry_reader.read_to_end_crc().await.expect("failed to read entry");
+    }
+}
+
+#[cfg(all(feature = "deflate", feature = "bzip2", feature = "zstd"))]
+#[tokio::test]
+async fn version_needed_to_extract() {
+    use crate::ExtraField;
+
+    let entries = [
+        (EntryOptions::new("stored.txt", Compression::Stored), 10),
+        (EntryOptions::new("deflate.txt", Compression::Deflate), 20),
+        (EntryOptions::new("dir/", Compression::Stored), 20),
+        (EntryOptions::new("bzip2.txt", Compression::Bz), 46),
+        (EntryOptions::new("zip64.txt", Compression::Stored).extra_field(ExtraField::Zip64), 45),
+        (EntryOptions::new("zstd.txt", Compression::Zstd), 63),
+    ];
+    let expected: Vec<u16> = entries.iter().map(|(_, version)| *version).collect();
+
+    let mut archive = Vec::<u8>::new();
+    let mut zip_writer = ZipFileWriter::new(&mut archive);
+    for (open_opts, _) in entries {
+        zip_writer.write_entry_whole(open_opts, b"data").await.expect("failed to write entry");
+    }
+    zip_writer.close().await.expect("failed to close writer");
+
+    // Reads the u16 at the provided offset from the start of each header with the provided signature.
+    let versions = |signature: &[u8], offset: usize| -> Vec<u16> {
+        let starts = archive.windows(4).enumerate().filter(|(_, window)| *window == signature).map(|(start, _)| start);
+        starts.map(|start| u16::from_le_bytes([archive[start + offset], archive[start + offset + 1]])).collect()
+    };
+    assert_eq!(expected, versions(b"PK\x03\x04", 4));
+    assert_eq!(expected, versions(b"PK\x01\x02", 6));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn text_entries() {
+    use crate::read::seek::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    zip_writer.write_entry_whole(EntryOptions::named("text.txt").text(true), b"line\n").await.unwrap();
+    zip_writer.write_entry_whole(EntryOptions::named("binary.bin"), &[0, 1, 2]).await.unwrap();
+    let mut entry_writer = zip_writer.write_entry_stream(EntryOptions::named("stream.txt").text(true)).await.unwrap();
+    entry_writer.write_all(b"line\n").await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    let flags: Vec<_> = zip_reader.entries().iter().map(|entry| entry.is_text()).collect();
+    assert_eq!(vec![true, false, true], flags);
+    assert_eq!(Some(1), zip_reader.entries()[0].internal_attributes());
+
+    // The flag is kept when entries are copied to another archive.
+    let mut copy = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut copy);
+    zip_writer.copy_entries(&mut zip_reader, |_| true).await.expect("failed to copy entries");
+    zip_writer.close().await.expect("failed to close writer");
+
+    copy.set_position(0);
+    let zip_reader = ZipFileReader::new(&mut copy).await.expect("failed to open reader");
+    let flags: Vec<_> = zip_reader.entries().iter().map(|entry| entry.is_text()).collect();
+    assert_eq!(vec![true, false, true], flags);
+}
+
+#[tokio::test]
+async fn entry_alignment() {
+    use crate::error::ZipError;
+    use crate::read::seek::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    zip_writer.alignment(4);
+
+    let names = ["a.txt", "bb.txt", "ccc.txt", "dddd.txt"];
+    for name in names {
+        zip_writer.write_entry_whole(EntryOptions::new(name, Compression::Stored), name.as_bytes()).await.unwrap();
+    }
+    let open_opts = EntryOptions::new("lib.so", Compression::Stored).alignment(4096);
+    let mut entry_writer = zip_writer.write_entry_stream(open_opts).await.expect("failed to open entry");
+    entry_writer.write_all(b"lib.so").await.expect("failed to write entry");
+    entry_writer.close().await.expect("failed to close entry");
+    zip_writer.close().await.expect("failed to close writer");
+
+    let archive = input_stream.get_ref().clone();
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+
+    let alignments = [4, 4, 4, 4, 4096];
+    for (index, (name, alignment)) in names.iter().chain(&["lib.so"]).zip(alignments).enumerate() {
+        let offset = zip_reader.entries()[index].offset.unwrap() as usize;
+        let lengths = &archive[offset + 26..offset + 30];
+        let data_offset = offset + 30 + u16::from_le_bytes([lengths[0], lengths[1]]) as usize;
+        let data_offset = data_offset + u16::from_le_bytes([lengths[2], lengths[3]]) as usize;
+        assert_eq!(0, data_offset % alignment);
+
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+        assert_eq!(*name, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+
+    // The padding needed to align the data following this filename overflows the field's length.
+    let open_opts = EntryOptions::new("a".repeat(65_500), Compression::Stored).alignment(u16::MAX);
+    let mut zip_writer = ZipFileWriter::new(Cursor::new(Vec::<u8>::new()));
+    assert!(matches!(zip_writer.write_entry_whole(open_opts, b"data").await, Err(ZipError::FieldTooLong(65_536))));
+}
+
+#[tokio::test]
+async fn sharded_entry_alignment() {
+    use crate::read::seek::ZipFileReader;
+    use crate::write::ShardedZipWriter;
+
+    let dir = TestDir::new("sharded_alignment");
+
+    // Each entry's data is padded to the next 4 KiB boundary, so no two entries fit within a part.
+    let factory = |part: usize| {
+        let path = dir.join(format!("part{}.zip", part));
+        async move { Ok(tokio::fs::File::create(path).await?) }
+    };
+    let mut writer = ShardedZipWriter::new(factory, 4608);
+    for index in 0..3 {
+        let opts = EntryOptions::new(format!("{}.bin", index), Compression::Stored).alignment(4096);
+        writer.write_entry_whole(opts, &[index as u8; 150]).await.expect("failed to write entry");
+    }
+
+    let summary = writer.close().await.expect("failed to close writer");
+    assert_eq!(3, summary.parts);
+    for part in 0..summary.parts {
+        let mut file = tokio::fs::File::open(dir.join(format!("part{}.zip", part))).await.expect("missing part");
+        assert!(file.metadata().await.unwrap().len() <= 4608);
+
+        let mut zip_reader = ZipFileReader::new(&mut file).await.expect("failed to open part");
+        let data = zip_reader.entry_reader(0).await.unwrap().read_to_end_crc().await.unwrap();
+        assert_eq!(vec![part as u8; 150], data);
+    }
+}
+
+#[tokio::test]
+async fn apk_signing_blocks() {
+    use crate::read::seek::ZipFileReader;
+
+    // A signing block holding a single ID-value pair.
+    let pair = [&12u64.to_le_bytes()[..], &0x7109871au32.to_le_bytes(), b"signatu"].concat();
+    let size = (pair.len() + 8 + 16) as u64;
+    let block = [&size.to_le_bytes()[..], &pair, &size.to_le_bytes(), b"APK Sig Block 42"].concat();
+
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let mut zip_writer = ZipFileWriter::new(&mut input_stream);
+    zip_writer.write_entry_whole(EntryOptions::new("a.txt", Compression::Stored), b"aaa").await.unwrap();
+    zip_writer.signing_block(Some(block.clone()));
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    assert_eq!(Some(&block), zip_reader.signing_block().await.expect("failed to read block").as_ref());
+
+    // Appended entries are written in place of the block, which is kept ahead of the central directory.
+    input_stream.set_position(0);
+    let mut zip_writer = ZipFileWriter::append(&mut input_stream).await.expect("failed to open archive");
+    zip_writer.write_entry_whole(EntryOptions::new("b.txt", Compression::Stored), b"bbb").await.unwrap();
+    zip_writer.close().await.expect("failed to close writer");
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    assert_eq!(Some(&block), zip_reader.signing_block().await.expect("failed to read block").as_ref());
+    assert_eq!(1, input_stream.get_ref().windows(16).filter(|window| *window == b"APK Sig Block 42").count());
+
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    for (index, expected) in ["aaa", "bbb"].iter().enumerate() {
+        let entry_reader = zip_reader.entry_reader(index).await.expect("failed to open entry");
+        assert_eq!(*expected, entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+    }
+
+    // Archives without a block have none.
+    let mut input_stream = Cursor::new(Vec::<u8>::new());
+    let zip_writer = ZipFileWriter::new(&mut input_stream);
+    zip_writer.close().await.expect("failed to close writer");
+    input_stream.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut input_stream).await.expect("failed to open reader");
+    assert_eq!(None, zip_reader.signing_block().await.expect("failed to read block"));
+}
+
+#[cfg(feature = "deflate")]
+#[tokio::test]
+async fn close_returns_inner_writer() {
+    use crate::read::seek::ZipFileReader;
+    use tokio::io::AsyncWriteExt;
+
+    let mut zip_writer = ZipFileWriter::new(Cursor::new(Vec::<u8>::new()));
+    zip_writer.write_entry_whole(EntryOptions::new("a.txt", Compression::Deflate), b"aaa").await.unwrap();
+    let mut output = zip_writer.close_with_comment("signed").await.expect("failed to close writer");
+
+    // The writer is positioned at the end of the archive, so data written to it follows the archive.
+    let archive_len = output.get_ref().len() as u64;
+    assert_eq!(archive_len, output.position());
+    output.write_all(b"SIGNATURE").await.expect("failed to write signature");
+    assert!(output.get_ref().ends_with(b"signedSIGNATURE"));
+
+    output.set_position(0);
+    let mut zip_reader = ZipFileReader::new(&mut output).await.expect("failed to open reader");
+    assert_eq!("signed", zip_reader.comment().as_str());
+    let entry_reader = zip_reader.entry_reader(0).await.expect("failed to open entry");
+    assert_eq!("aaa", entry_reader.read_to_string_crc().await.expect("failed to read entry"));
+}
@@ -0,0 +1,326 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Support for reading and writing files via `io_uring` (with the `uring` feature, on Linux only), using the
+//! `tokio-uring` runtime.
+//!
+//! `tokio-uring` operates on buffers owned by the kernel until each operation completes, rather than on the borrowed
+//! buffers of tokio's I/O traits. [`UringReader`] and [`UringWriter`] implement those traits over `tokio-uring` files
+//! by reading and writing in large blocks through owned buffers which are handed to the kernel and reused, so the seek
+//! reader and the writer may be used over them as over any other reader or writer.
+//!
+//! As such, data read through those traits (including by entry readers, which decompress via them) is still copied
+//! from the owned blocks into each [`ReadBuf`], and data written is copied into the owned blocks. Only the raw data of
+//! entries may be read without that copy, directly into a caller-owned buffer, via
+//! [`ZipFileReader::entry_data_owned()`].
+//!
+//! All types of this module may only be used within a `tokio-uring` runtime (eg. within `tokio_uring::start()`), and
+//! aren't `Send`.
+//!
+//! # Example
+//! ```no_run
+//! # use async_zip::read::seek::ZipFileReader;
+//! # use async_zip::uring::UringReader;
+//! # use async_zip::error::ZipError;
+//! #
+//! # fn run() -> Result<(), ZipError> {
+//! tokio_uring::start(async {
+//!     let mut zip = ZipFileReader::new(UringReader::open("./Archive.zip")?).await?;
+//!     let data = zip.entry_reader(0).await?.read_to_end_crc().await?;
+//!     Ok(())
+//! })
+//! # }
+//! ```
+
+use crate::error::{Result, ZipError};
+use crate::read::seek::ZipFileReader;
+
+use std::future::Future;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite, ReadBuf};
+use tokio_uring::buf::IoBuf;
+use tokio_uring::fs::File;
+
+/// The default size of the blocks in which files are read and written (256 KiB).
+pub const DEFAULT_BLOCK_SIZE: usize = 256 * 1024;
+
+type Operation = Pin<Box<dyn Future<Output = (std::io::Result<usize>, Vec<u8>)>>>;
+
+/// A reader which implements tokio's I/O traits over a `tokio-uring` file.
+///
+/// The file is read in blocks of [`DEFAULT_BLOCK_SIZE`] bytes (or as set via [`UringReader::block_size()`]) into an
+/// owned buffer, from which reads are served. Seeking never reads by itself.
+pub struct UringReader {
+    file: Rc<File>,
+    size: u64,
+    position: u64,
+    block_size: usize,
+
+    // The most recently read block (unless it's held by an in-flight read), and the offset it starts at.
+    buffer: Option<Vec<u8>>,
+    buffer_offset: u64,
+
+    // A read which is yet to complete, and the offset it starts at.
+    pending: Option<(u64, Operation)>,
+}
+
+impl UringReader {
+    /// Opens the file at the provided path for reading.
+    ///
+    /// As `tokio-uring` can't read a file's metadata, the file is opened (and its size read) via the standard library,
+    /// which blocks the calling thread.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::from_std(std::fs::File::open(path)?)
+    }
+
+    /// Constructs a new reader over a file opened via the standard library, reading its size from its metadata.
+    pub fn from_std(file: std::fs::File) -> std::io::Result<Self> {
+        let size = file.metadata()?.len();
+        Ok(UringReader {
+            file: Rc::new(File::from_std(file)),
+            size,
+            position: 0,
+            block_size: DEFAULT_BLOCK_SIZE,
+            buffer: Some(Vec::new()),
+            buffer_offset: 0,
+            pending: None,
+        })
+    }
+
+    /// Consume the reader and override the size of the blocks in which the file is read (defaults to
+    /// [`DEFAULT_BLOCK_SIZE`]).
+    pub fn block_size(mut self, size: usize) -> Self {
+        self.block_size = size.max(1);
+        self
+    }
+
+    /// Returns the size of the file in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Reads `length` bytes starting at `offset` directly into the provided buffer, replacing its contents.
+    ///
+    /// The buffer is handed to the kernel and returned alongside the result, whether or not the read succeeded.
+    pub async fn read_exact_at(
+        &self,
+        mut buffer: Vec<u8>,
+        offset: u64,
+        length: usize,
+    ) -> (std::io::Result<()>, Vec<u8>) {
+        buffer.clear();
+        buffer.reserve_exact(length);
+
+        while buffer.len() < length {
+            let filled = buffer.len();
+            let (result, slice) = self.file.read_at(buffer.slice(filled..length), offset + filled as u64).await;
+            buffer = slice.into_inner();
+
+            match result {
+                Ok(0) => return (Err(std::io::ErrorKind::UnexpectedEof.into()), buffer),
+                Ok(_) => {}
+                Err(error) => return (Err(error), buffer),
+            }
+        }
+
+        (Ok(()), buffer)
+    }
+}
+
+impl AsyncRead for UringReader {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let this = &mut *self;
+
+        loop {
+            if let Some(buffer) = &this.buffer {
+                let start = this.position.wrapping_sub(this.buffer_offset);
+                if this.position >= this.buffer_offset && start < buffer.len() as u64 {
+                    let available = &buffer[start as usize..];
+                    let length = std::cmp::min(available.len(), b.remaining());
+                    b.put_slice(&available[..length]);
+                    this.position += length as u64;
+                    return Poll::Ready(Ok(()));
+                }
+            }
+            if this.position >= this.size || b.remaining() == 0 {
+                return Poll::Ready(Ok(()));
+            }
+
+            // A read left over from before a seek is abandoned (along with its buffer).
+            if !matches!(&this.pending, Some((offset, _)) if *offset == this.position) {
+                let mut buffer = this.buffer.take().unwrap_or_default();
+                buffer.clear();
+                buffer.reserve_exact(std::cmp::max(this.block_size, b.remaining()));
+
+                let (file, position) = (this.file.clone(), this.position);
+                this.pending = Some((position, Box::pin(async move { file.read_at(buffer, position).await })));
+            }
+
+            let (offset, read) = this.pending.as_mut().unwrap();
+            let (result, buffer) = ready!(read.as_mut().poll(c));
+            this.buffer_offset = *offset;
+            this.buffer = Some(buffer);
+            this.pending = None;
+
+            if result? == 0 {
+                return Poll::Ready(Err(tokio::io::ErrorKind::UnexpectedEof.into()));
+            }
+        }
+    }
+}
+
+impl AsyncSeek for UringReader {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        let target = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+        };
+
+        self.position = target.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "seek to a negative or overflowing position")
+        })?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        Poll::Ready(Ok(self.position))
+    }
+}
+
+impl ZipFileReader<UringReader> {
+    /// Reads the data of the entry at the provided index as stored (ie. without decrypting or decompressing it)
+    /// directly into the provided buffer, replacing its contents.
+    ///
+    /// For Stored entries which aren't encrypted, this is the entry's uncompressed data. The data is read by the kernel
+    /// straight into the buffer, without being copied through a [`ReadBuf`].
+    pub async fn entry_data_owned(&mut self, index: usize, buffer: Vec<u8>) -> Result<Vec<u8>> {
+        let entry = self.entries.get(index).ok_or(ZipError::EntryIndexOutOfBounds)?;
+
+        if entry.partial() {
+            return Err(ZipError::PartialEntry);
+        }
+
+        entry.seek_to_data_offset(&mut self.reader).await?;
+        let length =
+            usize::try_from(entry.compressed_size.unwrap()).map_err(|_| ZipError::HeaderOutOfBounds("entry data"))?;
+
+        let (result, buffer) = self.reader.read_exact_at(buffer, self.reader.position, length).await;
+        result?;
+        Ok(buffer)
+    }
+}
+
+/// A writer which implements tokio's I/O traits over a `tokio-uring` file.
+///
+/// Writes are collected into an owned buffer of [`DEFAULT_BLOCK_SIZE`] bytes (or as set via
+/// [`UringWriter::block_size()`]), which is handed to the kernel once full whilst the next block is collected into a
+/// second buffer. Flushing waits for all blocks to be written, but doesn't sync the file to disk.
+pub struct UringWriter {
+    file: Rc<File>,
+    position: u64,
+    block_size: usize,
+
+    // The block being collected, and a spare buffer returned by the most recently completed write.
+    buffer: Vec<u8>,
+    spare: Option<Vec<u8>>,
+
+    // A write which is yet to complete, and the offset it starts at.
+    pending: Option<(u64, Operation)>,
+}
+
+impl UringWriter {
+    /// Creates (or truncates) the file at the provided path for writing.
+    pub async fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::new(File::create(path).await?))
+    }
+
+    /// Constructs a new writer which writes from the start of the provided file.
+    pub fn new(file: File) -> Self {
+        UringWriter {
+            file: Rc::new(file),
+            position: 0,
+            block_size: DEFAULT_BLOCK_SIZE,
+            buffer: Vec::with_capacity(DEFAULT_BLOCK_SIZE),
+            spare: None,
+            pending: None,
+        }
+    }
+
+    /// Consume the writer and override the size of the blocks in which the file is written (defaults to
+    /// [`DEFAULT_BLOCK_SIZE`]).
+    pub fn block_size(mut self, size: usize) -> Self {
+        self.block_size = size.max(1);
+        self
+    }
+
+    /// Hands the collected block to the kernel to be written.
+    fn submit(&mut self, buffer: Vec<u8>, position: u64) {
+        let file = self.file.clone();
+        self.pending = Some((position, Box::pin(async move { file.write_at(buffer, position).await })));
+    }
+
+    /// Waits for any in-flight write to complete, resubmitting the remainder of its block if it was partially written.
+    fn poll_pending(&mut self, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        while let Some((offset, write)) = &mut self.pending {
+            let (result, mut buffer) = ready!(write.as_mut().poll(c));
+            let offset = *offset;
+            self.pending = None;
+
+            match result? {
+                0 => return Poll::Ready(Err(tokio::io::ErrorKind::WriteZero.into())),
+                written if written < buffer.len() => {
+                    buffer.drain(..written);
+                    self.submit(buffer, offset + written as u64);
+                }
+                _ => {
+                    buffer.clear();
+                    self.spare = Some(buffer);
+                }
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    /// Hands the collected block to the kernel, once any in-flight write has completed.
+    fn poll_submit(&mut self, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        ready!(self.poll_pending(c))?;
+
+        let spare = self.spare.take().unwrap_or_else(|| Vec::with_capacity(self.block_size));
+        let buffer = std::mem::replace(&mut self.buffer, spare);
+        let position = self.position;
+        self.position += buffer.len() as u64;
+        self.submit(buffer, position);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for UringWriter {
+    fn poll_write(mut self: Pin<&mut Self>, c: &mut Context<'_>, buf: &[u8]) -> Poll<tokio::io::Result<usize>> {
+        if self.buffer.len() >= self.block_size {
+            ready!(self.poll_submit(c))?;
+        }
+
+        let length = std::cmp::min(self.block_size - self.buffer.len(), buf.len());
+        self.buffer.extend_from_slice(&buf[..length]);
+        Poll::Ready(Ok(length))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        if !self.buffer.is_empty() {
+            ready!(self.poll_submit(c))?;
+        }
+        self.poll_pending(c)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        self.poll_flush(c)
+    }
+}
@@ -2,14 +2,41 @@
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
 use crate::error::{Result, ZipError};
-use tokio::io::{AsyncRead, AsyncReadExt};
+use crate::spec::header::GeneralPurposeFlag;
+use crate::spec::string::{StringEncoding, ZipString};
+use std::io::IoSlice;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
-/// Read and return a dynamic length string from a reader which impls AsyncRead.
-pub async fn read_string<R: AsyncRead + Unpin>(reader: &mut R, length: usize) -> Result<String> {
-    let mut buffer = String::with_capacity(length);
-    reader.take(length as u64).read_to_string(&mut buffer).await?;
+/// Read and return a dynamic length string of the provided encoding from a reader which impls AsyncRead.
+pub async fn read_string<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    length: usize,
+    encoding: StringEncoding,
+) -> Result<ZipString> {
+    Ok(ZipString::new(read_bytes(reader, length).await?, encoding))
+}
 
-    Ok(buffer)
+/// The largest capacity which is allocated up front based on a size declared by (untrusted) headers (1 MiB).
+pub(crate) const MAX_SPECULATIVE_CAPACITY: u64 = 1024 * 1024;
+
+/// Returns the capacity to allocate up front for data of a declared size, which may be larger in reality.
+pub(crate) fn speculative_capacity(declared: impl Into<u64>) -> usize {
+    std::cmp::min(declared.into(), MAX_SPECULATIVE_CAPACITY) as usize
+}
+
+/// Converts the length of a variable-size field into its u16 header value if it doesn't exceed the maximum.
+pub(crate) fn field_length(length: usize) -> Result<u16> {
+    length.try_into().map_err(|_| ZipError::FieldTooLong(length))
+}
+
+/// Returns the encoding of an entry's filename and comment as declared by its general purpose flag.
+pub(crate) fn flag_encoding(flags: &GeneralPurposeFlag) -> StringEncoding {
+    match flags.filename_unicode {
+        true => StringEncoding::Utf8,
+        false => StringEncoding::Unknown,
+    }
 }
 
 /// Read and return a dynamic length vector of bytes from a reader which impls AsyncRead.
@@ -20,6 +47,26 @@ pub async fn read_bytes<R: AsyncRead + Unpin>(reader: &mut R, length: usize) ->
     Ok(buffer)
 }
 
+/// Write all of the provided buffers to a writer which impls AsyncWrite.
+///
+/// The buffers are handed to the writer together, so a record's fields reach a writer which supports vectored writes
+/// (such as a file or socket) in as few calls as possible, rather than in one call per field.
+pub(crate) async fn write_all_vectored<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    mut buffers: &mut [IoSlice<'_>],
+) -> std::io::Result<()> {
+    IoSlice::advance_slices(&mut buffers, 0);
+
+    while !buffers.is_empty() {
+        match writer.write_vectored(buffers).await? {
+            0 => return Err(std::io::ErrorKind::WriteZero.into()),
+            written => IoSlice::advance_slices(&mut buffers, written),
+        }
+    }
+
+    Ok(())
+}
+
 /// Assert that the next four-byte delimiter read by a reader which impls AsyncRead matches the expected delimiter.
 pub(crate) async fn assert_delimiter<R: AsyncRead + Unpin>(reader: &mut R, expected: u32) -> Result<()> {
     match reader.read_u32_le().await? {
@@ -27,3 +74,13 @@ pub(crate) async fn assert_delimiter<R: AsyncRead + Unpin>(reader: &mut R, expec
         actual => Err(ZipError::UnexpectedHeaderError(actual, expected)),
     }
 }
+
+/// Returns a closure which attaches the provided path to an I/O error for diagnostics.
+pub(crate) fn path_error(path: &Path) -> impl FnOnce(std::io::Error) -> ZipError + '_ {
+    move |err| ZipError::PathError(path.to_path_buf(), err)
+}
+
+/// Open a file for reading, attaching its path to any error.
+pub(crate) async fn open_file(path: &Path) -> Result<File> {
+    File::open(path).await.map_err(path_error(path))
+}
@@ -0,0 +1,86 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::error::{Result, ZipError};
+use crate::read::seek::{cd_record_entry, locate_cd, read_cd_record, read_signing_block, CentralDirectoryRecord};
+use crate::read::ParseMode;
+use crate::spec::extra::{split_fields, ZIP64_EXTRA_FIELD_ID};
+use crate::write::defaults::{CompressionLevel, ResolvedOptions};
+use crate::write::offset_writer::OffsetAsyncWriter;
+use crate::write::{CentralDirectoryEntry, ZipFileWriter};
+
+use std::io::SeekFrom;
+
+use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt, AsyncWrite};
+
+impl<W: AsyncRead + AsyncWrite + AsyncSeek + Unpin> ZipFileWriter<W> {
+    /// Constructs a ZIP file writer which adds entries to an existing archive, from a reader and writer over it (or a
+    /// mutable reference to one).
+    ///
+    /// The existing central directory is read and the writer positioned at its start, so new entries overwrite it.
+    /// When [`ZipFileWriter::close()`] is called, the central directory is rewritten with the existing records
+    /// followed by those of the new entries. Existing entries can be amended via [`ZipFileWriter::pending_entry_mut()`]
+    /// as with any other, and the archive comment is kept unless it's replaced.
+    ///
+    /// If the archive has an APK signing block (see [`ZipFileWriter::signing_block()`]), it's kept directly ahead of
+    /// the rewritten central directory, with new entries written in its place.
+    ///
+    /// The archive is corrupt until closed, so a copy should be taken first if that matters. The target can't be
+    /// truncated through these traits, so if the rewritten archive is shorter than the original (eg. because an
+    /// existing entry's comment was shortened), [`ZipError::TruncationRequired`] is returned once it has been written.
+    ///
+    /// [`ZipError::TruncationRequired`]: crate::error::ZipError::TruncationRequired
+    pub async fn append(mut existing: W) -> Result<Self> {
+        let location = locate_cd(&mut existing).await?;
+        let original_len = existing.seek(SeekFrom::End(0)).await?;
+        existing.seek(SeekFrom::Start(location.offset)).await?;
+
+        let mut cd_entries = Vec::new();
+        for _ in 0..location.num_of_entries {
+            let CentralDirectoryRecord { header, filename, extra, comment } = read_cd_record(&mut existing).await?;
+            let entry =
+                cd_record_entry(&header, filename.clone(), extra.clone(), comment.clone(), ParseMode::Standard)?;
+
+            // The Zip64 field is kept apart so that it's retained if the entry's extra field data is amended.
+            let (generated_extra, extra) = split_fields(&extra, ZIP64_EXTRA_FIELD_ID)?;
+            let opts = ResolvedOptions {
+                filename,
+                compression: entry.compression,
+                level: CompressionLevel::Default,
+                last_modified: entry.last_modified,
+                permissions: None,
+                text: entry.is_text(),
+                alignment: None,
+                // The local file headers of existing entries are left in place, so their extra fields aren't needed.
+                local_extra: Vec::new(),
+                extra,
+                force_zip64: false,
+                comment,
+                #[cfg(feature = "aes")]
+                password: None,
+                #[cfg(feature = "zstd")]
+                zstd_dictionary: None,
+                #[cfg(all(feature = "parallel", feature = "deflate"))]
+                parallel: None,
+            };
+
+            cd_entries.push(CentralDirectoryEntry { header, opts, generated_extra });
+        }
+
+        let signing_block = read_signing_block(&mut existing, location.offset).await?;
+        let start = signing_block.as_ref().map_or(location.offset, |(start, _)| *start);
+        existing.seek(SeekFrom::Start(start)).await?;
+
+        let mut writer = Self::new(existing);
+        // Offsets within archives with prepended data are relative to the start of the archive itself.
+        let offset = start.checked_sub(location.prepended).ok_or(ZipError::HeaderOutOfBounds("APK signing block"))?;
+        writer.writer = OffsetAsyncWriter::with_offset(writer.writer.into_inner(), offset as usize);
+        writer.cd_entries = cd_entries;
+        writer.original_len = Some(original_len - location.prepended);
+        writer.zip64_eocd = location.zip64;
+        writer.comment_opt = Some(location.comment.into_bytes());
+        writer.signing_block = signing_block.map(|(_, block)| block);
+
+        Ok(writer)
+    }
+}
@@ -1,80 +1,304 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
+use crate::backend::{BackendEncoder, Backends};
+use crate::error::{Result, ZipError};
 use crate::spec::compression::Compression;
+use crate::write::defaults::ResolvedOptions;
+use crate::write::encrypted_writer::EncryptedAsyncWriter;
+#[cfg(feature = "lzma")]
+use crate::write::lzma::LzmaHeaderWriter;
+#[cfg(feature = "parallel")]
+use crate::write::offloaded::OffloadedEncoder;
 use crate::write::offset_writer::OffsetAsyncWriter;
+#[cfg(all(feature = "parallel", feature = "deflate"))]
+use crate::write::parallel_deflate::ParallelDeflateEncoder;
+use crate::write::CompressionLevel;
 
-use std::io::Error;
+use std::io::{Error, IoSlice};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use async_compression::tokio::write::{BzEncoder, DeflateEncoder, LzmaEncoder, XzEncoder, ZstdEncoder};
+#[cfg(feature = "bzip2")]
+use async_compression::tokio::write::BzEncoder;
+#[cfg(feature = "deflate")]
+use async_compression::tokio::write::DeflateEncoder;
+#[cfg(feature = "lzma")]
+use async_compression::tokio::write::LzmaEncoder;
+#[cfg(feature = "xz")]
+use async_compression::tokio::write::XzEncoder;
+#[cfg(feature = "zstd")]
+use async_compression::tokio::write::ZstdEncoder;
 use tokio::io::AsyncWrite;
 
+/// An encoder for one of the compression methods built into the crate, which writes into an inner writer.
+///
+/// This holds the single mapping from built-in compression methods to their encoders, shared by entries written in
+/// whole, stream-written entries, and those whose compression is offloaded to a blocking task.
+pub(crate) enum CodecEncoder<W: AsyncWrite + Unpin> {
+    Stored(W),
+    #[cfg(feature = "deflate")]
+    Deflate(DeflateEncoder<W>),
+    #[cfg(feature = "bzip2")]
+    Bz(BzEncoder<W>),
+    #[cfg(feature = "lzma")]
+    Lzma(LzmaEncoder<LzmaHeaderWriter<W>>),
+    #[cfg(feature = "zstd")]
+    Zstd(ZstdEncoder<W>),
+    #[cfg(feature = "xz")]
+    Xz(XzEncoder<W>),
+}
+
+impl<W: AsyncWrite + Unpin> CodecEncoder<W> {
+    /// Constructs an encoder for the provided compression method, failing if it isn't built into the crate.
+    // The level is unused if no compression method's feature is enabled.
+    #[cfg_attr(
+        not(any(feature = "deflate", feature = "bzip2", feature = "lzma", feature = "zstd", feature = "xz")),
+        allow(unused_variables)
+    )]
+    pub(crate) fn new(writer: W, compression: Compression, level: CompressionLevel) -> Result<Self> {
+        Ok(match compression {
+            Compression::Stored => CodecEncoder::Stored(writer),
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => CodecEncoder::Deflate(DeflateEncoder::with_quality(writer, level.to_level())),
+            #[cfg(feature = "bzip2")]
+            Compression::Bz => CodecEncoder::Bz(BzEncoder::with_quality(writer, level.to_level())),
+            #[cfg(feature = "lzma")]
+            Compression::Lzma => {
+                CodecEncoder::Lzma(LzmaEncoder::with_quality(LzmaHeaderWriter::new(writer), level.to_level()))
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => CodecEncoder::Zstd(ZstdEncoder::with_quality(writer, level.to_level())),
+            #[cfg(feature = "xz")]
+            Compression::Xz => CodecEncoder::Xz(XzEncoder::with_quality(writer, level.to_level())),
+            Compression::Deflate64 => return Err(ZipError::FeatureNotSupported("Deflate64 compression")),
+            Compression::Shrink | Compression::Implode => {
+                return Err(ZipError::FeatureNotSupported("Shrink and Implode compression"))
+            }
+            Compression::Ppmd => return Err(ZipError::FeatureNotSupported("PPMd compression")),
+            // Methods whose Cargo feature is disabled (as custom methods are written via their backend).
+            compression => return Err(ZipError::UnsupportedCompressionError(compression.to_u16())),
+        })
+    }
+
+    pub(crate) fn get_ref(&self) -> &W {
+        match self {
+            CodecEncoder::Stored(inner) => inner,
+            #[cfg(feature = "deflate")]
+            CodecEncoder::Deflate(inner) => inner.get_ref(),
+            #[cfg(feature = "bzip2")]
+            CodecEncoder::Bz(inner) => inner.get_ref(),
+            #[cfg(feature = "lzma")]
+            CodecEncoder::Lzma(inner) => inner.get_ref().get_ref(),
+            #[cfg(feature = "zstd")]
+            CodecEncoder::Zstd(inner) => inner.get_ref(),
+            #[cfg(feature = "xz")]
+            CodecEncoder::Xz(inner) => inner.get_ref(),
+        }
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        match self {
+            CodecEncoder::Stored(inner) => inner,
+            #[cfg(feature = "deflate")]
+            CodecEncoder::Deflate(inner) => inner.into_inner(),
+            #[cfg(feature = "bzip2")]
+            CodecEncoder::Bz(inner) => inner.into_inner(),
+            #[cfg(feature = "lzma")]
+            CodecEncoder::Lzma(inner) => inner.into_inner().into_inner(),
+            #[cfg(feature = "zstd")]
+            CodecEncoder::Zstd(inner) => inner.into_inner(),
+            #[cfg(feature = "xz")]
+            CodecEncoder::Xz(inner) => inner.into_inner(),
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for CodecEncoder<W> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<std::result::Result<usize, Error>> {
+        match *self {
+            CodecEncoder::Stored(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
+            #[cfg(feature = "deflate")]
+            CodecEncoder::Deflate(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
+            #[cfg(feature = "bzip2")]
+            CodecEncoder::Bz(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
+            #[cfg(feature = "lzma")]
+            CodecEncoder::Lzma(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
+            #[cfg(feature = "zstd")]
+            CodecEncoder::Zstd(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
+            #[cfg(feature = "xz")]
+            CodecEncoder::Xz(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::result::Result<usize, Error>> {
+        match *self {
+            CodecEncoder::Stored(ref mut inner) => Pin::new(inner).poll_write_vectored(cx, bufs),
+            // Encoders consume their input one buffer at a time.
+            #[allow(unreachable_patterns)]
+            _ => {
+                let buf = bufs.iter().find(|buf| !buf.is_empty()).map_or(&[][..], |buf| &**buf);
+                self.poll_write(cx, buf)
+            }
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            CodecEncoder::Stored(inner) => inner.is_write_vectored(),
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
+        match *self {
+            CodecEncoder::Stored(ref mut inner) => Pin::new(inner).poll_flush(cx),
+            #[cfg(feature = "deflate")]
+            CodecEncoder::Deflate(ref mut inner) => Pin::new(inner).poll_flush(cx),
+            #[cfg(feature = "bzip2")]
+            CodecEncoder::Bz(ref mut inner) => Pin::new(inner).poll_flush(cx),
+            #[cfg(feature = "lzma")]
+            CodecEncoder::Lzma(ref mut inner) => Pin::new(inner).poll_flush(cx),
+            #[cfg(feature = "zstd")]
+            CodecEncoder::Zstd(ref mut inner) => Pin::new(inner).poll_flush(cx),
+            #[cfg(feature = "xz")]
+            CodecEncoder::Xz(ref mut inner) => Pin::new(inner).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
+        match *self {
+            CodecEncoder::Stored(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
+            #[cfg(feature = "deflate")]
+            CodecEncoder::Deflate(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
+            #[cfg(feature = "bzip2")]
+            CodecEncoder::Bz(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
+            #[cfg(feature = "lzma")]
+            CodecEncoder::Lzma(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
+            #[cfg(feature = "zstd")]
+            CodecEncoder::Zstd(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
+            #[cfg(feature = "xz")]
+            CodecEncoder::Xz(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
+        }
+    }
+}
+
 pub enum CompressedAsyncWriter<'b, W: AsyncWrite + Unpin> {
-    Stored(&'b mut OffsetAsyncWriter<W>),
-    Deflate(DeflateEncoder<&'b mut OffsetAsyncWriter<W>>),
-    Bz(BzEncoder<&'b mut OffsetAsyncWriter<W>>),
-    Lzma(LzmaEncoder<&'b mut OffsetAsyncWriter<W>>),
-    Zstd(ZstdEncoder<&'b mut OffsetAsyncWriter<W>>),
-    Xz(XzEncoder<&'b mut OffsetAsyncWriter<W>>),
+    Codec(CodecEncoder<EncryptedAsyncWriter<&'b mut OffsetAsyncWriter<W>>>),
+    Custom(BackendEncoder<EncryptedAsyncWriter<&'b mut OffsetAsyncWriter<W>>>),
+    #[cfg(feature = "parallel")]
+    Offloaded(OffloadedEncoder<EncryptedAsyncWriter<&'b mut OffsetAsyncWriter<W>>>),
+    #[cfg(all(feature = "parallel", feature = "deflate"))]
+    ParallelDeflate(ParallelDeflateEncoder<EncryptedAsyncWriter<&'b mut OffsetAsyncWriter<W>>>),
 }
 
 impl<'b, W: AsyncWrite + Unpin> CompressedAsyncWriter<'b, W> {
-    pub fn from_raw(writer: &'b mut OffsetAsyncWriter<W>, compression: Compression) -> Self {
-        match compression {
-            Compression::Stored => CompressedAsyncWriter::Stored(writer),
-            Compression::Deflate => CompressedAsyncWriter::Deflate(DeflateEncoder::new(writer)),
-            Compression::Bz => CompressedAsyncWriter::Bz(BzEncoder::new(writer)),
-            Compression::Lzma => CompressedAsyncWriter::Lzma(LzmaEncoder::new(writer)),
-            Compression::Zstd => CompressedAsyncWriter::Zstd(ZstdEncoder::new(writer)),
-            Compression::Xz => CompressedAsyncWriter::Xz(XzEncoder::new(writer)),
+    // Whether to offload compression is unused if the `parallel` feature is disabled.
+    #[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+    pub fn from_raw(
+        writer: EncryptedAsyncWriter<&'b mut OffsetAsyncWriter<W>>,
+        options: &ResolvedOptions,
+        backends: &Backends,
+        offload: bool,
+    ) -> Result<Self> {
+        let (compression, level) = (options.compression, options.level);
+        #[cfg(all(feature = "parallel", feature = "deflate"))]
+        if let (Compression::Deflate, Some(threads)) = (compression, options.parallel) {
+            return Ok(CompressedAsyncWriter::ParallelDeflate(ParallelDeflateEncoder::new(writer, level, threads)));
+        }
+        #[cfg(feature = "parallel")]
+        if offload && compression != Compression::Stored {
+            return Ok(CompressedAsyncWriter::Offloaded(OffloadedEncoder::new(writer, options, backends)?));
+        }
+        if let Some(backend) = options.backend(backends)? {
+            return Ok(CompressedAsyncWriter::Custom(BackendEncoder::new(writer, backend, level)?));
         }
+
+        Ok(CompressedAsyncWriter::Codec(CodecEncoder::new(writer, compression, level)?))
+    }
+
+    pub fn get_ref(&self) -> &OffsetAsyncWriter<W> {
+        let inner = match self {
+            CompressedAsyncWriter::Codec(inner) => inner.get_ref(),
+            CompressedAsyncWriter::Custom(inner) => inner.get_ref(),
+            #[cfg(feature = "parallel")]
+            CompressedAsyncWriter::Offloaded(inner) => inner.get_ref(),
+            #[cfg(all(feature = "parallel", feature = "deflate"))]
+            CompressedAsyncWriter::ParallelDeflate(inner) => inner.get_ref(),
+        };
+        inner.get_ref()
     }
 
     pub fn into_inner(self) -> &'b mut OffsetAsyncWriter<W> {
-        match self {
-            CompressedAsyncWriter::Stored(inner) => inner,
-            CompressedAsyncWriter::Deflate(inner) => inner.into_inner(),
-            CompressedAsyncWriter::Bz(inner) => inner.into_inner(),
-            CompressedAsyncWriter::Lzma(inner) => inner.into_inner(),
-            CompressedAsyncWriter::Zstd(inner) => inner.into_inner(),
-            CompressedAsyncWriter::Xz(inner) => inner.into_inner(),
-        }
+        let inner = match self {
+            CompressedAsyncWriter::Codec(inner) => inner.into_inner(),
+            CompressedAsyncWriter::Custom(inner) => inner.into_inner(),
+            #[cfg(feature = "parallel")]
+            CompressedAsyncWriter::Offloaded(inner) => inner.into_inner(),
+            #[cfg(all(feature = "parallel", feature = "deflate"))]
+            CompressedAsyncWriter::ParallelDeflate(inner) => inner.into_inner(),
+        };
+        inner.into_inner()
     }
 }
 
 impl<'b, W: AsyncWrite + Unpin> AsyncWrite for CompressedAsyncWriter<'b, W> {
     fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<std::result::Result<usize, Error>> {
         match *self {
-            CompressedAsyncWriter::Stored(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
-            CompressedAsyncWriter::Deflate(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
-            CompressedAsyncWriter::Bz(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
-            CompressedAsyncWriter::Lzma(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
-            CompressedAsyncWriter::Zstd(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
-            CompressedAsyncWriter::Xz(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
+            CompressedAsyncWriter::Codec(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
+            CompressedAsyncWriter::Custom(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
+            #[cfg(feature = "parallel")]
+            CompressedAsyncWriter::Offloaded(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
+            #[cfg(all(feature = "parallel", feature = "deflate"))]
+            CompressedAsyncWriter::ParallelDeflate(ref mut inner) => Pin::new(inner).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::result::Result<usize, Error>> {
+        if let CompressedAsyncWriter::Codec(ref mut inner) = *self {
+            return Pin::new(inner).poll_write_vectored(cx, bufs);
+        }
+
+        // Encoders consume their input one buffer at a time.
+        let buf = bufs.iter().find(|buf| !buf.is_empty()).map_or(&[][..], |buf| &**buf);
+        self.poll_write(cx, buf)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            CompressedAsyncWriter::Codec(inner) => inner.is_write_vectored(),
+            _ => false,
         }
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
         match *self {
-            CompressedAsyncWriter::Stored(ref mut inner) => Pin::new(inner).poll_flush(cx),
-            CompressedAsyncWriter::Deflate(ref mut inner) => Pin::new(inner).poll_flush(cx),
-            CompressedAsyncWriter::Bz(ref mut inner) => Pin::new(inner).poll_flush(cx),
-            CompressedAsyncWriter::Lzma(ref mut inner) => Pin::new(inner).poll_flush(cx),
-            CompressedAsyncWriter::Zstd(ref mut inner) => Pin::new(inner).poll_flush(cx),
-            CompressedAsyncWriter::Xz(ref mut inner) => Pin::new(inner).poll_flush(cx),
+            CompressedAsyncWriter::Codec(ref mut inner) => Pin::new(inner).poll_flush(cx),
+            CompressedAsyncWriter::Custom(ref mut inner) => Pin::new(inner).poll_flush(cx),
+            #[cfg(feature = "parallel")]
+            CompressedAsyncWriter::Offloaded(ref mut inner) => Pin::new(inner).poll_flush(cx),
+            #[cfg(all(feature = "parallel", feature = "deflate"))]
+            CompressedAsyncWriter::ParallelDeflate(ref mut inner) => Pin::new(inner).poll_flush(cx),
         }
     }
 
     fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
         match *self {
-            CompressedAsyncWriter::Stored(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
-            CompressedAsyncWriter::Deflate(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
-            CompressedAsyncWriter::Bz(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
-            CompressedAsyncWriter::Lzma(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
-            CompressedAsyncWriter::Zstd(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
-            CompressedAsyncWriter::Xz(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
+            CompressedAsyncWriter::Codec(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
+            CompressedAsyncWriter::Custom(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
+            #[cfg(feature = "parallel")]
+            CompressedAsyncWriter::Offloaded(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
+            #[cfg(all(feature = "parallel", feature = "deflate"))]
+            CompressedAsyncWriter::ParallelDeflate(ref mut inner) => Pin::new(inner).poll_shutdown(cx),
         }
     }
 }
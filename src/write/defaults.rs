@@ -0,0 +1,295 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::backend::{Backends, CompressionBackend};
+use crate::error::Result;
+use crate::spec::compression::Compression;
+use crate::spec::encryption::{AesStrength, Encryption};
+use crate::spec::extra::{alignment_field, ExtraField};
+use crate::spec::header::TEXT_ATTRIBUTE;
+use crate::spec::string::{StringEncoding, ZipString};
+use crate::write::{CompressionPolicy, EntryOptions};
+
+use async_compression::Level;
+use chrono::{DateTime, TimeZone, Utc};
+
+/// The mask of the file type bits of a Unix file mode.
+const UNIX_FILE_TYPE: u16 = 0o170000;
+const UNIX_DIRECTORY: u16 = 0o040000;
+const UNIX_REGULAR_FILE: u16 = 0o100000;
+
+/// The MS-DOS attribute held within the lowest byte of the external file attributes for directories.
+const MS_DOS_DIRECTORY: u32 = 0x10;
+
+/// The level of compression applied by a compression method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompressionLevel {
+    /// The fastest level supported by the method, usually producing larger output.
+    Fastest,
+    /// The method's own default level.
+    #[default]
+    Default,
+    /// The level producing the smallest output supported by the method.
+    Best,
+    /// A method-specific level (eg. 0-9 for Deflate or 1-22 for zstd), clamped to the method's maximum.
+    Precise(u32),
+}
+
+impl CompressionLevel {
+    // Levels are only converted by the encoders of compression methods whose features are enabled.
+    #[cfg_attr(
+        not(any(feature = "deflate", feature = "bzip2", feature = "lzma", feature = "zstd", feature = "xz")),
+        allow(dead_code)
+    )]
+    pub(crate) fn to_level(self) -> Level {
+        match self {
+            CompressionLevel::Fastest => Level::Fastest,
+            CompressionLevel::Default => Level::Default,
+            CompressionLevel::Best => Level::Best,
+            CompressionLevel::Precise(level) => Level::Precise(level),
+        }
+    }
+}
+
+/// The policy used to choose the last modification date of entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimestampPolicy {
+    /// Use the time at which each entry is written.
+    #[default]
+    Now,
+    /// Use a fixed date for every entry.
+    Fixed(DateTime<Utc>),
+    /// Use the date held by the `SOURCE_DATE_EPOCH` environment variable (in seconds since the Unix epoch), falling
+    /// back to the time at which each entry is written if it's unset or invalid.
+    ///
+    /// See the [reproducible builds specification](https://reproducible-builds.org/specs/source-date-epoch/).
+    SourceDateEpoch,
+}
+
+impl TimestampPolicy {
+    /// Returns the date to be used for an entry written now.
+    pub(crate) fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            TimestampPolicy::Now => Utc::now(),
+            TimestampPolicy::Fixed(timestamp) => *timestamp,
+            TimestampPolicy::SourceDateEpoch => std::env::var("SOURCE_DATE_EPOCH")
+                .ok()
+                .and_then(|epoch| epoch.trim().parse::<i64>().ok())
+                .and_then(|epoch| Utc.timestamp_opt(epoch, 0).single())
+                .unwrap_or_else(Utc::now),
+        }
+    }
+}
+
+/// A set of archive-wide defaults applied to every entry whose [`EntryOptions`] don't explicitly override them.
+///
+/// # Example
+/// ```
+/// # use async_zip::{Compression, write::{CompressionLevel, EntryDefaults, TimestampPolicy}};
+/// #
+/// let defaults = EntryDefaults {
+///     compression: Compression::Zstd,
+///     level: CompressionLevel::Precise(19),
+///     timestamp_policy: TimestampPolicy::SourceDateEpoch,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryDefaults {
+    /// The compression method used by entries constructed via [`EntryOptions::named()`].
+    pub compression: Compression,
+    /// The level of compression used by entries which don't set their own.
+    pub level: CompressionLevel,
+    /// The policy used to choose each entry's last modification date.
+    pub timestamp_policy: TimestampPolicy,
+    /// Unix permissions (eg. `0o644`) stored in the external attributes of entries which don't set their own, if any.
+    pub permissions: Option<u16>,
+    /// The policy used to choose the compression method of entries constructed via [`EntryOptions::named()`] from
+    /// their filename's extension, in place of `compression`, if any.
+    pub compression_policy: Option<CompressionPolicy>,
+}
+
+impl Default for EntryDefaults {
+    fn default() -> Self {
+        EntryDefaults {
+            compression: Compression::Deflate,
+            level: CompressionLevel::Default,
+            timestamp_policy: TimestampPolicy::Now,
+            permissions: None,
+            compression_policy: None,
+        }
+    }
+}
+
+impl EntryDefaults {
+    /// Returns the compression method of an entry with the provided filename which doesn't set its own.
+    pub(crate) fn compression_for(&self, filename: &ZipString) -> Compression {
+        match &self.compression_policy {
+            Some(policy) => policy.compression_for(filename.as_str()),
+            None => self.compression,
+        }
+    }
+}
+
+/// A set of entry options with every unset option filled in from a writer's defaults.
+pub(crate) struct ResolvedOptions {
+    pub(crate) filename: ZipString,
+    pub(crate) compression: Compression,
+    pub(crate) level: CompressionLevel,
+    pub(crate) last_modified: DateTime<Utc>,
+    pub(crate) permissions: Option<u16>,
+    pub(crate) text: bool,
+    pub(crate) alignment: Option<u16>,
+    // The entry's own extra field data for its central directory header, and for its local file header.
+    pub(crate) extra: Vec<u8>,
+    pub(crate) local_extra: Vec<u8>,
+    pub(crate) force_zip64: bool,
+    pub(crate) comment: ZipString,
+    #[cfg(feature = "aes")]
+    pub(crate) password: Option<crate::crypto::Password>,
+    #[cfg(feature = "zstd")]
+    pub(crate) zstd_dictionary: Option<crate::zstd_dictionary::ZstdDictionary>,
+    #[cfg(all(feature = "parallel", feature = "deflate"))]
+    pub(crate) parallel: Option<usize>,
+}
+
+impl EntryOptions {
+    /// Fills in every option which this set doesn't explicitly override from the provided defaults.
+    pub(crate) fn resolve(self, defaults: &EntryDefaults) -> Result<ResolvedOptions> {
+        let (mut extra, mut local_extra) = (self.extra.clone(), self.extra);
+        for field in &self.extra_fields {
+            field.write(self.filename.as_bytes(), true, &mut extra)?;
+            field.write(self.filename.as_bytes(), false, &mut local_extra)?;
+        }
+        let compression = self.compression.unwrap_or_else(|| defaults.compression_for(&self.filename));
+
+        Ok(ResolvedOptions {
+            filename: self.filename,
+            compression,
+            level: self.level.unwrap_or(defaults.level),
+            last_modified: self.last_modified.unwrap_or_else(|| defaults.timestamp_policy.timestamp()),
+            permissions: self.permissions.or(defaults.permissions),
+            text: self.text,
+            alignment: self.alignment,
+            extra,
+            local_extra,
+            force_zip64: self.extra_fields.contains(&ExtraField::Zip64),
+            comment: self.comment,
+            #[cfg(feature = "aes")]
+            password: self.password,
+            #[cfg(feature = "zstd")]
+            zstd_dictionary: self.zstd_dictionary,
+            #[cfg(all(feature = "parallel", feature = "deflate"))]
+            parallel: self.parallel,
+        })
+    }
+}
+
+impl ResolvedOptions {
+    /// Returns whether the entry's filename and comment should be flagged as UTF-8 (general purpose flag bit 11).
+    pub(crate) fn unicode(&self) -> bool {
+        self.filename.encoding() == StringEncoding::Utf8 && self.comment.encoding() == StringEncoding::Utf8
+    }
+
+    /// Returns whether the entry's data is to be encrypted.
+    #[cfg(feature = "aes")]
+    pub(crate) fn encrypted(&self) -> bool {
+        self.password.is_some()
+    }
+
+    /// Returns whether the entry's data is to be encrypted.
+    #[cfg(not(feature = "aes"))]
+    pub(crate) fn encrypted(&self) -> bool {
+        false
+    }
+
+    /// Returns the scheme with which the entry's data is to be encrypted, if any.
+    pub(crate) fn encryption(&self) -> Option<Encryption> {
+        self.encrypted().then_some(Encryption::Aes(AesStrength::Aes256))
+    }
+
+    /// Returns the method-specific bits (1 and 2) of the entry's general purpose flag.
+    pub(crate) fn compression_options(&self) -> u8 {
+        match self.compression {
+            // LZMA data is always written with an end marker.
+            #[cfg(feature = "lzma")]
+            Compression::Lzma => 0b01,
+            _ => 0,
+        }
+    }
+
+    /// Returns the backend through which the entry's data is compressed, if not by this crate itself.
+    pub(crate) fn backend<'a>(&'a self, backends: &'a Backends) -> Result<Option<&'a dyn CompressionBackend>> {
+        match self.compression {
+            Compression::Custom(method) => Ok(Some(&**backends.get(method)?)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(self.zstd_dictionary.as_ref().map(|dictionary| dictionary as _)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the compression method to be stored within the entry's headers, which is replaced by a marker if the
+    /// entry is AES-encrypted (the real method being held within the AES extra data field instead).
+    pub(crate) fn header_compression(&self) -> u16 {
+        match self.encrypted() {
+            true => crate::spec::encryption::AES_COMPRESSION_METHOD,
+            false => self.compression.to_u16(),
+        }
+    }
+
+    /// Returns the AES extra data field to be written for the entry, if it's encrypted.
+    pub(crate) fn encryption_extra(&self) -> Vec<u8> {
+        #[cfg(feature = "aes")]
+        if self.encrypted() {
+            use crate::spec::encryption::{AesExtraField, AES_VENDOR_VERSION};
+
+            let compression = self.compression.to_u16();
+            let field = AesExtraField { version: AES_VENDOR_VERSION, strength: AesStrength::Aes256, compression };
+            return field.to_bytes();
+        }
+
+        Vec::new()
+    }
+
+    /// Returns the alignment extra field with which the entry's local file header is padded (given the offset at which
+    /// the field would start), if its data is to be aligned.
+    pub(crate) fn alignment_extra(&self, offset: usize) -> Result<Vec<u8>> {
+        match self.alignment {
+            Some(alignment) if alignment > 1 && self.compression == Compression::Stored && !self.encrypted() => {
+                alignment_field(alignment, offset)
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns the internal attributes value for the entry.
+    pub(crate) fn internal_attributes(&self) -> u16 {
+        match self.text {
+            true => TEXT_ATTRIBUTE,
+            false => 0,
+        }
+    }
+
+    /// Returns the `version made by` and external attributes values for the entry.
+    ///
+    /// Unix permissions without a file type are given one (a directory if the filename ends with a slash, otherwise a
+    /// regular file), and directories also have the MS-DOS directory attribute set.
+    pub(crate) fn attributes(&self) -> (u16, u32) {
+        use crate::spec::version::{made_by, CreatorOs};
+
+        let dir = self.filename.as_bytes().ends_with(b"/");
+        let dos_attributes = match dir {
+            true => MS_DOS_DIRECTORY,
+            false => 0,
+        };
+
+        match self.permissions {
+            Some(permissions) if permissions & UNIX_FILE_TYPE == 0 => {
+                let file_type = if dir { UNIX_DIRECTORY } else { UNIX_REGULAR_FILE };
+                (made_by(CreatorOs::Unix), ((file_type | permissions) as u32) << 16 | dos_attributes)
+            }
+            Some(permissions) => (made_by(CreatorOs::Unix), (permissions as u32) << 16 | dos_attributes),
+            None => (made_by(CreatorOs::MsDos), dos_attributes),
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+#[cfg(feature = "aes")]
+use crate::crypto::aes::AesCipher;
+use crate::error::Result;
+use crate::write::defaults::ResolvedOptions;
+
+use std::borrow::Cow;
+use std::io::{Error, IoSlice};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+#[cfg(feature = "aes")]
+use std::task::ready;
+use tokio::io::AsyncWrite;
+
+/// A writer which encrypts an entry's compressed data before it reaches the inner writer, if the entry is to be
+/// encrypted.
+///
+/// The encryption header is written ahead of the data, and the authentication code once the writer is shut down.
+/// Unencrypted data is passed through untouched.
+pub struct EncryptedAsyncWriter<W: AsyncWrite + Unpin> {
+    writer: W,
+    #[cfg(feature = "aes")]
+    encryption: Option<AesEncryption>,
+}
+
+/// The encryption state of a single AES-encrypted entry.
+#[cfg(feature = "aes")]
+struct AesEncryption {
+    cipher: Option<AesCipher>,
+
+    // Encrypted bytes which have been accepted but not yet written to the inner writer.
+    pending: Vec<u8>,
+    pending_position: usize,
+}
+
+#[cfg(feature = "aes")]
+impl AesEncryption {
+    /// Writes all pending bytes to the inner writer.
+    fn poll_drain<W: AsyncWrite + Unpin>(&mut self, writer: &mut W, cx: &mut Context) -> Poll<std::io::Result<()>> {
+        while self.pending_position < self.pending.len() {
+            let written = ready!(Pin::new(&mut *writer).poll_write(cx, &self.pending[self.pending_position..]))?;
+            if written == 0 {
+                return Poll::Ready(Err(std::io::ErrorKind::WriteZero.into()));
+            }
+            self.pending_position += written;
+        }
+
+        self.pending.clear();
+        self.pending_position = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> EncryptedAsyncWriter<W> {
+    /// Constructs a writer for the data of an entry with the provided options.
+    pub(crate) fn new(writer: W, options: &ResolvedOptions) -> Result<Self> {
+        #[cfg(feature = "aes")]
+        if let Some(password) = &options.password {
+            let (cipher, header) = AesCipher::random(password.as_bytes(), crate::AesStrength::Aes256)?;
+            let encryption = AesEncryption { cipher: Some(cipher), pending: header, pending_position: 0 };
+            return Ok(EncryptedAsyncWriter { writer, encryption: Some(encryption) });
+        }
+
+        #[cfg(not(feature = "aes"))]
+        let _ = options;
+
+        Ok(EncryptedAsyncWriter {
+            writer,
+            #[cfg(feature = "aes")]
+            encryption: None,
+        })
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for EncryptedAsyncWriter<W> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<std::result::Result<usize, Error>> {
+        #[cfg(feature = "aes")]
+        if let EncryptedAsyncWriter { writer, encryption: Some(encryption) } = &mut *self {
+            ready!(encryption.poll_drain(writer, cx))?;
+
+            // The bytes are accepted once encrypted, and written out by the next call which drains them.
+            encryption.pending.extend_from_slice(buf);
+            encryption.cipher.as_mut().unwrap().encrypt(&mut encryption.pending);
+            return Poll::Ready(Ok(buf.len()));
+        }
+
+        Pin::new(&mut self.writer).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::result::Result<usize, Error>> {
+        #[cfg(feature = "aes")]
+        if let EncryptedAsyncWriter { writer, encryption: Some(encryption) } = &mut *self {
+            ready!(encryption.poll_drain(writer, cx))?;
+
+            for buf in bufs {
+                encryption.pending.extend_from_slice(buf);
+            }
+            encryption.cipher.as_mut().unwrap().encrypt(&mut encryption.pending);
+            return Poll::Ready(Ok(encryption.pending.len()));
+        }
+
+        Pin::new(&mut self.writer).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        // Encrypted data is buffered, so all of the buffers are accepted at once.
+        #[cfg(feature = "aes")]
+        if self.encryption.is_some() {
+            return true;
+        }
+
+        self.writer.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
+        #[cfg(feature = "aes")]
+        if let EncryptedAsyncWriter { writer, encryption: Some(encryption) } = &mut *self {
+            ready!(encryption.poll_drain(writer, cx))?;
+        }
+
+        Pin::new(&mut self.writer).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
+        #[cfg(feature = "aes")]
+        if let EncryptedAsyncWriter { writer, encryption: Some(encryption) } = &mut *self {
+            ready!(encryption.poll_drain(writer, cx))?;
+
+            if let Some(cipher) = encryption.cipher.take() {
+                encryption.pending.extend_from_slice(&cipher.authentication_code());
+                ready!(encryption.poll_drain(writer, cx))?;
+            }
+        }
+
+        Pin::new(&mut self.writer).poll_shutdown(cx)
+    }
+}
+
+/// Encrypts an entry's compressed data in full (along with its encryption header and authentication code), if the
+/// entry is to be encrypted.
+pub(crate) fn encrypt_data<'c>(options: &ResolvedOptions, data: &'c [u8]) -> Result<Cow<'c, [u8]>> {
+    #[cfg(feature = "aes")]
+    if let Some(password) = &options.password {
+        let (mut cipher, mut encrypted) = AesCipher::random(password.as_bytes(), crate::AesStrength::Aes256)?;
+        let header_length = encrypted.len();
+
+        encrypted.extend_from_slice(data);
+        cipher.encrypt(&mut encrypted[header_length..]);
+        encrypted.extend_from_slice(&cipher.authentication_code());
+        return Ok(Cow::Owned(encrypted));
+    }
+
+    #[cfg(not(feature = "aes"))]
+    let _ = options;
+
+    Ok(Cow::Borrowed(data))
+}
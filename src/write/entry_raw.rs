@@ -0,0 +1,181 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::error::{Result, ZipError};
+use crate::read::ZipEntry;
+use crate::spec::encryption::AES_COMPRESSION_METHOD;
+use crate::spec::extra::{exceeds_u32, header_value, split_fields, Zip64ExtendedInfo, ZIP64_EXTRA_FIELD_ID};
+use crate::spec::header::{CentralDirectoryHeader, GeneralPurposeFlag, LocalFileHeader};
+use crate::utils::write_all_vectored;
+use crate::write::defaults::{CompressionLevel, ResolvedOptions};
+use crate::write::{push_cd_entry, version_needed, CentralDirectoryEntry, ZipFileWriter};
+
+use std::io::IoSlice;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// A writer which copies an entry's already-compressed (and possibly encrypted) data from another archive as-is.
+pub struct EntryRawWriter<'b, 'c, W: AsyncWrite + Unpin> {
+    writer: &'b mut ZipFileWriter<W>,
+    entry: &'c ZipEntry,
+}
+
+impl<'b, 'c, W: AsyncWrite + Unpin> EntryRawWriter<'b, 'c, W> {
+    pub fn from_raw(writer: &'b mut ZipFileWriter<W>, entry: &'c ZipEntry) -> Self {
+        Self { writer, entry }
+    }
+
+    pub async fn write<R: AsyncRead + Unpin>(self, reader: R) -> Result<()> {
+        let entry = self.entry;
+        self.writer.assert_consistent()?;
+
+        if entry.partial() {
+            return Err(ZipError::PartialEntry);
+        } else if entry.data_descriptor() && entry.offset.is_none() {
+            // Stream-read entries only learn their sizes and CRC32 value from the data descriptor.
+            return Err(ZipError::FeatureNotSupported("Raw copies of stream-read entries with data descriptors"));
+        }
+
+        let (compressed_size, uncompressed_size) = (entry.compressed_size.unwrap(), entry.uncompressed_size.unwrap());
+        let crc = entry.crc32.unwrap_or_default();
+
+        // ZipCrypto verifies passwords against the modification time of entries flagged as having a data descriptor
+        // rather than their CRC32 value, so the flag (and so the descriptor) and the time are kept for such entries.
+        let descriptor = entry.data_descriptor() && entry.encrypted() && entry.aes.is_none();
+        let force = self.writer.force_zip64;
+
+        // As with stream-written entries, the local file header holds zeros when a data descriptor follows the data.
+        let (lfh_crc, lfh_compressed_size, lfh_uncompressed_size) = match descriptor {
+            true => (0, 0, 0),
+            false => (crc, compressed_size, uncompressed_size),
+        };
+        let force_lfh_zip64 = force || (descriptor && (exceeds_u32(compressed_size) || exceeds_u32(uncompressed_size)));
+        let lfh_zip64 = Zip64ExtendedInfo::local(lfh_uncompressed_size, lfh_compressed_size, force_lfh_zip64);
+        let lfh_zip64_bytes = lfh_zip64.to_bytes();
+
+        let extra = split_fields(entry.extra().map(Vec::as_slice).unwrap_or_default(), ZIP64_EXTRA_FIELD_ID)?.1;
+        let local_extra = match entry.local_extra.as_deref() {
+            Some(local_extra) => split_fields(local_extra, ZIP64_EXTRA_FIELD_ID)?.1,
+            None => extra.clone(),
+        };
+        let mut opts = ResolvedOptions {
+            filename: entry.filename().clone(),
+            compression: *entry.compression(),
+            level: CompressionLevel::Default,
+            last_modified: *entry.last_modified(),
+            permissions: None,
+            text: entry.is_text(),
+            alignment: None,
+            local_extra,
+            extra,
+            force_zip64: false,
+            comment: entry.zip_comment().cloned().unwrap_or_default(),
+            #[cfg(feature = "aes")]
+            password: None,
+            #[cfg(feature = "zstd")]
+            zstd_dictionary: None,
+            #[cfg(all(feature = "parallel", feature = "deflate"))]
+            parallel: None,
+        };
+        self.writer.normalise_options(&mut opts);
+        if descriptor {
+            opts.last_modified = *entry.last_modified();
+        }
+
+        let (mod_time, mod_date) = crate::spec::date::chrono_to_zip_time(&opts.last_modified);
+        let compression = match entry.aes {
+            Some(_) => AES_COMPRESSION_METHOD,
+            None => opts.compression.to_u16(),
+        };
+
+        let lf_header = LocalFileHeader {
+            compressed_size: header_value(lfh_zip64.compressed_size, lfh_compressed_size),
+            uncompressed_size: header_value(lfh_zip64.uncompressed_size, lfh_uncompressed_size),
+            compression,
+            crc: lfh_crc,
+            extra_field_length: (lfh_zip64_bytes.len() + opts.local_extra.len()) as u16,
+            file_name_length: opts.filename.len() as u16,
+            mod_time,
+            mod_date,
+            version: version_needed(&opts, !lfh_zip64.is_empty(), entry.encryption()),
+            flags: GeneralPurposeFlag {
+                data_descriptor: descriptor,
+                encrypted: entry.encrypted(),
+                compression_options: entry.compression_options,
+                filename_unicode: opts.unicode(),
+            },
+        };
+
+        let lh_offset = self.writer.writer.offset();
+        self.writer.open_entry = Some(lh_offset);
+        write_all_vectored(
+            &mut self.writer.writer,
+            &mut [
+                IoSlice::new(&crate::spec::delimiter::LFHD.to_le_bytes()),
+                IoSlice::new(&lf_header.to_slice()),
+                IoSlice::new(opts.filename.as_bytes()),
+                IoSlice::new(&lfh_zip64_bytes),
+                IoSlice::new(&opts.local_extra),
+            ],
+        )
+        .await?;
+
+        let copied = tokio::io::copy(&mut reader.take(compressed_size), &mut self.writer.writer).await?;
+        if copied != compressed_size {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+
+        if descriptor {
+            let mut descriptor = crate::spec::delimiter::DDD.to_le_bytes().to_vec();
+            descriptor.extend_from_slice(&crc.to_le_bytes());
+            if !lfh_zip64.is_empty() {
+                descriptor.extend_from_slice(&compressed_size.to_le_bytes());
+                descriptor.extend_from_slice(&uncompressed_size.to_le_bytes());
+            } else {
+                descriptor.extend_from_slice(&(compressed_size as u32).to_le_bytes());
+                descriptor.extend_from_slice(&(uncompressed_size as u32).to_le_bytes());
+            }
+            self.writer.writer.write_all(&descriptor).await?;
+        }
+
+        if let Some(progress) = &self.writer.progress {
+            progress.finish(opts.filename.as_str(), uncompressed_size, compressed_size);
+        }
+
+        let lh_offset = lh_offset as u64;
+        let cdh_zip64 = Zip64ExtendedInfo::central(uncompressed_size, compressed_size, lh_offset, force);
+        let generated_extra = cdh_zip64.to_bytes();
+        let made_by = crate::spec::version::made_by(crate::spec::version::CreatorOs::MsDos);
+        let (v_made_by, exter_attr) = match self.writer.deterministic {
+            Some(_) => opts.attributes(),
+            None => (entry.made_by.unwrap_or(made_by), entry.exter_attr.unwrap_or_default()),
+        };
+
+        let header = CentralDirectoryHeader {
+            v_made_by,
+            v_needed: version_needed(&opts, !cdh_zip64.is_empty() || !lfh_zip64.is_empty(), entry.encryption()),
+            compressed_size: header_value(cdh_zip64.compressed_size, compressed_size),
+            uncompressed_size: header_value(cdh_zip64.uncompressed_size, uncompressed_size),
+            compression,
+            crc,
+            extra_field_length: (generated_extra.len() + opts.extra.len()) as u16,
+            file_name_length: lf_header.file_name_length,
+            file_comment_length: opts.comment.len() as u16,
+            mod_time,
+            mod_date,
+            flags: lf_header.flags,
+            disk_start: 0,
+            inter_attr: entry.inter_attr.unwrap_or_default(),
+            exter_attr,
+            lh_offset: header_value(cdh_zip64.lh_offset, lh_offset),
+        };
+
+        self.writer.writer.drain().await?;
+
+        let entry = CentralDirectoryEntry { header, opts, generated_extra };
+        push_cd_entry(&mut self.writer.cd_entries, entry, self.writer.replace_existing);
+        self.writer.open_entry = None;
+
+        Ok(())
+    }
+}
@@ -1,18 +1,23 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
-use crate::error::Result;
+use crate::error::{Result, ZipError};
+use crate::spec::compression::Compression;
+use crate::spec::extra::{exceeds_u32, header_value, Zip64ExtendedInfo};
 use crate::spec::header::{CentralDirectoryHeader, GeneralPurposeFlag, LocalFileHeader};
+use crate::utils::write_all_vectored;
 use crate::write::compressed_writer::CompressedAsyncWriter;
+use crate::write::defaults::ResolvedOptions;
+use crate::write::encrypted_writer::EncryptedAsyncWriter;
 use crate::write::offset_writer::OffsetAsyncWriter;
-use crate::write::CentralDirectoryEntry;
-use crate::write::{EntryOptions, ZipFileWriter};
+use crate::write::progress::ProgressReporter;
+use crate::write::seekable::Seeker;
+use crate::write::{push_cd_entry, version_needed, CentralDirectoryEntry, ZipFileWriter};
 
-use std::io::Error;
+use std::io::{Error, IoSlice};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use chrono::Utc;
 use crc32fast::Hasher;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
@@ -22,54 +27,141 @@ use tokio::io::{AsyncWrite, AsyncWriteExt};
 /// - This writer cannot be manually constructed; instead, use [`ZipFileWriter::write_entry_stream()`].
 /// - [`EntryStreamWriter::close()`] must be called before a stream writer goes out of scope.
 /// - Utilities for working with [`AsyncWrite`] values are provided by [`AsyncWriteExt`].
-pub struct EntryStreamWriter<'a, 'b, W: AsyncWrite + Unpin> {
-    writer: OffsetAsyncWriter<CompressedAsyncWriter<'b, &'a mut W>>,
+pub struct EntryStreamWriter<'b, W: AsyncWrite + Unpin> {
+    writer: OffsetAsyncWriter<CompressedAsyncWriter<'b, W>>,
     cd_entries: &'b mut Vec<CentralDirectoryEntry>,
-    options: EntryOptions,
+    open_entry: &'b mut Option<usize>,
+    progress: Option<&'b ProgressReporter>,
+    last_progress: u64,
+    options: ResolvedOptions,
     hasher: Hasher,
     lfh: LocalFileHeader,
     lfh_offset: usize,
     data_offset: usize,
+    declared: Option<(u32, u32)>,
+    lfh_zip64: bool,
+    force_zip64: bool,
+    replace_existing: bool,
+    // Set if the local file header is to be patched once the entry is closed (in place of a data descriptor).
+    seeker: Option<Seeker<W>>,
 }
 
-impl<'a, 'b, W: AsyncWrite + Unpin> EntryStreamWriter<'a, 'b, W> {
+impl<'b, W: AsyncWrite + Unpin> EntryStreamWriter<'b, W> {
     pub(crate) async fn from_raw(
-        writer: &'b mut ZipFileWriter<'a, W>,
-        options: EntryOptions,
-    ) -> Result<EntryStreamWriter<'a, 'b, W>> {
+        writer: &'b mut ZipFileWriter<W>,
+        mut options: ResolvedOptions,
+        declared: Option<(u32, u32)>,
+    ) -> Result<EntryStreamWriter<'b, W>> {
+        writer.assert_consistent()?;
+        writer.normalise_options(&mut options);
+
+        if declared.is_some() && options.compression != Compression::Stored {
+            return Err(ZipError::FeatureNotSupported("Declared sizes for compressed entries"));
+        }
+        if declared.is_some() && options.encrypted() {
+            return Err(ZipError::FeatureNotSupported("Declared sizes for encrypted entries"));
+        }
+
         let lfh_offset = writer.writer.offset();
-        let lfh = EntryStreamWriter::write_lfh(writer, &options).await?;
+        writer.open_entry = Some(lfh_offset);
+
+        let seeker = writer.seeker.filter(|_| declared.is_none());
+        let (lfh, lfh_zip64) = EntryStreamWriter::write_lfh(writer, &options, declared, seeker.is_some()).await?;
         let data_offset = writer.writer.offset();
+        let force_zip64 = writer.force_zip64 || options.force_zip64;
+        let replace_existing = writer.replace_existing;
 
         let cd_entries = &mut writer.cd_entries;
-        let writer =
-            OffsetAsyncWriter::from_raw(CompressedAsyncWriter::from_raw(&mut writer.writer, options.compression));
+        let open_entry = &mut writer.open_entry;
+        let progress = writer.progress.as_ref();
+        let (writer_backends, offload) = (&writer.backends, writer.offload_compression);
+        let writer = EncryptedAsyncWriter::new(&mut writer.writer, &options)?;
+        let writer = CompressedAsyncWriter::from_raw(writer, &options, writer_backends, offload)?;
+        let writer = OffsetAsyncWriter::from_raw(writer);
 
-        Ok(EntryStreamWriter { writer, cd_entries, options, lfh, lfh_offset, data_offset, hasher: Hasher::new() })
+        Ok(EntryStreamWriter {
+            writer,
+            cd_entries,
+            open_entry,
+            progress,
+            last_progress: 0,
+            options,
+            lfh,
+            lfh_offset,
+            data_offset,
+            declared,
+            lfh_zip64,
+            force_zip64,
+            replace_existing,
+            seeker,
+            hasher: Hasher::new(),
+        })
     }
 
-    async fn write_lfh(writer: &'b mut ZipFileWriter<'a, W>, options: &EntryOptions) -> Result<LocalFileHeader> {
-        let (mod_time, mod_date) = crate::spec::date::chrono_to_zip_time(&Utc::now());
+    /// Returns the number of compressed bytes which have reached the underlying writer so far.
+    fn compressed_written(&self) -> usize {
+        self.writer.get_ref().get_ref().offset() - self.data_offset
+    }
+
+    /// Reports the entry's progress to the writer's progress reporter, if it has one.
+    fn report_progress(&mut self) {
+        if let Some(progress) = self.progress {
+            let (uncompressed, compressed) = (self.writer.offset() as u64, self.compressed_written() as u64);
+            self.last_progress =
+                progress.update(self.options.filename.as_str(), uncompressed, compressed, self.last_progress);
+        }
+    }
+
+    async fn write_lfh(
+        writer: &mut ZipFileWriter<W>,
+        options: &ResolvedOptions,
+        declared: Option<(u32, u32)>,
+        patched: bool,
+    ) -> Result<(LocalFileHeader, bool)> {
+        let (mod_time, mod_date) = crate::spec::date::chrono_to_zip_time(&options.last_modified);
+        let (size, crc) = declared.unwrap_or((0, 0));
+
+        // When the sizes are unknown, a forced Zip64 extra field holds zeros as the 32-bit fields would have.
+        let zip64 = Zip64ExtendedInfo::local(size.into(), size.into(), writer.force_zip64 || options.force_zip64);
+        let mut generated_extra = zip64.to_bytes();
+        generated_extra.extend_from_slice(&options.encryption_extra());
+
+        // Padding (if any) follows the entry's own fields, directly ahead of its data.
+        let fields_length = options.filename.len() + generated_extra.len() + options.local_extra.len();
+        let padding = options.alignment_extra(writer.writer.offset() + 30 + fields_length)?;
 
         let lfh = LocalFileHeader {
-            compressed_size: 0,
-            uncompressed_size: 0,
-            compression: options.compression.to_u16(),
-            crc: 0,
-            extra_field_length: options.extra.len() as u16,
-            file_name_length: options.filename.as_bytes().len() as u16,
+            compressed_size: header_value(zip64.compressed_size, size.into()),
+            uncompressed_size: header_value(zip64.uncompressed_size, size.into()),
+            compression: options.header_compression(),
+            crc,
+            extra_field_length: (generated_extra.len() + options.local_extra.len() + padding.len()) as u16,
+            file_name_length: options.filename.len() as u16,
             mod_time,
             mod_date,
-            version: 0,
-            flags: GeneralPurposeFlag { data_descriptor: true, encrypted: false },
+            version: version_needed(options, !zip64.is_empty(), options.encryption()),
+            flags: GeneralPurposeFlag {
+                data_descriptor: declared.is_none() && !patched,
+                encrypted: options.encrypted(),
+                compression_options: options.compression_options(),
+                filename_unicode: options.unicode(),
+            },
         };
 
-        writer.writer.write_all(&crate::spec::delimiter::LFHD.to_le_bytes()).await?;
-        writer.writer.write_all(&lfh.to_slice()).await?;
-        writer.writer.write_all(options.filename.as_bytes()).await?;
-        writer.writer.write_all(&options.extra).await?;
+        write_all_vectored(
+            &mut writer.writer,
+            &mut [
+                IoSlice::new(&crate::spec::delimiter::LFHD.to_le_bytes()),
+                IoSlice::new(&lfh.to_slice()),
+                IoSlice::new(options.filename.as_bytes()),
+                IoSlice::new(&generated_extra),
+                IoSlice::new(&options.local_extra),
+                IoSlice::new(&padding),
+            ],
+        )
+        .await?;
 
-        Ok(lfh)
+        Ok((lfh, !zip64.is_empty()))
     }
 
     /// Consumes this entry writer and completes all closing tasks.
@@ -81,54 +173,143 @@ impl<'a, 'b, W: AsyncWrite + Unpin> EntryStreamWriter<'a, 'b, W> {
     /// - Pushing that central directory header to the [`ZipFileWriter`]'s store.
     ///
     /// Failiure to call this function before going out of scope would result in a corrupted ZIP file.
+    ///
+    /// If the entry was opened with declared sizes, an error is returned if the data written doesn't match them. As
+    /// the data can't be unwound, the entry is then left open and must be abandoned via
+    /// [`ZipFileWriter::rollback_open_entry()`].
     pub async fn close(mut self) -> Result<()> {
         self.writer.shutdown().await?;
 
-        let crc = self.hasher.finalize();
-        let uncompressed_size = self.writer.offset() as u32;
+        // AES-encrypted (AE-2) entries omit their CRC32 value as their data is authenticated instead.
+        let crc = match self.options.encrypted() {
+            true => 0,
+            false => self.hasher.finalize(),
+        };
+        let uncompressed_size = self.writer.offset() as u64;
         let inner_writer = self.writer.into_inner().into_inner();
-        let compressed_size = (inner_writer.offset() - self.data_offset) as u32;
+        let compressed_size = (inner_writer.offset() - self.data_offset) as u64;
+
+        match self.declared {
+            Some((size, _)) if u64::from(size) != uncompressed_size => {
+                return Err(ZipError::DeclaredSizeMismatch(size.into(), uncompressed_size))
+            }
+            Some((_, declared_crc)) if declared_crc != crc => return Err(ZipError::CRC32CheckError),
+            Some(_) => {}
+            // Readers tell a 64-bit data descriptor apart by the local file header's Zip64 extra field, so without
+            // one, neither the header nor a descriptor can hold the sizes.
+            None if !self.lfh_zip64 && (exceeds_u32(compressed_size) || exceeds_u32(uncompressed_size)) => {
+                return Err(ZipError::Zip64NotReserved);
+            }
+            None if self.seeker.is_some() => {
+                // The CRC32 value and sizes follow the signature, version, flags, compression, and modification time.
+                let mut fields = crc.to_le_bytes().to_vec();
+                if !self.lfh_zip64 {
+                    fields.extend_from_slice(&(compressed_size as u32).to_le_bytes());
+                    fields.extend_from_slice(&(uncompressed_size as u32).to_le_bytes());
+                }
+
+                // Otherwise, the sizes are held by the Zip64 extra field, which is the first following the filename.
+                let zip64_fields = [uncompressed_size.to_le_bytes(), compressed_size.to_le_bytes()].concat();
+                let zip64_offset = self.lfh_offset + 30 + self.lfh.file_name_length as usize + 4;
 
-        inner_writer.write_all(&crate::spec::delimiter::DDD.to_le_bytes()).await?;
-        inner_writer.write_all(&crc.to_le_bytes()).await?;
-        inner_writer.write_all(&compressed_size.to_le_bytes()).await?;
-        inner_writer.write_all(&uncompressed_size.to_le_bytes()).await?;
+                let mut patches = vec![(self.lfh_offset + 14, &fields[..])];
+                if self.lfh_zip64 {
+                    patches.push((zip64_offset, &zip64_fields[..]));
+                }
 
+                inner_writer.patch(self.seeker.unwrap(), &patches).await?;
+            }
+            None => {
+                let mut descriptor = crate::spec::delimiter::DDD.to_le_bytes().to_vec();
+                descriptor.extend_from_slice(&crc.to_le_bytes());
+
+                // The data descriptor holds 64-bit sizes if the local file header signalled Zip64.
+                if self.lfh_zip64 {
+                    descriptor.extend_from_slice(&compressed_size.to_le_bytes());
+                    descriptor.extend_from_slice(&uncompressed_size.to_le_bytes());
+                } else {
+                    descriptor.extend_from_slice(&(compressed_size as u32).to_le_bytes());
+                    descriptor.extend_from_slice(&(uncompressed_size as u32).to_le_bytes());
+                }
+
+                inner_writer.write_all(&descriptor).await?;
+            }
+        };
+        inner_writer.drain().await?;
+
+        if let Some(progress) = self.progress {
+            progress.finish(self.options.filename.as_str(), uncompressed_size, compressed_size);
+        }
+
+        let lh_offset = self.lfh_offset as u64;
+        let zip64 = Zip64ExtendedInfo::central(uncompressed_size, compressed_size, lh_offset, self.force_zip64);
+        let mut generated_extra = zip64.to_bytes();
+        generated_extra.extend_from_slice(&self.options.encryption_extra());
+
+        let (v_made_by, exter_attr) = self.options.attributes();
         let cdh = CentralDirectoryHeader {
-            compressed_size,
-            uncompressed_size,
+            compressed_size: header_value(zip64.compressed_size, compressed_size),
+            uncompressed_size: header_value(zip64.uncompressed_size, uncompressed_size),
             crc,
-            v_made_by: 0,
-            v_needed: 0,
+            v_made_by,
+            v_needed: version_needed(&self.options, self.lfh_zip64 || !zip64.is_empty(), self.options.encryption()),
             compression: self.lfh.compression,
-            extra_field_length: self.lfh.extra_field_length,
+            extra_field_length: (generated_extra.len() + self.options.extra.len()) as u16,
             file_name_length: self.lfh.file_name_length,
             file_comment_length: self.options.comment.len() as u16,
             mod_time: self.lfh.mod_time,
             mod_date: self.lfh.mod_date,
             flags: self.lfh.flags,
             disk_start: 0,
-            inter_attr: 0,
-            exter_attr: 0,
-            lh_offset: self.lfh_offset as u32,
+            inter_attr: self.options.internal_attributes(),
+            exter_attr,
+            lh_offset: header_value(zip64.lh_offset, lh_offset),
         };
 
-        self.cd_entries.push(CentralDirectoryEntry { header: cdh, opts: self.options });
+        let entry = CentralDirectoryEntry { header: cdh, opts: self.options, generated_extra };
+        push_cd_entry(self.cd_entries, entry, self.replace_existing);
+        *self.open_entry = None;
+
         Ok(())
     }
 }
 
-impl<'a, 'b, W: AsyncWrite + Unpin> AsyncWrite for EntryStreamWriter<'a, 'b, W> {
+impl<'b, W: AsyncWrite + Unpin> AsyncWrite for EntryStreamWriter<'b, W> {
     fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<std::result::Result<usize, Error>> {
         let poll = Pin::new(&mut self.writer).poll_write(cx, buf);
 
         if let Poll::Ready(Ok(written)) = poll {
             self.hasher.update(&buf[0..written]);
+            self.report_progress();
         }
 
         poll
     }
 
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::result::Result<usize, Error>> {
+        let poll = Pin::new(&mut self.writer).poll_write_vectored(cx, bufs);
+
+        if let Poll::Ready(Ok(written)) = poll {
+            let mut remaining = written;
+            for buf in bufs {
+                let length = std::cmp::min(remaining, buf.len());
+                self.hasher.update(&buf[..length]);
+                remaining -= length;
+            }
+            self.report_progress();
+        }
+
+        poll
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.writer.is_write_vectored()
+    }
+
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<std::result::Result<(), Error>> {
         Pin::new(&mut self.writer).poll_flush(cx)
     }
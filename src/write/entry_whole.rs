@@ -1,123 +1,247 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
-use crate::error::Result;
+use crate::backend::{BackendEncoder, Backends};
+use crate::error::{Result, ZipError};
 use crate::spec::compression::Compression;
+use crate::spec::extra::{header_value, Zip64ExtendedInfo};
 use crate::spec::header::{CentralDirectoryHeader, GeneralPurposeFlag, LocalFileHeader};
-use crate::write::{CentralDirectoryEntry, EntryOptions, ZipFileWriter};
+use crate::utils::write_all_vectored;
+use crate::write::compressed_writer::CodecEncoder;
+use crate::write::defaults::ResolvedOptions;
+use crate::write::encrypted_writer::encrypt_data;
+#[cfg(all(feature = "parallel", feature = "deflate"))]
+use crate::write::parallel_deflate::ParallelDeflateEncoder;
+use crate::write::{push_cd_entry, version_needed, CentralDirectoryEntry, CompressionLevel, ZipFileWriter};
 
-use std::io::Cursor;
+use std::borrow::Cow;
+use std::io::{Cursor, IoSlice};
 
-use async_compression::tokio::write::{BzEncoder, DeflateEncoder, LzmaEncoder, XzEncoder, ZstdEncoder};
-use chrono::Utc;
 use crc32fast::Hasher;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
-pub struct EntryWholeWriter<'a, 'b, 'c, W: AsyncWrite + Unpin> {
-    writer: &'b mut ZipFileWriter<'a, W>,
-    opts: EntryOptions,
+pub struct EntryWholeWriter<'b, 'c, W: AsyncWrite + Unpin> {
+    writer: &'b mut ZipFileWriter<W>,
+    opts: ResolvedOptions,
     data: &'c [u8],
 }
 
-impl<'a, 'b, 'c, W: AsyncWrite + Unpin> EntryWholeWriter<'a, 'b, 'c, W> {
-    pub fn from_raw(writer: &'b mut ZipFileWriter<'a, W>, opts: EntryOptions, data: &'c [u8]) -> Self {
+impl<'b, 'c, W: AsyncWrite + Unpin> EntryWholeWriter<'b, 'c, W> {
+    pub fn from_raw(writer: &'b mut ZipFileWriter<W>, opts: ResolvedOptions, data: &'c [u8]) -> Self {
         Self { writer, opts, data }
     }
 
-    pub async fn write(self) -> Result<()> {
-        let mut _compressed_data: Option<Vec<u8>> = None;
-        let compressed_data = match &self.opts.compression {
-            Compression::Stored => self.data,
-            _ => {
-                _compressed_data = Some(compress(&self.opts.compression, self.data).await);
-                _compressed_data.as_ref().unwrap()
-            }
-        };
+    pub async fn write(mut self) -> Result<()> {
+        let (store_if_larger, backends) = (self.writer.store_if_larger, &self.writer.backends);
+        let compressed_data = compress_or_store(&mut self.opts, self.data, backends, store_if_larger).await?;
+        self.write_compressed(&compressed_data).await
+    }
+
+    /// Writes the entry using data which has already been compressed with the entry's compression method.
+    pub(crate) async fn write_compressed(mut self, compressed_data: &[u8]) -> Result<()> {
+        self.writer.normalise_options(&mut self.opts);
+        let compressed_data = encrypt_data(&self.opts, compressed_data)?;
+        self.write_prepared(&compressed_data).await
+    }
+
+    /// Writes the entry using data which has already been compressed and encrypted (if need be), with options which
+    /// have already been normalised by the writer.
+    pub(crate) async fn write_prepared(self, compressed_data: &[u8]) -> Result<()> {
+        self.writer.assert_consistent()?;
+        self.writer.open_entry = Some(self.writer.writer.offset());
+
+        let (mod_time, mod_date) = crate::spec::date::chrono_to_zip_time(&self.opts.last_modified);
+        let (v_made_by, exter_attr) = self.opts.attributes();
 
-        let (mod_time, mod_date) = crate::spec::date::chrono_to_zip_time(&Utc::now());
+        let force = self.writer.force_zip64 || self.opts.force_zip64;
+        let (uncompressed_size, compressed_size) = (self.data.len() as u64, compressed_data.len() as u64);
+        let lh_offset = self.writer.writer.offset() as u64;
+        let GeneratedFields { lfh_zip64, cdh_zip64, lfh_extra, cdh_extra, padding } =
+            GeneratedFields::new(&self.opts, uncompressed_size, compressed_size, lh_offset, force)?;
+
+        // AES-encrypted (AE-2) entries omit their CRC32 value as their data is authenticated instead.
+        let crc = match self.opts.encrypted() {
+            true => 0,
+            false => compute_crc(self.data),
+        };
+        let encrypted = self.opts.encrypted();
 
         let lf_header = LocalFileHeader {
-            compressed_size: compressed_data.len() as u32,
-            uncompressed_size: self.data.len() as u32,
-            compression: self.opts.compression.to_u16(),
-            crc: compute_crc(self.data),
-            extra_field_length: self.opts.extra.len() as u16,
-            file_name_length: self.opts.filename.as_bytes().len() as u16,
+            compressed_size: header_value(lfh_zip64.compressed_size, compressed_size),
+            uncompressed_size: header_value(lfh_zip64.uncompressed_size, uncompressed_size),
+            compression: self.opts.header_compression(),
+            crc,
+            extra_field_length: (lfh_extra.len() + self.opts.local_extra.len() + padding.len()) as u16,
+            file_name_length: self.opts.filename.len() as u16,
             mod_time,
             mod_date,
-            version: 0,
-            flags: GeneralPurposeFlag { data_descriptor: false, encrypted: false },
+            version: version_needed(&self.opts, !lfh_zip64.is_empty(), self.opts.encryption()),
+            flags: GeneralPurposeFlag {
+                data_descriptor: false,
+                encrypted,
+                compression_options: self.opts.compression_options(),
+                filename_unicode: self.opts.unicode(),
+            },
         };
 
         let header = CentralDirectoryHeader {
-            v_made_by: 0,
-            v_needed: 0,
-            compressed_size: lf_header.compressed_size,
-            uncompressed_size: lf_header.uncompressed_size,
+            v_made_by,
+            v_needed: version_needed(
+                &self.opts,
+                !cdh_zip64.is_empty() || !lfh_zip64.is_empty(),
+                self.opts.encryption(),
+            ),
+            compressed_size: header_value(cdh_zip64.compressed_size, compressed_size),
+            uncompressed_size: header_value(cdh_zip64.uncompressed_size, uncompressed_size),
             compression: lf_header.compression,
             crc: lf_header.crc,
-            extra_field_length: lf_header.extra_field_length,
+            extra_field_length: (cdh_extra.len() + self.opts.extra.len()) as u16,
             file_name_length: lf_header.file_name_length,
             file_comment_length: self.opts.comment.len() as u16,
             mod_time: lf_header.mod_time,
             mod_date: lf_header.mod_date,
             flags: lf_header.flags,
             disk_start: 0,
-            inter_attr: 0,
-            exter_attr: 0,
-            lh_offset: self.writer.writer.offset() as u32,
+            inter_attr: self.opts.internal_attributes(),
+            exter_attr,
+            lh_offset: header_value(cdh_zip64.lh_offset, lh_offset),
         };
 
-        self.writer.writer.write_all(&crate::spec::delimiter::LFHD.to_le_bytes()).await?;
-        self.writer.writer.write_all(&lf_header.to_slice()).await?;
-        self.writer.writer.write_all(self.opts.filename.as_bytes()).await?;
-        self.writer.writer.write_all(&self.opts.extra).await?;
-        self.writer.writer.write_all(compressed_data).await?;
+        // The entry's data follows its local file header within the same vectored write.
+        write_all_vectored(
+            &mut self.writer.writer,
+            &mut [
+                IoSlice::new(&crate::spec::delimiter::LFHD.to_le_bytes()),
+                IoSlice::new(&lf_header.to_slice()),
+                IoSlice::new(self.opts.filename.as_bytes()),
+                IoSlice::new(&lfh_extra),
+                IoSlice::new(&self.opts.local_extra),
+                IoSlice::new(&padding),
+                IoSlice::new(compressed_data),
+            ],
+        )
+        .await?;
+
+        if let Some(progress) = &self.writer.progress {
+            progress.finish(self.opts.filename.as_str(), self.data.len() as u64, compressed_data.len() as u64);
+        }
+
+        self.writer.writer.drain().await?;
 
-        self.writer.cd_entries.push(CentralDirectoryEntry { header, opts: self.opts });
+        let entry = CentralDirectoryEntry { header, opts: self.opts, generated_extra: cdh_extra };
+        push_cd_entry(&mut self.writer.cd_entries, entry, self.writer.replace_existing);
+        self.writer.open_entry = None;
 
         Ok(())
     }
 }
 
-async fn compress(compression: &Compression, data: &[u8]) -> Vec<u8> {
-    // TODO: Reduce reallocations of Vec by making a lower-bound estimate of the length reduction and
-    // pre-initialising the Vec to that length. Then truncate() to the actual number of bytes written.
+/// The fields which the writer generates for a whole entry, alongside those provided via its options.
+pub(crate) struct GeneratedFields {
+    lfh_zip64: Zip64ExtendedInfo,
+    cdh_zip64: Zip64ExtendedInfo,
+    // The Zip64 and AES extra fields of the entry's local file header and central directory header.
+    lfh_extra: Vec<u8>,
+    cdh_extra: Vec<u8>,
+    // The alignment padding (if any) which follows the entry's own local extra field data.
+    padding: Vec<u8>,
+}
+
+impl GeneratedFields {
+    /// Generates the fields of an entry whose local file header is to be written at the provided offset.
+    ///
+    /// The entry's options must already have been normalised, and its compressed size include any encryption overhead.
+    pub(crate) fn new(
+        opts: &ResolvedOptions,
+        uncompressed_size: u64,
+        compressed_size: u64,
+        lh_offset: u64,
+        force_zip64: bool,
+    ) -> Result<Self> {
+        let lfh_zip64 = Zip64ExtendedInfo::local(uncompressed_size, compressed_size, force_zip64);
+        let cdh_zip64 = Zip64ExtendedInfo::central(uncompressed_size, compressed_size, lh_offset, force_zip64);
+        let (mut lfh_extra, mut cdh_extra) = (lfh_zip64.to_bytes(), cdh_zip64.to_bytes());
+        lfh_extra.extend_from_slice(&opts.encryption_extra());
+        cdh_extra.extend_from_slice(&opts.encryption_extra());
+
+        // Padding (if any) follows the entry's own fields, directly ahead of its data.
+        let fields_length = opts.filename.len() + lfh_extra.len() + opts.local_extra.len();
+        let padding = opts.alignment_extra(lh_offset as usize + 30 + fields_length)?;
+
+        Ok(GeneratedFields { lfh_zip64, cdh_zip64, lfh_extra, cdh_extra, padding })
+    }
+
+    /// Returns the length of the entry's local file header, including its data.
+    pub(crate) fn local_length(&self, opts: &ResolvedOptions, compressed_size: u64) -> u64 {
+        let fields_length = opts.filename.len() + self.lfh_extra.len() + opts.local_extra.len() + self.padding.len();
+        30 + fields_length as u64 + compressed_size
+    }
+
+    /// Returns the length of the entry's central directory header.
+    pub(crate) fn central_length(&self, opts: &ResolvedOptions) -> u64 {
+        46 + (opts.filename.len() + self.cdh_extra.len() + opts.extra.len() + opts.comment.len()) as u64
+    }
+}
+
+/// Compresses data with the provided method, borrowing the data as-is if it's to be stored.
+pub(crate) async fn compress_data<'c>(
+    options: &ResolvedOptions,
+    data: &'c [u8],
+    backends: &Backends,
+) -> Result<Cow<'c, [u8]>> {
+    let (compression, level) = (&options.compression, options.level);
+    if let Some(backend) = options.backend(backends)? {
+        let mut writer = BackendEncoder::new(Cursor::new(Vec::new()), backend, level)?;
+        writer.write_all(data).await?;
+        writer.shutdown().await?;
+        return Ok(Cow::Owned(writer.into_inner().into_inner()));
+    }
+    #[cfg(all(feature = "parallel", feature = "deflate"))]
+    if let (Compression::Deflate, Some(threads)) = (compression, options.parallel) {
+        let mut writer = ParallelDeflateEncoder::new(Cursor::new(Vec::new()), level, threads);
+        writer.write_all(data).await?;
+        writer.shutdown().await?;
+        return Ok(Cow::Owned(writer.into_inner().into_inner()));
+    }
+
     match compression {
-        Compression::Deflate => {
-            let mut writer = DeflateEncoder::new(Cursor::new(Vec::new()));
-            writer.write_all(data).await.unwrap();
-            writer.shutdown().await.unwrap();
-            writer.into_inner().into_inner()
-        }
-        Compression::Bz => {
-            let mut writer = BzEncoder::new(Cursor::new(Vec::new()));
-            writer.write_all(data).await.unwrap();
-            writer.shutdown().await.unwrap();
-            writer.into_inner().into_inner()
-        }
-        Compression::Lzma => {
-            let mut writer = LzmaEncoder::new(Cursor::new(Vec::new()));
-            writer.write_all(data).await.unwrap();
-            writer.shutdown().await.unwrap();
-            writer.into_inner().into_inner()
-        }
-        Compression::Xz => {
-            let mut writer = XzEncoder::new(Cursor::new(Vec::new()));
-            writer.write_all(data).await.unwrap();
-            writer.shutdown().await.unwrap();
-            writer.into_inner().into_inner()
-        }
-        Compression::Zstd => {
-            let mut writer = ZstdEncoder::new(Cursor::new(Vec::new()));
-            writer.write_all(data).await.unwrap();
-            writer.shutdown().await.unwrap();
-            writer.into_inner().into_inner()
+        Compression::Stored => Ok(Cow::Borrowed(data)),
+        Compression::Deflate64 => Err(ZipError::FeatureNotSupported("Deflate64 compression")),
+        Compression::Shrink | Compression::Implode => {
+            Err(ZipError::FeatureNotSupported("Shrink and Implode compression"))
         }
-        _ => unreachable!(),
+        Compression::Ppmd => Err(ZipError::FeatureNotSupported("PPMd compression")),
+        _ => Ok(Cow::Owned(compress(compression, level, data).await?)),
     }
 }
 
+/// Compresses data as with [`compress_data()`], but stores it instead (updating the entry's method) if enabled and the
+/// compressed data would be larger.
+pub(crate) async fn compress_or_store<'c>(
+    options: &mut ResolvedOptions,
+    data: &'c [u8],
+    backends: &Backends,
+    store_if_larger: bool,
+) -> Result<Cow<'c, [u8]>> {
+    let compressed_data = compress_data(options, data, backends).await?;
+
+    if store_if_larger && compressed_data.len() > data.len() {
+        options.compression = Compression::Stored;
+        return Ok(Cow::Borrowed(data));
+    }
+
+    Ok(compressed_data)
+}
+
+async fn compress(compression: &Compression, level: CompressionLevel, data: &[u8]) -> Result<Vec<u8>> {
+    // TODO: Reduce reallocations of Vec by making a lower-bound estimate of the length reduction and
+    // pre-initialising the Vec to that length. Then truncate() to the actual number of bytes written.
+    let mut writer = CodecEncoder::new(Cursor::new(Vec::new()), *compression, level)?;
+    writer.write_all(data).await?;
+    writer.shutdown().await?;
+    Ok(writer.into_inner().into_inner())
+}
+
 fn compute_crc(data: &[u8]) -> u32 {
     let mut hasher = Hasher::new();
     hasher.update(data);
@@ -0,0 +1,59 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::error::Result;
+use crate::utils::path_error;
+use crate::write::ZipFileWriter;
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tokio::fs::{File, OpenOptions};
+use tokio::io::BufWriter;
+
+impl ZipFileWriter<BufWriter<File>> {
+    /// Creates (or truncates) the file at the provided path and constructs a new ZIP file writer which owns it, with
+    /// buffering.
+    pub async fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path).await.map_err(path_error(path))?;
+
+        Ok(Self::new(BufWriter::new(file)))
+    }
+
+    /// Creates the file at the provided path and constructs a new ZIP file writer which owns it, with buffering.
+    ///
+    /// Unlike [`ZipFileWriter::create()`], an error is returned if a file already exists at the path.
+    pub async fn create_new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new().write(true).create_new(true).open(path).await.map_err(path_error(path))?;
+
+        Ok(Self::new(BufWriter::new(file)))
+    }
+
+    /// Constructs a new ZIP file writer which writes to a temporary file alongside the provided path, and only
+    /// renames it over the path once [`ZipFileWriter::close()`] has succeeded.
+    ///
+    /// A process which crashes (or a writer which is dropped or fails) before closing therefore never leaves a
+    /// partially written archive at the destination path, though the temporary file is left behind.
+    pub async fn create_atomic(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let temp_path = temp_path(path);
+        let file =
+            OpenOptions::new().write(true).create_new(true).open(&temp_path).await.map_err(path_error(&temp_path))?;
+
+        let mut writer = Self::new(BufWriter::new(file));
+        writer.rename_on_close = Some((temp_path, path.to_path_buf()));
+
+        Ok(writer)
+    }
+}
+
+/// Returns a unique hidden path in the same directory as the provided path (so that it can be renamed atomically).
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let nanos =
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.subsec_nanos()).unwrap_or_default();
+
+    path.with_file_name(format!(".{}.{}-{}.tmp", name, std::process::id(), nanos))
+}
@@ -0,0 +1,86 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Conversion of the `.lzma` ("LZMA alone") framing written by `async-compression` into ZIP's LZMA framing.
+//!
+//! The `.lzma` header (the five bytes of properties followed by the eight-byte uncompressed size) is replaced by the
+//! LZMA SDK's version, the length of the properties, and the properties themselves. As the encoder always terminates
+//! its data with an end marker, entries are flagged as such (via bit 1 of the general purpose flag).
+
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::AsyncWrite;
+
+/// The length of the LZMA properties.
+const PROPERTIES_LENGTH: usize = 5;
+/// The length of the `.lzma` header (the properties, followed by the uncompressed size).
+const LZMA_HEADER_LENGTH: usize = PROPERTIES_LENGTH + 8;
+/// The version of the LZMA SDK recorded within the ZIP header (9.20, as with 7-Zip and Info-ZIP).
+const SDK_VERSION: [u8; 2] = [9, 20];
+
+/// A writer which replaces the `.lzma` header of the data written to it with a ZIP LZMA header.
+pub(crate) struct LzmaHeaderWriter<W> {
+    inner: W,
+    header: Vec<u8>,
+
+    // The ZIP header yet to be written to the inner writer, once the `.lzma` header has been written in full.
+    pending: Vec<u8>,
+}
+
+impl<W: AsyncWrite + Unpin> LzmaHeaderWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        LzmaHeaderWriter { inner, header: Vec::with_capacity(LZMA_HEADER_LENGTH), pending: Vec::new() }
+    }
+
+    pub(crate) fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn poll_pending(&mut self, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        while !self.pending.is_empty() {
+            let written = ready!(Pin::new(&mut self.inner).poll_write(c, &self.pending))?;
+            if written == 0 {
+                return Poll::Ready(Err(tokio::io::ErrorKind::WriteZero.into()));
+            }
+            self.pending.drain(..written);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for LzmaHeaderWriter<W> {
+    fn poll_write(mut self: Pin<&mut Self>, c: &mut Context<'_>, buf: &[u8]) -> Poll<tokio::io::Result<usize>> {
+        if self.header.len() < LZMA_HEADER_LENGTH {
+            let length = std::cmp::min(LZMA_HEADER_LENGTH - self.header.len(), buf.len());
+            self.header.extend_from_slice(&buf[..length]);
+
+            if self.header.len() == LZMA_HEADER_LENGTH {
+                let mut pending = SDK_VERSION.to_vec();
+                pending.extend_from_slice(&(PROPERTIES_LENGTH as u16).to_le_bytes());
+                pending.extend_from_slice(&self.header[..PROPERTIES_LENGTH]);
+                self.pending = pending;
+            }
+
+            return Poll::Ready(Ok(length));
+        }
+
+        ready!(self.poll_pending(c))?;
+        Pin::new(&mut self.inner).poll_write(c, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        ready!(self.poll_pending(c))?;
+        Pin::new(&mut self.inner).poll_flush(c)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        ready!(self.poll_pending(c))?;
+        Pin::new(&mut self.inner).poll_shutdown(c)
+    }
+}
@@ -44,119 +44,869 @@
 //! # }
 //! ```
 
+pub(crate) mod append;
 pub(crate) mod compressed_writer;
+pub(crate) mod defaults;
+pub(crate) mod encrypted_writer;
+pub(crate) mod entry_raw;
 pub(crate) mod entry_stream;
 pub(crate) mod entry_whole;
+pub(crate) mod file;
+#[cfg(feature = "lzma")]
+pub(crate) mod lzma;
+#[cfg(feature = "parallel")]
+pub(crate) mod offloaded;
 pub(crate) mod offset_writer;
+#[cfg(feature = "parallel")]
+pub(crate) mod parallel;
+#[cfg(all(feature = "parallel", feature = "deflate"))]
+pub(crate) mod parallel_deflate;
+pub(crate) mod policy;
+pub(crate) mod progress;
+pub(crate) mod seekable;
+pub(crate) mod sharded;
+#[cfg(feature = "sink")]
+pub(crate) mod sink;
+#[cfg(feature = "stream")]
+pub(crate) mod streaming;
 
+pub use defaults::{CompressionLevel, EntryDefaults, TimestampPolicy};
 pub use entry_stream::EntryStreamWriter;
+pub use policy::CompressionPolicy;
+pub use progress::EntryProgress;
+pub use sharded::{OversizedEntry, ShardSummary, ShardedZipWriter};
+#[cfg(feature = "sink")]
+pub use sink::{ZipSink, ZipStreamSink};
+#[cfg(feature = "stream")]
+pub use streaming::ArchiveStream;
 
-use crate::error::Result;
+use crate::backend::{Backends, CompressionBackend};
+use crate::error::{Result, ZipError};
+use crate::read::{seek, ZipEntry};
 use crate::spec::compression::Compression;
-use crate::spec::header::{CentralDirectoryHeader, EndOfCentralDirectoryHeader};
+use crate::spec::encryption::Encryption;
+use crate::spec::extra::ExtraField;
+use crate::spec::header::{
+    CentralDirectoryHeader, EndOfCentralDirectoryHeader, Zip64EndOfCentralDirectoryLocator,
+    Zip64EndOfCentralDirectoryRecord,
+};
+use crate::spec::string::{StringEncoding, ZipString};
+use crate::utils::{field_length, write_all_vectored};
+use defaults::ResolvedOptions;
+use entry_raw::EntryRawWriter;
 use entry_whole::EntryWholeWriter;
 use offset_writer::OffsetAsyncWriter;
+use progress::ProgressReporter;
+use seekable::Seeker;
 
-use tokio::io::{AsyncWrite, AsyncWriteExt};
+use std::io::IoSlice;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite, AsyncWriteExt};
+use tokio::sync::watch::Sender;
 
 /// A set of options for opening new ZIP entries.
+///
+/// Options which aren't explicitly set are taken from the writer's [`EntryDefaults`].
 pub struct EntryOptions {
-    filename: String,
-    compression: Compression,
+    filename: ZipString,
+    compression: Option<Compression>,
+    level: Option<CompressionLevel>,
     extra: Vec<u8>,
-    comment: String,
+    extra_fields: Vec<ExtraField>,
+    comment: ZipString,
+    permissions: Option<u16>,
+    last_modified: Option<DateTime<Utc>>,
+    text: bool,
+    alignment: Option<u16>,
+    #[cfg(feature = "aes")]
+    password: Option<crate::crypto::Password>,
+    #[cfg(feature = "zstd")]
+    zstd_dictionary: Option<crate::zstd_dictionary::ZstdDictionary>,
+    #[cfg(all(feature = "parallel", feature = "deflate"))]
+    parallel: Option<usize>,
 }
 
 impl EntryOptions {
     /// Construct a new set of options from its required constituents.
-    pub fn new(filename: String, compression: Compression) -> Self {
-        EntryOptions { filename, compression, extra: Vec::new(), comment: String::new() }
+    pub fn new(filename: impl Into<ZipString>, compression: Compression) -> Self {
+        Self::named(filename).compression(compression)
+    }
+
+    /// Construct a new set of options which uses the writer's default compression method.
+    ///
+    /// The filename may be any [`ZipString`], so names which aren't UTF-8 can be written with their exact bytes.
+    pub fn named(filename: impl Into<ZipString>) -> Self {
+        let filename = filename.into();
+        EntryOptions {
+            filename,
+            compression: None,
+            level: None,
+            extra: Vec::new(),
+            extra_fields: Vec::new(),
+            comment: ZipString::default(),
+            permissions: None,
+            last_modified: None,
+            text: false,
+            alignment: None,
+            #[cfg(feature = "aes")]
+            password: None,
+            #[cfg(feature = "zstd")]
+            zstd_dictionary: None,
+            #[cfg(all(feature = "parallel", feature = "deflate"))]
+            parallel: None,
+        }
+    }
+
+    /// Consume the options and override the compression method.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Consume the options and override the level of compression.
+    pub fn level(mut self, level: CompressionLevel) -> Self {
+        self.level = Some(level);
+        self
     }
 
     /// Consume the options and override the extra field data.
+    ///
+    /// The data is written as-is to both the local file header and central directory header, ahead of any fields
+    /// added via [`EntryOptions::extra_field()`].
     pub fn extra(mut self, extra: Vec<u8>) -> Self {
         self.extra = extra;
         self
     }
 
+    /// Consume the options and add a typed extra field, which is encoded appropriately for each of the entry's headers.
+    pub fn extra_field(mut self, field: ExtraField) -> Self {
+        self.extra_fields.push(field);
+        self
+    }
+
     /// Consume the options and override the file comment.
-    pub fn comment(mut self, comment: String) -> Self {
-        self.comment = comment;
+    pub fn comment(mut self, comment: impl Into<ZipString>) -> Self {
+        self.comment = comment.into();
+        self
+    }
+
+    /// Consume the options and override the Unix permissions (eg. `0o755`) stored in the entry's external attributes.
+    ///
+    /// The permissions may also include a file type (eg. `0o100755`); otherwise, the entry is marked as a directory if
+    /// its filename ends with a slash, and as a regular file if not. Entries with permissions are marked as created by
+    /// Unix (in their `version made by` value), so that extractors restore them.
+    pub fn permissions(mut self, permissions: u16) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    /// Consume the options and override the last modification date (eg. to preserve a file's original date), in place
+    /// of the one chosen by the writer's [`TimestampPolicy`].
+    ///
+    /// Dates are stored with the 2-second precision of MS-DOS dates, and are clamped to the range they can represent
+    /// (1980 to 2107).
+    pub fn last_modified(mut self, last_modified: DateTime<Utc>) -> Self {
+        self.last_modified = Some(last_modified);
+        self
+    }
+
+    /// Consume the options and override whether the entry is flagged as holding text (bit 0 of its internal file
+    /// attributes, unset by default), which some extractors use to convert its line endings.
+    ///
+    /// The flag is only stored, and the entry's data is written as-is regardless.
+    pub fn text(mut self, text: bool) -> Self {
+        self.text = text;
+        self
+    }
+
+    /// Consume the options and override the alignment of the entry's data (see [`ZipFileWriter::alignment()`]).
+    pub fn alignment(mut self, alignment: u16) -> Self {
+        self.alignment = Some(alignment);
+        self
+    }
+
+    /// Consume the options and encrypt the entry's data with the provided password, using WinZip's AES-256 encryption
+    /// scheme (AE-2).
+    ///
+    /// As AE-2 entries are authenticated instead, the entry's CRC32 value is omitted. Stream-written entries can't
+    /// also have declared sizes, and are only readable by seekable readers (not by [`read::stream`]).
+    ///
+    /// [`read::stream`]: crate::read::stream
+    #[cfg(feature = "aes")]
+    pub fn password(mut self, password: impl AsRef<[u8]>) -> Self {
+        self.password = Some(crate::crypto::Password::new(password.as_ref()));
+        self
+    }
+
+    /// Consume the options and compress the entry with the provided zstd dictionary, if it's compressed with zstd.
+    ///
+    /// Dictionaries considerably improve the compression of many small but similar entries. Readers must be given the
+    /// same dictionary (via [`ReaderOptions::zstd_dictionary()`]) to decompress the entry.
+    ///
+    /// [`ReaderOptions::zstd_dictionary()`]: crate::read::ReaderOptions::zstd_dictionary
+    #[cfg(feature = "zstd")]
+    pub fn zstd_dictionary(mut self, dictionary: impl Into<std::sync::Arc<[u8]>>) -> Self {
+        self.zstd_dictionary = Some(crate::zstd_dictionary::ZstdDictionary(dictionary.into()));
+        self
+    }
+
+    /// Consume the options and compress the entry across up to `threads` threads, if it's compressed with Deflate.
+    ///
+    /// As with pigz, the entry's data is split into 128 KiB blocks which are compressed independently on Tokio's
+    /// blocking thread pool and joined into a single Deflate stream, so that compressing very large entries makes use
+    /// of multiple cores. The output is slightly larger than if the data were compressed as a whole.
+    ///
+    /// # Panics
+    /// Writing the entry panics if this is set outside of a Tokio runtime.
+    #[cfg(all(feature = "parallel", feature = "deflate"))]
+    pub fn parallel(mut self, threads: usize) -> Self {
+        self.parallel = Some(threads);
         self
     }
 }
 
 pub(crate) struct CentralDirectoryEntry {
     pub header: CentralDirectoryHeader,
-    pub opts: EntryOptions,
+    pub opts: ResolvedOptions,
+    // Extra fields generated by the writer (ie. Zip64 and AES), which precede the entry's own extra field data.
+    pub generated_extra: Vec<u8>,
+}
+
+/// A mutable handle to an entry whose data has been written but whose central directory record is still pending.
+///
+/// Only fields which don't affect the entry's data may be amended (ie. not its sizes, CRC32 value, or offset). Any
+/// changes are applied when the central directory is written by [`ZipFileWriter::close()`].
+pub struct PendingEntry<'a> {
+    entry: &'a mut CentralDirectoryEntry,
+}
+
+impl<'a> PendingEntry<'a> {
+    /// Returns the entry's filename.
+    pub fn filename(&self) -> &str {
+        self.entry.opts.filename.as_str()
+    }
+
+    /// Overrides the entry's comment.
+    ///
+    /// The entry's local file header has already declared whether its filename and comment are UTF-8, so a non-ASCII
+    /// comment of the other encoding is rejected with [`ZipError::CommentEncodingMismatch`].
+    pub fn set_comment(&mut self, comment: impl Into<ZipString>) -> Result<()> {
+        let comment = comment.into();
+        let unicode = comment.encoding() == StringEncoding::Utf8;
+        if unicode != self.entry.header.flags.filename_unicode && !comment.as_bytes().is_ascii() {
+            return Err(ZipError::CommentEncodingMismatch);
+        }
+
+        self.entry.header.file_comment_length = field_length(comment.len())?;
+        self.entry.opts.comment = comment;
+        Ok(())
+    }
+
+    /// Overrides the entry's external file attributes.
+    pub fn set_external_attributes(&mut self, attributes: u32) {
+        self.entry.header.exter_attr = attributes;
+    }
+
+    /// Overrides the extra field data stored in the entry's central directory record.
+    ///
+    /// The extra field data already written into the entry's local file header is unaffected.
+    pub fn set_extra(&mut self, extra: Vec<u8>) -> Result<()> {
+        self.entry.header.extra_field_length = field_length(self.entry.generated_extra.len() + extra.len())?;
+        self.entry.opts.extra = extra;
+        Ok(())
+    }
+}
+
+/// Adds an entry's central directory record, in place of the first existing record with the same filename if replacing.
+pub(crate) fn push_cd_entry(cd_entries: &mut Vec<CentralDirectoryEntry>, entry: CentralDirectoryEntry, replace: bool) {
+    let existing =
+        replace.then(|| cd_entries.iter().position(|existing| existing.opts.filename == entry.opts.filename));
+
+    match existing.flatten() {
+        Some(index) => cd_entries[index] = entry,
+        None => cd_entries.push(entry),
+    }
+}
+
+/// Returns the `version needed to extract` of an entry: the highest version required by any of the features it uses
+/// (its compression method, whether it's a directory, whether it uses Zip64 structures, and how it's encrypted).
+pub(crate) fn version_needed(options: &ResolvedOptions, zip64: bool, encryption: Option<Encryption>) -> u16 {
+    use crate::spec::version::*;
+
+    let base = match options.filename.as_bytes().ends_with(b"/") {
+        true => V20_VERSION_NEEDED,
+        false => DEFAULT_VERSION_NEEDED,
+    };
+    let encryption = match encryption {
+        Some(Encryption::ZipCrypto) => V20_VERSION_NEEDED,
+        Some(Encryption::Aes(_)) => AES_VERSION_NEEDED,
+        None => 0,
+    };
+    let zip64 = if zip64 { ZIP64_VERSION_NEEDED } else { 0 };
+
+    [base, compression_version_needed(&options.compression), encryption, zip64].into_iter().max().unwrap()
 }
 
 /// A ZIP file writer which acts over AsyncWrite implementers.
 ///
 /// # Note
 /// - [`ZipFileWriter::close()`] must be called before a stream writer goes out of scope.
-pub struct ZipFileWriter<'a, W: AsyncWrite + Unpin> {
-    pub(crate) writer: OffsetAsyncWriter<&'a mut W>,
+pub struct ZipFileWriter<W: AsyncWrite + Unpin> {
+    pub(crate) writer: OffsetAsyncWriter<W>,
     pub(crate) cd_entries: Vec<CentralDirectoryEntry>,
-    comment_opt: Option<String>,
+    pub(crate) progress: Option<ProgressReporter>,
+    pub(crate) open_entry: Option<usize>,
+    pub(crate) poisoned: bool,
+    pub(crate) rename_on_close: Option<(PathBuf, PathBuf)>,
+    pub(crate) defaults: EntryDefaults,
+    pub(crate) force_zip64: bool,
+    pub(crate) replace_existing: bool,
+    pub(crate) store_if_larger: bool,
+    pub(crate) offload_compression: bool,
+    pub(crate) backends: Backends,
+    // The length of the output before it was rewound (eg. the archive appended to), and whether the archive appended
+    // to ended with Zip64 structures which must be kept.
+    pub(crate) original_len: Option<u64>,
+    pub(crate) zip64_eocd: bool,
+    pub(crate) comment_opt: Option<Vec<u8>>,
+    pub(crate) seeker: Option<Seeker<W>>,
+    pub(crate) deterministic: Option<DateTime<Utc>>,
+    pub(crate) alignment: u16,
+    pub(crate) signing_block: Option<Vec<u8>>,
 }
 
-impl<'a, W: AsyncWrite + Unpin> ZipFileWriter<'a, W> {
-    /// Construct a new ZIP file writer from a mutable reference to a writer.
-    pub fn new(writer: &'a mut W) -> Self {
-        Self { writer: OffsetAsyncWriter::from_raw(writer), cd_entries: Vec::new(), comment_opt: None }
+impl<W: AsyncWrite + Unpin> ZipFileWriter<W> {
+    /// Construct a new ZIP file writer from a writer (or a mutable reference to one).
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: OffsetAsyncWriter::from_raw(writer),
+            cd_entries: Vec::new(),
+            progress: None,
+            open_entry: None,
+            poisoned: false,
+            rename_on_close: None,
+            defaults: EntryDefaults::default(),
+            force_zip64: false,
+            replace_existing: false,
+            store_if_larger: false,
+            offload_compression: false,
+            backends: Backends::default(),
+            original_len: None,
+            zip64_eocd: false,
+            comment_opt: None,
+            seeker: None,
+            deterministic: None,
+            alignment: 1,
+            signing_block: None,
+        }
+    }
+
+    /// Report the progress of each subsequently written entry via a watch channel.
+    ///
+    /// Updates are published at most once every `interval` uncompressed bytes, with a final update carrying the
+    /// entry's totals published when it's closed. As updates are only ever stored in the channel, the writer is never
+    /// blocked by a slow or dropped receiver.
+    pub fn progress(&mut self, sender: Sender<EntryProgress>, interval: u64) {
+        self.progress = Some(ProgressReporter::new(sender, interval));
+    }
+
+    /// Set the defaults applied to every subsequently written entry whose options don't explicitly override them.
+    pub fn default_options(&mut self, defaults: EntryDefaults) {
+        self.defaults = defaults;
+    }
+
+    /// Write Zip64 structures for every subsequently written entry and the end of central directory, even when all
+    /// sizes and offsets fit within their 32-bit limits.
+    ///
+    /// Zip64 structures are otherwise written automatically, but only where a limit is exceeded. The local file header
+    /// of an entry of unknown size is written before its size is known, so it only has a Zip64 extra field (which
+    /// signals that its sizes, or those of its data descriptor, are 64-bit) when forced. Such entries which may exceed
+    /// 4 GiB must be written with Zip64 forced (here or via [`ExtraField::Zip64`]), as [`ZipError::Zip64NotReserved`]
+    /// is otherwise returned when they're closed.
+    pub fn force_zip64(&mut self, force: bool) {
+        self.force_zip64 = force;
+    }
+
+    /// Replace any existing entry with the same filename as each subsequently written entry (ie. update mode).
+    ///
+    /// Once the new entry has been written, the replaced entry's central directory record is dropped and the new
+    /// entry's record takes its place (so the indices of pending entries are unchanged). This is primarily useful
+    /// alongside [`ZipFileWriter::append()`] to update entries of an existing archive. The replaced entry's data
+    /// remains within the archive, so whilst readers which rely on the central directory only see the new entry,
+    /// sequential readers (eg. [`read::stream`]) will encounter both.
+    ///
+    /// [`read::stream`]: crate::read::stream
+    pub fn replace_existing(&mut self, replace: bool) {
+        self.replace_existing = replace;
+    }
+
+    /// Store each subsequently written entry instead if compressing it would make its data larger (eg. for data which
+    /// is already compressed, such as JPEG images or MP4 videos), as Info-ZIP does.
+    ///
+    /// As an entry's data must be compressed before it can be compared, this only applies to entries written via
+    /// [`ZipFileWriter::write_entry_whole()`]; entries written via [`ZipFileWriter::write_entry_stream()`] are always
+    /// compressed with their chosen method.
+    pub fn store_if_larger(&mut self, store: bool) {
+        self.store_if_larger = store;
+    }
+
+    /// Compress each subsequently stream-written entry on Tokio's blocking thread pool, rather than within calls to
+    /// [`EntryStreamWriter`]'s `poll_write()` on the executor, so that compressing large entries doesn't starve other
+    /// tasks.
+    ///
+    /// Data is compressed in chunks of up to 256 KiB, each by a blocking task whilst the next is buffered. Stored
+    /// entries aren't affected, nor are those written via [`ZipFileWriter::write_entry_whole()`] (see
+    /// [`ZipFileWriter::write_entries_parallel()`] instead).
+    ///
+    /// # Panics
+    /// Writing to entries panics if this is enabled outside of a Tokio runtime.
+    #[cfg(feature = "parallel")]
+    pub fn offload_compression(&mut self, offload: bool) {
+        self.offload_compression = offload;
+    }
+
+    /// Register a backend through which subsequently written entries compressed with its method are written.
+    ///
+    /// Backends are only consulted for methods without built-in support (ie. [`Compression::Custom`]), and replace any
+    /// previously registered for the same method. Writing an entry whose method has no backend fails with
+    /// [`ZipError::UnsupportedCompressionError`].
+    pub fn compression_backend(&mut self, backend: impl CompressionBackend + 'static) {
+        self.backends.register(std::sync::Arc::new(backend));
+    }
+
+    /// Write the archive reproducibly, such that writing the same entries (with the same options) always produces an
+    /// identical archive, or not if `None` (the default).
+    ///
+    /// Every subsequently written entry's last modification date is fixed to the provided date (overriding those set
+    /// via [`EntryOptions::last_modified()`] or preserved from a directory tree's metadata), and its Unix permissions
+    /// are dropped so that its external attributes only depend on whether it's a directory. When the archive is closed,
+    /// its central directory records are written in order of their filenames rather than in the order the entries were
+    /// written.
+    ///
+    /// Entries copied from other archives (see [`ZipFileWriter::copy_entry_raw()`]) are normalised likewise, except
+    /// that ZipCrypto-encrypted entries with data descriptors keep their modification dates, which their passwords are
+    /// verified against. Extra fields (eg. [`ExtraField::ExtendedTimestamp`]) are written as provided, and
+    /// AES-encrypted entries are never reproducible as they're encrypted with a random salt.
+    pub fn deterministic(&mut self, timestamp: Option<DateTime<Utc>>) {
+        self.deterministic = timestamp;
+    }
+
+    /// Align the data of each subsequently written Stored entry to a multiple of the provided number of bytes from the
+    /// start of the archive (one, the default, disables it), as Android's `zipalign` does.
+    ///
+    /// Android requires the data of uncompressed entries within APKs to be aligned to 4 bytes (or to 4096 bytes for
+    /// shared libraries), so that it can be memory-mapped directly. Local file headers are padded via an extra field
+    /// with the same header ID (0xd935) as written by `zipalign` and `apksigner`. Compressed and encrypted entries
+    /// aren't aligned, nor are entries copied from other archives. Individual entries may override the alignment via
+    /// [`EntryOptions::alignment()`].
+    pub fn alignment(&mut self, alignment: u16) {
+        self.alignment = alignment;
+    }
+
+    /// Write the provided APK signing block directly ahead of the central directory when the archive is closed, or no
+    /// block if `None` (the default, unless appending to an archive which has one).
+    ///
+    /// The block is written as-is, so its signatures only remain valid if the entries they cover are unchanged. See
+    /// [`seek::ZipFileReader::signing_block()`] to read the block of another archive.
+    pub fn signing_block(&mut self, block: Option<Vec<u8>>) {
+        self.signing_block = block;
+    }
+
+    /// Applies writer-wide settings (its alignment, and its reproducible mode if enabled) to the options of an entry
+    /// about to be written.
+    pub(crate) fn normalise_options(&self, options: &mut ResolvedOptions) {
+        options.alignment = options.alignment.or(Some(self.alignment));
+        if let Some(timestamp) = self.deterministic {
+            options.last_modified = timestamp;
+            options.permissions = None;
+        }
+    }
+
+    /// Coalesce writes to the inner writer within a buffer of the provided size (zero, the default, disables it).
+    ///
+    /// Each entry's headers, filename, extra fields, and data are otherwise written separately, so writing many small
+    /// entries to an unbuffered writer (eg. a [`File`]) makes many small writes. The buffer is drained to the inner
+    /// writer once each entry has been written, and when the archive is closed. Writes at least as large as the buffer
+    /// bypass it.
+    ///
+    /// [`File`]: tokio::fs::File
+    pub fn output_buffer_size(&mut self, size: usize) {
+        self.writer.set_buffer_capacity(size);
     }
 
     /// Write a new ZIP entry of known size and data.
+    ///
+    /// As the data is compressed before anything is written, the local file header is written complete with the
+    /// entry's CRC32 value and sizes (without a data descriptor), whether or not the writer is seekable.
     pub async fn write_entry_whole(&mut self, options: EntryOptions, data: &[u8]) -> Result<()> {
+        let options = options.resolve(&self.defaults)?;
         EntryWholeWriter::from_raw(self, options, data).write().await
     }
 
+    /// Write a new directory entry with the provided options' filename.
+    ///
+    /// A trailing slash is appended to the filename if it doesn't already end with one, and the entry is written as an
+    /// empty Stored entry with the MS-DOS directory attribute set (along with the Unix directory file type, if the
+    /// options have permissions), so that extractors create it as an empty directory.
+    pub async fn write_dir_entry(&mut self, options: EntryOptions) -> Result<()> {
+        let mut options = options.compression(Compression::Stored);
+        if !options.filename.as_bytes().ends_with(b"/") {
+            let mut filename = options.filename.as_bytes().to_vec();
+            filename.push(b'/');
+            options.filename = ZipString::new(filename, options.filename.encoding());
+        }
+
+        self.write_entry_whole(options, &[]).await
+    }
+
+    /// Write a new ZIP entry of known size and data (as with [`ZipFileWriter::write_entry_whole()`]) from a buffer
+    /// such as [`Bytes`].
+    ///
+    /// Contiguous buffers are compressed and written directly from their memory without being copied. Otherwise (eg.
+    /// for a chain of buffers), the data is first gathered into a single buffer.
+    ///
+    /// [`Bytes`]: bytes::Bytes
+    #[cfg(feature = "bytes")]
+    pub async fn write_entry_whole_buf(&mut self, options: EntryOptions, mut data: impl bytes::Buf) -> Result<()> {
+        if data.chunk().len() == data.remaining() {
+            return self.write_entry_whole(options, data.chunk()).await;
+        }
+
+        let data = data.copy_to_bytes(data.remaining());
+        self.write_entry_whole(options, &data).await
+    }
+
+    /// Write new ZIP entries of known size and data, compressing up to `concurrency` of them at once on Tokio's
+    /// blocking thread pool.
+    ///
+    /// Entries are written in the order provided and are identical to those written via
+    /// [`ZipFileWriter::write_entry_whole()`], but compressing them on separate threads makes use of multiple cores.
+    /// At most `concurrency` entries' compressed data is held in memory at once (with a minimum of one).
+    ///
+    /// # Panics
+    /// Panics if called outside of a Tokio runtime.
+    #[cfg(feature = "parallel")]
+    pub async fn write_entries_parallel<D>(
+        &mut self,
+        entries: impl IntoIterator<Item = (EntryOptions, D)>,
+        concurrency: usize,
+    ) -> Result<()>
+    where
+        D: AsRef<[u8]> + Send + 'static,
+    {
+        parallel::write_entries(self, entries, concurrency).await
+    }
+
+    /// Copy an entry from another archive without decompressing and recompressing its data.
+    ///
+    /// The reader must yield the entry's data exactly as it's stored within the source archive (eg. via
+    /// [`seek::ZipFileReader::raw_entry_reader()`]). The entry's name, comment, compression method, CRC32 value, sizes,
+    /// modification date, attributes, and extra fields are all carried over, with any Zip64 extra field regenerated.
+    /// Encrypted entries remain encrypted with their original password.
+    ///
+    /// The local file header is given the extra fields of the source's local file header where the entry holds them
+    /// (ie. entries returned by [`seek::ZipFileReader::raw_entry()`] or read by a stream reader), and those of its
+    /// central directory header otherwise.
+    ///
+    /// [`seek::ZipFileReader::raw_entry_reader()`]: crate::read::seek::ZipFileReader::raw_entry_reader
+    /// [`seek::ZipFileReader::raw_entry()`]: crate::read::seek::ZipFileReader::raw_entry
+    pub async fn copy_entry_raw<R: AsyncRead + Unpin>(&mut self, entry: &ZipEntry, reader: R) -> Result<()> {
+        EntryRawWriter::from_raw(self, entry).write(reader).await
+    }
+
+    /// Copy each entry of another archive for which the filter returns true, in order and without decompressing and
+    /// recompressing their data (see [`ZipFileWriter::copy_entry_raw()`]).
+    pub async fn copy_entries<R>(
+        &mut self,
+        reader: &mut seek::ZipFileReader<R>,
+        mut filter: impl FnMut(&ZipEntry) -> bool,
+    ) -> Result<()>
+    where
+        R: AsyncRead + AsyncSeek + Unpin,
+    {
+        for index in 0..reader.entries().len() {
+            if filter(&reader.entries()[index]) {
+                let (entry, raw_reader) = reader.raw_entry(index).await?;
+                self.copy_entry_raw(&entry, raw_reader).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copy every complete entry of another archive, skipping those reported as partial, and return the number of
+    /// entries copied.
+    ///
+    /// Along with [`seek::ZipFileReader::with_recovery()`] (or [`seek::ZipFileReader::recover()`]), this rebuilds a
+    /// damaged archive with a fresh central directory, much like `zip -FF`.
+    ///
+    /// [`seek::ZipFileReader::with_recovery()`]: crate::read::seek::ZipFileReader::with_recovery
+    /// [`seek::ZipFileReader::recover()`]: crate::read::seek::ZipFileReader::recover
+    pub async fn repair_from<R>(&mut self, reader: &mut seek::ZipFileReader<R>) -> Result<usize>
+    where
+        R: AsyncRead + AsyncSeek + Unpin,
+    {
+        let complete = reader.entries().iter().filter(|entry| !entry.partial()).count();
+        self.copy_entries(reader, |entry| !entry.partial()).await?;
+        Ok(complete)
+    }
+
+    /// Copy every entry of another archive except those with the provided filenames, without decompressing and
+    /// recompressing their data.
+    ///
+    /// As ZIP files can't be shrunk in place, this is how entries are removed: the remaining entries are copied into a
+    /// new archive, which then replaces the original.
+    pub async fn remove_entries<R>(&mut self, reader: &mut seek::ZipFileReader<R>, filenames: &[&str]) -> Result<()>
+    where
+        R: AsyncRead + AsyncSeek + Unpin,
+    {
+        self.copy_entries(reader, |entry| !filenames.contains(&entry.name())).await
+    }
+
     /// Write an entry of unknown size and data via streaming (ie. using a data descriptor).
-    pub async fn write_entry_stream<'b>(&'b mut self, options: EntryOptions) -> Result<EntryStreamWriter<'a, 'b, W>> {
-        EntryStreamWriter::from_raw(self, options).await
+    ///
+    /// An entry which may exceed 4 GiB must be written with Zip64 forced (see [`ZipFileWriter::force_zip64()`]).
+    pub async fn write_entry_stream(&mut self, options: EntryOptions) -> Result<EntryStreamWriter<'_, W>> {
+        let options = options.resolve(&self.defaults)?;
+        EntryStreamWriter::from_raw(self, options, None).await
+    }
+
+    /// Write an entry of known size and CRC32 value via streaming, without buffering its data or using a data
+    /// descriptor.
+    ///
+    /// The local file header is written complete up front, so this is currently only supported for Stored entries
+    /// (whose compressed size is known). The data written is verified against the declared values when the entry is
+    /// closed.
+    pub async fn write_entry_stream_with_sizes(
+        &mut self,
+        options: EntryOptions,
+        uncompressed_size: u32,
+        crc: u32,
+    ) -> Result<EntryStreamWriter<'_, W>> {
+        let options = options.resolve(&self.defaults)?;
+        EntryStreamWriter::from_raw(self, options, Some((uncompressed_size, crc))).await
+    }
+
+    /// Write an entry via streaming (as with [`ZipFileWriter::write_entry_stream()`]) with data copied from the
+    /// provided reader until it reaches EOF, closing the entry once it has.
+    ///
+    /// Returns the number of (uncompressed) bytes copied into the entry. If reading or writing fails, the entry is left
+    /// open and must be abandoned via [`ZipFileWriter::rollback_open_entry()`].
+    pub async fn write_entry_from_reader<R>(&mut self, options: EntryOptions, reader: &mut R) -> Result<u64>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        let mut entry_writer = self.write_entry_stream(options).await?;
+        let copied = tokio::io::copy(reader, &mut entry_writer).await?;
+        entry_writer.close().await?;
+
+        Ok(copied)
+    }
+
+    /// Abandons an entry which failed part-way through being written so that further entries can be written.
+    ///
+    /// An entry is left open if its [`EntryStreamWriter`] is dropped without being closed (eg. because the source of
+    /// its data failed), or if writing a whole entry returned an error. Until this function is called, attempts to
+    /// write further entries or close the writer will return [`ZipError::EntryStillOpen`].
+    ///
+    /// The abandoned entry is omitted from the central directory. If the writer was constructed via
+    /// [`ZipFileWriter::new_seekable()`], it seeks back to the start of the entry's local file header so that its
+    /// partially written data is overwritten by what follows. The target can't be truncated through these traits, so
+    /// if the finished archive is shorter than the data already written, [`ZipError::TruncationRequired`] is returned
+    /// once it has been closed. Otherwise, the partially written data remains in the output, so readers which rely on
+    /// the central directory are unaffected, whereas sequential readers will encounter it.
+    ///
+    /// If the inner writer itself returned an error, the number of bytes it actually accepted is unknown and the
+    /// archive can't be recovered, so [`ZipError::WriterPoisoned`] is returned here and from all further calls.
+    pub async fn rollback_open_entry(&mut self) -> Result<()> {
+        if self.writer.errored() {
+            self.poisoned = true;
+        }
+        if self.poisoned {
+            return Err(ZipError::WriterPoisoned);
+        }
+
+        if let (Some(seeker), Some(lfh_offset)) = (self.seeker, self.open_entry) {
+            let len = self.writer.offset() as u64;
+            self.original_len = Some(self.original_len.map_or(len, |original_len| original_len.max(len)));
+
+            if let Err(error) = self.writer.rewind(seeker, lfh_offset).await {
+                self.poisoned = true;
+                return Err(error.into());
+            }
+        }
+
+        self.open_entry = None;
+        Ok(())
+    }
+
+    /// Returns an error if the writer isn't in a consistent state where a new entry may be written.
+    pub(crate) fn assert_consistent(&self) -> Result<()> {
+        if self.poisoned {
+            return Err(ZipError::WriterPoisoned);
+        }
+        if self.open_entry.is_some() {
+            return Err(ZipError::EntryStillOpen);
+        }
+
+        Ok(())
+    }
+
+    /// Returns a handle to amend the central directory record of a previously written entry at the provided index.
+    pub fn pending_entry_mut(&mut self, index: usize) -> Option<PendingEntry<'_>> {
+        self.cd_entries.get_mut(index).map(|entry| PendingEntry { entry })
+    }
+
+    /// Returns a handle to amend the central directory record of the first previously written entry with the
+    /// provided filename.
+    pub fn pending_entry_by_name_mut(&mut self, filename: &str) -> Option<PendingEntry<'_>> {
+        self.cd_entries.iter_mut().find(|entry| entry.opts.filename == *filename).map(|entry| PendingEntry { entry })
     }
 
     /// Set the ZIP file comment.
     pub fn comment(&mut self, comment: String) {
-        self.comment_opt = Some(comment);
+        self.comment_opt = Some(comment.into_bytes());
+    }
+
+    /// Consumes this ZIP writer, setting the ZIP file comment (held within the end of central directory header) and
+    /// completing all closing tasks (see [`ZipFileWriter::close()`]).
+    pub async fn close_with_comment(mut self, comment: impl Into<ZipString>) -> Result<W> {
+        self.comment_opt = Some(comment.into().into_bytes());
+        self.close().await
     }
 
     /// Consumes this ZIP writer and completes all closing tasks.
     ///
     /// This includes:
     /// - Writing all central directroy headers.
+    /// - Writing the Zip64 end of central directory record and locator (if required).
     /// - Writing the end of central directory header.
     /// - Writing the file comment.
+    /// - Flushing the inner writer.
+    /// - Renaming the temporary file over its destination (if constructed via [`ZipFileWriter::create_atomic()`]).
     ///
     /// Failiure to call this function before going out of scope would result in a corrupted ZIP file.
-    pub async fn close(mut self) -> Result<()> {
+    ///
+    /// The inner writer is returned once the archive is complete, positioned at its end, so that further data may be
+    /// written after it (eg. a detached signature).
+    pub async fn close(mut self) -> Result<W> {
+        self.assert_consistent()?;
+        let comment = self.comment_opt.take().unwrap_or_default();
+        let file_comm_length = field_length(comment.len())?;
+
+        if let Some(block) = &self.signing_block {
+            self.writer.write_all(block).await?;
+        }
         let cd_offset = self.writer.offset();
 
+        if self.deterministic.is_some() {
+            self.cd_entries.sort_by(|a, b| a.opts.filename.as_bytes().cmp(b.opts.filename.as_bytes()));
+        }
         for entry in &self.cd_entries {
-            self.writer.write_all(&crate::spec::delimiter::CDFHD.to_le_bytes()).await?;
-            self.writer.write_all(&entry.header.to_slice()).await?;
-            self.writer.write_all(entry.opts.filename.as_bytes()).await?;
-            self.writer.write_all(&entry.opts.extra).await?;
-            self.writer.write_all(entry.opts.comment.as_bytes()).await?;
+            write_all_vectored(
+                &mut self.writer,
+                &mut [
+                    IoSlice::new(&crate::spec::delimiter::CDFHD.to_le_bytes()),
+                    IoSlice::new(&entry.header.to_slice()),
+                    IoSlice::new(entry.opts.filename.as_bytes()),
+                    IoSlice::new(&entry.generated_extra),
+                    IoSlice::new(&entry.opts.extra),
+                    IoSlice::new(entry.opts.comment.as_bytes()),
+                ],
+            )
+            .await?;
         }
 
+        let num_of_entries = self.cd_entries.len() as u64;
+        let (cd_offset, cd_size) = (cd_offset as u64, (self.writer.offset() - cd_offset) as u64);
+
+        // Where a value doesn't fit (or Zip64 is forced), the EOCDH holds a sentinel and the Zip64 record holds it.
+        let force = self.force_zip64 || self.zip64_eocd;
+        let saturate_u16 = |value: u64| match force || value >= u16::MAX as u64 {
+            true => u16::MAX,
+            false => value as u16,
+        };
+        let saturate_u32 = |value: u64| match force || crate::spec::extra::exceeds_u32(value) {
+            true => crate::spec::extra::ZIP64_SENTINEL,
+            false => value as u32,
+        };
+
         let header = EndOfCentralDirectoryHeader {
             disk_num: 0,
             start_cent_dir_disk: 0,
-            num_of_entries_disk: self.cd_entries.len() as u16,
-            num_of_entries: self.cd_entries.len() as u16,
-            size_cent_dir: (self.writer.offset() - cd_offset) as u32,
-            cent_dir_offset: cd_offset as u32,
-            file_comm_length: self.comment_opt.as_ref().map(|v| v.len() as u16).unwrap_or_default(),
+            num_of_entries_disk: saturate_u16(num_of_entries),
+            num_of_entries: saturate_u16(num_of_entries),
+            size_cent_dir: saturate_u32(cd_size),
+            cent_dir_offset: saturate_u32(cd_offset),
+            file_comm_length,
         };
 
-        self.writer.write_all(&crate::spec::delimiter::EOCDD.to_le_bytes()).await?;
-        self.writer.write_all(&header.to_slice()).await?;
-        if let Some(comment) = self.comment_opt {
-            self.writer.write_all(comment.as_bytes()).await?;
+        if header.num_of_entries == u16::MAX
+            || header.size_cent_dir == crate::spec::extra::ZIP64_SENTINEL
+            || header.cent_dir_offset == crate::spec::extra::ZIP64_SENTINEL
+        {
+            let record_offset = self.writer.offset() as u64;
+            let record = Zip64EndOfCentralDirectoryRecord {
+                size_of_record: 44,
+                v_made_by: crate::spec::version::made_by(crate::spec::version::CreatorOs::MsDos),
+                v_needed: crate::spec::version::ZIP64_VERSION_NEEDED,
+                disk_num: 0,
+                start_cent_dir_disk: 0,
+                num_of_entries_disk: num_of_entries,
+                num_of_entries,
+                size_cent_dir: cd_size,
+                cent_dir_offset: cd_offset,
+            };
+            let locator = Zip64EndOfCentralDirectoryLocator { start_record_disk: 0, record_offset, total_disks: 1 };
+
+            write_all_vectored(
+                &mut self.writer,
+                &mut [
+                    IoSlice::new(&crate::spec::delimiter::ZIP64_EOCDRD.to_le_bytes()),
+                    IoSlice::new(&record.to_slice()),
+                    IoSlice::new(&crate::spec::delimiter::ZIP64_EOCDLD.to_le_bytes()),
+                    IoSlice::new(&locator.to_slice()),
+                ],
+            )
+            .await?;
         }
 
-        Ok(())
+        write_all_vectored(
+            &mut self.writer,
+            &mut [
+                IoSlice::new(&crate::spec::delimiter::EOCDD.to_le_bytes()),
+                IoSlice::new(&header.to_slice()),
+                IoSlice::new(&comment),
+            ],
+        )
+        .await?;
+
+        self.writer.flush().await?;
+
+        if let Some(original_len) = self.original_len {
+            let len = self.writer.offset() as u64;
+            if len < original_len {
+                return Err(ZipError::TruncationRequired(len));
+            }
+        }
+
+        if let Some((temp_path, path)) = self.rename_on_close.take() {
+            // The file has been flushed (so has no write in flight), and may be renamed whilst it's still open.
+            tokio::fs::rename(&temp_path, &path).await.map_err(crate::utils::path_error(&path))?;
+        }
+
+        Ok(self.writer.into_inner())
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<W: futures_io::AsyncWrite + Unpin> ZipFileWriter<crate::compat::Compat<W>> {
+    /// Construct a new ZIP file writer from a writer implementing the `futures-io` traits rather than tokio's (or a
+    /// mutable reference to one).
+    pub fn from_futures_io(writer: W) -> Self {
+        Self::new(crate::compat::Compat::new(writer))
     }
 }
@@ -0,0 +1,218 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Compression of stream-written entries on Tokio's blocking thread pool.
+//!
+//! Data is buffered into chunks which are each compressed by a blocking task whilst the next is buffered, so at most
+//! two chunks of input (and one chunk's compressed data) are held at once. As the encoder writes into memory, the
+//! blocking task never waits on the entry's underlying writer.
+
+use crate::backend::{lock, BackendWriter, Backends};
+use crate::error::Result;
+use crate::write::compressed_writer::CodecEncoder;
+use crate::write::defaults::ResolvedOptions;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+
+/// The amount of input buffered before it's handed to a blocking task to be compressed.
+const CHUNK_SIZE: usize = 256 * 1024;
+
+type Encoder = Box<dyn AsyncWrite + Unpin + Send>;
+
+/// The operation performed on an encoder by a blocking task once it has written a chunk of input.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Operation {
+    Write,
+    Flush,
+    Shutdown,
+}
+
+enum State {
+    // Held within a mutex (which is never locked, as it's only accessed mutably) so that entry writers remain `Sync`.
+    Idle(Mutex<Encoder>),
+    Busy(JoinHandle<tokio::io::Result<Encoder>>),
+    Finished,
+    // The kind and message of the error which the encoder failed with, which is returned by every later call.
+    Failed(tokio::io::ErrorKind, String),
+}
+
+/// An encoder which compresses an entry's data on Tokio's blocking thread pool.
+pub(crate) struct OffloadedEncoder<W> {
+    inner: W,
+    state: State,
+    output: Arc<Mutex<Vec<u8>>>,
+    input: Vec<u8>,
+
+    // Whether a flush (or shutdown) has been handed to a blocking task which is yet to complete.
+    dispatched: bool,
+
+    // Compressed data taken from the encoder, which is yet to be written to the inner writer.
+    pending: Vec<u8>,
+    pending_position: usize,
+}
+
+impl<W: AsyncWrite + Unpin> OffloadedEncoder<W> {
+    pub(crate) fn new(inner: W, options: &ResolvedOptions, backends: &Backends) -> Result<Self> {
+        let (writer, output) = BackendWriter::new();
+        let encoder = encoder(writer, options, backends)?;
+
+        Ok(OffloadedEncoder {
+            inner,
+            state: State::Idle(Mutex::new(encoder)),
+            output,
+            input: Vec::new(),
+            dispatched: false,
+            pending: Vec::new(),
+            pending_position: 0,
+        })
+    }
+
+    pub(crate) fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Waits for any blocking task to complete, and writes all compressed data taken from the encoder to the inner
+    /// writer.
+    fn poll_idle(&mut self, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        if let Some(error) = self.failure() {
+            return Poll::Ready(Err(error));
+        }
+        if let State::Busy(handle) = &mut self.state {
+            let encoder = match ready!(Pin::new(handle).poll(c)) {
+                Ok(encoder) => encoder,
+                Err(error) => std::panic::resume_unwind(error.into_panic()),
+            };
+
+            match encoder {
+                Ok(encoder) => self.state = State::Idle(Mutex::new(encoder)),
+                Err(error) => {
+                    self.state = State::Failed(error.kind(), error.to_string());
+                    return Poll::Ready(Err(error));
+                }
+            }
+        }
+
+        loop {
+            if self.pending_position == self.pending.len() {
+                self.pending.clear();
+                self.pending_position = 0;
+                std::mem::swap(&mut self.pending, &mut lock(&self.output));
+
+                if self.pending.is_empty() {
+                    return Poll::Ready(Ok(()));
+                }
+            }
+
+            let written = ready!(Pin::new(&mut self.inner).poll_write(c, &self.pending[self.pending_position..]))?;
+            if written == 0 {
+                return Poll::Ready(Err(tokio::io::ErrorKind::WriteZero.into()));
+            }
+            self.pending_position += written;
+        }
+    }
+
+    /// Returns an error if the encoder has previously failed.
+    fn failure(&self) -> Option<tokio::io::Error> {
+        match &self.state {
+            State::Failed(kind, message) => Some(tokio::io::Error::new(*kind, message.as_str())),
+            _ => None,
+        }
+    }
+
+    /// Hands the buffered input to a blocking task to be compressed, once any previous task has completed.
+    fn poll_dispatch(&mut self, c: &mut Context<'_>, operation: Operation) -> Poll<tokio::io::Result<()>> {
+        ready!(self.poll_idle(c))?;
+
+        let mut encoder = match std::mem::replace(&mut self.state, State::Finished) {
+            State::Idle(encoder) => encoder.into_inner().unwrap_or_else(PoisonError::into_inner),
+            _ => return Poll::Ready(Err(finished_error())),
+        };
+        let input = std::mem::take(&mut self.input);
+        let handle = Handle::current();
+
+        self.state = State::Busy(tokio::task::spawn_blocking(move || {
+            handle.block_on(async {
+                encoder.write_all(&input).await?;
+                match operation {
+                    Operation::Write => (),
+                    Operation::Flush => encoder.flush().await?,
+                    Operation::Shutdown => encoder.shutdown().await?,
+                }
+                Ok(encoder)
+            })
+        }));
+
+        Poll::Ready(Ok(()))
+    }
+
+    /// Performs a flush or shutdown of the encoder, and waits for it to complete.
+    fn poll_operation(&mut self, c: &mut Context<'_>, operation: Operation) -> Poll<tokio::io::Result<()>> {
+        if matches!(self.state, State::Finished) {
+            return Poll::Ready(Ok(()));
+        }
+        if !self.dispatched {
+            ready!(self.poll_dispatch(c, operation))?;
+            self.dispatched = true;
+        }
+
+        ready!(self.poll_idle(c))?;
+        self.dispatched = false;
+
+        if operation == Operation::Shutdown {
+            self.state = State::Finished;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for OffloadedEncoder<W> {
+    fn poll_write(mut self: Pin<&mut Self>, c: &mut Context<'_>, buf: &[u8]) -> Poll<tokio::io::Result<usize>> {
+        if let Some(error) = self.failure() {
+            return Poll::Ready(Err(error));
+        }
+        if matches!(self.state, State::Finished) {
+            return Poll::Ready(Err(finished_error()));
+        }
+        if self.input.len() == CHUNK_SIZE {
+            ready!(self.poll_dispatch(c, Operation::Write))?;
+        }
+
+        let length = std::cmp::min(CHUNK_SIZE - self.input.len(), buf.len());
+        self.input.extend_from_slice(&buf[..length]);
+        Poll::Ready(Ok(length))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        ready!(self.poll_operation(c, Operation::Flush))?;
+        Pin::new(&mut self.inner).poll_flush(c)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        ready!(self.poll_operation(c, Operation::Shutdown))?;
+        Pin::new(&mut self.inner).poll_shutdown(c)
+    }
+}
+
+fn finished_error() -> tokio::io::Error {
+    tokio::io::Error::other("attempted to write after the encoder was shut down")
+}
+
+/// Constructs an encoder for the entry's compression method which writes into memory.
+fn encoder(writer: BackendWriter, options: &ResolvedOptions, backends: &Backends) -> Result<Encoder> {
+    if let Some(backend) = options.backend(backends)? {
+        return backend.encoder(writer, options.level);
+    }
+
+    Ok(Box::new(CodecEncoder::new(writer, options.compression, options.level)?))
+}
@@ -1,25 +1,90 @@
 // Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
 // MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
 
-use std::io::Error;
+use crate::write::seekable::Seeker;
+
+use std::io::{Error, ErrorKind, IoSlice, SeekFrom};
 use std::pin::Pin;
-use std::task::{Context, Poll};
+use std::task::{ready, Context, Poll};
 
-use tokio::io::AsyncWrite;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
 /// An async writer wrapper which tracks the current byte offset.
 ///
 /// This type is only ever used internally to track offsets needed for central directory headers, and to easily
 /// calculate compressed & uncompressed file sizes.
+///
+/// Writes may optionally be coalesced within a buffer (see [`OffsetAsyncWriter::set_buffer_capacity()`]), in which case
+/// the offset includes any bytes which are buffered but yet to be written to the inner writer.
 pub struct OffsetAsyncWriter<W: AsyncWrite + Unpin> {
     writer: W,
     offset: usize,
+    errored: bool,
+
+    // Buffered data which is yet to be written to the inner writer, from `drained` onwards.
+    buffer: Vec<u8>,
+    capacity: usize,
+    drained: usize,
 }
 
 impl<W: AsyncWrite + Unpin> OffsetAsyncWriter<W> {
     /// Constructs a new offset writer from a generic writer implementing AsyncWrite.
     pub fn from_raw(writer: W) -> Self {
-        Self { writer, offset: 0 }
+        Self::with_offset(writer, 0)
+    }
+
+    /// Constructs a new offset writer from a generic writer which is already positioned at the provided offset.
+    pub fn with_offset(writer: W, offset: usize) -> Self {
+        Self { writer, offset, errored: false, buffer: Vec::new(), capacity: 0, drained: 0 }
+    }
+
+    /// Sets the capacity of the buffer within which smaller writes are coalesced (zero, the default, disables it).
+    ///
+    /// Writes at least as large as the capacity bypass the buffer once it has been drained.
+    pub fn set_buffer_capacity(&mut self, capacity: usize) {
+        self.buffer.reserve(capacity.saturating_sub(self.buffer.len()));
+        self.capacity = capacity;
+    }
+
+    /// Writes all buffered data to the inner writer (without flushing it).
+    pub async fn drain(&mut self) -> std::io::Result<()> {
+        std::future::poll_fn(|cx| self.poll_drain(cx)).await
+    }
+
+    fn poll_drain(&mut self, cx: &mut Context) -> Poll<Result<(), Error>> {
+        while self.drained < self.buffer.len() {
+            let poll = Pin::new(&mut self.writer).poll_write(cx, &self.buffer[self.drained..]);
+            match ready!(poll) {
+                Ok(0) => {
+                    self.errored = true;
+                    return Poll::Ready(Err(ErrorKind::WriteZero.into()));
+                }
+                Ok(written) => self.drained += written,
+                Err(error) => {
+                    self.errored = true;
+                    return Poll::Ready(Err(error));
+                }
+            }
+        }
+
+        self.buffer.clear();
+        self.drained = 0;
+        Poll::Ready(Ok(()))
+    }
+
+    /// Buffers the provided data if it fits within the buffer once drained, returning whether or not it did.
+    fn poll_buffer(&mut self, cx: &mut Context, bufs: &[IoSlice<'_>]) -> Poll<Result<bool, Error>> {
+        let length: usize = bufs.iter().map(|buf| buf.len()).sum();
+        if self.buffer.len() + length > self.capacity {
+            ready!(self.poll_drain(cx))?;
+        }
+        if length >= self.capacity {
+            return Poll::Ready(Ok(false));
+        }
+
+        bufs.iter().for_each(|buf| self.buffer.extend_from_slice(buf));
+        self.offset += length;
+        Poll::Ready(Ok(true))
     }
 
     /// Returns the current writer byte offset.
@@ -27,6 +92,61 @@ impl<W: AsyncWrite + Unpin> OffsetAsyncWriter<W> {
         self.offset
     }
 
+    /// Returns whether or not the inner writer has ever returned an error.
+    ///
+    /// After an error, the inner writer may have accepted bytes which weren't reported, so the tracked offset can no
+    /// longer be trusted.
+    pub fn errored(&self) -> bool {
+        self.errored
+    }
+
+    /// Overwrites previously written data at the provided offsets (in ascending order), before returning to the end.
+    ///
+    /// Buffered data is written and the inner writer flushed first, so that it's positioned at the current offset.
+    pub(crate) async fn patch(&mut self, seeker: Seeker<W>, patches: &[(usize, &[u8])]) -> std::io::Result<()> {
+        let result = self.patch_inner(seeker, patches).await;
+        self.errored |= result.is_err();
+        result
+    }
+
+    async fn patch_inner(&mut self, seeker: Seeker<W>, patches: &[(usize, &[u8])]) -> std::io::Result<()> {
+        self.drain().await?;
+        self.writer.flush().await?;
+
+        let mut position = self.offset;
+        for (offset, data) in patches {
+            seeker.seek(&mut self.writer, SeekFrom::Current(*offset as i64 - position as i64)).await?;
+            self.writer.write_all(data).await?;
+            position = offset + data.len();
+        }
+
+        seeker.seek(&mut self.writer, SeekFrom::Current(self.offset as i64 - position as i64)).await?;
+        Ok(())
+    }
+
+    /// Discards previously written data from the provided offset onwards, such that it's overwritten by later writes.
+    ///
+    /// Buffered data is written and the inner writer flushed first, so that it's positioned at the current offset.
+    pub(crate) async fn rewind(&mut self, seeker: Seeker<W>, offset: usize) -> std::io::Result<()> {
+        let result = self.rewind_inner(seeker, offset).await;
+        self.errored |= result.is_err();
+        result
+    }
+
+    async fn rewind_inner(&mut self, seeker: Seeker<W>, offset: usize) -> std::io::Result<()> {
+        self.drain().await?;
+        self.writer.flush().await?;
+
+        seeker.seek(&mut self.writer, SeekFrom::Current(offset as i64 - self.offset as i64)).await?;
+        self.offset = offset;
+        Ok(())
+    }
+
+    /// Returns a shared reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
     /// Consumes this writer and returns its inner value.
     pub fn into_inner(self) -> W {
         self.writer
@@ -35,20 +155,56 @@ impl<W: AsyncWrite + Unpin> OffsetAsyncWriter<W> {
 
 impl<W: AsyncWrite + Unpin> AsyncWrite for OffsetAsyncWriter<W> {
     fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<Result<usize, Error>> {
+        if self.capacity > 0 && ready!(self.poll_buffer(cx, &[IoSlice::new(buf)]))? {
+            return Poll::Ready(Ok(buf.len()));
+        }
+
         let poll = Pin::new(&mut self.writer).poll_write(cx, buf);
 
-        if let Poll::Ready(Ok(inner)) = poll {
-            self.offset += inner;
+        match poll {
+            Poll::Ready(Ok(inner)) => self.offset += inner,
+            Poll::Ready(Err(_)) => self.errored = true,
+            Poll::Pending => {}
+        };
+
+        poll
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<Result<usize, Error>> {
+        if self.capacity > 0 && ready!(self.poll_buffer(cx, bufs))? {
+            return Poll::Ready(Ok(bufs.iter().map(|buf| buf.len()).sum()));
         }
 
+        let poll = Pin::new(&mut self.writer).poll_write_vectored(cx, bufs);
+
+        match poll {
+            Poll::Ready(Ok(inner)) => self.offset += inner,
+            Poll::Ready(Err(_)) => self.errored = true,
+            Poll::Pending => {}
+        };
+
         poll
     }
 
+    fn is_write_vectored(&self) -> bool {
+        self.capacity > 0 || self.writer.is_write_vectored()
+    }
+
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Error>> {
-        Pin::new(&mut self.writer).poll_flush(cx)
+        ready!(self.poll_drain(cx))?;
+        let poll = Pin::new(&mut self.writer).poll_flush(cx);
+        self.errored |= matches!(poll, Poll::Ready(Err(_)));
+        poll
     }
 
     fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Error>> {
-        Pin::new(&mut self.writer).poll_shutdown(cx)
+        ready!(self.poll_drain(cx))?;
+        let poll = Pin::new(&mut self.writer).poll_shutdown(cx);
+        self.errored |= matches!(poll, Poll::Ready(Err(_)));
+        poll
     }
 }
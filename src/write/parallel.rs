@@ -0,0 +1,77 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Compression of whole entries on Tokio's blocking thread pool, ahead of them being written in order.
+
+use crate::error::Result;
+use crate::write::defaults::ResolvedOptions;
+use crate::write::entry_whole::{compress_or_store, EntryWholeWriter};
+use crate::write::{EntryOptions, ZipFileWriter};
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+use tokio::io::AsyncWrite;
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+
+/// An entry whose data is being compressed, which yields its (possibly updated) options, its data, and its compressed
+/// data (unless it's stored).
+type Job<D> = JoinHandle<Result<(ResolvedOptions, D, Option<Vec<u8>>)>>;
+
+pub(crate) async fn write_entries<W, D>(
+    writer: &mut ZipFileWriter<W>,
+    entries: impl IntoIterator<Item = (EntryOptions, D)>,
+    concurrency: usize,
+) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    D: AsRef<[u8]> + Send + 'static,
+{
+    let mut entries = entries.into_iter();
+    let mut jobs: VecDeque<Job<D>> = VecDeque::new();
+
+    loop {
+        while jobs.len() < concurrency.max(1) {
+            match entries.next() {
+                Some((options, data)) => jobs.push_back(spawn(writer, options, data)?),
+                None => break,
+            }
+        }
+
+        let Some(job) = jobs.pop_front() else {
+            return Ok(());
+        };
+
+        let (options, data, compressed_data) = match job.await {
+            Ok(result) => result?,
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        };
+
+        let compressed_data = compressed_data.as_deref().unwrap_or(data.as_ref());
+        EntryWholeWriter::from_raw(writer, options, data.as_ref()).write_compressed(compressed_data).await?;
+    }
+}
+
+/// Spawns the compression of an entry's data onto the blocking thread pool.
+fn spawn<W, D>(writer: &ZipFileWriter<W>, options: EntryOptions, data: D) -> Result<Job<D>>
+where
+    W: AsyncWrite + Unpin,
+    D: AsRef<[u8]> + Send + 'static,
+{
+    let mut options = options.resolve(&writer.defaults)?;
+    let (backends, store_if_larger) = (writer.backends.clone(), writer.store_if_larger);
+    let handle = Handle::current();
+
+    // Compression only ever writes into memory, so the future completes without waiting on any IO.
+    Ok(tokio::task::spawn_blocking(move || {
+        let compressed_data =
+            handle.block_on(compress_or_store(&mut options, data.as_ref(), &backends, store_if_larger))?;
+        let compressed_data = match compressed_data {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(compressed_data) => Some(compressed_data),
+        };
+
+        Ok((options, data, compressed_data))
+    }))
+}
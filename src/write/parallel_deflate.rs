@@ -0,0 +1,147 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Deflate compression of a single entry across multiple threads, in the style of pigz.
+//!
+//! An entry's data is split into fixed-size blocks which are each compressed independently (without the preceding
+//! block as a dictionary) on Tokio's blocking thread pool. Every block but the last ends with a sync flush, which
+//! leaves the stream byte-aligned with no final block marker, so concatenating the compressed blocks in order forms a
+//! single valid Deflate stream. Compression is marginally worse than when compressing the data as a whole.
+
+use crate::write::CompressionLevel;
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io::Cursor;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use async_compression::tokio::write::DeflateEncoder;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+
+/// The size of each independently compressed block (as with pigz).
+const BLOCK_SIZE: usize = 128 * 1024;
+
+/// A Deflate encoder which compresses blocks of an entry's data on up to a given number of threads at once.
+pub(crate) struct ParallelDeflateEncoder<W> {
+    inner: W,
+    level: CompressionLevel,
+    threads: usize,
+    input: Vec<u8>,
+
+    // The blocks being compressed, in order, and whether the final block has been dispatched.
+    jobs: VecDeque<JoinHandle<tokio::io::Result<Vec<u8>>>>,
+    finished: bool,
+
+    // A compressed block which is yet to be written to the inner writer.
+    pending: Vec<u8>,
+    pending_position: usize,
+}
+
+impl<W: AsyncWrite + Unpin> ParallelDeflateEncoder<W> {
+    pub(crate) fn new(inner: W, level: CompressionLevel, threads: usize) -> Self {
+        ParallelDeflateEncoder {
+            inner,
+            level,
+            threads: threads.max(1),
+            input: Vec::new(),
+            jobs: VecDeque::new(),
+            finished: false,
+            pending: Vec::new(),
+            pending_position: 0,
+        }
+    }
+
+    pub(crate) fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Hands the buffered input to a blocking task to be compressed as the next block.
+    fn dispatch(&mut self, last: bool) {
+        let (input, level, handle) = (std::mem::take(&mut self.input), self.level, Handle::current());
+
+        self.jobs.push_back(tokio::task::spawn_blocking(move || {
+            handle.block_on(async {
+                let mut encoder = DeflateEncoder::with_quality(Cursor::new(Vec::new()), level.to_level());
+                encoder.write_all(&input).await?;
+                match last {
+                    true => encoder.shutdown().await?,
+                    false => encoder.flush().await?,
+                }
+                Ok(encoder.into_inner().into_inner())
+            })
+        }));
+    }
+
+    /// Writes compressed blocks to the inner writer, in order, until no more than `remaining` are being compressed.
+    fn poll_complete(&mut self, c: &mut Context<'_>, remaining: usize) -> Poll<tokio::io::Result<()>> {
+        loop {
+            while self.pending_position < self.pending.len() {
+                let written = ready!(Pin::new(&mut self.inner).poll_write(c, &self.pending[self.pending_position..]))?;
+                if written == 0 {
+                    return Poll::Ready(Err(tokio::io::ErrorKind::WriteZero.into()));
+                }
+                self.pending_position += written;
+            }
+
+            if self.jobs.len() <= remaining {
+                return Poll::Ready(Ok(()));
+            }
+
+            let block = match ready!(Pin::new(&mut self.jobs[0]).poll(c)) {
+                Ok(block) => block,
+                Err(error) => std::panic::resume_unwind(error.into_panic()),
+            };
+            self.jobs.pop_front();
+            self.pending = block?;
+            self.pending_position = 0;
+        }
+    }
+
+    /// Dispatches the buffered input as the next block, once fewer blocks than the number of threads are in progress.
+    fn poll_dispatch(&mut self, c: &mut Context<'_>, last: bool) -> Poll<tokio::io::Result<()>> {
+        ready!(self.poll_complete(c, self.threads - 1))?;
+        self.dispatch(last);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for ParallelDeflateEncoder<W> {
+    fn poll_write(mut self: Pin<&mut Self>, c: &mut Context<'_>, buf: &[u8]) -> Poll<tokio::io::Result<usize>> {
+        if self.finished {
+            return Poll::Ready(Err(tokio::io::Error::other("attempted to write after the encoder was shut down")));
+        }
+        if self.input.len() == BLOCK_SIZE {
+            ready!(self.poll_dispatch(c, false))?;
+        }
+
+        let length = std::cmp::min(BLOCK_SIZE - self.input.len(), buf.len());
+        self.input.extend_from_slice(&buf[..length]);
+        Poll::Ready(Ok(length))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        if !self.input.is_empty() {
+            ready!(self.poll_dispatch(c, false))?;
+        }
+
+        ready!(self.poll_complete(c, 0))?;
+        Pin::new(&mut self.inner).poll_flush(c)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, c: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        if !self.finished {
+            ready!(self.poll_dispatch(c, true))?;
+            self.finished = true;
+        }
+
+        ready!(self.poll_complete(c, 0))?;
+        Pin::new(&mut self.inner).poll_shutdown(c)
+    }
+}
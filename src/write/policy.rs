@@ -0,0 +1,90 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::spec::compression::Compression;
+use crate::write::EntryOptions;
+
+use std::collections::HashMap;
+
+/// File extensions of formats which are already compressed, and so gain nothing from being compressed again.
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "7z", "aac", "apk", "avi", "avif", "br", "bz2", "docx", "flac", "gif", "gz", "heic", "jar", "jpeg", "jpg", "lz",
+    "lz4", "lzma", "m4a", "m4v", "mkv", "mov", "mp3", "mp4", "odt", "ogg", "opus", "png", "pptx", "rar", "tgz", "webm",
+    "webp", "whl", "woff", "woff2", "xlsx", "xz", "zip", "zst",
+];
+
+/// A policy which chooses the compression method of an entry from its filename's extension.
+///
+/// Extensions are matched case-insensitively against the final component of the filename, without the leading dot.
+/// Filenames without an extension (including dotfiles such as `.gitignore` and names ending in a dot) always use the
+/// default method.
+///
+/// A policy may be applied to every entry which doesn't set its own method via [`EntryDefaults::compression_policy`],
+/// or to individual entries via [`CompressionPolicy::options()`].
+///
+/// # Example
+/// ```
+/// # use async_zip::{Compression, write::CompressionPolicy};
+/// #
+/// let policy = CompressionPolicy::store_compressed(Compression::Deflate).extension("svg", Compression::Zstd);
+///
+/// assert_eq!(Compression::Stored, policy.compression_for("assets/logo.PNG"));
+/// assert_eq!(Compression::Zstd, policy.compression_for("assets/logo.svg"));
+/// assert_eq!(Compression::Deflate, policy.compression_for("README"));
+/// ```
+///
+/// [`EntryDefaults::compression_policy`]: crate::write::EntryDefaults::compression_policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressionPolicy {
+    default: Compression,
+    overrides: HashMap<String, Compression>,
+}
+
+impl CompressionPolicy {
+    /// Construct a new policy which uses the provided method for every entry.
+    pub fn new(default: Compression) -> Self {
+        CompressionPolicy { default, overrides: HashMap::new() }
+    }
+
+    /// Construct a new policy which stores well-known compressed formats (images, video, audio, and archives) and
+    /// uses the provided method for everything else.
+    pub fn store_compressed(default: Compression) -> Self {
+        COMPRESSED_EXTENSIONS.iter().fold(Self::new(default), |policy, ext| policy.extension(ext, Compression::Stored))
+    }
+
+    /// Consume the policy and override the method used for a specific extension (without the leading dot).
+    pub fn extension(mut self, extension: &str, compression: Compression) -> Self {
+        self.overrides.insert(extension.to_ascii_lowercase(), compression);
+        self
+    }
+
+    /// Returns the method used for filenames without a matching extension override.
+    pub fn default_compression(&self) -> Compression {
+        self.default
+    }
+
+    /// Returns the method which should be used for an entry with the provided filename.
+    pub fn compression_for(&self, filename: &str) -> Compression {
+        extension(filename)
+            .and_then(|ext| self.overrides.get(&ext.to_ascii_lowercase()))
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// Construct a set of entry options for the provided filename using the method chosen by this policy.
+    pub fn options(&self, filename: String) -> EntryOptions {
+        let compression = self.compression_for(&filename);
+        EntryOptions::new(filename, compression)
+    }
+}
+
+/// Returns the extension of a filename's final component, if it has one.
+fn extension(filename: &str) -> Option<&str> {
+    let component = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+
+    match component.rfind('.') {
+        Some(0) | None => None,
+        Some(index) if index + 1 == component.len() => None,
+        Some(index) => Some(&component[index + 1..]),
+    }
+}
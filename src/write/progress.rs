@@ -0,0 +1,58 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use tokio::sync::watch::Sender;
+
+/// A snapshot of the bytes written for a single entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntryProgress {
+    /// The name of the entry being written.
+    pub entry_name: String,
+    /// The number of uncompressed bytes accepted by the entry so far.
+    pub uncompressed_written: u64,
+    /// The number of compressed bytes which have reached the underlying writer so far.
+    pub compressed_written: u64,
+    /// Whether or not the entry has been closed and these are its final totals.
+    pub finished: bool,
+}
+
+/// A progress sink shared by all entries of a [`ZipFileWriter`](crate::write::ZipFileWriter).
+///
+/// Updates are published via a watch channel so a slow (or absent) receiver can never block the writer; receivers
+/// only ever observe the latest snapshot.
+pub(crate) struct ProgressReporter {
+    sender: Sender<EntryProgress>,
+    interval: u64,
+}
+
+impl ProgressReporter {
+    pub(crate) fn new(sender: Sender<EntryProgress>, interval: u64) -> Self {
+        Self { sender, interval }
+    }
+
+    /// Publishes an update if at least the configured interval of uncompressed bytes has passed since `last`.
+    ///
+    /// Returns the value which should be stored as `last` for the next call.
+    pub(crate) fn update(&self, name: &str, uncompressed: u64, compressed: u64, last: u64) -> u64 {
+        if uncompressed.saturating_sub(last) < self.interval {
+            return last;
+        }
+
+        self.publish(name, uncompressed, compressed, false);
+        uncompressed
+    }
+
+    /// Publishes the final totals for an entry.
+    pub(crate) fn finish(&self, name: &str, uncompressed: u64, compressed: u64) {
+        self.publish(name, uncompressed, compressed, true);
+    }
+
+    fn publish(&self, name: &str, uncompressed: u64, compressed: u64, finished: bool) {
+        self.sender.send_replace(EntryProgress {
+            entry_name: name.to_string(),
+            uncompressed_written: uncompressed,
+            compressed_written: compressed,
+            finished,
+        });
+    }
+}
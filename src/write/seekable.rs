@@ -0,0 +1,59 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::write::ZipFileWriter;
+
+use std::io::SeekFrom;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncSeek, AsyncWrite};
+
+/// The seek operations of a writer, captured as function pointers so that writers which aren't seekable share the same
+/// type (and entry writers' futures remain `Send`).
+pub(crate) struct Seeker<W> {
+    start: fn(Pin<&mut W>, SeekFrom) -> std::io::Result<()>,
+    complete: fn(Pin<&mut W>, &mut Context<'_>) -> Poll<std::io::Result<u64>>,
+}
+
+impl<W> Clone for Seeker<W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<W> Copy for Seeker<W> {}
+
+impl<W: AsyncSeek + Unpin> Seeker<W> {
+    fn new() -> Self {
+        Seeker { start: W::start_seek, complete: W::poll_complete }
+    }
+}
+
+impl<W: Unpin> Seeker<W> {
+    pub(crate) async fn seek(&self, writer: &mut W, position: SeekFrom) -> std::io::Result<u64> {
+        (self.start)(Pin::new(writer), position)?;
+        std::future::poll_fn(|cx| (self.complete)(Pin::new(writer), cx)).await
+    }
+}
+
+impl<W: AsyncWrite + AsyncSeek + Unpin> ZipFileWriter<W> {
+    /// Construct a new ZIP file writer from a seekable writer (or a mutable reference to one).
+    ///
+    /// Stream entries (ie. those opened via [`ZipFileWriter::write_entry_stream()`]) are written without data
+    /// descriptors: once an entry has been closed, the writer seeks back and fills in the CRC32 value and sizes of its
+    /// local file header before returning to the end of the archive. This produces archives which are readable by
+    /// consumers that don't support data descriptors.
+    ///
+    /// As the local file header is written before the entry's size is known, an entry which may exceed 4 GiB must be
+    /// written with Zip64 forced (see [`ZipFileWriter::force_zip64()`] and [`ExtraField::Zip64`]) so that its header
+    /// has room for 64-bit sizes; otherwise, [`ZipError::Zip64NotReserved`] is returned when it's closed.
+    ///
+    /// [`ExtraField::Zip64`]: crate::ExtraField::Zip64
+    /// [`ZipError::Zip64NotReserved`]: crate::error::ZipError::Zip64NotReserved
+    pub fn new_seekable(writer: W) -> Self {
+        let mut writer = Self::new(writer);
+        writer.seeker = Some(Seeker::new());
+        writer
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+use crate::backend::{Backends, CompressionBackend};
+use crate::error::{Result, ZipError};
+use crate::write::defaults::ResolvedOptions;
+use crate::write::encrypted_writer::encrypt_data;
+use crate::write::entry_whole::{compress_or_store, EntryWholeWriter, GeneratedFields};
+use crate::write::{EntryDefaults, EntryOptions, ZipFileWriter};
+
+use std::future::Future;
+use std::sync::Arc;
+
+use tokio::io::AsyncWrite;
+
+/// The fixed size of an end of central directory header (including its delimiter).
+const EOCDH_LENGTH: u64 = 22;
+
+/// The behaviour of a [`ShardedZipWriter`] when a single entry alone would exceed the maximum part size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OversizedEntry {
+    /// Write the entry into its own part, which will exceed the maximum size.
+    OwnPart,
+    /// Return [`ZipError::ShardSizeExceeded`] and don't write the entry.
+    Error,
+}
+
+/// A summary of the parts written by a [`ShardedZipWriter`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShardSummary {
+    /// The number of parts which were written.
+    pub parts: usize,
+    /// The name of each written entry paired with the index of the part it was written to, in writing order.
+    pub entries: Vec<(String, usize)>,
+}
+
+/// A writer which splits entries across as many standalone ZIP files as needed to keep each under a maximum size.
+///
+/// Unlike spanned archives, every part is a complete archive (with its own central directory) which can be extracted
+/// independently. New parts are requested from the provided factory with their zero-based index.
+///
+/// # Example
+/// ```no_run
+/// # use async_zip::{Compression, write::{EntryOptions, ShardedZipWriter}};
+/// # use tokio::fs::File;
+/// # use async_zip::error::ZipError;
+/// #
+/// # async fn run() -> Result<(), ZipError> {
+/// let factory = |part: usize| async move { Ok(File::create(format!("foo.part{}.zip", part)).await?) };
+/// let mut writer = ShardedZipWriter::new(factory, 2 * 1024 * 1024 * 1024);
+///
+/// let opts = EntryOptions::new(String::from("bar.txt"), Compression::Deflate);
+/// writer.write_entry_whole(opts, b"This is an example file.").await?;
+///
+/// let summary = writer.close().await?;
+/// assert_eq!(summary.parts, 1);
+/// #   Ok(())
+/// # }
+/// ```
+pub struct ShardedZipWriter<W: AsyncWrite + Unpin, F> {
+    factory: F,
+    max_size: u64,
+    oversized: OversizedEntry,
+    defaults: EntryDefaults,
+    backends: Backends,
+    store_if_larger: bool,
+    current: Option<ZipFileWriter<W>>,
+    cd_size: u64,
+    summary: ShardSummary,
+}
+
+impl<W, F, Fut> ShardedZipWriter<W, F>
+where
+    W: AsyncWrite + Unpin,
+    F: FnMut(usize) -> Fut,
+    Fut: Future<Output = Result<W>>,
+{
+    /// Construct a new sharded writer from a part factory and the maximum size of each part in bytes.
+    pub fn new(factory: F, max_size: u64) -> Self {
+        Self {
+            factory,
+            max_size,
+            oversized: OversizedEntry::OwnPart,
+            defaults: EntryDefaults::default(),
+            backends: Backends::default(),
+            store_if_larger: false,
+            current: None,
+            cd_size: 0,
+            summary: ShardSummary::default(),
+        }
+    }
+
+    /// Consume the writer and override the behaviour for entries which are alone larger than the maximum part size.
+    pub fn oversized(mut self, oversized: OversizedEntry) -> Self {
+        self.oversized = oversized;
+        self
+    }
+
+    /// Consume the writer and set the defaults applied to every entry whose options don't explicitly override them.
+    pub fn default_options(mut self, defaults: EntryDefaults) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    /// Consume the writer and store each entry instead if compressing it would make its data larger.
+    ///
+    /// See [`ZipFileWriter::store_if_larger()`] for details.
+    pub fn store_if_larger(mut self, store: bool) -> Self {
+        self.store_if_larger = store;
+        self
+    }
+
+    /// Consume the writer and register a backend through which entries compressed with its method are written.
+    ///
+    /// See [`ZipFileWriter::compression_backend()`] for details.
+    pub fn compression_backend(mut self, backend: impl CompressionBackend + 'static) -> Self {
+        self.backends.register(Arc::new(backend));
+        self
+    }
+
+    /// Write a new ZIP entry of known size and data into the current part, or a new part if it wouldn't fit.
+    pub async fn write_entry_whole(&mut self, options: EntryOptions, data: &[u8]) -> Result<()> {
+        let mut options = options.resolve(&self.defaults)?;
+        let compressed_data = compress_or_store(&mut options, data, &self.backends, self.store_if_larger).await?;
+
+        let compressed_data = encrypt_data(&options, &compressed_data)?;
+
+        // The sizes of the entry's headers are taken from the same fields that a part writes, given their offset. Parts
+        // are never forced to use Zip64 nor aligned by default, so the options needn't be normalised beforehand.
+        let sizes = |options: &ResolvedOptions, offset: u64| -> Result<(u64, u64)> {
+            let (uncompressed_size, compressed_size) = (data.len() as u64, compressed_data.len() as u64);
+            let fields =
+                GeneratedFields::new(options, uncompressed_size, compressed_size, offset, options.force_zip64)?;
+            Ok((fields.local_length(options, compressed_size), fields.central_length(options)))
+        };
+
+        let (lfh_size, cdh_size) = sizes(&options, 0)?;
+        if lfh_size + cdh_size + EOCDH_LENGTH > self.max_size && self.oversized == OversizedEntry::Error {
+            return Err(ZipError::ShardSizeExceeded);
+        }
+
+        let written = self.current.as_ref().map(|writer| writer.writer.offset() as u64);
+        let fits = |written: u64| -> Result<bool> {
+            let (lfh_size, cdh_size) = sizes(&options, written)?;
+            Ok(written + lfh_size + self.cd_size + cdh_size + EOCDH_LENGTH <= self.max_size)
+        };
+
+        match written {
+            Some(written) if written == 0 || fits(written)? => {}
+            _ => self.next_part().await?,
+        }
+
+        let name = options.filename.as_str().to_string();
+        let writer = self.current.as_mut().unwrap();
+        writer.normalise_options(&mut options);
+        let (_, cdh_size) = sizes(&options, writer.writer.offset() as u64)?;
+        EntryWholeWriter::from_raw(writer, options, data).write_prepared(&compressed_data).await?;
+
+        self.cd_size += cdh_size;
+        self.summary.entries.push((name, self.summary.parts - 1));
+
+        Ok(())
+    }
+
+    /// Consumes this writer, closes the current part, and returns a summary of all parts written.
+    pub async fn close(mut self) -> Result<ShardSummary> {
+        if let Some(writer) = self.current.take() {
+            writer.close().await?;
+        }
+
+        Ok(self.summary)
+    }
+
+    async fn next_part(&mut self) -> Result<()> {
+        if let Some(writer) = self.current.take() {
+            writer.close().await?;
+        }
+
+        let mut writer = ZipFileWriter::new((self.factory)(self.summary.parts).await?);
+        writer.default_options(self.defaults.clone());
+        writer.backends = self.backends.clone();
+
+        self.current = Some(writer);
+        self.cd_size = 0;
+        self.summary.parts += 1;
+
+        Ok(())
+    }
+}
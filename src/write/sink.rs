@@ -0,0 +1,219 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Adapters which drive a [`ZipFileWriter`] from the `futures` [`Sink`] interface.
+
+use crate::error::{Result, ZipError};
+use crate::write::{EntryOptions, ZipFileWriter};
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_sink::Sink;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+type PendingEntry<'a, W> = Pin<Box<dyn Future<Output = (ZipFileWriter<W>, Result<()>)> + Send + 'a>>;
+type PendingClose<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+enum State<'a, W: AsyncWrite + Unpin> {
+    // The writer is boxed as it's far larger than the other variants.
+    Idle(Box<ZipFileWriter<W>>),
+    Writing(PendingEntry<'a, W>),
+    Closing(PendingClose<'a>),
+    Closed,
+}
+
+/// The state machine shared by both sink adapters; only one entry is ever in flight at a time.
+struct SinkCore<'a, W: AsyncWrite + Unpin> {
+    state: State<'a, W>,
+}
+
+impl<'a, W: AsyncWrite + Unpin + Send + 'a> SinkCore<'a, W> {
+    fn new(writer: ZipFileWriter<W>) -> Self {
+        Self { state: State::Idle(Box::new(writer)) }
+    }
+
+    /// Drives any in-flight entry to completion and returns the idle writer.
+    fn poll_idle(&mut self, cx: &mut Context<'_>) -> Poll<Result<&mut ZipFileWriter<W>>> {
+        if let State::Writing(pending) = &mut self.state {
+            let (writer, result) = ready!(pending.as_mut().poll(cx));
+            self.state = State::Idle(Box::new(writer));
+            result?;
+        }
+
+        match &mut self.state {
+            State::Idle(writer) => Poll::Ready(Ok(&mut **writer)),
+            _ => Poll::Ready(Err(ZipError::SinkUnavailable)),
+        }
+    }
+
+    fn start(&mut self, write: impl FnOnce(ZipFileWriter<W>) -> PendingEntry<'a, W>) -> Result<()> {
+        match std::mem::replace(&mut self.state, State::Closed) {
+            State::Idle(writer) => {
+                self.state = State::Writing(write(*writer));
+                Ok(())
+            }
+            state => {
+                self.state = state;
+                Err(ZipError::SinkUnavailable)
+            }
+        }
+    }
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        ready!(self.poll_idle(cx))?;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let writer = ready!(self.poll_idle(cx))?;
+        Poll::Ready(ready!(Pin::new(&mut writer.writer).poll_flush(cx)).map_err(ZipError::from))
+    }
+
+    fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        loop {
+            match &mut self.state {
+                State::Writing(_) => {
+                    ready!(self.poll_idle(cx))?;
+                }
+                State::Idle(_) => {
+                    if let State::Idle(writer) = std::mem::replace(&mut self.state, State::Closed) {
+                        self.state = State::Closing(Box::pin(async move { writer.close().await.map(drop) }));
+                    }
+                }
+                State::Closing(pending) => {
+                    let result = ready!(pending.as_mut().poll(cx));
+                    self.state = State::Closed;
+                    return Poll::Ready(result);
+                }
+                State::Closed => return Poll::Ready(Ok(())),
+            }
+        }
+    }
+}
+
+/// A [`Sink`] which writes each `(EntryOptions, Bytes)` item it receives as one whole entry.
+///
+/// Only one entry is written at a time, so the sink only becomes ready for another item once the previous one has been
+/// fully written to the underlying writer. Flushing the sink flushes the underlying writer, and closing it writes the
+/// central directory (as [`ZipFileWriter::close()`] does).
+///
+/// # Example
+/// ```no_run
+/// # use async_zip::write::{EntryOptions, ZipFileWriter, ZipSink};
+/// # use async_zip::Compression;
+/// # use bytes::Bytes;
+/// # use futures_util::{stream, StreamExt};
+/// # use tokio::fs::File;
+/// #
+/// # async fn run() -> async_zip::error::Result<()> {
+/// let files = vec![("foo.txt", "foo"), ("bar.txt", "bar")];
+///
+/// let zip_sink = ZipSink::new(ZipFileWriter::new(File::create("./foo.zip").await?));
+/// stream::iter(files)
+///     .map(|(name, data)| Ok((EntryOptions::new(name, Compression::Deflate), Bytes::from(data))))
+///     .forward(zip_sink)
+///     .await?;
+/// #   Ok(())
+/// # }
+/// ```
+pub struct ZipSink<'a, W: AsyncWrite + Unpin> {
+    core: SinkCore<'a, W>,
+}
+
+impl<'a, W: AsyncWrite + Unpin + Send + 'a> ZipSink<'a, W> {
+    /// Construct a new sink which writes entries to the provided ZIP file writer.
+    pub fn new(writer: ZipFileWriter<W>) -> Self {
+        Self { core: SinkCore::new(writer) }
+    }
+}
+
+impl<'a, W: AsyncWrite + Unpin + Send + 'a> Sink<(EntryOptions, Bytes)> for ZipSink<'a, W> {
+    type Error = ZipError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.get_mut().core.poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, (options, data): (EntryOptions, Bytes)) -> Result<()> {
+        self.get_mut().core.start(|mut writer| {
+            Box::pin(async move {
+                let result = writer.write_entry_whole(options, &data).await;
+                (writer, result)
+            })
+        })
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.get_mut().core.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.get_mut().core.poll_close(cx)
+    }
+}
+
+/// A [`Sink`] which writes each `(EntryOptions, S)` item it receives as one streamed entry, where `S` is a stream of
+/// the entry's data.
+///
+/// This behaves as [`ZipSink`] does but never holds an entry's data in memory all at once, so is better suited to large
+/// entries. Each entry is written with a data descriptor via [`ZipFileWriter::write_entry_stream()`].
+pub struct ZipStreamSink<'a, W: AsyncWrite + Unpin, S> {
+    core: SinkCore<'a, W>,
+    phantom: PhantomData<fn(S)>,
+}
+
+impl<'a, W: AsyncWrite + Unpin + Send + 'a, S> ZipStreamSink<'a, W, S> {
+    /// Construct a new sink which writes streamed entries to the provided ZIP file writer.
+    pub fn new(writer: ZipFileWriter<W>) -> Self {
+        Self { core: SinkCore::new(writer), phantom: PhantomData }
+    }
+}
+
+impl<'a, W, S> Sink<(EntryOptions, S)> for ZipStreamSink<'a, W, S>
+where
+    W: AsyncWrite + Unpin + Send + 'a,
+    S: Stream<Item = Bytes> + Send + 'a,
+{
+    type Error = ZipError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.get_mut().core.poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, (options, data): (EntryOptions, S)) -> Result<()> {
+        self.get_mut().core.start(|mut writer| {
+            Box::pin(async move {
+                let result = write_stream(&mut writer, options, data).await;
+                (writer, result)
+            })
+        })
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.get_mut().core.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.get_mut().core.poll_close(cx)
+    }
+}
+
+async fn write_stream<W, S>(writer: &mut ZipFileWriter<W>, options: EntryOptions, data: S) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    S: Stream<Item = Bytes>,
+{
+    let mut data = std::pin::pin!(data);
+    let mut entry_writer = writer.write_entry_stream(options).await?;
+
+    while let Some(chunk) = std::future::poll_fn(|cx| data.as_mut().poll_next(cx)).await {
+        entry_writer.write_all(&chunk).await?;
+    }
+
+    entry_writer.close().await
+}
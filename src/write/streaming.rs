@@ -0,0 +1,60 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! A writer whose output is yielded from the `futures` [`Stream`] interface, for use as an HTTP response body.
+
+use crate::chunks::Chunks;
+use crate::write::ZipFileWriter;
+
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use tokio::io::DuplexStream;
+
+/// A [`Stream`] which yields the output of a [`ZipFileWriter`] in chunks as it's written.
+///
+/// At most the capacity passed to [`ZipFileWriter::new_streaming()`] is held between the writer and this stream, so
+/// writes wait whilst this stream isn't being polled. As such, the writer and this stream must be driven concurrently
+/// (eg. by writing the archive within a spawned task). The stream ends once the writer has been dropped (including the
+/// inner writer returned by [`ZipFileWriter::close()`]).
+pub struct ArchiveStream {
+    chunks: Chunks<DuplexStream>,
+}
+
+impl ZipFileWriter<DuplexStream> {
+    /// Construct a new ZIP file writer whose output is yielded by the returned [`ArchiveStream`], with at most
+    /// `capacity` bytes held in between.
+    ///
+    /// # Example
+    /// ```
+    /// # use async_zip::{Compression, error::ZipError, write::{EntryOptions, ZipFileWriter}};
+    /// #
+    /// # async fn run() -> Result<(), ZipError> {
+    /// let (mut writer, stream) = ZipFileWriter::new_streaming(64 * 1024);
+    ///
+    /// tokio::spawn(async move {
+    ///     let options = EntryOptions::new("foo.txt".to_string(), Compression::Deflate);
+    ///     writer.write_entry_whole(options, b"Hello, world!").await?;
+    ///     writer.close().await.map(drop)
+    /// });
+    ///
+    /// // Respond with `stream` as the body (eg. via `axum::body::Body::from_stream()`).
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn new_streaming(capacity: usize) -> (Self, ArchiveStream) {
+        let capacity = capacity.max(1);
+        let (writer, reader) = tokio::io::duplex(capacity);
+        (Self::new(writer), ArchiveStream { chunks: Chunks::new(reader, capacity) })
+    }
+}
+
+impl Stream for ArchiveStream {
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(ready!(self.get_mut().chunks.poll_chunk(cx)).transpose())
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright (c) 2021 Harry [Majored] [hello@majored.pw]
+// MIT License (https://github.com/Majored/rs-async-zip/blob/main/LICENSE)
+
+//! Compression and decompression of zstd entries with a dictionary.
+//!
+//! `async-compression` doesn't support dictionaries, so a dictionary is implemented as a [`CompressionBackend`] for the
+//! zstd method which drives zstd's streaming API directly. Entries written with a dictionary are standard zstd frames
+//! (which record the dictionary's ID), and so are read as any other zstd entry given the same dictionary.
+
+use crate::backend::{BackendReader, BackendWriter, CompressionBackend};
+use crate::error::Result;
+use crate::write::CompressionLevel;
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, ReadBuf};
+use zstd::stream::raw::{Decoder, Encoder, InBuffer, Operation, OutBuffer};
+
+/// The size of the buffer which compressed data is written into before being passed on.
+const OUTPUT_BUFFER_SIZE: usize = 32 * 1024;
+
+/// A zstd dictionary, shared by every entry compressed or decompressed with it.
+#[derive(Clone)]
+pub(crate) struct ZstdDictionary(pub(crate) Arc<[u8]>);
+
+impl std::fmt::Debug for ZstdDictionary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ZstdDictionary({} bytes)", self.0.len())
+    }
+}
+
+impl CompressionBackend for ZstdDictionary {
+    fn method(&self) -> u16 {
+        crate::Compression::Zstd.to_u16()
+    }
+
+    fn decoder(&self, reader: BackendReader) -> Result<Box<dyn AsyncRead + Unpin + Send>> {
+        let decoder = Decoder::with_dictionary(&self.0)?;
+        Ok(Box::new(DictionaryDecoder { reader, decoder, finished: false }))
+    }
+
+    fn encoder(&self, writer: BackendWriter, level: CompressionLevel) -> Result<Box<dyn AsyncWrite + Unpin + Send>> {
+        let level = match level {
+            CompressionLevel::Fastest => 1,
+            CompressionLevel::Default => zstd::DEFAULT_COMPRESSION_LEVEL,
+            CompressionLevel::Best => zstd::zstd_safe::max_c_level(),
+            CompressionLevel::Precise(level) => (level as i32).clamp(1, zstd::zstd_safe::max_c_level()),
+        };
+
+        let encoder = Encoder::with_dictionary(level, &self.0)?;
+        Ok(Box::new(DictionaryEncoder { writer, encoder, output: vec![0; OUTPUT_BUFFER_SIZE], finished: false }))
+    }
+}
+
+/// A decoder which decompresses a single zstd frame, consuming no input beyond its end.
+struct DictionaryDecoder<R> {
+    reader: R,
+    decoder: Decoder<'static>,
+    finished: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncRead for DictionaryDecoder<R> {
+    fn poll_read(mut self: Pin<&mut Self>, c: &mut Context<'_>, b: &mut ReadBuf<'_>) -> Poll<tokio::io::Result<()>> {
+        let this = &mut *self;
+
+        while !this.finished && b.remaining() > 0 {
+            let input = ready!(Pin::new(&mut this.reader).poll_fill_buf(c))?;
+            let eof = input.is_empty();
+
+            let mut input = InBuffer::around(input);
+            let mut output = OutBuffer::around(b.initialize_unfilled());
+            let hint = this.decoder.run(&mut input, &mut output)?;
+            let (consumed, produced) = (input.pos(), output.pos());
+
+            Pin::new(&mut this.reader).consume(consumed);
+            b.advance(produced);
+            this.finished = hint == 0;
+
+            if eof && produced == 0 && !this.finished {
+                return Poll::Ready(Err(tokio::io::ErrorKind::UnexpectedEof.into()));
+            }
+            if produced > 0 {
+                break;
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// An encoder which compresses data into a single zstd frame.
+struct DictionaryEncoder {
+    writer: BackendWriter,
+    encoder: Encoder<'static>,
+    output: Vec<u8>,
+    finished: bool,
+}
+
+impl DictionaryEncoder {
+    /// Repeatedly performs an operation which writes into the output buffer until it reports nothing remaining.
+    fn drain(
+        &mut self,
+        mut operation: impl FnMut(&mut Encoder<'static>, &mut OutBuffer<'_, [u8]>) -> std::io::Result<usize>,
+    ) -> std::io::Result<()> {
+        loop {
+            let mut output = OutBuffer::around(self.output.as_mut_slice());
+            let remaining = operation(&mut self.encoder, &mut output)?;
+            let produced = output.pos();
+            self.writer.append(&self.output[..produced]);
+
+            if remaining == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl AsyncWrite for DictionaryEncoder {
+    fn poll_write(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<tokio::io::Result<usize>> {
+        let this = &mut *self;
+        let mut input = InBuffer::around(buf);
+
+        while input.pos() < buf.len() {
+            let mut output = OutBuffer::around(this.output.as_mut_slice());
+            this.encoder.run(&mut input, &mut output)?;
+            let produced = output.pos();
+            this.writer.append(&this.output[..produced]);
+        }
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        Poll::Ready(self.drain(|encoder, output| encoder.flush(output)))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<tokio::io::Result<()>> {
+        if !self.finished {
+            self.drain(|encoder, output| encoder.finish(output, true))?;
+            self.finished = true;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}